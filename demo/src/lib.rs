@@ -14,11 +14,13 @@ live_reload! {
     update: update;
     unload: unload;
     deinit: deinit;
+    state_layout: State::LAYOUT_HASH;
 }
 
-#[repr(C)]
-struct State {
-    counter: usize,
+reloadable_state! {
+    struct State {
+        counter: usize,
+    }
 }
 
 fn init(host: &mut Host, state: &mut State) {
@@ -0,0 +1,200 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use super::{Error, Reloadable, ShouldQuit};
+
+/// How long the worker waits for a change before checking again anyway, for
+/// a trigger, a reload put off by the gate, or being asked to stop.
+const POLL: Duration = Duration::from_millis(100);
+
+/// A [`Reloadable`][] that reloads by itself on a background thread, made
+/// with [`Reloadable::spawn_autoreload`][].
+///
+/// # Concurrency
+///
+/// The `Reloadable` sits behind a mutex shared by the worker thread and this
+/// handle, and everything that touches the library, the `Host` or the
+/// `State` happens while holding it:
+///
+/// - The worker waits for the watcher to see a change without the lock, so
+///   it never holds up the host while idle.
+/// - When a change comes in, or every 100 ms regardless (to ask a
+///   [`Builder::trigger`][], or retry a reload the gate put off), it takes
+///   the lock and calls [`reload`][], which applies the change if there is
+///   one. It then calls the `on_reload` callback, still under the lock, so
+///   the callback sees the new library and a `State` nothing else is using.
+/// - The host calls [`update`](#method.update), or anything else through
+///   [`lock`](#method.lock), which takes the same lock. So an update and a
+///   reload never overlap: an update waits for a reload in progress to
+///   finish, and a reload waits for the update.
+///
+/// Everything else about a reload is unchanged, including the reload gate
+/// and the library's `is_safe_to_reload`, which are asked on the worker
+/// thread. Holding the guard from `lock` for long stops reloads for that
+/// long, which is one way to keep the library still across several calls.
+///
+/// Dropping the handle stops the worker, and then drops the `Reloadable` as
+/// usual, calling `deinit`; [`stop`](#method.stop) stops the worker and
+/// hands the `Reloadable` back instead. This is only available with the
+/// `autoreload` feature enabled.
+///
+/// [`Reloadable`]: struct.Reloadable.html
+/// [`Reloadable::spawn_autoreload`]: struct.Reloadable.html#method.spawn_autoreload
+/// [`Builder::trigger`]: struct.Builder.html#method.trigger
+/// [`reload`]: struct.Reloadable.html#method.reload
+pub struct AutoReload<Host> {
+    app: Arc<Mutex<Reloadable<Host>>>,
+    stop: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl<Host: Send + 'static> Reloadable<Host> {
+    /// Move the `Reloadable` to a background thread that reloads the library
+    /// whenever it changes, instead of the host calling [`reload`][].
+    ///
+    /// `on_reload` is called on that thread after every reload, with the
+    /// `Reloadable` and the result, so it can look at the new library's
+    /// `Host` and `State`, or report a reload that failed. See
+    /// [`AutoReload`][] for how the two threads share the `Reloadable`.
+    /// This is for event-driven hosts that have no frame loop to call
+    /// `reload` from. It's only available with the `autoreload` feature
+    /// enabled.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use std::sync::Arc;
+    /// use std::sync::atomic::{AtomicBool, Ordering};
+    /// use std::sync::mpsc::channel;
+    /// use std::time::Duration;
+    /// use live_reload::Builder;
+    ///
+    /// let requested = Arc::new(AtomicBool::new(false));
+    /// let trigger = requested.clone();
+    /// let app = Builder::new("")
+    ///     .trigger(move || trigger.swap(false, Ordering::Relaxed))
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    ///
+    /// let (reloaded, reloads) = channel();
+    /// let auto = app.spawn_autoreload(move |app, result| {
+    ///     result.unwrap();
+    ///     reloaded.send(app.reload_count()).unwrap();
+    /// }).unwrap();
+    ///
+    /// auto.update();
+    /// requested.store(true, Ordering::Relaxed);
+    /// assert_eq!(reloads.recv_timeout(Duration::from_secs(10)), Ok(1));
+    /// auto.update();
+    ///
+    /// let app = auto.stop();
+    /// assert_eq!(app.reload_count(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`AutoReload`]: struct.AutoReload.html
+    pub fn spawn_autoreload<F>(self, mut on_reload: F) -> Result<AutoReload<Host>, Error>
+    where
+        F: FnMut(&mut Reloadable<Host>, Result<(), Error>) + Send + 'static,
+    {
+        let app = Arc::new(Mutex::new(self));
+        let stop = Arc::new(AtomicBool::new(false));
+        let worker = {
+            let app = app.clone();
+            let stop = stop.clone();
+            thread::Builder::new()
+                .name("live-reload-autoreload".into())
+                .spawn(move || {
+                    while !stop.load(Ordering::SeqCst) {
+                        // Take a fresh waiter each time, since rearming the
+                        // watcher replaces the queue.
+                        let waiter = lock(&app).event_waiter();
+                        waiter.wait(POLL);
+                        if stop.load(Ordering::SeqCst) {
+                            break;
+                        }
+                        let mut app = lock(&app);
+                        let before = app.reload_count();
+                        let result = app.reload();
+                        if result.is_err() || app.reload_count() != before {
+                            on_reload(&mut app, result);
+                        }
+                    }
+                })?
+        };
+        Ok(AutoReload {
+            app,
+            stop,
+            worker: Some(worker),
+        })
+    }
+}
+
+impl<Host> AutoReload<Host> {
+    /// Take the lock on the `Reloadable`, waiting for a reload in progress to
+    /// finish, and keeping the worker from reloading until the guard is
+    /// dropped.
+    pub fn lock(&self) -> MutexGuard<'_, Reloadable<Host>> {
+        lock(&self.app)
+    }
+
+    /// Call the library's `update`, like [`Reloadable::update`][], under the
+    /// lock.
+    ///
+    /// [`Reloadable::update`]: struct.Reloadable.html#method.update
+    pub fn update(&self) -> ShouldQuit {
+        self.lock().update()
+    }
+
+    /// Stop the worker thread, and take back the `Reloadable`.
+    ///
+    /// This waits for a reload in progress to finish, and for the worker to
+    /// notice, which takes at most about 100 ms.
+    pub fn stop(mut self) -> Reloadable<Host> {
+        self.join();
+        let app = self.app.clone();
+        drop(self);
+        match Arc::try_unwrap(app) {
+            Ok(app) => app.into_inner().unwrap_or_else(PoisonError::into_inner),
+            Err(_) => unreachable!("the worker has stopped, so nothing else holds the Reloadable"),
+        }
+    }
+
+    /// Tell the worker to stop, and wait for it.
+    fn join(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        if let Some(worker) = self.worker.take() {
+            // A panic in `on_reload` has already been reported, and leaves
+            // the `Reloadable` as usable as a panic in any other caller.
+            let _ = worker.join();
+        }
+    }
+}
+
+impl<Host> Drop for AutoReload<Host> {
+    fn drop(&mut self) {
+        self.join();
+    }
+}
+
+/// Lock `app`, even if a panic on the other thread poisoned it.
+fn lock<Host>(app: &Mutex<Reloadable<Host>>) -> MutexGuard<'_, Reloadable<Host>> {
+    app.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,75 @@
+//! Checks for a host's build script.
+//!
+//! A library built against another version of this crate, or for another
+//! `Host`, is refused when it's loaded, which is only noticed once the host
+//! runs. A build script that can see the library can catch it earlier, and
+//! fail the build instead.
+
+use std::path::Path;
+
+use internals::ABI_VERSION;
+use with_std::Reloadable;
+
+/// Check that the library at `path` can be loaded by a host whose `Host` has
+/// the fingerprint `expected_host_hash`, returning why not as a message for
+/// the build output.
+///
+/// This opens the library with [`Reloadable::inspect`][], and checks that it
+/// exports a `RELOAD_API`, with this crate's ABI version and all of its
+/// lifecycle functions, and that it was built for the same `Host`. Get the
+/// fingerprint from [`internals::host_hash`][], which means the `Host` has to
+/// be in a crate the build script can depend on too. A library that doesn't
+/// export a fingerprint passes that check, just as it's loaded by default.
+/// Opening the library runs its static initializers, and it has to be built
+/// for the machine the build script runs on, so this doesn't work when cross
+/// compiling.
+///
+/// ```rust,no_run
+/// # extern crate live_reload;
+/// # mod host_api { pub struct Host; }
+/// // build.rs
+/// fn main() {
+///     let library = "target/debug/libgame.so";
+///     println!("cargo:rerun-if-changed={}", library);
+///     let expected = live_reload::internals::host_hash::<host_api::Host>();
+///     if let Err(err) = live_reload::build::verify_library(library, expected) {
+///         panic!("{}", err);
+///     }
+/// }
+/// ```
+///
+/// [`Reloadable::inspect`]: ../struct.Reloadable.html#method.inspect
+/// [`internals::host_hash`]: ../internals/fn.host_hash.html
+pub fn verify_library<P: AsRef<Path>>(path: P, expected_host_hash: u64) -> Result<(), String> {
+    let path = path.as_ref();
+    let info = Reloadable::inspect(path)
+        .map_err(|err| format!("live-reload: {} can't be loaded: {}", path.display(), err))?;
+    if !info.modules().is_empty() {
+        return Err(format!(
+            "live-reload: {} exports modules with live_reload_multi!, not a RELOAD_API",
+            path.display(),
+        ));
+    }
+    match info.abi_version() {
+        Some(ABI_VERSION) => {}
+        found => {
+            return Err(format!(
+                "live-reload: {} was built with ABI version {}, but the host expects {}; \
+                 rebuild it against the same version of live-reload",
+                path.display(),
+                found.map_or("unknown".to_owned(), |found| found.to_string()),
+                ABI_VERSION,
+            ))
+        }
+    }
+    match info.host_hash() {
+        Some(found) if found != expected_host_hash => Err(format!(
+            "live-reload: {} was built for a different Host (fingerprint {:#018x}, expected {:#018x}); \
+             check that it uses the host's Host type",
+            path.display(),
+            found,
+            expected_host_hash,
+        )),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,84 @@
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::time::{Duration, Instant, SystemTime};
+
+use trigger::ReloadTrigger;
+use with_std::file_mtime;
+
+/// How long to wait after a build finishes before starting the next one.
+const REBUILD_DELAY: Duration = Duration::from_secs(1);
+
+/// The command set with `Builder::build_command`.
+pub(crate) struct BuildCommand {
+    pub(crate) program: OsString,
+    pub(crate) args: Vec<OsString>,
+    pub(crate) dir: Option<PathBuf>,
+}
+
+/// Runs the build command over and over in the background, and asks for a
+/// reload when a run succeeds and leaves a newer library behind.
+pub(crate) struct BuildTrigger {
+    command: Command,
+    library: PathBuf,
+    /// The build in progress, with the library's mtime when it started.
+    running: Option<(Child, Option<SystemTime>)>,
+    next_build: Instant,
+}
+
+impl BuildTrigger {
+    /// Start the first build straight away, so that a command that can't be
+    /// run at all is an error here, rather than going unnoticed.
+    pub(crate) fn new(build: BuildCommand, library: &Path) -> io::Result<Self> {
+        let mut command = Command::new(build.program);
+        command.args(build.args);
+        if let Some(dir) = build.dir {
+            command.current_dir(dir);
+        }
+        let before = file_mtime(library);
+        let child = command.spawn()?;
+        Ok(BuildTrigger {
+            command,
+            library: library.to_path_buf(),
+            running: Some((child, before)),
+            next_build: Instant::now(),
+        })
+    }
+}
+
+impl ReloadTrigger for BuildTrigger {
+    fn should_reload(&mut self) -> bool {
+        if let Some((mut child, before)) = self.running.take() {
+            let succeeded = match child.try_wait() {
+                Ok(None) => {
+                    self.running = Some((child, before));
+                    return false;
+                }
+                Ok(Some(status)) => status.success(),
+                Err(_) => false,
+            };
+            self.next_build = Instant::now() + REBUILD_DELAY;
+            return succeeded && file_mtime(&self.library) > before;
+        }
+        if Instant::now() >= self.next_build {
+            let before = file_mtime(&self.library);
+            match self.command.spawn() {
+                Ok(child) => self.running = Some((child, before)),
+                // It started before, so this is likely to pass; try again
+                // after the usual delay.
+                Err(_) => self.next_build = Instant::now() + REBUILD_DELAY,
+            }
+        }
+        false
+    }
+}
+
+impl Drop for BuildTrigger {
+    fn drop(&mut self) {
+        if let Some((ref mut child, _)) = self.running {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
@@ -0,0 +1,1958 @@
+use std::env;
+use std::ffi::{OsStr, OsString};
+use std::io;
+use std::marker::PhantomData;
+use std::time::Duration;
+use std::path::{Path, PathBuf};
+
+use ::notify::{self, RecursiveMode};
+
+use super::{Error, HostCheckMode, LibraryInfo, LifecyclePhase, PhaseInfo, Profile, Reloadable, ReloadTrigger, ShrinkAction};
+#[cfg(feature = "in-process")]
+use super::internals::ReloadApi;
+use events;
+use with_std::watched_dir;
+use build_command::BuildCommand;
+use temp_copy::CopyConfig;
+use open_flags::OpenFlags;
+use store::{ReservedStore, StateStore};
+#[cfg(feature = "verify")]
+use verify::Verifier;
+use watchdog::OnHang;
+
+/// Wraps each phase of work, and runs it by calling the closure it's given.
+pub(crate) type Instrument = fn(&PhaseInfo, &mut dyn FnMut() -> bool);
+
+/// The largest `State` a library may report by default, 256 MiB.
+const DEFAULT_MAX_STATE_SIZE: usize = 256 << 20;
+
+/// The options a `Reloadable` was built with.
+pub(crate) struct Config<Host> {
+    /// Where to copy the library before loading it, if it should be copied.
+    pub(crate) copy: Option<CopyConfig>,
+    /// The directory that a relative library path is resolved against.
+    pub(crate) base_dir: Option<PathBuf>,
+    /// Whether to resolve relative paths against the executable's directory,
+    /// rather than the working directory.
+    pub(crate) relative_to_exe: bool,
+    /// Whether to watch subdirectories of the library's directory too.
+    pub(crate) recursive_watch: bool,
+    /// How long the watcher waits for writes to settle before reporting them.
+    pub(crate) debounce: Duration,
+    /// The most watcher events each call to `reload` handles.
+    pub(crate) max_events_per_reload: usize,
+    /// Extensions of files that are never the library, like debug symbols.
+    pub(crate) ignored_extensions: Vec<String>,
+    /// Decides what to do when a reload shrinks the `State`.
+    pub(crate) on_state_shrink: Option<fn(&mut Host, usize, usize) -> ShrinkAction>,
+    /// Hears about the `State` changing size without a new state version.
+    pub(crate) on_unexpected_size_change: Option<fn(&mut Host, usize, usize)>,
+    /// How many more times to try loading a library that fails to load.
+    pub(crate) reload_retries: u32,
+    /// How long to wait before each retry.
+    pub(crate) retry_delay: Duration,
+    /// Whether to put off `init` until the first update.
+    pub(crate) lazy_init: bool,
+    /// Whether to call `reload` right after `init`.
+    pub(crate) reload_on_first_load: bool,
+    /// Whether `update_until_quit` stops on Ctrl-C.
+    pub(crate) handle_interrupts: bool,
+    /// Whether `reload` only notes changes, for `apply_deferred_reload`.
+    pub(crate) defer_reloads: bool,
+    /// Whether to ignore changes that leave the library's mtime the same.
+    pub(crate) require_mtime_change: bool,
+    /// How long after a reload further changes are held back.
+    pub(crate) reload_cooldown: Duration,
+    /// How long a call into the library may take, and who to tell if one
+    /// takes longer.
+    pub(crate) watchdog: Option<(Duration, OnHang)>,
+    /// Whether to tell the observer the state's checksum around each reload.
+    pub(crate) trace_state_checksums: bool,
+    /// How many entries the reload log keeps, or 0 to keep none.
+    pub(crate) reload_log_capacity: usize,
+    /// Gets the final say on whether a detected change is reloaded.
+    pub(crate) before_reload: Option<fn(&LibraryInfo) -> bool>,
+    /// Wraps reloads and updates, for profiling.
+    pub(crate) instrument: Option<Instrument>,
+    /// Where to keep the `State`, if not in the default `Vec<u64>`.
+    pub(crate) store: Option<Box<dyn StateStore + Send>>,
+    /// The tag the library's build id must have.
+    pub(crate) build_tag: &'static str,
+    /// Whether to refuse libraries with a different build id.
+    pub(crate) check_build_id: bool,
+    /// Whether to refuse libraries with a different panic strategy.
+    pub(crate) check_panic_strategy: bool,
+    /// The profile the library must have been built with, if any.
+    pub(crate) required_profile: Option<Profile>,
+    /// How to check the fingerprint of the library's `Host`.
+    pub(crate) host_check: HostCheckMode,
+    /// The largest `State` in bytes a library may report.
+    pub(crate) max_state_size: usize,
+    /// The capacity of the store set with `reserve_state`, if it's in use.
+    pub(crate) reserved_state: Option<usize>,
+    /// Hears about errors from the watcher, instead of `reload` failing.
+    pub(crate) on_watch_error: Option<fn(&notify::Error)>,
+    /// How the dynamic loader should open the library.
+    pub(crate) open_flags: OpenFlags,
+    /// The names to look for the `ReloadApi` under, in order.
+    pub(crate) api_symbols: Vec<String>,
+    /// How many replaced versions of the library to keep loaded.
+    pub(crate) retain_old_images: usize,
+    /// Libraries loaded before the library, in order, and reloaded with it.
+    pub(crate) companions: Vec<PathBuf>,
+    /// Whether to refuse libraries that failed to load until they change.
+    pub(crate) quarantine_bad_libraries: bool,
+    /// Whether to call the functions in `debugger` around reloads.
+    pub(crate) debugger_friendly: bool,
+    /// How many failed reloads within how long stop reloading, if any.
+    pub(crate) reload_loop: Option<(u32, Duration)>,
+    /// Decides which changed files are the library, instead of comparing
+    /// their paths with its own.
+    pub(crate) event_filter: Option<EventFilter>,
+    /// Maps the watched path to the library file to open, if they differ.
+    pub(crate) resolve_path: Option<PathResolver>,
+    /// Decides when to reload, in place of the filesystem watcher.
+    pub(crate) trigger: Option<Box<dyn ReloadTrigger>>,
+    /// Builds the library in the background, in place of the watcher.
+    pub(crate) build_command: Option<BuildCommand>,
+    /// Leave out the watcher, so only `reload_now` reloads.
+    pub(crate) headless: bool,
+    /// Checks each version of the library before it's opened.
+    #[cfg(feature = "verify")]
+    pub(crate) verifier: Option<Verifier>,
+}
+
+/// Decides whether a changed file is the library.
+pub(crate) type EventFilter = Box<dyn Fn(&Path) -> bool + Send + Sync>;
+
+/// Maps the watched path to the library file to open.
+pub(crate) type PathResolver = Box<dyn Fn(&Path) -> PathBuf + Send + Sync>;
+
+impl<Host> Default for Config<Host> {
+    fn default() -> Self {
+        Config {
+            copy: if cfg!(windows) { Some(CopyConfig::default()) } else { None },
+            base_dir: None,
+            relative_to_exe: false,
+            recursive_watch: false,
+            debounce: Duration::from_secs(1),
+            max_events_per_reload: 256,
+            ignored_extensions: events::SIDECAR_EXTENSIONS.iter().map(|&ext| ext.to_owned()).collect(),
+            on_state_shrink: None,
+            on_unexpected_size_change: None,
+            reload_retries: 0,
+            retry_delay: Duration::from_millis(0),
+            lazy_init: false,
+            reload_on_first_load: false,
+            handle_interrupts: false,
+            defer_reloads: false,
+            require_mtime_change: false,
+            reload_cooldown: Duration::from_secs(0),
+            watchdog: None,
+            trace_state_checksums: false,
+            reload_log_capacity: 0,
+            before_reload: None,
+            instrument: None,
+            store: None,
+            build_tag: "",
+            check_build_id: true,
+            check_panic_strategy: true,
+            required_profile: None,
+            host_check: HostCheckMode::default(),
+            max_state_size: DEFAULT_MAX_STATE_SIZE,
+            reserved_state: None,
+            on_watch_error: None,
+            open_flags: OpenFlags::default(),
+            api_symbols: vec!["RELOAD_API".to_owned()],
+            retain_old_images: 0,
+            companions: Vec::new(),
+            quarantine_bad_libraries: false,
+            debugger_friendly: false,
+            reload_loop: None,
+            event_filter: None,
+            resolve_path: None,
+            trigger: None,
+            headless: false,
+            build_command: None,
+            #[cfg(feature = "verify")]
+            verifier: None,
+        }
+    }
+}
+
+impl<Host> Config<Host> {
+    /// Turn the library path into an absolute one, so that neither the
+    /// platform's library search path nor later changes to the working
+    /// directory affect which file gets loaded and watched.
+    pub(crate) fn resolve(&self, path: &Path) -> io::Result<PathBuf> {
+        let start = if self.relative_to_exe {
+            let exe = env::current_exe()?;
+            match exe.parent() {
+                Some(dir) => dir.to_path_buf(),
+                None => return Err(io::Error::new(io::ErrorKind::NotFound, "the executable has no directory")),
+            }
+        } else {
+            env::current_dir()?
+        };
+        let base = match self.base_dir {
+            Some(ref dir) => start.join(dir),
+            None => start,
+        };
+        Ok(base.join(path))
+    }
+
+    /// Resolve the paths of the companion libraries like the library's own.
+    pub(crate) fn resolve_companions(&mut self) -> io::Result<()> {
+        let mut resolved = Vec::with_capacity(self.companions.len());
+        for companion in &self.companions {
+            resolved.push(self.resolve(companion)?);
+        }
+        self.companions = resolved;
+        Ok(())
+    }
+
+    /// The library at `path` and then its companion libraries, which are
+    /// all reloaded as a group.
+    pub(crate) fn group<'a>(&'a self, path: &'a Path) -> Vec<&'a Path> {
+        let mut group = vec![path];
+        group.extend(self.companions.iter().map(PathBuf::as_path));
+        group
+    }
+
+    /// The directories to watch for the library at `path`: its own, and
+    /// those of any companion libraries that live elsewhere.
+    pub(crate) fn watched_dirs<'a>(&'a self, path: &'a Path) -> Vec<&'a Path> {
+        let mut dirs = vec![watched_dir(path)];
+        for companion in &self.companions {
+            let dir = watched_dir(companion);
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        dirs
+    }
+
+    /// The library file to open for the watched path `path`, as mapped by
+    /// `resolve_path`.
+    pub(crate) fn library_file(&self, path: &Path) -> PathBuf {
+        match self.resolve_path {
+            Some(ref resolve) => resolve(path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    pub(crate) fn recursive_mode(&self) -> RecursiveMode {
+        if self.recursive_watch {
+            RecursiveMode::Recursive
+        } else {
+            RecursiveMode::NonRecursive
+        }
+    }
+}
+
+/// Configures and creates a [`Reloadable`][].
+///
+/// [`Reloadable::new`][] uses the default options, which are the right choice
+/// most of the time. A `Builder` lets you adjust them first:
+///
+/// ```rust,no_run
+/// # extern crate live_reload;
+/// # fn main() {
+/// # struct Host;
+/// use live_reload::Builder;
+///
+/// let app = Builder::new("target/debug/libreload.so")
+///     .copy_to("target/debug/shadow".into())
+///     .build(Host)
+///     .expect("Should successfully load");
+/// # }
+/// ```
+///
+/// [`Reloadable`]: struct.Reloadable.html
+/// [`Reloadable::new`]: struct.Reloadable.html#method.new
+pub struct Builder<Host> {
+    path: PathBuf,
+    config: Config<Host>,
+    _host: PhantomData<fn(Host)>,
+}
+
+impl<Host> Builder<Host> {
+    /// Start building a `Reloadable` for the library at `path`.
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        Builder {
+            path: path.as_ref().to_path_buf(),
+            config: Config::default(),
+            _host: PhantomData,
+        }
+    }
+
+    /// Load a temporary copy of the library instead of the library itself.
+    ///
+    /// Windows won't let a build overwrite a library that is loaded, so this
+    /// is on by default there, and off everywhere else. The copy is removed
+    /// when it is unloaded. Copies left behind by host processes that are no
+    /// longer running (say, ones that crashed) are cleaned up when the
+    /// `Reloadable` is built; see [`TEMP_COPY_PREFIX`][] for how copies are
+    /// named.
+    ///
+    /// [`TEMP_COPY_PREFIX`]: constant.TEMP_COPY_PREFIX.html
+    pub fn copy_library(mut self, copy: bool) -> Self {
+        self.config.copy = if copy {
+            Some(self.config.copy.unwrap_or_default())
+        } else {
+            None
+        };
+        self
+    }
+
+    /// Put the temporary copies of the library in `dir`.
+    ///
+    /// This defaults to `std::env::temp_dir()`, which might not be writable on
+    /// locked-down systems, or you might prefer to keep the copies next to the
+    /// library. This turns on [`copy_library`][].
+    ///
+    /// [`copy_library`]: #method.copy_library
+    pub fn copy_to(mut self, dir: PathBuf) -> Self {
+        let mut copy = self.config.copy.unwrap_or_default();
+        copy.dir = dir;
+        self.config.copy = Some(copy);
+        self
+    }
+
+    /// Choose the name of the temporary copies of the library.
+    ///
+    /// `name` is given the path of the library, and returns the file name to
+    /// use for its copy. The crate prefixes this with the host's process id and
+    /// a counter, so that concurrent hosts and successive reloads never collide.
+    /// This turns on [`copy_library`][].
+    ///
+    /// [`copy_library`]: #method.copy_library
+    pub fn copy_name(mut self, name: fn(&Path) -> PathBuf) -> Self {
+        let mut copy = self.config.copy.unwrap_or_default();
+        copy.name = name;
+        self.config.copy = Some(copy);
+        self
+    }
+
+    /// Resolve a relative library path against `dir`.
+    ///
+    /// By default, a relative path is resolved against the working directory
+    /// at the time the `Reloadable` is built. That's rarely what you want when
+    /// the host is itself a plugin loaded by some other program, since that
+    /// program decides the working directory. Either way, the path is made
+    /// absolute once up front, so changing the working directory later
+    /// doesn't affect reloads.
+    pub fn base_dir(mut self, dir: PathBuf) -> Self {
+        self.config.base_dir = Some(dir);
+        self
+    }
+
+    /// Resolve a relative library path against the directory of the host's
+    /// executable, rather than the working directory.
+    ///
+    /// A shipped build can be launched from anywhere, but its library sits
+    /// in a known place next to the executable, so this finds it however the
+    /// host was started. A relative [`base_dir`](#method.base_dir) is
+    /// resolved against the executable's directory too. The executable's
+    /// path comes from `std::env::current_exe`, which has already followed
+    /// symlinks on some platforms, but not on others.
+    ///
+    /// Only the library's path is affected. The temporary copies made with
+    /// [`copy_library`][] still go to `std::env::temp_dir()`, or to the
+    /// directory given to [`copy_to`][], which is used as it is, so a
+    /// relative one there is still relative to the working directory.
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::Builder;
+    ///
+    /// // Finds `libgame.so` next to the executable, wherever it's run from.
+    /// let app = Builder::new("libgame.so").relative_to_exe(true).build(Host);
+    /// # }
+    /// ```
+    ///
+    /// [`copy_library`]: #method.copy_library
+    /// [`copy_to`]: #method.copy_to
+    pub fn relative_to_exe(mut self, relative: bool) -> Self {
+        self.config.relative_to_exe = relative;
+        self
+    }
+
+    /// Watch the subdirectories of the library's directory as well.
+    ///
+    /// Some build tools write the library into a fresh, nested directory, and
+    /// then point a stable symlink at it. Only the directory holding the
+    /// symlink is watched by default, so writes to the real file are missed.
+    /// With recursive watching, the whole tree below it is watched, and a
+    /// change to whatever file the symlink currently leads to triggers a
+    /// reload. Reloads always go through the symlink, so they pick up the
+    /// new target.
+    ///
+    /// This has a cost: every subdirectory gets its own watch, which is slow
+    /// to set up on large trees (like a whole `target` directory), can exhaust
+    /// the system's limit on watches, and wakes the watcher for every file
+    /// written anywhere in the tree.
+    pub fn recursive_watch(mut self, recursive: bool) -> Self {
+        self.config.recursive_watch = recursive;
+        self
+    }
+
+    /// Watch the library's directory with `mode`.
+    ///
+    /// This is the same as [`recursive_watch`](#method.recursive_watch),
+    /// for when the mode is already at hand as a `notify::RecursiveMode`.
+    pub fn recursive_mode(self, mode: RecursiveMode) -> Self {
+        self.recursive_watch(mode == RecursiveMode::Recursive)
+    }
+
+    /// Have the watcher wait `debounce` for writes to the library to settle
+    /// before reporting a change. The default is one second.
+    ///
+    /// A shorter debounce picks up changes sooner, but a build that writes
+    /// the library several times over can then trigger a reload part way
+    /// through. This can be changed later with
+    /// [`Reloadable::set_debounce`][].
+    ///
+    /// [`Reloadable::set_debounce`]: struct.Reloadable.html#method.set_debounce
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.config.debounce = debounce;
+        self
+    }
+
+    /// Handle at most `count` of the watcher's events in each call to
+    /// [`reload`][]. The default is 256.
+    ///
+    /// Every file written in the library's directory queues an event, so a
+    /// directory that's constantly busy, say with a log file, could keep
+    /// `reload` handling events for as long as they keep coming. With a
+    /// limit, the events past it wait in the queue for the next call, so
+    /// each frame does a bounded amount of work, at the cost of noticing a
+    /// change to the library a few calls later under such a flood. A `count`
+    /// of zero is taken as one.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # if cfg!(miri) { return; } // Miri can't watch files.
+    /// use std::fs;
+    /// use std::thread;
+    /// use std::time::{Duration, Instant};
+    /// use live_reload::Builder;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("live-reload-event-flood-{}", std::process::id()));
+    /// fs::create_dir_all(&dir).unwrap();
+    /// // The library isn't there yet, so this waits for it, watching `dir`.
+    /// let mut app = Builder::new(dir.join("libgame.so"))
+    ///     .debounce(Duration::from_millis(10))
+    ///     .max_events_per_reload(4)
+    ///     .build(())
+    ///     .unwrap();
+    ///
+    /// for i in 0..32 {
+    ///     fs::write(dir.join(format!("log-{}.txt", i)), "noise").unwrap();
+    /// }
+    /// let deadline = Instant::now() + Duration::from_secs(10);
+    /// while app.pending_event_count() < 16 && Instant::now() < deadline {
+    ///     thread::sleep(Duration::from_millis(10));
+    /// }
+    /// let before = app.pending_event_count();
+    /// assert!(before >= 16);
+    /// app.reload().unwrap();
+    /// // More events may have come in meanwhile, but no more than 4 went.
+    /// assert!(app.pending_event_count() + 4 >= before);
+    /// # drop(app);
+    /// # fs::remove_dir_all(&dir).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    pub fn max_events_per_reload(mut self, count: usize) -> Self {
+        self.config.max_events_per_reload = count.max(1);
+        self
+    }
+
+    /// Reload when `trigger` says so, instead of when the library's file
+    /// changes.
+    ///
+    /// No filesystem watcher is set up, so the options for it, like
+    /// [`debounce`](#method.debounce), don't apply, and
+    /// [`Reloadable::reload_blocking_until_changed`][] returns straight away.
+    /// Each [`Reloadable::reload`][] asks `trigger` instead. See
+    /// [`ReloadTrigger`][] for more.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host;
+    /// #[repr(C)] pub struct State;
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    /// fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::Builder;
+    ///
+    /// let mut commands = vec!["reload", "", ""].into_iter();
+    /// let mut app = Builder::new("")
+    ///     .trigger(move || commands.next() == Some("reload"))
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// for _ in 0..3 {
+    ///     app.reload().unwrap();
+    /// }
+    /// assert_eq!(app.reload_count(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`Reloadable::reload_blocking_until_changed`]: struct.Reloadable.html#method.reload_blocking_until_changed
+    /// [`Reloadable::reload`]: struct.Reloadable.html#method.reload
+    /// [`ReloadTrigger`]: trait.ReloadTrigger.html
+    pub fn trigger<T: ReloadTrigger + 'static>(mut self, trigger: T) -> Self {
+        self.config.trigger = Some(Box::new(trigger));
+        self
+    }
+
+    /// Don't watch the library at all, if `headless` is true.
+    ///
+    /// No filesystem watcher is made, so there's no background thread and
+    /// nothing happening behind the host's back, which is what a benchmark
+    /// or a reproducible test wants. [`Reloadable::reload`][] never finds a
+    /// change, and [`Reloadable::reload_now`][] is the only way to reload,
+    /// including to load a library that wasn't there when this was built.
+    /// This is what [`Reloadable::new_headless`][] uses.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # if cfg!(miri) { return; }
+    /// use live_reload::Builder;
+    ///
+    /// let dir = std::env::temp_dir().join("live-reload-headless-doctest");
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// let mut app = Builder::new(dir.join("libgame.so")).headless(true).build(()).unwrap();
+    /// assert!(!app.is_watching());
+    /// app.reload().unwrap();
+    /// assert!(!app.is_loaded());
+    /// # }
+    /// ```
+    ///
+    /// [`Reloadable::reload`]: struct.Reloadable.html#method.reload
+    /// [`Reloadable::reload_now`]: struct.Reloadable.html#method.reload_now
+    /// [`Reloadable::new_headless`]: struct.Reloadable.html#method.new_headless
+    pub fn headless(mut self, headless: bool) -> Self {
+        self.config.headless = headless;
+        self
+    }
+
+    /// Build the library with `program` and `args` in the background, and
+    /// reload whenever a build succeeds, instead of watching the library's
+    /// file.
+    ///
+    /// Watching the file can catch a build halfway through writing it, and
+    /// reloads on every write, even one from a build that then fails. With
+    /// a build command, [`Reloadable::reload`][] starts the command if it
+    /// isn't running, and once it exits successfully, and only if the library
+    /// is newer than it was when that build started, counts that as a
+    /// change. So a reload never waits for the build, but a library is only
+    /// ever swapped in after its build has finished. A second after each
+    /// build, the next one starts, which for `cargo build` costs little when
+    /// nothing changed.
+    ///
+    /// The command inherits the host's working directory, unless
+    /// [`build_command_dir`](#method.build_command_dir) says otherwise, and
+    /// its stdout and stderr, so build errors show up as usual. The first
+    /// build starts right away, so a command that can't be started at all
+    /// fails [`build`](#method.build) with [`Error::Io`][]. A later one that
+    /// can't be started is tried again a second later. A build still running
+    /// when the `Reloadable` is dropped is killed. This takes the place of
+    /// any [`trigger`](#method.trigger).
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::{Builder, Error};
+    ///
+    /// let built = Builder::new(std::env::temp_dir().join("libgame.so"))
+    ///     .build_command("no-such-build-tool", &[] as &[&str])
+    ///     .build(Host);
+    /// assert!(matches!(built, Err(Error::Io(_))));
+    /// # }
+    /// ```
+    ///
+    /// A host's loop then only has to reload and update:
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::Builder;
+    ///
+    /// let mut app = Builder::new("target/debug/libgame.so")
+    ///     .build_command("cargo", &["build", "-p", "game"])
+    ///     .build(Host)
+    ///     .unwrap();
+    /// loop {
+    ///     app.reload().unwrap();
+    ///     app.update();
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`Reloadable::reload`]: struct.Reloadable.html#method.reload
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    pub fn build_command<P, I, S>(mut self, program: P, args: I) -> Self
+    where
+        P: AsRef<OsStr>,
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let dir = self.config.build_command.take().and_then(|build| build.dir);
+        self.config.build_command = Some(BuildCommand {
+            program: program.as_ref().to_owned(),
+            args: args.into_iter().map(|arg| arg.as_ref().to_owned()).collect::<Vec<OsString>>(),
+            dir,
+        });
+        self
+    }
+
+    /// Run the command set with [`build_command`](#method.build_command) in
+    /// `dir`, rather than the host's working directory.
+    ///
+    /// This does nothing unless `build_command` is called first.
+    pub fn build_command_dir<P: Into<PathBuf>>(mut self, dir: P) -> Self {
+        if let Some(ref mut build) = self.config.build_command {
+            build.dir = Some(dir.into());
+        }
+        self
+    }
+
+    /// Refuse to open the library unless `check` accepts it, given the
+    /// library's bytes and the detached signature in the file at `signature`.
+    ///
+    /// Both files are read again for every version of the library, on
+    /// [`build`](#method.build) and on each reload, so a new build has to come
+    /// with a new signature. A version that fails the check isn't opened, and
+    /// its load fails with [`Error::SignatureInvalid`][]; a signature file
+    /// that can't be read fails it with [`Error::Io`][].
+    ///
+    /// `check` is where the cryptography goes, so any signature scheme and
+    /// crate will do, with the public key captured by the closure. When the
+    /// library is copied before it's loaded (see [`copy_library`][]), it's the
+    /// copy that's checked, so the bytes checked are the bytes loaded.
+    /// Otherwise, something able to write to the library between the check and
+    /// the load could slip in other code. This is only available with the
+    /// `verify` feature enabled.
+    ///
+    /// ```rust
+    /// # if cfg!(miri) { return; } // Miri can't open files.
+    /// use std::fs;
+    /// use live_reload::{Builder, Error};
+    ///
+    /// # fn ed25519_verify(_key: &[u8; 32], _message: &[u8], _signature: &[u8]) -> bool { false }
+    /// let public_key = [0u8; 32];
+    ///
+    /// let dir = std::env::temp_dir().join("live-reload-verify-signature-doctest");
+    /// fs::create_dir_all(&dir).unwrap();
+    /// fs::write(dir.join("libplugin.so"), b"tampered").unwrap();
+    /// fs::write(dir.join("libplugin.so.sig"), [0u8; 64]).unwrap();
+    ///
+    /// let result = Builder::new(dir.join("libplugin.so"))
+    ///     .verify_signature(dir.join("libplugin.so.sig"), move |library, signature| {
+    ///         ed25519_verify(&public_key, library, signature)
+    ///     })
+    ///     .build(());
+    /// assert!(matches!(result, Err(Error::SignatureInvalid)));
+    /// # fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    ///
+    /// [`Error::SignatureInvalid`]: enum.Error.html#variant.SignatureInvalid
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    /// [`copy_library`]: #method.copy_library
+    #[cfg(feature = "verify")]
+    pub fn verify_signature<P, F>(mut self, signature: P, check: F) -> Self
+    where
+        P: Into<PathBuf>,
+        F: Fn(&[u8], &[u8]) -> bool + Send + Sync + 'static,
+    {
+        self.config.verifier = Some(Verifier {
+            signature: signature.into(),
+            check: Box::new(check),
+        });
+        self
+    }
+
+    /// Decide which changed files count as the library with `filter`.
+    ///
+    /// By default, a change only triggers a reload if the changed file is the
+    /// library, or the file its path resolves to through a symlink. Builds
+    /// that write the library somewhere else first, and then move or link it
+    /// into place, can call for a different test. `filter` replaces the
+    /// default one: it's given the path of each file the watcher says was
+    /// written or created, and returns whether that means the library
+    /// changed. Files with an [ignored extension][] are still skipped before
+    /// `filter` sees them, and only the library's directory (or its tree,
+    /// with [`recursive_watch`][]) is watched.
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::Builder;
+    ///
+    /// // The build finishes by touching a stamp file next to the library.
+    /// let app = Builder::new("target/debug/libgame.so")
+    ///     .event_filter(|changed| changed.file_name().is_some_and(|name| name == "build.stamp"))
+    ///     .build(Host);
+    /// # }
+    /// ```
+    ///
+    /// [ignored extension]: #method.ignore_extension
+    /// [`recursive_watch`]: #method.recursive_watch
+    pub fn event_filter<F>(mut self, filter: F) -> Self
+    where
+        F: Fn(&Path) -> bool + Send + Sync + 'static,
+    {
+        self.config.event_filter = Some(Box::new(filter));
+        self
+    }
+
+    /// Open the library at whatever path `resolve` maps the watched path to.
+    ///
+    /// Some builds give every artifact its own name, like
+    /// `libgame-3f2a9c.so`, and then update a marker file that says which one
+    /// is the latest. Build such a `Reloadable` with the marker's path: that's
+    /// what's watched, so writing the marker triggers the reload. Each time
+    /// the library is loaded, the first time and on every reload, `resolve`
+    /// is called with the watched path, and the file it returns is the one
+    /// that's opened. It's only called for the watched library, not for
+    /// [`Reloadable::reload_from`][] or the [companions][].
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::Builder;
+    ///
+    /// // The build writes the artifact's file name into `latest`.
+    /// let app = Builder::new("target/debug/latest")
+    ///     .resolve_path(|marker| {
+    ///         let name = std::fs::read_to_string(marker).unwrap_or_default();
+    ///         marker.with_file_name(name.trim())
+    ///     })
+    ///     .build(Host);
+    /// # }
+    /// ```
+    ///
+    /// [`Reloadable::reload_from`]: struct.Reloadable.html#method.reload_from
+    /// [companions]: #method.companion_library
+    pub fn resolve_path<F>(mut self, resolve: F) -> Self
+    where
+        F: Fn(&Path) -> PathBuf + Send + Sync + 'static,
+    {
+        self.config.resolve_path = Some(Box::new(resolve));
+        self
+    }
+
+    /// Ignore changes to files with the extension `ext`.
+    ///
+    /// Compilers write other files next to the library, like debug symbols
+    /// and import libraries, and the watcher reports every one of them. Only
+    /// a change to the library itself triggers a reload, but working that out
+    /// can mean resolving symlinks, so files that can never be the library
+    /// are skipped by extension first. By default, these are `pdb`, `ilk`,
+    /// `exp` and `lib` (MSVC), `dSYM` (macOS debug symbol bundles, including
+    /// the files inside them), and `d` (Cargo's dependency info). Add your own
+    /// build's artifacts with this, without the leading dot.
+    pub fn ignore_extension(mut self, ext: &str) -> Self {
+        self.config.ignored_extensions.push(ext.to_owned());
+        self
+    }
+
+    /// Open the library with `flags`, such as to make its symbols visible to
+    /// libraries loaded after it.
+    ///
+    /// See [`OpenFlags`][] for which flags are safe to use with reloading.
+    ///
+    /// [`OpenFlags`]: struct.OpenFlags.html
+    pub fn open_flags(mut self, flags: OpenFlags) -> Self {
+        self.config.open_flags = flags;
+        self
+    }
+
+    /// Look for the library's `ReloadApi` under each of `names` in turn,
+    /// using the first one it exports, instead of only `RELOAD_API`.
+    ///
+    /// A library exports its `ReloadApi` under another name with the
+    /// `symbol:` entry of [`live_reload!`][], so that a host can go on
+    /// loading libraries of an older generation while newer ones roll out,
+    /// by listing the newest name first:
+    ///
+    /// ```rust,no_run
+    /// # use live_reload::Builder;
+    /// # pub struct Host;
+    /// let app = Builder::new("target/debug/libgame.so")
+    ///     .symbol_versions(&["RELOAD_API_V2", "RELOAD_API"])
+    ///     .build(Host)?;
+    /// println!("loaded {:?}", app.library_info().unwrap().api_symbol());
+    /// # Ok::<(), live_reload::Error>(())
+    /// ```
+    ///
+    /// Loading fails with [`Error::MissingSymbol`][] if the library exports
+    /// none of them. Whichever name is found, the `ReloadApi` behind it is
+    /// checked as usual, so the `Host` and `State` still have to match.
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`Error::MissingSymbol`]: enum.Error.html#variant.MissingSymbol
+    pub fn symbol_versions(mut self, names: &[&str]) -> Self {
+        self.config.api_symbols = names.iter().map(|&name| name.to_owned()).collect();
+        self
+    }
+
+    /// Keep up to `count` replaced versions of the library loaded after a
+    /// reload, rather than unloading them straight away.
+    ///
+    /// Function pointers (and `&'static` data) that came from the library
+    /// dangle as soon as it's unloaded, so a host that hands them out to
+    /// long-lived callbacks can crash right after a reload. With this, the
+    /// previous versions stay loaded until `count` newer ones have replaced
+    /// them, or until [`Reloadable::release_old_images`][], which gives
+    /// in-flight pointers time to be dropped or looked up again.
+    ///
+    /// This only puts the problem off: a pointer that outlives the retained
+    /// versions still dangles. Each version kept also keeps all of its code
+    /// and statics in memory, and its temporary copy on disk, and anything
+    /// its statics own isn't freed until it's released.
+    ///
+    /// The dynamic loader hands back an image that's already loaded when the
+    /// same path is opened again, so each version has to be loaded from its
+    /// own copy. A `count` above zero turns on [`copy_library`][].
+    ///
+    /// [`Reloadable::release_old_images`]: struct.Reloadable.html#method.release_old_images
+    /// [`copy_library`]: #method.copy_library
+    pub fn retain_old_images(mut self, count: usize) -> Self {
+        self.config.retain_old_images = count;
+        if count > 0 {
+            self.config.copy = Some(self.config.copy.unwrap_or_default());
+        }
+        self
+    }
+
+    /// Load another library along with the library, and reload the two
+    /// together.
+    ///
+    /// Some builds split the code across a main library and one or more
+    /// companion libraries that it links against, which all have to be
+    /// swapped at once. Each call adds one more companion at `path`, which
+    /// is resolved like the library's own path. The library still provides
+    /// the `ReloadApi` and the one `State`: the companions are only opened,
+    /// and never called into directly.
+    ///
+    /// A change to any of them reloads the whole group, in a fixed order:
+    ///
+    /// 1. `unload` is called on the library, as for any reload.
+    /// 2. The library is closed, and then the companions, in the reverse of
+    ///    the order they were added.
+    /// 3. The companions are opened in the order they were added, and then
+    ///    the library, so each one is loaded after those it depends on.
+    /// 4. `reload` is called on the new version of the library.
+    ///
+    /// If any of them fails to load, the ones opened so far are closed again
+    /// and the reload fails, the same as when the library itself fails. The
+    /// companions are opened with the same [`open_flags`][], and copied like
+    /// the library with [`copy_library`][]. Versions kept by
+    /// [`retain_old_images`][] keep their companions open with them. A
+    /// companion in another directory is watched there too, and anything
+    /// else that looks for a change to the library looks at the whole group:
+    /// the modification times for [`require_mtime_change`][] and
+    /// [`resume_watching`][], and the contents hashed for
+    /// [`quarantine_bad_libraries`][] and [`detect_reload_loops`][].
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::Builder;
+    ///
+    /// let app = Builder::new("target/debug/libgame.so")
+    ///     .companion_library("target/debug/libgame_physics.so")
+    ///     .companion_library("target/debug/libgame_render.so")
+    ///     .build(Host);
+    /// # }
+    /// ```
+    ///
+    /// [`open_flags`]: #method.open_flags
+    /// [`copy_library`]: #method.copy_library
+    /// [`retain_old_images`]: #method.retain_old_images
+    /// [`require_mtime_change`]: #method.require_mtime_change
+    /// [`resume_watching`]: struct.Reloadable.html#method.resume_watching
+    /// [`quarantine_bad_libraries`]: #method.quarantine_bad_libraries
+    /// [`detect_reload_loops`]: #method.detect_reload_loops
+    pub fn companion_library<P: Into<PathBuf>>(mut self, path: P) -> Self {
+        self.config.companions.push(path.into());
+        self
+    }
+
+    /// Make reloads easier to follow in a debugger.
+    ///
+    /// Stepping through the library and reloading it loses every breakpoint
+    /// set in it, and can pull the code out from under a frame the debugger
+    /// is looking at. With this on, the previous version of the library is
+    /// kept loaded, as if by [`retain_old_images`][] with at least 1, and
+    /// each reload calls the functions in the [`debugger`][] module, which a
+    /// debugger script can break on to set its breakpoints again in the new
+    /// version. Turning it off again stops the calls, but keeps the images.
+    ///
+    /// [`retain_old_images`]: #method.retain_old_images
+    /// [`debugger`]: debugger/index.html
+    pub fn debugger_friendly(mut self, on: bool) -> Self {
+        self.config.debugger_friendly = on;
+        if on {
+            let count = self.config.retain_old_images.max(1);
+            self = self.retain_old_images(count);
+        }
+        self
+    }
+
+    /// Decide what happens when a reload shrinks the `State`.
+    ///
+    /// When a newly loaded library reports a smaller `State` than the previous
+    /// one (say, because you removed a field), the bytes past the new end are
+    /// zeroed by default, so that stale data doesn't linger there if the
+    /// `State` grows again. If a hook is set, it's called with the host, the
+    /// old size, and the new size before `reload` runs, and can instead
+    /// [abort][] the reload to keep the state exactly as it was.
+    ///
+    /// [abort]: enum.ShrinkAction.html#variant.Abort
+    pub fn on_state_shrink(mut self, hook: fn(&mut Host, usize, usize) -> ShrinkAction) -> Self {
+        self.config.on_state_shrink = Some(hook);
+        self
+    }
+
+    /// Call `hook` when a reload changes the size of the `State` without
+    /// bumping its version.
+    ///
+    /// A `State` that changes size by accident, through a field behind a
+    /// `#[cfg]` or a type from a dependency that grew, gets its bytes
+    /// reinterpreted by the new version, and the damage only shows up later.
+    /// A deliberate change comes with a new `state_version` and migrations
+    /// (see [`live_reload!`][]), so a size change with the same version as
+    /// before is likely the accident. `hook` is called with the host, the
+    /// old size, and the new size, before `reload` runs, to warn about it.
+    /// For a library without migrations, the version is always 0, so every
+    /// size change is reported.
+    ///
+    /// This only warns: the reload goes ahead either way, and a shrinking
+    /// `State` is still handled by [`on_state_shrink`][] afterwards.
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::Builder;
+    ///
+    /// let app = Builder::new("target/debug/libgame.so")
+    ///     .on_unexpected_size_change(|_, old, new| {
+    ///         eprintln!("warning: State went from {} to {} bytes without a version bump", old, new);
+    ///     })
+    ///     .build(Host);
+    /// # }
+    /// ```
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`on_state_shrink`]: #method.on_state_shrink
+    pub fn on_unexpected_size_change(mut self, hook: fn(&mut Host, usize, usize)) -> Self {
+        self.config.on_unexpected_size_change = Some(hook);
+        self
+    }
+
+    /// Retry loading a changed library that fails to load.
+    ///
+    /// The watcher can notice a new build while the linker is still writing
+    /// it, and a half-written library fails to load. With this set,
+    /// [`reload_now`][] tries up to `count` more times, waiting `delay` before
+    /// each try, and only returns an error once every try has failed. (On
+    /// platforms that use ELF, a library that is shorter than its headers say
+    /// is refused without loading it, since loading it would crash.) Each
+    /// wait blocks the thread that's reloading. Libraries built against an
+    /// incompatible ABI are never retried, since waiting won't help them.
+    /// Neither is the first load, by [`build`](#method.build).
+    ///
+    /// Some linkers write the library to an anonymous file and link it into
+    /// place, which the watcher only sees as the file being created, and its
+    /// contents can still be landing when the reload starts. So with this
+    /// set, a reload also waits while the library is empty, and loads it
+    /// again `delay` later if its size changed while it was being loaded. A
+    /// library that's already complete is loaded without waiting. Each wait
+    /// counts as one of the `count` tries, and once they run out, the
+    /// library is kept whatever its size.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// use std::io::ErrorKind;
+    /// use std::time::Duration;
+    /// use live_reload::{Builder, Error};
+    ///
+    /// // Any ELF file cut short looks like a library that's still being linked.
+    /// # if cfg!(target_os = "linux") && !cfg!(miri) {
+    /// let exe = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    /// let path = std::env::temp_dir().join("live-reload-truncated-doctest.so");
+    /// std::fs::write(&path, &exe[..exe.len() / 2]).unwrap();
+    ///
+    /// let app = Builder::new(&path)
+    ///     .reload_retries(3, Duration::from_millis(50))
+    ///     .build(());
+    /// match app {
+    ///     Err(Error::Io(ref err)) => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+    ///     _ => panic!("a truncated library shouldn't load"),
+    /// }
+    /// # std::fs::remove_file(&path).unwrap();
+    /// # }
+    /// # }
+    /// ```
+    ///
+    /// A library that's still growing when it's created is waited for:
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// # if cfg!(target_os = "linux") && !cfg!(miri) {
+    /// use std::io::{ErrorKind, Write};
+    /// use std::thread;
+    /// use std::time::Duration;
+    /// use live_reload::{Builder, Error};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("live-reload-growing-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// let path = dir.join("libgrowing.so");
+    /// let mut file = std::fs::File::create(&path).unwrap();
+    /// let exe = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    /// let writer = thread::spawn(move || {
+    ///     for chunk in exe.chunks(exe.len() / 4 + 1) {
+    ///         file.write_all(chunk).unwrap();
+    ///         thread::sleep(Duration::from_millis(20));
+    ///     }
+    /// });
+    ///
+    /// let mut app = Builder::new("unused")
+    ///     .reload_retries(50, Duration::from_millis(100))
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// // An executable isn't a library, but it's no longer cut short either.
+    /// match app.reload_from(&path) {
+    ///     Err(Error::Io(ref err)) => assert_ne!(err.kind(), ErrorKind::UnexpectedEof),
+    ///     Err(Error::MissingSymbol { .. }) => {}
+    ///     _ => panic!("an executable shouldn't load"),
+    /// }
+    /// writer.join().unwrap();
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// # }
+    /// # }
+    /// ```
+    ///
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    pub fn reload_retries(mut self, count: u32, delay: Duration) -> Self {
+        self.config.reload_retries = count;
+        self.config.retry_delay = delay;
+        self
+    }
+
+    /// Stop reloading once `failures` reloads have failed within `window`.
+    ///
+    /// A library that can't load, or panics in `reload` every time, leaves
+    /// nothing loaded, so every [`reload`][] tries it again, and a busy host
+    /// loop can end up doing nothing else. With this set, the reload that
+    /// fails for the `failures`th time within `window` returns
+    /// [`Error::ReloadLoop`][] instead, and later calls to `reload` don't try
+    /// again until the library's contents change, or until
+    /// [`Reloadable::reset_reload_loop`][]. Only the failures of `reload`
+    /// count, not those of [`reload_now`][].
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    /// [`Error::ReloadLoop`]: enum.Error.html#variant.ReloadLoop
+    /// [`Reloadable::reset_reload_loop`]: struct.Reloadable.html#method.reset_reload_loop
+    pub fn detect_reload_loops(mut self, failures: u32, window: Duration) -> Self {
+        self.config.reload_loop = Some((failures, window));
+        self
+    }
+
+    /// Don't try a library file again once it has failed to load, until its
+    /// contents change.
+    ///
+    /// A build that's broken, say for a mismatched `Host`, or one whose
+    /// `validate_state` rejects the state, fails the same way every time,
+    /// so there's no point loading it again while it's unchanged. With this
+    /// on, the contents of a library that fails to load, or whose `reload`
+    /// panics or rejects the state, are hashed and remembered. A change that
+    /// [`Reloadable::reload`][] sees to a library with the same contents is
+    /// then quietly forgotten, while [`Reloadable::reload_now`][] fails
+    /// straight away with [`Error::QuarantinedLibrary`][]. A library that
+    /// loads clears the quarantine, as does
+    /// [`Reloadable::clear_quarantine`][]. This hashes the library file
+    /// before each reload, and only applies to libraries loaded from a
+    /// file.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # if cfg!(miri) { return; } // Miri can't watch files.
+    /// use live_reload::{Builder, Error};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("live-reload-quarantine-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// let path = dir.join("libbroken.so");
+    ///
+    /// let mut app = Builder::new(&path).quarantine_bad_libraries(true).build(()).unwrap();
+    /// std::fs::write(&path, [0x7f; 4096]).unwrap();
+    /// assert!(matches!(app.reload_now(), Err(Error::Io(_))));
+    /// assert!(matches!(app.reload_now(), Err(Error::QuarantinedLibrary { .. })));
+    ///
+    /// // A new build is tried again.
+    /// std::fs::write(&path, [0x7e; 4096]).unwrap();
+    /// assert!(matches!(app.reload_now(), Err(Error::Io(_))));
+    /// # drop(app);
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`Reloadable::reload`]: struct.Reloadable.html#method.reload
+    /// [`Reloadable::reload_now`]: struct.Reloadable.html#method.reload_now
+    /// [`Reloadable::clear_quarantine`]: struct.Reloadable.html#method.clear_quarantine
+    /// [`Error::QuarantinedLibrary`]: enum.Error.html#variant.QuarantinedLibrary
+    pub fn quarantine_bad_libraries(mut self, on: bool) -> Self {
+        self.config.quarantine_bad_libraries = on;
+        self
+    }
+
+    /// Put off calling the library's `init` until the first update.
+    ///
+    /// Normally `init` is called as soon as the `Reloadable` is built. With
+    /// this on, it's called by the first [`update`][] (or [`try_update`][])
+    /// instead, or by [`ensure_init`][] if you'd rather say exactly when, so
+    /// that the host can finish setting up whatever `init` needs first.
+    /// Until then, reloads swap the library without calling `unload` or
+    /// `reload`, and dropping the `Reloadable` doesn't call `deinit`. A state
+    /// that was recovered, like a [memory-mapped][] one, still gets `reload`
+    /// straight away, since it was initialized before. Debug builds check
+    /// that the library's `update` and `reload` are never handed a `State`
+    /// before its `init` has run, and panic if they would be.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host {
+    ///     log: Vec<&'static str>,
+    /// }
+    ///
+    /// struct State;
+    ///
+    /// fn init(host: &mut Host, _: &mut State) { host.log.push("init"); }
+    /// fn reload(host: &mut Host, _: &mut State) { host.log.push("reload"); }
+    /// fn unload(host: &mut Host, _: &mut State) { host.log.push("unload"); }
+    /// fn update(host: &mut Host, _: &mut State) -> ShouldQuit {
+    ///     host.log.push("update");
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: reload;
+    ///     update: update;
+    ///     unload: unload;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::Builder;
+    ///
+    /// let host = Host { log: Vec::new() };
+    /// let mut app = Builder::new("").lazy_init(true).build_in_process(RELOAD_API, host).unwrap();
+    /// assert!(app.host().log.is_empty());
+    ///
+    /// // A reload before the first update leaves the library alone.
+    /// app.reload_now().unwrap();
+    /// assert!(app.host().log.is_empty());
+    ///
+    /// app.update();
+    /// app.reload_now().unwrap();
+    /// assert_eq!(app.host().log, ["init", "update", "unload", "reload"]);
+    /// # }
+    /// ```
+    ///
+    /// [`update`]: struct.Reloadable.html#method.update
+    /// [`try_update`]: struct.Reloadable.html#method.try_update
+    /// [`ensure_init`]: struct.Reloadable.html#method.ensure_init
+    /// [memory-mapped]: struct.Reloadable.html#method.new_mmap
+    pub fn lazy_init(mut self, lazy: bool) -> Self {
+        self.config.lazy_init = lazy;
+        self
+    }
+
+    /// Call the library's `reload` right after its `init`, so that the first
+    /// load goes through `reload` like every later one.
+    ///
+    /// This suits a library that does all of its setup in `reload`, and keeps
+    /// `init` to what only has to happen once. The order is always `init`
+    /// then `reload`, on the same `State`, wherever `init` is called: in
+    /// [`build`](#method.build), on the first update with
+    /// [`lazy_init`](#method.lazy_init), and in
+    /// [`Reloadable::restart`][]. The extra `reload` doesn't count as a
+    /// reload in [`Reloadable::reload_count`][]. If it panics, the error is
+    /// returned as if `init` had panicked, but `init` isn't run again.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host {
+    ///     log: Vec<&'static str>,
+    /// }
+    ///
+    /// struct State;
+    ///
+    /// fn init(host: &mut Host, _: &mut State) { host.log.push("init"); }
+    /// fn reload(host: &mut Host, _: &mut State) { host.log.push("reload"); }
+    /// fn update(host: &mut Host, _: &mut State) -> ShouldQuit {
+    ///     host.log.push("update");
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: reload;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::Builder;
+    ///
+    /// let host = Host { log: Vec::new() };
+    /// let mut app = Builder::new("")
+    ///     .run_reload_on_first_load(true)
+    ///     .build_in_process(RELOAD_API, host)
+    ///     .unwrap();
+    /// app.update();
+    /// app.reload_now().unwrap();
+    /// assert_eq!(app.host().log, ["init", "reload", "update", "reload"]);
+    /// assert_eq!(app.reload_count(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`Reloadable::restart`]: struct.Reloadable.html#method.restart
+    /// [`Reloadable::reload_count`]: struct.Reloadable.html#method.reload_count
+    pub fn run_reload_on_first_load(mut self, run: bool) -> Self {
+        self.config.reload_on_first_load = run;
+        self
+    }
+
+    /// Whether [`Reloadable::update_until_quit`][] catches Ctrl-C (and
+    /// `SIGTERM`) while it runs, to stop the loop cleanly. The default is
+    /// `false`.
+    ///
+    /// Turning this on replaces the host's own signal handlers for as long
+    /// as the loop runs, so it's only for hosts that don't install any.
+    /// Without it, a host's handler can stop the loop with [`interrupt`][].
+    ///
+    /// [`Reloadable::update_until_quit`]: struct.Reloadable.html#method.update_until_quit
+    /// [`interrupt`]: fn.interrupt.html
+    pub fn handle_interrupts(mut self, handle: bool) -> Self {
+        self.config.handle_interrupts = handle;
+        self
+    }
+
+    /// Split reloading in two: [`Reloadable::reload`][] only notes that the
+    /// library changed, and [`Reloadable::apply_deferred_reload`][] swaps
+    /// it in.
+    ///
+    /// A reload in the middle of a frame leaves anything the frame already
+    /// took from the library or the `State` dangling. With this, `reload` can
+    /// stay at the top of the frame, and the swap happens where
+    /// `apply_deferred_reload` is called, at the end of the frame, once
+    /// nothing is held on to any more. Unlike a reload gate, there's no
+    /// predicate: the place in the frame is the condition.
+    ///
+    /// [`Reloadable::reload`]: struct.Reloadable.html#method.reload
+    /// [`Reloadable::apply_deferred_reload`]: struct.Reloadable.html#method.apply_deferred_reload
+    pub fn defer_reloads(mut self, defer: bool) -> Self {
+        self.config.defer_reloads = defer;
+        self
+    }
+
+    /// Only reload when the library's modification time has changed.
+    ///
+    /// The watcher reports some events that don't mean the library was
+    /// rebuilt, like its permissions changing. With this on, [`reload`][]
+    /// compares the library's modification time with the one it had when it
+    /// was loaded (see [`last_loaded_mtime`][]) before doing anything else,
+    /// and ignores the change if they're the same. This is cheap, but coarse:
+    /// a build that touches the library without changing it still reloads.
+    /// With [`companion_library`][], a change to any of the group's
+    /// modification times counts.
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`last_loaded_mtime`]: struct.Reloadable.html#method.last_loaded_mtime
+    /// [`companion_library`]: #method.companion_library
+    pub fn require_mtime_change(mut self, require: bool) -> Self {
+        self.config.require_mtime_change = require;
+        self
+    }
+
+    /// Reload at most once per `cooldown`.
+    ///
+    /// The debounce only waits for writes to settle, so a burst of rebuilds,
+    /// say from saving every file in an editor, can still reload several
+    /// times in a row, stuttering each time. With a cooldown, a change that
+    /// [`Reloadable::reload`][] sees within `cooldown` of the last successful
+    /// reload is held back until the cooldown is over, and the latest build
+    /// is then loaded once. Reloads you ask for with
+    /// [`Reloadable::reload_now`][] aren't held back. The default is no
+    /// cooldown.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use std::time::Duration;
+    /// use live_reload::Builder;
+    ///
+    /// let cooldown = Duration::from_millis(500);
+    /// let mut app = Builder::new("")
+    ///     .reload_cooldown(cooldown)
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// for _ in 0..20 {
+    ///     app.inject_change_event();
+    ///     app.reload().unwrap();
+    /// }
+    /// assert_eq!(app.reload_count(), 1);
+    /// assert!(app.has_pending_reload());
+    ///
+    /// std::thread::sleep(cooldown);
+    /// app.reload().unwrap();
+    /// assert_eq!(app.reload_count(), 2);
+    /// assert!(!app.has_pending_reload());
+    /// # }
+    /// ```
+    ///
+    /// [`Reloadable::reload`]: struct.Reloadable.html#method.reload
+    /// [`Reloadable::reload_now`]: struct.Reloadable.html#method.reload_now
+    pub fn reload_cooldown(mut self, cooldown: Duration) -> Self {
+        self.config.reload_cooldown = cooldown;
+        self
+    }
+
+    /// Checksum the `State` right before each reload's `unload`, and again
+    /// right after its `reload`, and hand both to the observer's
+    /// [`on_state_checksums`][].
+    ///
+    /// This is for tracking down a reload that corrupts the state: a version
+    /// that shouldn't touch it should leave the checksum alone, and one that
+    /// migrates it should change it. Each checksum reads the whole `State`
+    /// (see [`Reloadable::state_checksum`][]), so this is off by default.
+    /// Nothing is reported for a reload before the library has been started,
+    /// or one that fails.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use std::sync::{Arc, Mutex};
+    /// use live_reload::{Builder, Reloadable, ReloadObserver, ShouldQuit};
+    ///
+    /// # pub struct Host;
+    /// #[repr(C)] pub struct State { frames: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// fn reload(_: &mut Host, state: &mut State) { state.frames += 1 }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: reload;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    ///
+    /// struct Checksums(Arc<Mutex<Vec<(u64, u64)>>>);
+    ///
+    /// impl ReloadObserver<Host> for Checksums {
+    ///     fn on_state_checksums(&mut self, _: &Reloadable<Host>, before: u64, after: u64) {
+    ///         self.0.lock().unwrap().push((before, after));
+    ///     }
+    /// }
+    ///
+    /// # fn main() {
+    /// let checksums = Arc::new(Mutex::new(Vec::new()));
+    /// let mut app = Builder::new("unused")
+    ///     .trace_state_checksums(true)
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// app.set_observer(Box::new(Checksums(checksums.clone())));
+    /// app.reload_now().unwrap();
+    ///
+    /// // The reload bumped `frames`, so the checksum moved.
+    /// let (before, after) = checksums.lock().unwrap()[0];
+    /// assert_ne!(before, after);
+    /// assert_eq!(after, app.state_checksum());
+    /// # }
+    /// ```
+    ///
+    /// [`on_state_checksums`]: trait.ReloadObserver.html#method.on_state_checksums
+    /// [`Reloadable::state_checksum`]: struct.Reloadable.html#method.state_checksum
+    pub fn trace_state_checksums(mut self, trace: bool) -> Self {
+        self.config.trace_state_checksums = trace;
+        self
+    }
+
+    /// Keep a log of the last `capacity` lifecycle steps and errors, to read
+    /// back with [`Reloadable::reload_log`][].
+    ///
+    /// Each [entry][] has the time, the kind of step, and a line of detail,
+    /// which is what a dev console needs to show the reload history without
+    /// writing a [`ReloadObserver`][] to collect it. The log hears about the
+    /// same steps as the observer, except for `on_state_checksums`, and the
+    /// oldest entries are dropped once there are `capacity` of them. It's
+    /// kept alongside any observer. The default is 0, which keeps no log.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::{Builder, ReloadLogKind};
+    ///
+    /// let mut app = Builder::new("unused")
+    ///     .reload_log_capacity(3)
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// app.reload_now().unwrap();
+    /// app.reload_now().unwrap();
+    ///
+    /// let kinds: Vec<_> = app.reload_log().iter().map(|entry| entry.kind()).collect();
+    /// assert_eq!(kinds, [ReloadLogKind::Reload, ReloadLogKind::Unload, ReloadLogKind::Reload]);
+    /// # }
+    /// ```
+    ///
+    /// [`Reloadable::reload_log`]: struct.Reloadable.html#method.reload_log
+    /// [entry]: struct.ReloadLogEntry.html
+    /// [`ReloadObserver`]: trait.ReloadObserver.html
+    pub fn reload_log_capacity(mut self, capacity: usize) -> Self {
+        self.config.reload_log_capacity = capacity;
+        self
+    }
+
+    /// Only load libraries whose build id has this tag.
+    ///
+    /// Every library built with [`live_reload!`][] exports a build id, made
+    /// of the version of `live-reload` it was built with and the `build_tag`
+    /// it was given, if any. A library whose build id doesn't match the
+    /// host's, with the tag set here (which is empty by default), fails to
+    /// load with [`Error::BuildIdMismatch`][]. Bump the tag on both sides
+    /// whenever the `Host` changes in a way its size doesn't show.
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`Error::BuildIdMismatch`]: enum.Error.html#variant.BuildIdMismatch
+    pub fn build_tag(mut self, tag: &'static str) -> Self {
+        self.config.build_tag = tag;
+        self
+    }
+
+    /// Whether to refuse libraries with a different build id.
+    ///
+    /// This is on by default. Turn it off if you intentionally run a host and
+    /// library built with different versions of `live-reload`, or different
+    /// tags (see [`build_tag`](#method.build_tag)). The ABI version is still
+    /// checked either way.
+    pub fn check_build_id(mut self, check: bool) -> Self {
+        self.config.check_build_id = check;
+        self
+    }
+
+    /// Whether to refuse libraries built with a different panic strategy.
+    ///
+    /// Every library built with [`live_reload!`][] exports the panic
+    /// strategy (`panic = "unwind"` or `"abort"` in its Cargo profile) it was
+    /// built with. The library's panics are caught on its side of the
+    /// boundary, which relies on the two sides agreeing: a library that
+    /// unwinds into a host built to abort is undefined behavior, and one that
+    /// aborts can't have its panics caught at all. So a library whose
+    /// strategy differs from the host's fails to load with
+    /// [`Error::PanicStrategyMismatch`][], unless this is turned off.
+    ///
+    /// Libraries only started exporting their strategy with ABI version 13,
+    /// so one built against an earlier version of this crate is refused
+    /// with [`Error::AbiMismatch`][] instead, and needs rebuilding.
+    ///
+    /// This can't tell whether the two were built by the same compiler,
+    /// which matters just as much. Put the compiler version in the
+    /// [`build_tag`](#method.build_tag) to check that too.
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`Error::PanicStrategyMismatch`]: enum.Error.html#variant.PanicStrategyMismatch
+    /// [`Error::AbiMismatch`]: enum.Error.html#variant.AbiMismatch
+    pub fn check_panic_strategy(mut self, check: bool) -> Self {
+        self.config.check_panic_strategy = check;
+        self
+    }
+
+    /// Refuse libraries built with a different profile than `profile`.
+    ///
+    /// Every library built with [`live_reload!`][] exports whether it was
+    /// built with `debug_assertions`. A release build reloaded over a debug
+    /// one, or the other way around, runs at a different speed and can behave
+    /// differently, which usually means the library was picked up from the
+    /// wrong `target/` directory. With this set, such a library fails to load
+    /// with [`Error::ProfileMismatch`][] instead.
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::{Builder, Profile};
+    ///
+    /// let app = Builder::new("target/debug/libreloadable.so")
+    ///     .require_build_profile(Profile::current())
+    ///     .build(Host);
+    /// # }
+    /// ```
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`Error::ProfileMismatch`]: enum.Error.html#variant.ProfileMismatch
+    pub fn require_build_profile(mut self, profile: Profile) -> Self {
+        self.config.required_profile = Some(profile);
+        self
+    }
+
+    /// How to check that a library was built for the same `Host`.
+    ///
+    /// A library's `Host` fingerprint, which is the size and alignment of its
+    /// `Host`, is compared with the host's when it's loaded, and a library
+    /// whose fingerprint differs fails to load with
+    /// [`Error::MismatchedHost`][]. The default, [`HostCheckMode::Lenient`][],
+    /// lets libraries built before fingerprints existed load anyway. Once
+    /// they've all been rebuilt, [`HostCheckMode::Strict`][] refuses any
+    /// library without one.
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::{Builder, Error, HostCheckMode};
+    ///
+    /// let loaded = Builder::new("target/debug/libold.so")
+    ///     .host_check(HostCheckMode::Strict)
+    ///     .build(Host);
+    /// match loaded {
+    ///     Err(Error::MismatchedHost) => eprintln!("rebuild the library against this host"),
+    ///     Err(err) => panic!("{}", err),
+    ///     Ok(_) => {}
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`Error::MismatchedHost`]: enum.Error.html#variant.MismatchedHost
+    /// [`HostCheckMode::Lenient`]: enum.HostCheckMode.html#variant.Lenient
+    /// [`HostCheckMode::Strict`]: enum.HostCheckMode.html#variant.Strict
+    pub fn host_check(mut self, mode: HostCheckMode) -> Self {
+        self.config.host_check = mode;
+        self
+    }
+
+    /// Refuse libraries whose `State` is larger than `max` bytes.
+    ///
+    /// A corrupt library can report any size at all, and trying to allocate
+    /// a buffer for it would abort the host. So a library that reports a
+    /// size over the maximum, 256 MiB by default, fails to load with
+    /// [`Error::StateTooLarge`][] instead, before anything is allocated.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// use live_reload::internals::{PanicSink, ReloadApi, ABI_VERSION};
+    /// use live_reload::{Error, Reloadable, ShouldQuit};
+    ///
+    /// fn nothing(_: &mut (), _: *mut (), _: &mut PanicSink) {}
+    /// fn update(_: &mut (), _: *mut (), _: &mut PanicSink) -> ShouldQuit {
+    ///     ShouldQuit::No
+    /// }
+    ///
+    /// let bogus = ReloadApi {
+    ///     abi_version: ABI_VERSION,
+    ///     capabilities: 0,
+    ///     size: || usize::MAX,
+    ///     init: nothing,
+    ///     reload: nothing,
+    ///     update,
+    ///     unload: nothing,
+    ///     deinit: nothing,
+    ///     is_safe_to_reload: None,
+    ///     reload_progress: None,
+    ///     update_with_events: None,
+    ///     update_with_extra: None,
+    ///     validate_state: None,
+    ///     requirements: None,
+    ///     state_version: 0,
+    ///     migrate: None,
+    ///     serialize: None,
+    ///     deserialize: None,
+    ///     persistent_range: None,
+    /// };
+    /// match Reloadable::new_in_process(bogus, ()) {
+    ///     Err(Error::StateTooLarge { reported, .. }) => assert_eq!(reported, usize::MAX),
+    ///     _ => panic!("the bogus size should have been refused"),
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`Error::StateTooLarge`]: enum.Error.html#variant.StateTooLarge
+    pub fn max_state_size(mut self, max: usize) -> Self {
+        self.config.max_state_size = max;
+        self
+    }
+
+    /// Call `hook` when the filesystem watcher reports an error, such as
+    /// losing its watch on the library's directory.
+    ///
+    /// Either way, [`reload`][] replaces the failed watcher with a fresh one,
+    /// and queues a reload if the library changed in the meantime. Without a
+    /// hook, it then returns [`Error::WatchLost`][] so that the failure isn't
+    /// missed. With one, the error goes to `hook` instead, and `reload`
+    /// carries on.
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`Error::WatchLost`]: enum.Error.html#variant.WatchLost
+    pub fn on_watch_error(mut self, hook: fn(&notify::Error)) -> Self {
+        self.config.on_watch_error = Some(hook);
+        self
+    }
+
+    /// Decide whether a changed library should be reloaded at all.
+    ///
+    /// When [`reload`][] finds that the library has changed, and nothing is
+    /// holding the reload off, `hook` is called with what's known about the
+    /// new library just before the swap. If it returns false, the reload is
+    /// cancelled: the current library keeps running, and the change is
+    /// forgotten until the library changes again. This is the place to check
+    /// the new build with your own criteria. It isn't consulted when there's
+    /// no library loaded, or by [`reload_now`][].
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    pub fn before_reload(mut self, hook: fn(&LibraryInfo) -> bool) -> Self {
+        self.config.before_reload = Some(hook);
+        self
+    }
+
+    /// Wrap every reload and update in `hook`, so that they can be timed.
+    ///
+    /// `hook` is given a description of the [phase][] that's about to run,
+    /// and a closure that runs it and returns whether it succeeded. It should
+    /// call the closure exactly once (if it doesn't, the phase runs after
+    /// `hook` returns anyway, and calling it again does nothing). This is the
+    /// place to hook up a profiler. For example, to see reloads and updates
+    /// as spans with the `tracing` crate:
+    ///
+    /// ```rust,ignore
+    /// fn instrument(info: &PhaseInfo, run: &mut dyn FnMut() -> bool) {
+    ///     let span = match info.phase() {
+    ///         Phase::Reload => tracing::info_span!(
+    ///             "live_reload.reload",
+    ///             reload_count = info.reload_count(),
+    ///             state_size = info.state_size(),
+    ///             ok = tracing::field::Empty,
+    ///         ),
+    ///         Phase::Update => tracing::trace_span!("live_reload.update"),
+    ///     };
+    ///     let _entered = span.enter();
+    ///     let ok = run();
+    ///     span.record("ok", ok);
+    /// }
+    ///
+    /// let app = Builder::new("target/debug/libreload.so")
+    ///     .instrument(instrument)
+    ///     .build(Host)?;
+    /// ```
+    ///
+    /// Without a hook, there's no cost beyond checking for one.
+    ///
+    /// [phase]: enum.Phase.html
+    pub fn instrument(mut self, hook: fn(&PhaseInfo, &mut dyn FnMut() -> bool)) -> Self {
+        self.config.instrument = Some(hook);
+        self
+    }
+
+    /// Call `on_hang` when a call into the library runs for longer than
+    /// `timeout`.
+    ///
+    /// A library stuck in an endless loop freezes the host with no word of
+    /// why. With this set, a watchdog thread times every lifecycle call
+    /// (`init`, `reload`, `update`, `unload` and `deinit`), and calls
+    /// `on_hang` on that thread with the [`LifecyclePhase`][] of any that
+    /// takes longer than `timeout`, once per call, say to log a warning.
+    ///
+    /// This only detects a hang; it can't stop one. Native code can't be
+    /// safely interrupted, so the call goes on running, and the host stays
+    /// stuck until it returns. A slow call that does return is reported the
+    /// same way, so set `timeout` well above the longest call you expect.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// fn update(_: &mut Host, _: &mut State) -> ShouldQuit {
+    ///     std::thread::sleep(std::time::Duration::from_millis(200));
+    ///     ShouldQuit::No
+    /// }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use std::sync::mpsc::channel;
+    /// use std::time::Duration;
+    /// use live_reload::{Builder, LifecyclePhase};
+    ///
+    /// let (hung, hangs) = channel();
+    /// let mut app = Builder::new("unused")
+    ///     .lifecycle_watchdog(Duration::from_millis(50), move |phase| {
+    ///         eprintln!("the library's {} is taking a while", phase);
+    ///         hung.send(phase).unwrap();
+    ///     })
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// app.update();
+    /// assert_eq!(hangs.recv_timeout(Duration::from_secs(10)), Ok(LifecyclePhase::Update));
+    /// # }
+    /// ```
+    ///
+    /// [`LifecyclePhase`]: enum.LifecyclePhase.html
+    pub fn lifecycle_watchdog<F>(mut self, timeout: Duration, on_hang: F) -> Self
+    where
+        F: FnMut(LifecyclePhase) + Send + 'static,
+    {
+        self.config.watchdog = Some((timeout, Box::new(on_hang)));
+        self
+    }
+
+    /// Keep the `State` in `store` instead of a `Vec<u64>`.
+    ///
+    /// This lets the `State` live in memory you manage yourself, such as an
+    /// arena shared with the rest of your engine, so that reloads that resize
+    /// it don't go through the global allocator. If `store` already holds
+    /// some bytes, the library's `reload` is called on them instead of `init`
+    /// on the first load, just like with a recovered [memory-mapped][] state.
+    ///
+    /// [memory-mapped]: struct.Reloadable.html#method.new_mmap
+    pub fn state_store<S: StateStore + Send + 'static>(mut self, store: S) -> Self {
+        self.config.store = Some(Box::new(store));
+        self.config.reserved_state = None;
+        self
+    }
+
+    /// Allocate `max_bytes` for the `State` up front, so that it never moves.
+    ///
+    /// By default the `State` is reallocated whenever a reload changes its
+    /// size, which can move it. With a reservation, the `State` always starts
+    /// at the same address for the life of the `Reloadable`, so a pointer
+    /// into it can be kept across reloads. A library that reports a `State`
+    /// larger than `max_bytes` (or than [`max_state_size`][], whichever is
+    /// smaller) fails to load with [`Error::StateTooLarge`][], and the
+    /// current state is kept.
+    ///
+    /// The memory is allocated zeroed, which most platforms do lazily, so a
+    /// generous reservation costs little until it's used. This replaces any
+    /// store set with [`state_store`][] or [`state_capacity_hint`][], and
+    /// vice versa.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct Small { a: u64 }
+    /// # fn nothing<T>(_: &mut Host, _: &mut T) {}
+    /// # fn update<T>(_: &mut Host, _: &mut T) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: Small;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Builder;
+    ///
+    /// let mut app = Builder::new("unused")
+    ///     .reserve_state(4096)
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// let before = app.state_bytes().as_ptr();
+    /// app.reload_now().unwrap();
+    /// assert_eq!(app.state_bytes().as_ptr(), before);
+    ///
+    /// // A `State` that doesn't fit is refused.
+    /// match Builder::new("unused").reserve_state(4).build_in_process(RELOAD_API, Host) {
+    ///     Err(live_reload::Error::StateTooLarge { reported: 8, max: 4 }) => {}
+    ///     _ => panic!("an 8-byte State shouldn't fit in 4 bytes"),
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`max_state_size`]: #method.max_state_size
+    /// [`Error::StateTooLarge`]: enum.Error.html#variant.StateTooLarge
+    /// [`state_store`]: #method.state_store
+    /// [`state_capacity_hint`]: #method.state_capacity_hint
+    pub fn reserve_state(mut self, max_bytes: usize) -> Self {
+        self.config.store = Some(Box::new(ReservedStore::new(max_bytes)));
+        self.config.reserved_state = Some(max_bytes);
+        self
+    }
+
+    /// Allocate room for a `State` of `bytes` up front, so that loading a
+    /// library whose `State` fits doesn't allocate.
+    ///
+    /// The `State` is kept in a `Vec<u64>` as usual, but with this capacity
+    /// from the start, so the first `init` and any later reload that keeps
+    /// the `State` within it don't go through the allocator, which avoids a
+    /// hitch on the first frame. It's only a hint: unlike with
+    /// [`reserve_state`][], a `State` larger than `bytes` is still loaded,
+    /// and the buffer grows (and moves) to fit it. This replaces any store
+    /// set with [`state_store`][] or `reserve_state`, and vice versa.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { frames: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Builder;
+    ///
+    /// let mut app = Builder::new("unused")
+    ///     .state_capacity_hint(64 * 1024)
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// assert_eq!(app.state_bytes().len(), 8);
+    /// let before = app.state_bytes().as_ptr();
+    /// app.reload_now().unwrap();
+    /// assert_eq!(app.state_bytes().as_ptr(), before);
+    /// # }
+    /// ```
+    ///
+    /// [`reserve_state`]: #method.reserve_state
+    /// [`state_store`]: #method.state_store
+    pub fn state_capacity_hint(mut self, bytes: usize) -> Self {
+        self.config.store = Some(Box::new(Vec::<u64>::with_capacity(bytes.div_ceil(8))));
+        self.config.reserved_state = None;
+        self
+    }
+
+    /// Load the library and create the `Reloadable`.
+    ///
+    /// See [`Reloadable::new`][] for what happens on the first load.
+    ///
+    /// [`Reloadable::new`]: struct.Reloadable.html#method.new
+    pub fn build(self, host: Host) -> Result<Reloadable<Host>, Error> {
+        Reloadable::with_config(self.path, host, self.config)
+    }
+
+    /// Create a `Reloadable` that drives `api`, which is built into the host,
+    /// with these options.
+    ///
+    /// See [`Reloadable::new_in_process`][] for how this works. The path given
+    /// to [`new`](#method.new) isn't used, and neither are the options that
+    /// only matter for a library file. This is only available with the
+    /// `in-process` feature enabled.
+    ///
+    /// [`Reloadable::new_in_process`]: struct.Reloadable.html#method.new_in_process
+    #[cfg(feature = "in-process")]
+    pub fn build_in_process(self, api: ReloadApi<Host>, host: Host) -> Result<Reloadable<Host>, Error> {
+        Reloadable::in_process_with_config(api, host, self.config)
+    }
+}
@@ -0,0 +1,80 @@
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// Check that the library at `path` isn't still being written.
+///
+/// Loading a half-written ELF library doesn't fail cleanly: the loader maps
+/// segments that extend past the end of the file, and the process dies with
+/// `SIGBUS` when it touches them. So for ELF files, this checks that the file
+/// is at least as long as its headers say it should be, and returns an
+/// `UnexpectedEof` error if it isn't. Other formats are assumed complete,
+/// unless they're empty, which is how a linker leaves the file when it
+/// truncates it before writing.
+pub(crate) fn check_complete(path: &Path) -> io::Result<()> {
+    let mut file = File::open(path)?;
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("{} is an empty library file, it may still be being written", path.display()),
+        ));
+    }
+    let mut ident = [0; 16];
+    if file.read_exact(&mut ident).is_err() || &ident[..4] != b"\x7fELF" {
+        return Ok(());
+    }
+    let elf = Elf {
+        is_64: ident[4] == 2,
+        is_le: ident[5] == 1,
+    };
+    let (phoff, shoff, sizes_at) = if elf.is_64 {
+        (elf.read(&mut file, 0x20, 8)?, elf.read(&mut file, 0x28, 8)?, 0x36)
+    } else {
+        (elf.read(&mut file, 0x1c, 4)?, elf.read(&mut file, 0x20, 4)?, 0x2a)
+    };
+    let phentsize = elf.read(&mut file, sizes_at, 2)?;
+    let phnum = elf.read(&mut file, sizes_at + 2, 2)?;
+    let shentsize = elf.read(&mut file, sizes_at + 4, 2)?;
+    let shnum = elf.read(&mut file, sizes_at + 6, 2)?;
+
+    let mut end = shoff.saturating_add(shentsize.saturating_mul(shnum));
+    end = end.max(phoff.saturating_add(phentsize.saturating_mul(phnum)));
+    if end <= len {
+        for index in 0..phnum {
+            let header = phoff + index * phentsize;
+            let (offset, filesz) = if elf.is_64 {
+                (elf.read(&mut file, header + 0x08, 8)?, elf.read(&mut file, header + 0x20, 8)?)
+            } else {
+                (elf.read(&mut file, header + 0x04, 4)?, elf.read(&mut file, header + 0x10, 4)?)
+            };
+            end = end.max(offset.saturating_add(filesz));
+        }
+    }
+    if end > len {
+        return Err(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            format!("{} is truncated, it may still be being written", path.display()),
+        ));
+    }
+    Ok(())
+}
+
+/// The layout of the fields of an ELF file.
+struct Elf {
+    is_64: bool,
+    is_le: bool,
+}
+
+impl Elf {
+    /// Read the `size`-byte unsigned field at `offset`.
+    fn read(&self, file: &mut File, offset: u64, size: usize) -> io::Result<u64> {
+        let mut bytes = [0; 8];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut bytes[..size])?;
+        if !self.is_le {
+            bytes[..size].reverse();
+        }
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
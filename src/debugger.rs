@@ -0,0 +1,54 @@
+//! Functions for a debugger to break on, to hear about reloads.
+//!
+//! A breakpoint set in a library is lost when the library is unloaded, and
+//! the new version is a different image, often at a different path when
+//! libraries are copied before loading. With [`Builder::debugger_friendly`][]
+//! on, a [`Reloadable`][] calls [`reload_starting`][] just before the old
+//! version is unloaded, and [`library_loaded`][] as soon as the new version
+//! is loaded, before any of its code runs. Neither function does anything,
+//! but a debugger script can break on them to set the breakpoints again, in
+//! time for the new version's `reload`. With GDB:
+//!
+//! ```text
+//! break live_reload::debugger::library_loaded
+//! commands
+//!   silent
+//!   break game::update
+//!   continue
+//! end
+//! ```
+//!
+//! Or with LLDB:
+//!
+//! ```text
+//! breakpoint set --name live_reload::debugger::library_loaded
+//! breakpoint command add --one-liner "breakpoint set --name game::update; continue"
+//! ```
+//!
+//! [`Builder::debugger_friendly`]: ../struct.Builder.html#method.debugger_friendly
+//! [`Reloadable`]: ../struct.Reloadable.html
+//! [`reload_starting`]: fn.reload_starting.html
+//! [`library_loaded`]: fn.library_loaded.html
+
+use std::hint::black_box;
+use std::path::Path;
+
+/// Called just before the library loaded from `path` is unloaded for a
+/// reload.
+///
+/// `path` is the file the image was loaded from, which is the temporary copy
+/// when the library is copied.
+#[inline(never)]
+pub fn reload_starting(path: &Path) {
+    black_box(path);
+}
+
+/// Called as soon as a new version of the library has been loaded from
+/// `path`, before its `reload` or `init` runs.
+///
+/// `path` is the file the image was loaded from, which is the temporary copy
+/// when the library is copied.
+#[inline(never)]
+pub fn library_loaded(path: &Path) {
+    black_box(path);
+}
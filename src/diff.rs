@@ -0,0 +1,116 @@
+use alloc::slice;
+use alloc::vec::Vec;
+
+/// Unchanged stretches shorter than this are folded into the runs around
+/// them, since a run of its own would cost more than the bytes it skips.
+const MIN_GAP: usize = 16;
+
+/// The difference between two saves of the state, made with
+/// [`SaveState::diff`][].
+///
+/// The state is opaque bytes, so the diff is too: each run of bytes that
+/// changed is kept as its offset and the XOR of its old and new bytes.
+/// Stretches that didn't change take no room at all, which makes a diff of a
+/// large `State` that changed in a few places far smaller than a full
+/// [`SaveState`][]. It's turned back into the newer save with
+/// [`Reloadable::apply_diff`][], given the same base it was made from.
+///
+/// [`SaveState::diff`]: struct.SaveState.html#method.diff
+/// [`SaveState`]: struct.SaveState.html
+/// [`Reloadable::apply_diff`]: struct.Reloadable.html#method.apply_diff
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StateDiff {
+    /// The size in bytes of the `State` of the base.
+    base_size: usize,
+    /// The size in bytes of the `State` the diff leads to.
+    size: usize,
+    /// How many `u64`s the save the diff leads to holds.
+    units: usize,
+    runs: Vec<Run>,
+}
+
+/// A stretch of bytes that changed, at `offset`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Run {
+    offset: usize,
+    xor: Vec<u8>,
+}
+
+impl StateDiff {
+    /// Diff `new` against `base`, where the two hold `State`s of
+    /// `new_size` and `base_size` bytes.
+    ///
+    /// Where one is longer than the other, the missing bytes count as zeros.
+    pub(crate) fn between(base: &[u8], base_size: usize, new: &[u8], new_size: usize) -> Self {
+        let byte = |bytes: &[u8], at: usize| bytes.get(at).cloned().unwrap_or(0);
+        let mut runs: Vec<Run> = Vec::new();
+        for at in 0..base.len().max(new.len()) {
+            let xor = byte(base, at) ^ byte(new, at);
+            if xor == 0 {
+                continue;
+            }
+            let extend = match runs.last() {
+                Some(run) => at - (run.offset + run.xor.len()) < MIN_GAP,
+                None => false,
+            };
+            if extend {
+                let run = runs.last_mut().unwrap();
+                for gap in run.offset + run.xor.len()..at {
+                    run.xor.push(byte(base, gap) ^ byte(new, gap));
+                }
+                run.xor.push(xor);
+            } else {
+                runs.push(Run { offset: at, xor: vec![xor] });
+            }
+        }
+        StateDiff {
+            base_size,
+            size: new_size,
+            units: new.len() / 8,
+            runs,
+        }
+    }
+
+    /// The size in bytes of the `State` of the base the diff was made from.
+    pub(crate) fn base_size(&self) -> usize {
+        self.base_size
+    }
+
+    /// The size in bytes of the `State` the diff leads to.
+    pub fn state_size(&self) -> usize {
+        self.size
+    }
+
+    /// How many bytes of the `State` the diff covers, counting the short
+    /// unchanged stretches folded in between changes.
+    ///
+    /// This is roughly how much room the diff takes.
+    pub fn changed_bytes(&self) -> usize {
+        self.runs.iter().map(|run| run.xor.len()).sum()
+    }
+
+    /// Whether the two saves held the same bytes.
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty() && self.base_size == self.size
+    }
+
+    /// Apply the diff to `base`, the units of the save it was made from,
+    /// returning the units of the save it leads to.
+    pub(crate) fn apply(&self, base: &[u64]) -> Vec<u64> {
+        let mut units = base.to_vec();
+        let end = self.runs.last().map_or(0, |run| run.offset + run.xor.len());
+        units.resize(self.units.max(end.div_ceil(8)), 0);
+        {
+            let bytes = unsafe {
+                slice::from_raw_parts_mut(units.as_mut_ptr() as *mut u8, units.len() * 8)
+            };
+            for run in &self.runs {
+                for (byte, xor) in bytes[run.offset..].iter_mut().zip(&run.xor) {
+                    *byte ^= *xor;
+                }
+            }
+        }
+        units.truncate(self.units);
+        units
+    }
+}
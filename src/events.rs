@@ -0,0 +1,275 @@
+use std::collections::VecDeque;
+use std::path::Path;
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ::notify::DebouncedEvent;
+
+/// The most events that are kept waiting. Past this, they're all replaced by
+/// a single `Rescan`, which counts as a change to the library.
+const MAX_QUEUED: usize = 256;
+
+/// The queue of events coming from the filesystem watcher.
+///
+/// The watcher sends into an unbounded channel, so a thread moves its events
+/// into a queue of our own as they arrive, dropping any that repeats one
+/// already waiting and collapsing the lot into a `Rescan` once there are
+/// too many. Only whether something changed matters, so this loses nothing,
+/// and the queue stays small however long it goes without being handled.
+pub(crate) struct Events {
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    queue: Mutex<Queue>,
+    /// Signalled whenever an event is queued, or the watcher goes away.
+    ready: Condvar,
+}
+
+struct Queue {
+    events: VecDeque<DebouncedEvent>,
+    /// Whether the watcher is gone, so no more events will come.
+    closed: bool,
+}
+
+impl Events {
+    pub(crate) fn new(rx: Receiver<DebouncedEvent>) -> Self {
+        let events = Events::closed();
+        events.lock().closed = false;
+        let shared = events.shared.clone();
+        let spawned = thread::Builder::new()
+            .name("live-reload-events".into())
+            .spawn(move || {
+                for evt in rx.iter() {
+                    push(&mut lock(&shared), evt);
+                    shared.ready.notify_all();
+                }
+                lock(&shared).closed = true;
+                shared.ready.notify_all();
+            });
+        if spawned.is_err() {
+            events.lock().closed = true;
+        }
+        events
+    }
+
+    /// A queue that never gets any events, for a `Reloadable` with no
+    /// watcher.
+    pub(crate) fn closed() -> Self {
+        Events {
+            shared: Arc::new(Shared {
+                queue: Mutex::new(Queue { events: VecDeque::new(), closed: true }),
+                ready: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Take the next event, if there is one waiting.
+    pub(crate) fn try_recv(&mut self) -> Option<DebouncedEvent> {
+        self.lock().events.pop_front()
+    }
+
+    /// Wait up to `timeout` for the next event.
+    pub(crate) fn recv_timeout(&mut self, timeout: Duration) -> Result<DebouncedEvent, RecvTimeoutError> {
+        let deadline = Instant::now() + timeout;
+        let mut queue = self.lock();
+        loop {
+            if let Some(evt) = queue.events.pop_front() {
+                return Ok(evt);
+            }
+            if queue.closed {
+                return Err(RecvTimeoutError::Disconnected);
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                return Err(RecvTimeoutError::Timeout);
+            }
+            queue = self.shared.ready
+                .wait_timeout(queue, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+
+    /// Queue `evt` as if the watcher had sent it.
+    #[cfg(feature = "testing")]
+    pub(crate) fn inject(&mut self, evt: DebouncedEvent) {
+        push(&mut self.lock(), evt);
+    }
+
+    /// Count the events waiting to be handled.
+    pub(crate) fn len(&self) -> usize {
+        self.lock().events.len()
+    }
+
+    /// Whether the watcher is gone, or has reported an error that's still
+    /// waiting to be handled.
+    pub(crate) fn is_broken(&self) -> bool {
+        let queue = self.lock();
+        queue.closed || queue.events.iter().any(|evt| matches!(*evt, DebouncedEvent::Error(..)))
+    }
+
+    /// A handle that can wait for events from another thread, without
+    /// taking them.
+    #[cfg(feature = "autoreload")]
+    pub(crate) fn waiter(&self) -> Waiter {
+        Waiter { shared: self.shared.clone() }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Queue> {
+        lock(&self.shared)
+    }
+}
+
+/// Waits for events on a queue that belongs to someone else.
+#[cfg(feature = "autoreload")]
+pub(crate) struct Waiter {
+    shared: Arc<Shared>,
+}
+
+#[cfg(feature = "autoreload")]
+impl Waiter {
+    /// Wait up to `timeout` for an event to be queued, leaving it there.
+    ///
+    /// A queue that will never get another event waits out the whole
+    /// timeout, rather than returning straight away.
+    pub(crate) fn wait(&self, timeout: Duration) {
+        let deadline = Instant::now() + timeout;
+        let mut queue = lock(&self.shared);
+        while queue.events.is_empty() {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                return;
+            }
+            if queue.closed {
+                drop(queue);
+                thread::sleep(remaining);
+                return;
+            }
+            queue = self.shared.ready
+                .wait_timeout(queue, remaining)
+                .unwrap_or_else(PoisonError::into_inner)
+                .0;
+        }
+    }
+}
+
+fn lock(shared: &Shared) -> MutexGuard<'_, Queue> {
+    shared.queue.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Queue `evt`, unless the same event is already waiting.
+fn push(queue: &mut Queue, evt: DebouncedEvent) {
+    let events = &mut queue.events;
+    if events.len() >= MAX_QUEUED {
+        // Keep an error, so that the watcher still gets replaced.
+        let error = events
+            .iter()
+            .position(|queued| matches!(*queued, DebouncedEvent::Error(..)))
+            .and_then(|at| events.remove(at));
+        events.clear();
+        events.extend(error);
+        events.push_back(DebouncedEvent::Rescan);
+    }
+    if !events.iter().any(|queued| same_event(queued, &evt)) {
+        events.push_back(evt);
+    }
+}
+
+/// Do `a` and `b` say the same thing happened?
+fn same_event(a: &DebouncedEvent, b: &DebouncedEvent) -> bool {
+    use ::notify::DebouncedEvent::*;
+    match (a, b) {
+        (NoticeWrite(a), NoticeWrite(b)) |
+        (NoticeRemove(a), NoticeRemove(b)) |
+        (Create(a), Create(b)) |
+        (Write(a), Write(b)) |
+        (Chmod(a), Chmod(b)) |
+        (Remove(a), Remove(b)) => a == b,
+        (Rename(a_from, a_to), Rename(b_from, b_to)) => a_from == b_from && a_to == b_to,
+        (Rescan, Rescan) => true,
+        _ => false,
+    }
+}
+
+/// Extensions of the files that are written next to a library, but are never
+/// the library itself.
+pub(crate) const SIDECAR_EXTENSIONS: &[&str] = &["pdb", "ilk", "exp", "lib", "dSYM", "d"];
+
+/// Does this watcher event mean that the library has changed?
+///
+/// `path` is the canonical path of the library as of the last load, and
+/// `request_path` the path it was asked for, which might be a symlink. Files
+/// with one of the `ignored` extensions, or inside a directory with one, are
+/// never the library.
+pub(crate) fn is_change<S: AsRef<str>>(
+    evt: &DebouncedEvent,
+    path: &Path,
+    request_path: &Path,
+    ignored: &[S],
+) -> bool {
+    is_change_by(evt, request_path, ignored, |changed| is_library(changed, path, request_path))
+}
+
+/// Like `is_change`, but with `filter` deciding whether a file that's not
+/// ignored is the library.
+pub(crate) fn is_change_by<S, F>(evt: &DebouncedEvent, request_path: &Path, ignored: &[S], filter: F) -> bool
+where
+    S: AsRef<str>,
+    F: FnOnce(&Path) -> bool,
+{
+    use ::notify::DebouncedEvent::*;
+    match *evt {
+        NoticeWrite(ref changed) |
+        Write(ref changed) |
+        Create(ref changed) => !is_ignored(changed, request_path, ignored) && filter(changed),
+        // Events were dropped, so the library might have changed.
+        Rescan => true,
+        _ => false,
+    }
+}
+
+/// The file this watcher event says was written or created, if any.
+pub(crate) fn written_path(evt: &DebouncedEvent) -> Option<&Path> {
+    use ::notify::DebouncedEvent::*;
+    match *evt {
+        NoticeWrite(ref changed) | Write(ref changed) | Create(ref changed) | Rename(_, ref changed) => {
+            Some(changed)
+        }
+        _ => None,
+    }
+}
+
+/// Does `changed`, or any directory it's in below the library's directory,
+/// have one of the `ignored` extensions?
+pub(crate) fn is_ignored<S: AsRef<str>>(changed: &Path, request_path: &Path, ignored: &[S]) -> bool {
+    let relative = match request_path.parent().and_then(|dir| changed.strip_prefix(dir).ok()) {
+        Some(relative) => relative,
+        None => return false,
+    };
+    relative.ancestors().any(|ancestor| match ancestor.extension() {
+        Some(ext) => ignored.iter().any(|ignored| ext == ignored.as_ref()),
+        None => false,
+    })
+}
+
+/// Is this watcher event about something being removed?
+pub(crate) fn is_removal(evt: &DebouncedEvent) -> bool {
+    use ::notify::DebouncedEvent::*;
+    matches!(*evt, NoticeRemove(_) | Remove(_))
+}
+
+/// Is `changed` the library, either directly or through a symlink?
+fn is_library(changed: &Path, path: &Path, request_path: &Path) -> bool {
+    if changed == path || changed == request_path {
+        return true;
+    }
+    // The symlink might have been retargeted since the last load, so check
+    // where it leads now.
+    match (changed.canonicalize(), request_path.canonicalize()) {
+        (Ok(ref changed), Ok(ref target)) => changed == target,
+        _ => false,
+    }
+}
@@ -0,0 +1,50 @@
+use super::Reloadable;
+
+/// A value taken from a [`Reloadable`][], like a pointer into the library or
+/// the `State`, tagged with the [`load_generation`][] it was taken in.
+///
+/// Anything that points into the library dangles once that version is
+/// unloaded, and anything that points into the `State` once it moves, and
+/// using either is undefined behavior. Tagging such a value with
+/// [`Reloadable::tag`][] when it's taken makes that a check instead: it's
+/// only handed back by [`get`](#method.get) while the `Reloadable` is still
+/// in the same generation.
+///
+/// The tag is only a promise about the `Reloadable` it came from, so always
+/// check it against that one.
+///
+/// [`Reloadable`]: struct.Reloadable.html
+/// [`load_generation`]: struct.Reloadable.html#method.load_generation
+/// [`Reloadable::tag`]: struct.Reloadable.html#method.tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Generational<T> {
+    value: T,
+    generation: u64,
+}
+
+impl<T> Generational<T> {
+    pub(crate) fn new(value: T, generation: u64) -> Self {
+        Generational { value, generation }
+    }
+
+    /// The generation the value was taken in.
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    /// Whether `app` is still in the generation the value was taken in, so
+    /// that the value is still good.
+    pub fn is_current<Host>(&self, app: &Reloadable<Host>) -> bool {
+        self.generation == app.load_generation()
+    }
+
+    /// The value, if it's still current for `app`.
+    pub fn get<Host>(&self, app: &Reloadable<Host>) -> Option<&T> {
+        if self.is_current(app) { Some(&self.value) } else { None }
+    }
+
+    /// Take the value out, whatever its generation.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+}
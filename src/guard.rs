@@ -0,0 +1,175 @@
+use std::cell::Cell;
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+
+use super::Error;
+
+/// Where in the library's source a panic happened.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PanicLocation {
+    /// The source file that panicked.
+    pub file: String,
+    /// The line in `file`.
+    pub line: u32,
+    /// The column in `line`.
+    pub column: u32,
+}
+
+impl fmt::Display for PanicLocation {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+/// One of the lifecycle functions a library exports with [`live_reload!`][].
+///
+/// This names a single call into the library, to say which one panicked or
+/// failed. It's not a [`Phase`][], which is a piece of the host's own work
+/// that [`Builder::instrument`][] wraps: a reload is one `Phase`, but it
+/// calls `unload`, `reload` and sometimes `init`, and a panic in it needs to
+/// say which of those it came from.
+///
+/// [`live_reload!`]: macro.live_reload.html
+/// [`Phase`]: enum.Phase.html
+/// [`Builder::instrument`]: struct.Builder.html#method.instrument
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LifecyclePhase {
+    /// The library's `init`.
+    Init,
+    /// The library's `reload`.
+    Reload,
+    /// The library's `update`.
+    Update,
+    /// The library's `unload`.
+    Unload,
+    /// The library's `deinit`.
+    Deinit,
+    /// The library's `requirements`.
+    Requirements,
+    /// The library's `is_safe_to_reload`.
+    IsSafeToReload,
+    /// The library's `serialize`, as a state is saved.
+    Serialize,
+    /// The library's `deserialize`, as a state is loaded.
+    Deserialize,
+    /// The library's `persistent_range`, as it's restarted.
+    PersistentRange,
+}
+
+impl fmt::Display for LifecyclePhase {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            LifecyclePhase::Init => "init",
+            LifecyclePhase::Reload => "reload",
+            LifecyclePhase::Update => "update",
+            LifecyclePhase::Unload => "unload",
+            LifecyclePhase::Deinit => "deinit",
+            LifecyclePhase::Requirements => "requirements",
+            LifecyclePhase::IsSafeToReload => "is_safe_to_reload",
+            LifecyclePhase::Serialize => "serialize",
+            LifecyclePhase::Deserialize => "deserialize",
+            LifecyclePhase::PersistentRange => "persistent_range",
+        };
+        fmt.write_str(name)
+    }
+}
+
+/// Receives the message and location of a panic that the library caught.
+pub type PanicSink<'a> = dyn FnMut(&str, Option<&PanicLocation>) + 'a;
+
+/// Serializes the guarded calls, so that they don't swap the panic hook out
+/// from under each other.
+static HOOK_LOCK: Mutex<()> = Mutex::new(());
+
+thread_local! {
+    // Whether this thread is the one inside `catch`. This can't use
+    // `thread::current`, which gives the thread a destructor in the library's
+    // copy of the standard library, and that crashes when the thread exits
+    // after the library has been unloaded. A `Cell<bool>` has nothing to drop.
+    static GUARDED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run a lifecycle function, reporting a panic to `sink` instead of letting it
+/// unwind.
+///
+/// This runs on the library's side of the boundary, since a library built as
+/// a `cdylib` has its own copy of the standard library, and the host can't
+/// catch its panics. While `call` runs, a panic hook is installed that records
+/// the location of a panic on this thread. Panics on any other thread are
+/// passed on to the hook that was installed before, which is put back
+/// afterwards.
+pub fn catch<R, F: FnOnce() -> R>(sink: &mut PanicSink, call: F) -> Option<R> {
+    let _lock = HOOK_LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let caught = Arc::new(Mutex::new(None));
+    let previous = Arc::new(panic::take_hook());
+    {
+        let caught = caught.clone();
+        let previous = previous.clone();
+        panic::set_hook(Box::new(move |info| {
+            if !GUARDED.with(Cell::get) {
+                return previous(info);
+            }
+            let location = info.location().map(|location| PanicLocation {
+                file: location.file().to_owned(),
+                line: location.line(),
+                column: location.column(),
+            });
+            if let Ok(mut caught) = caught.lock() {
+                *caught = location;
+            }
+        }));
+    }
+    // Put back whatever was there, so that returning from a nested call
+    // doesn't leave the rest of an outer one unguarded.
+    let outer = GUARDED.with(|guarded| guarded.replace(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(call));
+    GUARDED.with(|guarded| guarded.set(outer));
+    // Dropping our hook releases its reference to the previous one.
+    drop(panic::take_hook());
+    match Arc::try_unwrap(previous) {
+        Ok(previous) => panic::set_hook(previous),
+        Err(_) => unreachable!("the guard's panic hook outlived it"),
+    }
+    match result {
+        Ok(value) => Some(value),
+        Err(payload) => {
+            let message = if let Some(message) = payload.downcast_ref::<&str>() {
+                *message
+            } else if let Some(message) = payload.downcast_ref::<String>() {
+                message
+            } else {
+                "Box<dyn Any>"
+            };
+            let location = caught.lock().ok().and_then(|mut caught| caught.take());
+            sink(message, location.as_ref());
+            None
+        }
+    }
+}
+
+/// Make a call into the library, turning a panic it caught into
+/// `Error::LibraryPanicked`.
+pub(crate) fn call<R, F: FnOnce(&mut PanicSink) -> R>(phase: LifecyclePhase, call: F) -> Result<R, Error> {
+    let mut panicked = None;
+    let result = call(&mut |message: &str, location: Option<&PanicLocation>| {
+        panicked = Some(Error::LibraryPanicked {
+            phase,
+            message: message.to_owned(),
+            location: location.cloned(),
+        });
+    });
+    match panicked {
+        Some(err) => Err(err),
+        None => Ok(result),
+    }
+}
+
+/// Make a call into the library, and panic on the host's side if the library
+/// panicked.
+pub(crate) fn call_or_panic<R, F: FnOnce(&mut PanicSink) -> R>(phase: LifecyclePhase, call: F) -> R {
+    match self::call(phase, call) {
+        Ok(result) => result,
+        Err(err) => panic!("{}", err),
+    }
+}
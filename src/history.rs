@@ -0,0 +1,54 @@
+use std::collections::VecDeque;
+
+use super::SaveState;
+
+/// A bounded record of past states, for undo and redo.
+pub(crate) struct History {
+    entries: VecDeque<SaveState>,
+    capacity: usize,
+    /// The index of the entry that matches the current state.
+    position: usize,
+}
+
+impl History {
+    pub(crate) fn new(capacity: usize) -> Self {
+        History {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            position: 0,
+        }
+    }
+
+    /// Record a new current state, forgetting anything that could be redone.
+    pub(crate) fn push(&mut self, state: SaveState) {
+        if self.capacity == 0 {
+            return;
+        }
+        if !self.entries.is_empty() {
+            self.entries.truncate(self.position + 1);
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(state);
+        self.position = self.entries.len() - 1;
+    }
+
+    /// Step back to the previous state, if there is one.
+    pub(crate) fn undo(&mut self) -> Option<&SaveState> {
+        if self.position == 0 {
+            return None;
+        }
+        self.position -= 1;
+        self.entries.get(self.position)
+    }
+
+    /// Step forward to the next state, if one was undone.
+    pub(crate) fn redo(&mut self) -> Option<&SaveState> {
+        if self.position + 1 >= self.entries.len() {
+            return None;
+        }
+        self.position += 1;
+        self.entries.get(self.position)
+    }
+}
@@ -0,0 +1,876 @@
+//! Exported for compilation reasons but not useful, only look if you're curious.
+//!
+//! This module holds to the `ReloadApi` struct, which is what what is looked up
+//! by the `Reloadable` in order to communicate with the reloadable library. It
+//! needs to be exported in order to avoid forcing the type definition into the
+//! pub symbols of the wrapped library. An instance of `ReloadApi` called
+//! `RELOAD_API` is generated by the [`live_reload!`][] macro.
+//!
+//! [`live_reload!`]: ../macro.live_reload.html
+
+#[doc(hidden)]
+pub use std::mem::{align_of, size_of};
+#[doc(hidden)]
+pub use guard::catch;
+pub use guard::PanicSink;
+#[cfg(feature = "std")]
+#[doc(hidden)]
+pub use recording::{record_call, Recorder};
+
+/// Marks a field of a `host_api!` builder that has been set.
+#[doc(hidden)]
+pub struct Set;
+
+/// Marks a field of a `host_api!` builder that hasn't been set yet.
+#[doc(hidden)]
+pub struct Unset;
+
+/// The version of the `ReloadApi` layout.
+///
+/// This is bumped whenever the fields of `ReloadApi` change, or a host starts
+/// requiring something more of what a library exports, so that a host can
+/// refuse a library that was built against an incompatible version of this
+/// crate.
+pub const ABI_VERSION: u32 = 17;
+
+/// The version of `live-reload` itself, which is part of a [`BuildId`][].
+///
+/// [`BuildId`]: struct.BuildId.html
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Identifies what a library was built with, beyond what the
+/// [`ABI_VERSION`][] covers.
+///
+/// [`ABI_VERSION`]: constant.ABI_VERSION.html
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildId {
+    /// The [`VERSION`][] of `live-reload` the library was built with.
+    ///
+    /// [`VERSION`]: constant.VERSION.html
+    pub version: &'static str,
+    /// The tag given to `live_reload!`, or an empty string.
+    pub tag: &'static str,
+}
+
+impl ::std::fmt::Display for BuildId {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        if self.tag.is_empty() {
+            write!(fmt, "{}", self.version)
+        } else {
+            write!(fmt, "{}+{}", self.version, self.tag)
+        }
+    }
+}
+
+/// How a crate was built to handle panics, as set by `panic` in its Cargo
+/// profile.
+///
+/// A library exports the strategy it was built with, and a host refuses one
+/// that differs from its own (see [`Builder::check_panic_strategy`][]).
+///
+/// [`Builder::check_panic_strategy`]: ../struct.Builder.html#method.check_panic_strategy
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PanicStrategy {
+    /// Panics unwind the stack, and can be caught.
+    Unwind = 1,
+    /// Panics abort the process straight away.
+    Abort = 2,
+}
+
+impl PanicStrategy {
+    /// The strategy of the crate this is compiled into.
+    ///
+    /// Every crate in a build shares the final artifact's strategy, so for
+    /// the host this is the host's own.
+    pub const fn current() -> Self {
+        if cfg!(panic = "unwind") {
+            PanicStrategy::Unwind
+        } else {
+            PanicStrategy::Abort
+        }
+    }
+
+    /// The strategy stored as `raw` in a library's metadata, if it's one
+    /// this version knows.
+    pub fn from_raw(raw: u8) -> Option<Self> {
+        match raw {
+            1 => Some(PanicStrategy::Unwind),
+            2 => Some(PanicStrategy::Abort),
+            _ => None,
+        }
+    }
+}
+
+impl ::std::fmt::Display for PanicStrategy {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            PanicStrategy::Unwind => write!(fmt, "unwind"),
+            PanicStrategy::Abort => write!(fmt, "abort"),
+        }
+    }
+}
+
+/// Whether a crate was built with `debug_assertions`, as in Cargo's `dev`
+/// profile, or without, as in `release`.
+///
+/// A library exports the profile it was built with, which is reported by
+/// [`LibraryInfo::is_debug_build`][] and can be required with
+/// [`Builder::require_build_profile`][].
+///
+/// [`LibraryInfo::is_debug_build`]: ../struct.LibraryInfo.html#method.is_debug_build
+/// [`Builder::require_build_profile`]: ../struct.Builder.html#method.require_build_profile
+#[repr(u8)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    /// Built with `debug_assertions`.
+    Debug = 1,
+    /// Built without `debug_assertions`.
+    Release = 2,
+}
+
+impl Profile {
+    /// The profile of the crate this is compiled into.
+    pub const fn current() -> Self {
+        if cfg!(debug_assertions) {
+            Profile::Debug
+        } else {
+            Profile::Release
+        }
+    }
+
+    /// The profile stored as `raw` in a library's metadata, if it's one this
+    /// version knows.
+    pub fn from_raw(raw: u8) -> Option<Self> {
+        match raw {
+            1 => Some(Profile::Debug),
+            2 => Some(Profile::Release),
+            _ => None,
+        }
+    }
+}
+
+impl ::std::fmt::Display for Profile {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        match *self {
+            Profile::Debug => write!(fmt, "debug"),
+            Profile::Release => write!(fmt, "release"),
+        }
+    }
+}
+
+/// The optional `ReloadApi` functions a library implements.
+///
+/// A library sets the bit for each optional function it provides, and the
+/// host only calls one whose bit is set. So a host can load a library built
+/// before some function existed, and ignores any bits it doesn't know about
+/// from a library built after. This is returned by
+/// [`LibraryInfo::capabilities`][].
+///
+/// ```rust
+/// # extern crate live_reload;
+/// # fn main() {
+/// use live_reload::Capabilities;
+///
+/// let capabilities = Capabilities::from_bits(0b1000000011);
+/// assert!(capabilities.contains(Capabilities::IS_SAFE_TO_RELOAD | Capabilities::RELOAD_PROGRESS));
+/// assert_eq!(capabilities.to_string(), "is_safe_to_reload | reload_progress | 0x200");
+/// # }
+/// ```
+///
+/// [`LibraryInfo::capabilities`]: ../struct.LibraryInfo.html#method.capabilities
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// The library has an `is_safe_to_reload` function.
+    pub const IS_SAFE_TO_RELOAD: Capabilities = Capabilities(1 << 0);
+    /// The library has a `reload_progress` function.
+    pub const RELOAD_PROGRESS: Capabilities = Capabilities(1 << 1);
+    /// The library's `update` takes the host's input events.
+    pub const UPDATE_WITH_EVENTS: Capabilities = Capabilities(1 << 2);
+    /// The library's `update` borrows extra data from the host.
+    pub const UPDATE_WITH_EXTRA: Capabilities = Capabilities(1 << 3);
+    /// The library has a `validate_state` function.
+    pub const VALIDATE_STATE: Capabilities = Capabilities(1 << 4);
+    /// The library has a `requirements` function.
+    pub const REQUIREMENTS: Capabilities = Capabilities(1 << 5);
+    /// The library has a table of `State` migrations.
+    pub const MIGRATIONS: Capabilities = Capabilities(1 << 6);
+    /// The library has `serialize` and `deserialize` functions.
+    pub const SERIALIZE: Capabilities = Capabilities(1 << 7);
+    /// The library has a `persistent_range` function.
+    pub const PERSISTENT_RANGE: Capabilities = Capabilities(1 << 8);
+
+    /// Every capability this version of `live-reload` knows about, with its
+    /// name.
+    const NAMED: [(Capabilities, &'static str); 9] = [
+        (Capabilities::IS_SAFE_TO_RELOAD, "is_safe_to_reload"),
+        (Capabilities::RELOAD_PROGRESS, "reload_progress"),
+        (Capabilities::UPDATE_WITH_EVENTS, "update_with_events"),
+        (Capabilities::UPDATE_WITH_EXTRA, "update_with_extra"),
+        (Capabilities::VALIDATE_STATE, "validate_state"),
+        (Capabilities::REQUIREMENTS, "requirements"),
+        (Capabilities::MIGRATIONS, "migrations"),
+        (Capabilities::SERIALIZE, "serialize"),
+        (Capabilities::PERSISTENT_RANGE, "persistent_range"),
+    ];
+
+    /// No optional functions.
+    pub const fn empty() -> Self {
+        Capabilities(0)
+    }
+
+    /// The capabilities in the raw `bits`, keeping any unknown ones.
+    pub const fn from_bits(bits: u32) -> Self {
+        Capabilities(bits)
+    }
+
+    /// The raw bits, as stored in `ReloadApi::capabilities`.
+    pub const fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether every capability in `other` is also in `self`.
+    pub const fn contains(self, other: Capabilities) -> bool {
+        self.0 & other.0 == other.0
+    }
+
+    /// Whether there are no capabilities at all.
+    pub const fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+}
+
+impl ::std::ops::BitOr for Capabilities {
+    type Output = Capabilities;
+
+    fn bitor(self, other: Capabilities) -> Capabilities {
+        Capabilities(self.0 | other.0)
+    }
+}
+
+/// Lists the capabilities by name, like `is_safe_to_reload | reload_progress`,
+/// or `none`. Unknown bits are shown in hex.
+impl ::std::fmt::Display for Capabilities {
+    fn fmt(&self, fmt: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+        if self.is_empty() {
+            return write!(fmt, "none");
+        }
+        let mut rest = self.0;
+        let mut separator = "";
+        for &(capability, name) in &Capabilities::NAMED {
+            if self.contains(capability) {
+                write!(fmt, "{}{}", separator, name)?;
+                rest &= !capability.0;
+                separator = " | ";
+            }
+        }
+        if rest != 0 {
+            write!(fmt, "{}{:#x}", separator, rest)?;
+        }
+        Ok(())
+    }
+}
+
+/// The starting point of a layout hash, before any fields are hashed in.
+///
+/// Layout hashes are FNV-1a, so that they come out the same on every
+/// platform and with every compiler.
+#[doc(hidden)]
+pub const LAYOUT_HASH_SEED: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Hash `part` into `hash`, followed by a byte that can't appear in UTF-8,
+/// so that neighboring parts can't run together.
+#[doc(hidden)]
+pub const fn hash_str(hash: u64, part: &str) -> u64 {
+    hash_bytes(hash_bytes(hash, part.as_bytes()), &[0xff])
+}
+
+/// Hash `n` into `hash`, as a little-endian `u64`.
+#[doc(hidden)]
+pub const fn hash_usize(hash: u64, n: usize) -> u64 {
+    hash_bytes(hash, &(n as u64).to_le_bytes())
+}
+
+/// The fingerprint of a `Host` type, which a library exports as
+/// `RELOAD_HOST_HASH` for the host to compare with its own.
+///
+/// Only the size and alignment go into it, since those are all that's known
+/// the same way on both sides, so it can't tell apart two `Host`s that agree
+/// on them.
+pub const fn host_hash<Host>() -> u64 {
+    hash_usize(hash_usize(LAYOUT_HASH_SEED, size_of::<Host>()), align_of::<Host>())
+}
+
+/// Hash `bytes` on their own, the same way as a layout hash.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    hash_bytes(LAYOUT_HASH_SEED, bytes)
+}
+
+const fn hash_bytes(mut hash: u64, bytes: &[u8]) -> u64 {
+    let mut i = 0;
+    while i < bytes.len() {
+        hash ^= bytes[i] as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+        i += 1;
+    }
+    hash
+}
+
+/// The form of `reload` that reports its progress, from `0.0` to `1.0`,
+/// through the `fn(f32)` it's given.
+pub type ReloadProgress<Host> = fn(&mut Host, *mut (), fn(f32), &mut PanicSink);
+
+/// The form of `update` that's also given the host's input events, as a
+/// pointer to the first one, the number of them, and the size of each.
+pub type UpdateWithEvents<Host> =
+    fn(&mut Host, *mut (), *const (), usize, usize, &mut PanicSink) -> super::ShouldQuit;
+
+/// The form of `update` that borrows extra data from the host for the call,
+/// as a pointer to it and its size.
+pub type UpdateWithExtra<Host> =
+    fn(&mut Host, *mut (), *mut (), usize, &mut PanicSink) -> super::ShouldQuit;
+
+/// The form of `is_safe_to_reload`, which looks at the State and says
+/// whether a reload can happen now.
+pub type IsSafeToReload = fn(*const (), &mut PanicSink) -> bool;
+
+/// The form of `validate_state`, which looks at the State and says whether
+/// it's consistent.
+pub type ValidateState = fn(*const (), &mut PanicSink) -> bool;
+
+/// The form of `requirements`, which says how big the resources the host
+/// provides the library should be, or nothing if it panicked.
+pub type Requirements = fn(&mut PanicSink) -> Option<HostRequirements>;
+
+/// The form of `migrate`, which takes the `State` from the version it's
+/// given to the next one, given the bytes of the `State` the last version
+/// left.
+pub type Migrate<Host> = fn(&mut Host, *mut (), u32, &[u8], &mut PanicSink);
+
+/// The form of `serialize`, which writes the `State` out, and returns
+/// whether it all went through.
+pub type Serialize = fn(*const (), &mut StateWriter, &mut PanicSink) -> bool;
+
+/// The form of `deserialize`, which reads the `State` back in, and returns
+/// whether it all went through.
+pub type Deserialize = fn(*mut (), &mut StateReader, &mut PanicSink) -> bool;
+
+/// The form of `persistent_range`, which says which bytes of the `State`
+/// survive a restart, as their offset and length.
+pub type PersistentRange = fn(&mut PanicSink) -> (usize, usize);
+
+/// Where a library's `serialize` writes the `State` to.
+///
+/// This is the host's writer, passed as a context pointer and a function,
+/// so that it doesn't depend on how either side lays out a `dyn Write`. The
+/// library writes to it as a `std::io::Write`.
+#[repr(C)]
+pub struct StateWriter {
+    context: *mut (),
+    write: fn(*mut (), *const u8, usize) -> bool,
+}
+
+impl StateWriter {
+    /// A writer that appends to `out`.
+    #[cfg(feature = "std")]
+    pub(crate) fn to_vec(out: &mut Vec<u8>) -> Self {
+        StateWriter {
+            context: out as *mut Vec<u8> as *mut (),
+            write: |context, bytes, len| {
+                let out = unsafe { &mut *(context as *mut Vec<u8>) };
+                out.extend_from_slice(unsafe { ::std::slice::from_raw_parts(bytes, len) });
+                true
+            },
+        }
+    }
+}
+
+impl ::std::io::Write for StateWriter {
+    fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+        if (self.write)(self.context, buf.as_ptr(), buf.len()) {
+            Ok(buf.len())
+        } else {
+            Err(::std::io::Error::other("the host couldn't take the serialized State"))
+        }
+    }
+
+    fn flush(&mut self) -> ::std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Where a library's `deserialize` reads the `State` from.
+///
+/// Like a [`StateWriter`][], this is the host's reader as a context pointer
+/// and a function, which the library reads from as a `std::io::Read`.
+///
+/// [`StateWriter`]: struct.StateWriter.html
+#[repr(C)]
+pub struct StateReader {
+    context: *mut (),
+    /// Fills up to `len` bytes, and returns how many it filled, with 0 at
+    /// the end.
+    read: fn(*mut (), *mut u8, usize) -> usize,
+}
+
+impl StateReader {
+    /// A reader that takes bytes off the front of `input`.
+    #[cfg(feature = "std")]
+    pub(crate) fn from_slice(input: &mut &[u8]) -> Self {
+        StateReader {
+            context: input as *mut &[u8] as *mut (),
+            read: |context, bytes, len| {
+                let input = unsafe { &mut *(context as *mut &[u8]) };
+                let buf = unsafe { ::std::slice::from_raw_parts_mut(bytes, len) };
+                ::std::io::Read::read(input, buf).unwrap_or(0)
+            },
+        }
+    }
+}
+
+impl ::std::io::Read for StateReader {
+    fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+        Ok((self.read)(self.context, buf.as_mut_ptr(), buf.len()))
+    }
+}
+
+/// The sizes of the resources a library wants the host to set aside for it,
+/// like a scratch arena that has to outlive reloads, as reported by its
+/// `requirements` function.
+///
+/// The host reads these with [`Reloadable::requirements`][] before `init`,
+/// and allocates accordingly. Besides `scratch_bytes`, what each of the
+/// `buffers` is for is up to the library and the host to agree on, like the
+/// fields of the `Host` itself. Anything unused is zero.
+///
+/// [`Reloadable::requirements`]: ../struct.Reloadable.html#method.requirements
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct HostRequirements {
+    /// The size in bytes of the scratch memory the library wants.
+    pub scratch_bytes: u64,
+    /// The sizes of up to eight more resources, in whatever unit the library
+    /// and the host agree on.
+    pub buffers: [u64; 8],
+}
+
+/// Contains function pointers for all the parts of the reloadable object lifecycle.
+#[repr(C)]
+pub struct ReloadApi<Host> {
+    /// The [`ABI_VERSION`][] the library was built with.
+    ///
+    /// This must stay the first field, so that it can be read before
+    /// trusting anything else about the layout.
+    ///
+    /// [`ABI_VERSION`]: constant.ABI_VERSION.html
+    pub abi_version: u32,
+    /// The bits of the [`Capabilities`][] the library implements.
+    ///
+    /// An optional function whose bit isn't set is never called, even if
+    /// it's there.
+    ///
+    /// [`Capabilities`]: struct.Capabilities.html
+    pub capabilities: u32,
+    /// Returns the size of the State struct so that the host can allocate
+    /// space for it.
+    pub size: fn() -> usize,
+    /// Initializes the State struct when the program is first started.
+    ///
+    /// Each of the lifecycle functions reports a panic to the
+    /// [`PanicSink`][] it's given, rather than letting it unwind into the
+    /// host.
+    ///
+    /// [`PanicSink`]: type.PanicSink.html
+    pub init: fn(&mut Host, *mut (), &mut PanicSink),
+    /// Makes any necessary updates when the program is reloaded.
+    ///
+    /// This will probably be normally empty. If you changed the State
+    /// struct since the last compile, then it won't necessarily be
+    /// correctly initialized. For safety, you should make your State struct
+    /// `#[repr(C)]` and only add members at the end.
+    pub reload: fn(&mut Host, *mut (), &mut PanicSink),
+    /// Update the
+    pub update: fn(&mut Host, *mut (), &mut PanicSink) -> super::ShouldQuit,
+    /// Prepare for the library to be unloaded before a new version loads.
+    ///
+    /// This will probably normally be empty except for short periods in
+    /// development when you're making lots of live changes and need to do
+    /// some kind of migration.
+    pub unload: fn(&mut Host, *mut (), &mut PanicSink),
+    /// Do final shutdowns before the program completely quits.
+    pub deinit: fn(&mut Host, *mut (), &mut PanicSink),
+    /// Says whether the State is somewhere a reload can safely happen.
+    ///
+    /// A changed library isn't swapped in by `reload` while this returns
+    /// false, or panics. Reloading is always safe if this is `None`, or
+    /// `capabilities` doesn't have `IS_SAFE_TO_RELOAD`.
+    pub is_safe_to_reload: Option<IsSafeToReload>,
+    /// Like `reload`, but also given a function to report its progress
+    /// through, from `0.0` to `1.0`.
+    ///
+    /// This is called instead of `reload` when the host has set a
+    /// progress function with [`Reloadable::set_reload_progress`][], and
+    /// `capabilities` has `RELOAD_PROGRESS`.
+    ///
+    /// [`Reloadable::set_reload_progress`]: ../struct.Reloadable.html#method.set_reload_progress
+    pub reload_progress: Option<ReloadProgress<Host>>,
+    /// Like `update`, but also given a slice of the host's input events.
+    ///
+    /// This is called instead of `update` by
+    /// [`Reloadable::update_with_events`][], when `capabilities` has
+    /// `UPDATE_WITH_EVENTS`. The library checks that the size of an event
+    /// matches its own event type, and panics if it doesn't.
+    ///
+    /// [`Reloadable::update_with_events`]: ../struct.Reloadable.html#method.update_with_events
+    pub update_with_events: Option<UpdateWithEvents<Host>>,
+    /// Like `update`, but also given a mutable borrow of data the host
+    /// lends it for the call.
+    ///
+    /// This is called instead of `update` by [`Reloadable::update_with`][],
+    /// when `capabilities` has `UPDATE_WITH_EXTRA`. The library checks that
+    /// the size of the data matches its own type, and panics if it doesn't.
+    ///
+    /// [`Reloadable::update_with`]: ../struct.Reloadable.html#method.update_with
+    pub update_with_extra: Option<UpdateWithExtra<Host>>,
+    /// Checks that the State is consistent, after a reload.
+    ///
+    /// This is called by [`Reloadable::reload_now`][] once `reload` has
+    /// run, when `capabilities` has `VALIDATE_STATE`, and the reload is
+    /// rolled back if it returns false. A panic counts as false.
+    ///
+    /// [`Reloadable::reload_now`]: ../struct.Reloadable.html#method.reload_now
+    pub validate_state: Option<ValidateState>,
+    /// Says how big the resources the host provides should be.
+    ///
+    /// This is called by [`Reloadable::requirements`][], when
+    /// `capabilities` has `REQUIREMENTS`.
+    ///
+    /// [`Reloadable::requirements`]: ../struct.Reloadable.html#method.requirements
+    pub requirements: Option<Requirements>,
+    /// The version of the State, which is the number of steps `migrate`
+    /// knows, when `capabilities` has `MIGRATIONS`.
+    pub state_version: u32,
+    /// Migrates the State from the version it's given to the next.
+    ///
+    /// [`Reloadable::reload_now`][] calls this for each version from the
+    /// one of the state it has up to `state_version`, before `reload`, when
+    /// `capabilities` has `MIGRATIONS`.
+    ///
+    /// [`Reloadable::reload_now`]: ../struct.Reloadable.html#method.reload_now
+    pub migrate: Option<Migrate<Host>>,
+    /// Writes the State out in the library's own format, for
+    /// [`Reloadable::save_state`][], when `capabilities` has `SERIALIZE`.
+    ///
+    /// [`Reloadable::save_state`]: ../struct.Reloadable.html#method.save_state
+    pub serialize: Option<Serialize>,
+    /// Reads back what `serialize` wrote, over the State, for
+    /// [`Reloadable::load_state`][], when `capabilities` has `SERIALIZE`.
+    ///
+    /// [`Reloadable::load_state`]: ../struct.Reloadable.html#method.load_state
+    pub deserialize: Option<Deserialize>,
+    /// Says which bytes of the State [`Reloadable::restart`][] keeps, when
+    /// `capabilities` has `PERSISTENT_RANGE`.
+    ///
+    /// [`Reloadable::restart`]: ../struct.Reloadable.html#method.restart
+    pub persistent_range: Option<PersistentRange>,
+}
+
+// These can't be derived, since that would require `Host: Copy`.
+impl<Host> Clone for ReloadApi<Host> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<Host> Copy for ReloadApi<Host> {}
+
+impl<Host> ReloadApi<Host> {
+    /// The optional functions the library says it implements.
+    pub fn capabilities(&self) -> Capabilities {
+        Capabilities::from_bits(self.capabilities)
+    }
+
+    /// Call `reload`, or `reload_progress` if both the library and the
+    /// host support reporting progress.
+    #[cfg(feature = "std")]
+    pub(crate) fn call_reload(
+        &self,
+        host: &mut Host,
+        state: *mut (),
+        progress: Option<fn(f32)>,
+        sink: &mut PanicSink,
+    ) {
+        let reload_progress = if self.capabilities().contains(Capabilities::RELOAD_PROGRESS) {
+            self.reload_progress
+        } else {
+            None
+        };
+        match (reload_progress, progress) {
+            (Some(reload_progress), Some(progress)) => reload_progress(host, state, progress, sink),
+            _ => (self.reload)(host, state, sink),
+        }
+    }
+
+    /// Call `update_with_events` with `events`, or `update` without them if
+    /// the library doesn't take events.
+    pub(crate) fn call_update<E>(
+        &self,
+        host: &mut Host,
+        state: *mut (),
+        events: &[E],
+        sink: &mut PanicSink,
+    ) -> super::ShouldQuit {
+        match self.update_with_events {
+            Some(update) if self.capabilities().contains(Capabilities::UPDATE_WITH_EVENTS) => {
+                let raw = events.as_ptr() as *const ();
+                update(host, state, raw, events.len(), size_of::<E>(), sink)
+            }
+            _ => (self.update)(host, state, sink),
+        }
+    }
+
+    /// Call `update_with_extra` with `extra`, or `update` without it if the
+    /// library doesn't take any.
+    pub(crate) fn call_update_with<T>(
+        &self,
+        host: &mut Host,
+        state: *mut (),
+        extra: &mut T,
+        sink: &mut PanicSink,
+    ) -> super::ShouldQuit {
+        match self.update_with_extra {
+            Some(update) if self.capabilities().contains(Capabilities::UPDATE_WITH_EXTRA) => {
+                update(host, state, extra as *mut T as *mut (), size_of::<T>(), sink)
+            }
+            _ => (self.update)(host, state, sink),
+        }
+    }
+
+    /// Ask `validate_state` whether `state` is consistent, if the library
+    /// has it. A panic counts as inconsistent.
+    #[cfg(feature = "std")]
+    pub(crate) fn state_is_valid(&self, state: *const ()) -> bool {
+        match self.validate_state {
+            Some(validate_state) if self.capabilities().contains(Capabilities::VALIDATE_STATE) => {
+                ::guard::call(::guard::LifecyclePhase::Reload, |sink| validate_state(state, sink)).unwrap_or(false)
+            }
+            _ => true,
+        }
+    }
+
+    /// Ask `requirements` for the library's requirements, if it has it.
+    #[cfg(feature = "std")]
+    pub(crate) fn host_requirements(&self) -> Option<Result<HostRequirements, super::Error>> {
+        match self.requirements {
+            Some(requirements) if self.capabilities().contains(Capabilities::REQUIREMENTS) => {
+                match ::guard::call(::guard::LifecyclePhase::Requirements, requirements) {
+                    Ok(found) => found.map(Ok),
+                    Err(err) => Some(Err(err)),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The library's version of the `State`, and the function that
+    /// migrates to it, or 0 and none if it has no migrations.
+    #[cfg(feature = "std")]
+    pub(crate) fn state_migrations(&self) -> (u32, Option<Migrate<Host>>) {
+        match self.migrate {
+            Some(migrate) if self.capabilities().contains(Capabilities::MIGRATIONS) => {
+                (self.state_version, Some(migrate))
+            }
+            _ => (0, None),
+        }
+    }
+
+    /// Write `state` out with `serialize`, if the library has it. A panic,
+    /// or a write that doesn't go through, gives `None`.
+    #[cfg(feature = "std")]
+    pub(crate) fn serialize_state(&self, state: *const ()) -> Option<Vec<u8>> {
+        match self.serialize {
+            Some(serialize) if self.capabilities().contains(Capabilities::SERIALIZE) => {
+                let mut out = Vec::new();
+                let mut writer = StateWriter::to_vec(&mut out);
+                let written = ::guard::call(::guard::LifecyclePhase::Serialize, |sink| serialize(state, &mut writer, sink));
+                if written.unwrap_or(false) { Some(out) } else { None }
+            }
+            _ => None,
+        }
+    }
+
+    /// Read `bytes` over `state` with `deserialize`, and say whether that
+    /// went through, or `None` if the library doesn't have it. A panic
+    /// counts as not going through.
+    #[cfg(feature = "std")]
+    pub(crate) fn deserialize_state(&self, state: *mut (), mut bytes: &[u8]) -> Option<bool> {
+        match self.deserialize {
+            Some(deserialize) if self.capabilities().contains(Capabilities::SERIALIZE) => {
+                let mut reader = StateReader::from_slice(&mut bytes);
+                let read = ::guard::call(::guard::LifecyclePhase::Deserialize, |sink| deserialize(state, &mut reader, sink));
+                Some(read.unwrap_or(false))
+            }
+            _ => None,
+        }
+    }
+
+    /// Ask `persistent_range` which bytes of the State to keep through a
+    /// restart, as a range, if the library has it.
+    #[cfg(feature = "std")]
+    pub(crate) fn persistent_bytes(&self) -> Result<Option<::std::ops::Range<usize>>, super::Error> {
+        match self.persistent_range {
+            Some(persistent_range) if self.capabilities().contains(Capabilities::PERSISTENT_RANGE) => {
+                let (offset, len) = ::guard::call(::guard::LifecyclePhase::PersistentRange, persistent_range)?;
+                Ok(Some(offset..offset.saturating_add(len)))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Ask `is_safe_to_reload` about `state`, if the library has it. A panic
+    /// counts as not safe.
+    #[cfg(feature = "std")]
+    pub(crate) fn allows_reload(&self, state: *const ()) -> bool {
+        match self.is_safe_to_reload {
+            Some(is_safe_to_reload) if self.capabilities().contains(Capabilities::IS_SAFE_TO_RELOAD) => {
+                ::guard::call(::guard::LifecyclePhase::IsSafeToReload, |sink| is_safe_to_reload(state, sink))
+                    .unwrap_or(false)
+            }
+            _ => true,
+        }
+    }
+
+    /// Check that none of the required function pointers of the
+    /// `ReloadApi` behind `api` are null, nor any optional ones that its
+    /// `capabilities` claim.
+    ///
+    /// A `fn` can never be null in Rust, so a library that gets this
+    /// wrong is corrupt, and calling into it would jump to address zero.
+    /// This only catches the null case: any other garbage pointer still
+    /// can't be told apart from a real function.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// use live_reload::Error;
+    /// use live_reload::internals::ReloadApi;
+    ///
+    /// let zeroed = [0usize; 16];
+    /// let api = zeroed.as_ptr() as *const ReloadApi<()>;
+    /// match unsafe { ReloadApi::validate(api) } {
+    ///     Err(Error::InvalidApi { function: "size" }) => {}
+    ///     other => panic!("expected InvalidApi, got {:?}", other),
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// # Safety
+    ///
+    /// `api` must point to a readable `ReloadApi`-sized block of memory,
+    /// whose `abi_version` matches [`ABI_VERSION`][].
+    ///
+    /// [`ABI_VERSION`]: constant.ABI_VERSION.html
+    pub unsafe fn validate(api: *const Self) -> Result<(), super::Error> {
+        // Read each pointer as a plain address, since a null `fn` can't
+        // be held onto without undefined behavior.
+        let functions = [
+            ("size", ::std::ptr::addr_of!((*api).size) as *const usize),
+            ("init", ::std::ptr::addr_of!((*api).init) as *const usize),
+            ("reload", ::std::ptr::addr_of!((*api).reload) as *const usize),
+            ("update", ::std::ptr::addr_of!((*api).update) as *const usize),
+            ("unload", ::std::ptr::addr_of!((*api).unload) as *const usize),
+            ("deinit", ::std::ptr::addr_of!((*api).deinit) as *const usize),
+        ];
+        for &(function, address) in &functions {
+            if ::std::ptr::read_unaligned(address) == 0 {
+                return Err(super::Error::InvalidApi { function });
+            }
+        }
+        // An optional function is only required once its bit is set.
+        let capabilities = ::std::ptr::read(::std::ptr::addr_of!((*api).capabilities));
+        let capabilities = Capabilities::from_bits(capabilities);
+        let optional = [
+            (
+                Capabilities::IS_SAFE_TO_RELOAD,
+                "is_safe_to_reload",
+                ::std::ptr::addr_of!((*api).is_safe_to_reload) as *const usize,
+            ),
+            (
+                Capabilities::RELOAD_PROGRESS,
+                "reload_progress",
+                ::std::ptr::addr_of!((*api).reload_progress) as *const usize,
+            ),
+            (
+                Capabilities::UPDATE_WITH_EVENTS,
+                "update_with_events",
+                ::std::ptr::addr_of!((*api).update_with_events) as *const usize,
+            ),
+            (
+                Capabilities::UPDATE_WITH_EXTRA,
+                "update_with_extra",
+                ::std::ptr::addr_of!((*api).update_with_extra) as *const usize,
+            ),
+            (
+                Capabilities::VALIDATE_STATE,
+                "validate_state",
+                ::std::ptr::addr_of!((*api).validate_state) as *const usize,
+            ),
+            (
+                Capabilities::REQUIREMENTS,
+                "requirements",
+                ::std::ptr::addr_of!((*api).requirements) as *const usize,
+            ),
+            (
+                Capabilities::MIGRATIONS,
+                "migrate",
+                ::std::ptr::addr_of!((*api).migrate) as *const usize,
+            ),
+            (
+                Capabilities::SERIALIZE,
+                "serialize",
+                ::std::ptr::addr_of!((*api).serialize) as *const usize,
+            ),
+            (
+                Capabilities::SERIALIZE,
+                "deserialize",
+                ::std::ptr::addr_of!((*api).deserialize) as *const usize,
+            ),
+            (
+                Capabilities::PERSISTENT_RANGE,
+                "persistent_range",
+                ::std::ptr::addr_of!((*api).persistent_range) as *const usize,
+            ),
+        ];
+        for &(capability, function, address) in &optional {
+            if capabilities.contains(capability) && ::std::ptr::read_unaligned(address) == 0 {
+                return Err(super::Error::InvalidApi { function });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// One named module of a library that exports several.
+#[repr(C)]
+pub struct ReloadApiEntry<Host: 'static> {
+    /// The name of the module, which identifies it across reloads.
+    pub name: &'static str,
+    /// The lifecycle functions of the module.
+    pub api: ReloadApi<Host>,
+}
+
+/// The modules exported by a library, as declared by `live_reload_multi!`.
+#[repr(C)]
+pub struct ReloadApiTable<Host: 'static> {
+    /// The [`ABI_VERSION`][] the library was built with.
+    ///
+    /// Like in `ReloadApi`, this must stay the first field.
+    ///
+    /// [`ABI_VERSION`]: constant.ABI_VERSION.html
+    pub abi_version: u32,
+    /// The modules, in the order they should be updated.
+    pub entries: &'static [ReloadApiEntry<Host>],
+}
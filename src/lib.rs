@@ -1,5 +1,5 @@
 // Support using part of the library without the standard library!
-#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(not(any(feature = "std", feature = "static")), no_std)]
 #![deny(missing_docs)]
 
 //! A library for doing live-reloading game development.
@@ -92,6 +92,10 @@
 //! }
 //! ```
 //!
+//! A host without a main loop, say one driven by events, can have the reloads
+//! happen on a background thread instead, with the `autoreload` feature and
+//! [`Reloadable::spawn_autoreload`][].
+//!
 //! # Library Example
 //!
 //! A live-reloadable library needs to register its entry-points so that the
@@ -117,7 +121,7 @@
 //!   even more often than `reload`, but you might need it for some debugging or
 //!   data migration purpose.
 //! - `deinit` gets called when the host program is actually shutting down--it's
-//!   called on the drop of the [`Reloadable`][].
+//!   called on the drop of the [`Reloadable`][], or by [`shutdown`][].
 //!
 //! Here's an example of a live-reloadable library that handles a counter.
 //!
@@ -181,12 +185,115 @@
 //! time that [`save_state`][] was called, while the [`load_state`][] method accepts
 //! a reference to a [`SaveState`][] struct, and loads the saved state.
 //!
+//! With the `mmap` feature enabled on Unix, [`new_mmap`][] keeps the state in
+//! a memory-mapped file instead, so that it survives the host crashing and can
+//! be picked up again by the next run. With the `shm` feature, [`new_shared`][]
+//! keeps it in a named shared-memory segment, so that several host processes
+//! can share one state.
+//!
+//! # Safety
+//!
+//! Loading a library runs its code, and the `State` handed to it is only a
+//! buffer of bytes as far as the host knows, so a host has to trust the
+//! library it loads to be a `live_reload!` library built against the same
+//! `Host`. The ABI version, build id, and `ReloadApi` checks catch the usual
+//! ways of getting that wrong, but can't prove it. Beyond that trust, the
+//! lifecycle API is safe: loading, reloading, updating, saving and restoring
+//! state, and the [`Builder`][] options all work without any `unsafe` on the
+//! host's side, so a host can use them under `#![forbid(unsafe_code)]`.
+//!
+//! When the library comes from somewhere less trusted, like a plugin
+//! download, the `verify` feature adds [`Builder::verify_signature`][], which
+//! checks each version of the library against a detached signature before
+//! it's opened.
+//!
+//! ```rust,no_run
+//! #![forbid(unsafe_code)]
+//! # extern crate live_reload;
+//! # struct Host;
+//! use live_reload::{Reloadable, ShouldQuit};
+//!
+//! # fn main() {
+//! let mut app = Reloadable::new("target/debug/libreload.so", Host)
+//!     .expect("Should successfully load");
+//! let save = app.save_state();
+//! while app.update() == ShouldQuit::No {
+//!     app.reload().expect("Should successfully reload");
+//! }
+//! app.load_state(&save);
+//! # }
+//! ```
+//!
+//! The few operations that can break the library's assumptions are marked
+//! `unsafe`, with the conditions to uphold under their `# Safety` heading:
+//!
+//! - [`Reloadable::state_bytes_mut`][] lets you write arbitrary bytes into
+//!   the `State`.
+//! - [`Reloadable::get_symbol`][] reads any symbol as whatever type you ask
+//!   for.
+//! - [`Reloadable::update_with_events`][] and [`Reloadable::update_with`][]
+//!   hand the library a value of whatever type you pass, checking only its
+//!   size against the library's.
+//! - [`StateStore`][] is an `unsafe trait`, since the library writes through
+//!   the pointer it hands out.
+//!
+//! The safe ways of writing the `State`, which are loading a save, starting
+//! from one with [`Reloadable::new_with_state`][], restoring a snapshot,
+//! applying a diff, copying it between `Reloadable`s, and undo and redo,
+//! only ever write one taken from a `State` of the same size and,
+//! when both libraries report one (see [`reloadable_state!`][]), the same
+//! layout. Anything else is refused with an error, or a panic where the
+//! method doesn't return a `Result`.
+//!
+//! The items in [`internals`][] are for the macros, and mostly deal in raw
+//! pointers, so a host shouldn't need them.
+//!
+//! All that raw pointer handling is checked with [Miri][]. Miri can't load a
+//! dynamic library, but [`Reloadable::new_in_process`][] and
+//! [`Reloadable::with_mock`][] run the same `ReloadApi`, state buffer and
+//! lifecycle code without one, and without touching the filesystem, as does
+//! [`MultiReloadable::new_in_process`][] for each module's state. The
+//! tests in `tests/miri.rs` drive them through the state buffer, its
+//! alignment, saves and the whole lifecycle:
+//!
+//! ```sh
+//! cargo +nightly miri test --test miri
+//! ```
+//!
+//! [Miri]: https://github.com/rust-lang/miri
+//! [`Reloadable::new_in_process`]: struct.Reloadable.html#method.new_in_process
+//! [`Reloadable::with_mock`]: struct.Reloadable.html#method.with_mock
+//! [`MultiReloadable::new_in_process`]: struct.MultiReloadable.html#method.new_in_process
+//! [`Builder`]: struct.Builder.html
+//! [`Builder::verify_signature`]: struct.Builder.html#method.verify_signature
+//! [`Reloadable::spawn_autoreload`]: struct.Reloadable.html#method.spawn_autoreload
+//! [`Reloadable::state_bytes_mut`]: struct.Reloadable.html#method.state_bytes_mut
+//! [`Reloadable::get_symbol`]: struct.Reloadable.html#method.get_symbol
+//! [`Reloadable::update_with_events`]: struct.Reloadable.html#method.update_with_events
+//! [`Reloadable::update_with`]: struct.Reloadable.html#method.update_with
+//! [`Reloadable::new_with_state`]: struct.Reloadable.html#method.new_with_state
+//! [`StateStore`]: trait.StateStore.html
+//! [`reloadable_state!`]: macro.reloadable_state.html
+//! [`internals`]: internals/index.html
+//!
 //! [`Reloadable`]: struct.Reloadable.html
 //! [`reload`]: struct.Reloadable.html#method.reload
+//! [`shutdown`]: struct.Reloadable.html#method.shutdown
 //! [`save_state`]: struct.Reloadable.html#method.save_state
 //! [`load_state`]: struct.Reloadable.html#method.load_state
+//! [`new_mmap`]: struct.Reloadable.html#method.new_mmap
+//! [`new_shared`]: struct.Reloadable.html#method.new_shared
 //! [`live_reload!`]: macro.live_reload.html
 //! 
+//! # Shipping a Static Build
+//!
+//! For release, the library can be linked straight into the host instead.
+//! With `default-features = false` and the `static` feature, [`Reloadable`][]
+//! binds to the `RELOAD_API` of the library crate linked into the host, and
+//! reloading does nothing, so the same host code drives both builds without
+//! any dynamic loading or filesystem watching. See the `Reloadable` docs in
+//! that configuration for how to set up the features.
+//!
 //! # Support for `no_std` Libraries
 //! 
 //! If you want your library to be `no_std`, then you can import `live-reload`
@@ -199,23 +306,176 @@
 //! default-features = false
 //! features = []
 //! ```
+//!
+//! That leaves out everything that allocates. On a target with an allocator
+//! but no `std`, the `alloc` feature adds back [`SaveState`][],
+//! [`StateDiff`][] and the [`StateStore`][] trait, with its implementation
+//! for `Vec<u64>`, for a loader of your own to keep its `State` in, without
+//! pulling in `libloading` or `notify`.
+//!
+//! [`SaveState`]: struct.SaveState.html
+//! [`StateDiff`]: struct.StateDiff.html
+//! [`StateStore`]: trait.StateStore.html
 
+#[cfg(feature = "alloc")]
+#[macro_use]
+extern crate alloc;
 #[cfg(feature = "std")]
 extern crate notify;
 #[cfg(feature = "std")]
 extern crate libloading;
+#[cfg(all(feature = "std", unix))]
+extern crate libc;
 
 #[cfg(feature = "std")]
 mod with_std;
 #[cfg(feature = "std")]
 pub use with_std::*;
+#[cfg(feature = "std")]
+mod builder;
+#[cfg(feature = "std")]
+pub use builder::Builder;
+#[cfg(feature = "std")]
+mod temp_copy;
+#[cfg(feature = "std")]
+mod history;
+#[cfg(feature = "alloc")]
+mod diff;
+#[cfg(feature = "alloc")]
+pub use diff::StateDiff;
+#[cfg(feature = "alloc")]
+mod save;
+#[cfg(feature = "alloc")]
+pub use save::{Generation, SaveState};
+#[cfg(feature = "std")]
+mod events;
+#[cfg(feature = "std")]
+pub use temp_copy::TEMP_COPY_PREFIX;
+#[cfg(feature = "std")]
+mod open_flags;
+#[cfg(feature = "std")]
+pub use open_flags::OpenFlags;
+#[cfg(feature = "alloc")]
+mod store;
+#[cfg(feature = "alloc")]
+pub use store::StateStore;
+#[cfg(feature = "std")]
+mod snapshot;
+#[cfg(feature = "std")]
+mod complete;
+#[cfg(feature = "verify")]
+mod verify;
+#[cfg(all(feature = "static", not(feature = "std")))]
+mod static_reload;
+#[cfg(all(feature = "static", not(feature = "std")))]
+pub use static_reload::*;
+#[cfg(any(feature = "std", feature = "static"))]
+mod guard;
+#[cfg(any(feature = "std", feature = "static"))]
+pub use guard::{LifecyclePhase, PanicLocation};
+#[cfg(any(feature = "std", feature = "static"))]
+pub mod internals;
+#[cfg(any(feature = "std", feature = "static"))]
+pub use internals::{Capabilities, HostRequirements, PanicStrategy, Profile};
+#[cfg(feature = "std")]
+mod multi;
+#[cfg(feature = "std")]
+pub use multi::MultiReloadable;
+#[cfg(feature = "std")]
+mod trigger;
+#[cfg(feature = "std")]
+mod typed;
+#[cfg(feature = "std")]
+pub use typed::{StateMirror, TypedReloadable};
+#[cfg(feature = "std")]
+mod build_command;
+#[cfg(feature = "std")]
+pub use trigger::ReloadTrigger;
+#[cfg(feature = "std")]
+mod metrics;
+#[cfg(feature = "std")]
+pub use metrics::ReloadMetrics;
+#[cfg(feature = "std")]
+mod watchdog;
+#[cfg(feature = "std")]
+mod generational;
+#[cfg(feature = "std")]
+pub use generational::Generational;
+#[cfg(feature = "std")]
+mod observer;
+#[cfg(feature = "std")]
+pub use observer::ReloadObserver;
+#[cfg(feature = "std")]
+mod runner;
+#[cfg(feature = "std")]
+mod save_handle;
+#[cfg(feature = "std")]
+pub use save_handle::{SaveHandle, UpdateHandle};
+#[cfg(feature = "std")]
+mod reload_log;
+#[cfg(feature = "std")]
+pub use reload_log::{ReloadLogEntry, ReloadLogKind};
+#[cfg(feature = "std")]
+mod timestep;
+#[cfg(feature = "std")]
+pub use timestep::FixedTimestep;
+#[cfg(feature = "std")]
+pub mod debugger;
+#[cfg(feature = "std")]
+pub mod build;
+#[cfg(feature = "autoreload")]
+mod autoreload;
+#[cfg(feature = "autoreload")]
+pub use autoreload::AutoReload;
+#[cfg(feature = "std")]
+pub use runner::{interrupt, run};
+#[cfg(feature = "testing")]
+mod mock;
+#[cfg(feature = "testing")]
+pub use mock::MockLibrary;
+#[cfg(feature = "std")]
+mod shared_library;
+#[cfg(feature = "std")]
+pub use shared_library::SharedLibrary;
+#[cfg(feature = "std")]
+mod recording;
+#[cfg(feature = "std")]
+pub use recording::{HostCall, RecordHost, RecordingHost};
+
+#[cfg(all(feature = "mmap", unix))]
+mod mmap;
+#[cfg(all(feature = "mmap", unix))]
+pub use mmap::MappedState;
+#[cfg(all(feature = "shm", unix))]
+mod shm;
+#[cfg(all(feature = "shm", unix))]
+pub use shm::SharedState;
 
 /// Should the main program quit? More self-documenting than a boolean!
 ///
 /// This type is returned by the [`update`][] method, since with a boolean it's
 /// often unclear if `true` means "should continue" or "should quit".
 ///
+/// # ABI
+///
+/// A library's `update` returns this to the host across the library
+/// boundary, so both have to agree on its layout: it's a single byte, `0`
+/// for `No` and `1` for `Yes`, which the crate checks when it's compiled.
+///
+/// ```rust
+/// # extern crate live_reload;
+/// # fn main() {
+/// use std::mem::size_of;
+/// use live_reload::ShouldQuit;
+///
+/// assert_eq!(size_of::<ShouldQuit>(), 1);
+/// assert_eq!(ShouldQuit::No as u8, 0);
+/// assert_eq!(ShouldQuit::Yes as u8, 1);
+/// # }
+/// ```
+///
 /// [`update`]: struct.Reloadable.html#method.update
+#[repr(u8)]
 #[derive(Debug, PartialEq, Eq)]
 pub enum ShouldQuit {
     /// The wrapped library thinks the main program should continue running.
@@ -224,10 +484,110 @@ pub enum ShouldQuit {
     Yes = 1,
 }
 
+// Changing the layout of `ShouldQuit` breaks every library built before, so
+// it needs an `ABI_VERSION` bump along with these.
+const _: () = assert!(core::mem::size_of::<ShouldQuit>() == 1);
+const _: () = assert!(ShouldQuit::No as u8 == 0 && ShouldQuit::Yes as u8 == 1);
+
+impl ShouldQuit {
+    /// Combine the answers of two libraries: quit if either of them wants to.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// use live_reload::ShouldQuit;
+    ///
+    /// assert_eq!(ShouldQuit::No.combine(ShouldQuit::Yes), ShouldQuit::Yes);
+    /// assert_eq!(ShouldQuit::No.combine(ShouldQuit::No), ShouldQuit::No);
+    /// # }
+    /// ```
+    pub fn combine(self, other: ShouldQuit) -> ShouldQuit {
+        match (self, other) {
+            (ShouldQuit::No, ShouldQuit::No) => ShouldQuit::No,
+            _ => ShouldQuit::Yes,
+        }
+    }
+
+    /// Combine the answers of any number of libraries: quit if any of them
+    /// wants to. With no answers at all, there's no reason to quit.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// use live_reload::ShouldQuit;
+    ///
+    /// let answers = vec![ShouldQuit::No, ShouldQuit::Yes, ShouldQuit::No];
+    /// assert_eq!(ShouldQuit::any(answers), ShouldQuit::Yes);
+    /// assert_eq!(ShouldQuit::any(None), ShouldQuit::No);
+    /// # }
+    /// ```
+    pub fn any<I: IntoIterator<Item = ShouldQuit>>(answers: I) -> ShouldQuit {
+        answers.into_iter().fold(ShouldQuit::No, ShouldQuit::combine)
+    }
+}
+
+/// Break out of a loop once an update says to quit.
+///
+/// `quit_on!(expr)` evaluates `expr`, which has to be a [`ShouldQuit`][],
+/// and `break`s the innermost enclosing loop if it's `ShouldQuit::Yes`. To
+/// break an outer loop instead, give its label first, as in
+/// `quit_on!('frames, expr)`. This saves writing out
+/// `if app.update() == ShouldQuit::Yes { break; }` in every host loop, and
+/// fits into any loop, unlike [`Reloadable::update_until_quit`][].
+///
+/// ```rust
+/// #[macro_use] extern crate live_reload;
+///
+/// use live_reload::ShouldQuit;
+///
+/// # fn main() {
+/// let mut frames = 0;
+/// let mut update = || {
+///     frames += 1;
+///     if frames == 3 { ShouldQuit::Yes } else { ShouldQuit::No }
+/// };
+///
+/// let mut passes = 0;
+/// 'frames: loop {
+///     for _ in 0..2 {
+///         passes += 1;
+///         quit_on!('frames, update());
+///     }
+/// }
+/// assert_eq!(passes, 3);
+///
+/// let mut ticks = 0;
+/// loop {
+///     ticks += 1;
+///     quit_on!(if ticks == 5 { ShouldQuit::Yes } else { ShouldQuit::No });
+/// }
+/// assert_eq!(ticks, 5);
+/// # }
+/// ```
+///
+/// [`ShouldQuit`]: enum.ShouldQuit.html
+/// [`Reloadable::update_until_quit`]: struct.Reloadable.html#method.update_until_quit
+#[macro_export]
+macro_rules! quit_on {
+    ($label:lifetime, $quit:expr) => {
+        if $quit == ::live_reload::ShouldQuit::Yes {
+            break $label;
+        }
+    };
+    ($quit:expr) => {
+        if $quit == ::live_reload::ShouldQuit::Yes {
+            break;
+        }
+    };
+}
+
 /// Declare the API functions for a live-reloadable library.
 ///
 /// This generates wrappers around higher-level lifecycle functions, and then
-/// exports them in a struct that the reloader can find.
+/// exports them in a struct that the reloader can find. The wrappers are
+/// closures inside that struct, so the only items added to your crate are the
+/// exported `RELOAD_API` and the statics next to it that describe the library,
+/// like `RELOAD_BUILD_ID`.
 ///
 /// You need to to specify the host API type, define a struct that represents
 /// the state of your program, and then define methods for `init`, `reload`,
@@ -236,12 +596,209 @@ pub enum ShouldQuit {
 /// immediately after and before the library is loaded/reloaded. `update` is
 /// called by the wrapping application as needed.
 ///
+/// The host keeps the `State` 8-byte aligned, so a `State` that needs more
+/// than that, like one holding a `u128` or marked `#[repr(align(16))]`, is
+/// refused at compile time:
+///
+/// ```rust,compile_fail
+/// # #[macro_use] extern crate live_reload;
+/// # use live_reload::ShouldQuit;
+/// #[repr(C, align(16))]
+/// pub struct State { lanes: [f32; 4] }
+/// # fn nothing(_: &mut (), _: &mut State) {}
+/// # fn update(_: &mut (), _: &mut State) -> ShouldQuit { ShouldQuit::No }
+///
+/// live_reload! {
+///     state: State;
+///     init: nothing;
+///     reload: nothing;
+///     update: update;
+///     unload: nothing;
+///     deinit: nothing;
+/// }
+/// # fn main() {}
+/// ```
+///
+/// Optionally, you can also give an `is_safe_to_reload` function, which takes
+/// a `&State` and returns a `bool`. While it returns `false`, [`reload`][]
+/// puts off swapping in a changed library, for when the state is in the
+/// middle of something that a code change would break. A panic in it counts
+/// as `false`. Without it, reloading is always considered safe.
+///
+/// ```rust
+/// # #[macro_use] extern crate live_reload;
+/// use live_reload::{Reloadable, ShouldQuit};
+///
+/// pub struct Host;
+/// #[repr(C)] pub struct State { frames: u32 }
+///
+/// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+///     state.frames += 1;
+///     ShouldQuit::No
+/// }
+/// fn settled(state: &State) -> bool {
+///     assert!(state.frames > 1, "still starting up");
+///     true
+/// }
+/// # fn nothing(_: &mut Host, _: &mut State) {}
+///
+/// live_reload! {
+///     host: Host;
+///     state: State;
+///     init: nothing;
+///     reload: nothing;
+///     update: update;
+///     unload: nothing;
+///     deinit: nothing;
+///     is_safe_to_reload: settled;
+/// }
+///
+/// # fn main() {
+/// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+/// app.update();
+/// app.inject_change_event();
+/// app.reload().unwrap();
+/// assert_eq!(app.reload_count(), 0);
+/// assert!(app.has_pending_reload());
+///
+/// app.update();
+/// app.reload().unwrap();
+/// assert_eq!(app.reload_count(), 1);
+/// # }
+/// ```
+///
+/// A `reload` that migrates a lot of data can also come with a
+/// `reload_progress` function, which takes a `progress: fn(f32)` after the
+/// `&mut State`, to call with how far along it is, from `0.0` to `1.0`. It's
+/// called instead of `reload` when the host has asked for progress with
+/// [`Reloadable::set_reload_progress`][], so `reload` would usually just call
+/// it with a `progress` that does nothing.
+///
+/// Each optional function given is also recorded in the library's
+/// [`Capabilities`][], so a host only calls the ones the library has, and
+/// can check them with [`LibraryInfo::capabilities`][].
+///
+/// To have the host pass input events straight to `update`, rather than
+/// through the `Host`, give their type as `events: InputEvent;` right after
+/// the `state`. `update` then takes a `&[InputEvent]` after the
+/// `&mut State`, which is filled in by [`Reloadable::update_with_events`][]
+/// and empty for a plain `update`. `InputEvent` has to be `#[repr(C)]`, and
+/// is best declared next to the `Host`, so that both sides share it.
+///
+/// Data that only lives for one frame, like a command buffer or an arena,
+/// can be lent to `update` instead of being kept in the `Host`. Give its type
+/// as `extra: Frame;` right after the `state`, and `update` then takes a
+/// `&mut Frame` after the `&mut State`, which is the one passed to
+/// [`Reloadable::update_with`][]. A plain `update` passes a fresh
+/// `Frame::default()`, so the type has to implement `Default`, as well as be
+/// `#[repr(C)]` and shared with the host like an `InputEvent`. `events` and
+/// `extra` can't both be given.
+///
+/// To check the `State` after every reload, give a `validate_state`
+/// function, after `reload_progress`, which takes a `&State` and returns
+/// whether it's consistent, like whether all entity indices are in range.
+/// When it returns `false` (or panics), [`Reloadable::reload_now`][] rolls
+/// the reload back.
+///
+/// A library that knows how big some resource the host owns should be, like
+/// a scratch arena that has to outlive reloads, can say so with a
+/// `requirements` function, after `validate_state`, which returns a
+/// [`HostRequirements`][]. The host reads it with
+/// [`Reloadable::requirements`][] before `init`.
+///
+/// A `State` that changes shape over many builds can be given a chain of
+/// migrations, as `migrations: [v0_to_v1, v1_to_v2];` after `requirements`.
+/// Each one takes the `State` from one version to the next, the way `reload`
+/// would, so the `State`'s version is the number of migrations. When a
+/// reload goes from an older version to a newer one, even several versions
+/// on, [`Reloadable::reload_now`][] runs each step in between in order,
+/// before `reload`. A library only ever adds migrations to the end of the
+/// list.
+///
+/// A step takes a `&[u8]` after the `&mut State`, holding the bytes of the
+/// `State` as the old version left them, before any step ran, since the
+/// `&mut State` is already laid out as the new version's. The first step
+/// reads a field that moved from there, and later ones find what the steps
+/// before them did in the `State`. If a step panics, the `State` is put back
+/// as the old version left it, so the next reload runs every step again.
+///
+/// A `State` that holds pointers or handles, or whose layout changes too
+/// much for its bytes to be loaded back, can be saved in the library's own
+/// format instead, with `serialize: save; deserialize: load;` after
+/// `migrations`. `save` takes a `&State` and a `&mut dyn std::io::Write`,
+/// and `load` a `&mut State` and a `&mut dyn std::io::Read`, and both return
+/// an `std::io::Result<()>`. [`Reloadable::save_state`][] then keeps what
+/// `save` wrote alongside the bytes, and [`Reloadable::load_state`][] hands
+/// it to `load`, falling back to the bytes if that fails.
+///
+/// Some of the `State`, like settings or a player profile, may be meant to
+/// outlive a [`Reloadable::restart`][]. Lay it out in one stretch of the
+/// `State`, and give a `persistent_range` function after `deserialize`,
+/// which returns the offset and length of that stretch in bytes. A restart
+/// then keeps those bytes as they were, where everything else is zeroed.
+///
+/// If the `State` is declared with [`reloadable_state!`][], pass its
+/// `LAYOUT_HASH` as `state_layout`, after `persistent_range`. The host can then
+/// tell when a reload changed the layout of the `State`, from
+/// [`LibraryInfo::state_layout`][], and [`Reloadable::load_state_checked`][]
+/// refuses a save taken with a different layout.
+///
+/// The library also exports a build id, made of the version of `live-reload`
+/// it was built with and an optional `build_tag` string, which comes last. A
+/// host refuses a library whose build id doesn't match its own, since even
+/// with matching sizes, a `Host` with differently typed fields would corrupt
+/// memory. If you change the `Host` in ways the sizes can't catch, change the
+/// tag on both sides (see [`Builder::build_tag`][]). The sizes are compared
+/// through a fingerprint of the `Host`'s size and alignment, which the host
+/// checks as set with [`Builder::host_check`][].
+///
+/// Logic that needs nothing from the host, like a pure state machine, can
+/// leave out the `host` and start with `state`. The `Host` is then `()`, so
+/// the library is loaded as a `Reloadable<()>`, and each function still
+/// takes the `&mut ()` first, which costs nothing to pass.
+///
+/// A library can only export one `RELOAD_API`, so to declare more than one
+/// `live_reload!` in the same crate, start all but (at most) one of them with
+/// `module: name;`. That puts the statics in a `pub mod name` instead, without
+/// exporting them, which is useful for running them in the host with
+/// [`Reloadable::new_in_process`][], say in tests. To export several modules
+/// from one library, use [`live_reload_multi!`][] instead.
+///
+/// To roll out a breaking change to the `Host` without replacing every
+/// library at once, start the new generation with `symbol: "RELOAD_API_V2";`,
+/// which exports the `ReloadApi` under that name instead. A host built for
+/// both generations lists the names it knows in
+/// [`Builder::symbol_versions`][], and can tell which one it got from
+/// [`LibraryInfo::api_symbol`][].
+///
+/// [`Builder::symbol_versions`]: struct.Builder.html#method.symbol_versions
+/// [`LibraryInfo::api_symbol`]: struct.LibraryInfo.html#method.api_symbol
+/// [`reload`]: struct.Reloadable.html#method.reload
+/// [`Reloadable::set_reload_progress`]: struct.Reloadable.html#method.set_reload_progress
+/// [`Capabilities`]: struct.Capabilities.html
+/// [`LibraryInfo::capabilities`]: struct.LibraryInfo.html#method.capabilities
+/// [`Reloadable::update_with_events`]: struct.Reloadable.html#method.update_with_events
+/// [`Reloadable::update_with`]: struct.Reloadable.html#method.update_with
+/// [`Reloadable::reload_now`]: struct.Reloadable.html#method.reload_now
+/// [`HostRequirements`]: struct.HostRequirements.html
+/// [`Reloadable::requirements`]: struct.Reloadable.html#method.requirements
+/// [`Reloadable::save_state`]: struct.Reloadable.html#method.save_state
+/// [`Reloadable::load_state`]: struct.Reloadable.html#method.load_state
+/// [`Reloadable::restart`]: struct.Reloadable.html#method.restart
+/// [`reloadable_state!`]: macro.reloadable_state.html
+/// [`LibraryInfo::state_layout`]: struct.LibraryInfo.html#method.state_layout
+/// [`Reloadable::load_state_checked`]: struct.Reloadable.html#method.load_state_checked
+/// [`Builder::build_tag`]: struct.Builder.html#method.build_tag
+/// [`Builder::host_check`]: struct.Builder.html#method.host_check
+/// [`Reloadable::new_in_process`]: struct.Reloadable.html#method.new_in_process
+/// [`live_reload_multi!`]: macro.live_reload_multi.html
+///
 /// # Example
 ///
 /// ```rust
 /// # #[macro_use] extern crate live_reload;
 /// # fn main() {}
-/// # #[repr(C)] struct State {}
+/// # #[repr(C)] struct State { blending: bool }
 /// # mod host_api { pub struct Host; }
 /// # use host_api::Host;
 /// # fn my_init(_: &mut Host, _: &mut State) {}
@@ -258,59 +815,1045 @@ pub enum ShouldQuit {
 ///     update: my_update;
 ///     unload: my_unload;
 ///     deinit: my_deinit;
+///     is_safe_to_reload: my_is_safe_to_reload;
+///     build_tag: "host-v2";
+/// }
+///
+/// fn my_is_safe_to_reload(state: &State) -> bool {
+///     !state.blending
+/// }
+///
+/// live_reload! {
+///     module: headless;
+///     host: host_api::Host;
+///     state: State;
+///     init: my_init;
+///     reload: my_reload;
+///     update: my_update;
+///     unload: my_unload;
+///     deinit: my_deinit;
+/// }
+///
+/// # fn check() { let _ = &headless::RELOAD_API; }
+/// ```
+///
+/// A library with no `Host`, run in-process:
+///
+/// ```rust
+/// # #[macro_use] extern crate live_reload;
+/// use live_reload::{Reloadable, ShouldQuit};
+///
+/// #[repr(C)]
+/// pub struct State {
+///     light: u8,
+/// }
+///
+/// fn init(_: &mut (), state: &mut State) {
+///     state.light = 0;
+/// }
+/// fn step(_: &mut (), state: &mut State) -> ShouldQuit {
+///     state.light = (state.light + 1) % 3;
+///     ShouldQuit::No
+/// }
+/// fn nothing(_: &mut (), _: &mut State) {}
+///
+/// live_reload! {
+///     state: State;
+///     init: init;
+///     reload: nothing;
+///     update: step;
+///     unload: nothing;
+///     deinit: nothing;
+/// }
+///
+/// # fn main() {
+/// let mut app: Reloadable<()> = Reloadable::new_in_process(RELOAD_API, ()).unwrap();
+/// for _ in 0..4 {
+///     app.update();
+/// }
+/// app.reload_now().unwrap();
+/// assert_eq!(app.state_bytes(), [1]);
+/// # }
+/// ```
+///
+/// A library of the next generation, exported side by side with the old
+/// name:
+///
+/// ```rust
+/// # #[macro_use] extern crate live_reload;
+/// # fn main() {}
+/// # pub struct HostV2;
+/// # #[repr(C)] struct State;
+/// # use live_reload::ShouldQuit;
+/// # fn nothing(_: &mut HostV2, _: &mut State) {}
+/// # fn update(_: &mut HostV2, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+/// live_reload! {
+///     symbol: "RELOAD_API_V2";
+///     host: HostV2;
+///     state: State;
+///     init: nothing;
+///     reload: nothing;
+///     update: update;
+///     unload: nothing;
+///     deinit: nothing;
 /// }
 /// ```
 #[macro_export]
 macro_rules! live_reload {
-    (host: $Host:ty;
+    (module: $module:ident; $($rest:tt)*) => {
+        pub mod $module {
+            #[allow(unused_imports)]
+            use super::*;
+
+            live_reload!(@items [] [] $($rest)*);
+        }
+    };
+
+    (symbol: $symbol:literal; $($rest:tt)*) => {
+        live_reload!(@items [#[no_mangle]] [#[export_name = $symbol]] $($rest)*);
+    };
+
+    (host: $($rest:tt)*) => {
+        live_reload!(@items [#[no_mangle]] [#[no_mangle]] host: $($rest)*);
+    };
+
+    (state: $($rest:tt)*) => {
+        live_reload!(@items [#[no_mangle]] [#[no_mangle]] state: $($rest)*);
+    };
+
+    (@items [$(#[$attr:meta])*] [$(#[$api_attr:meta])*] state: $($rest:tt)*) => {
+        live_reload!(@items [$(#[$attr])*] [$(#[$api_attr])*] host: (); state: $($rest)*);
+    };
+
+    (@items [$(#[$attr:meta])*] [$(#[$api_attr:meta])*]
+     host: $Host:ty;
      state: $State:ty;
+     $(events: $Event:ty;)?
+     $(extra: $Extra:ty;)?
      init: $init:ident;
      reload: $reload:ident;
      update: $update:ident;
      unload: $unload:ident;
-     deinit: $deinit:ident;) => {
+     deinit: $deinit:ident;
+     $(is_safe_to_reload: $is_safe_to_reload:ident;)?
+     $(reload_progress: $reload_progress:ident;)?
+     $(validate_state: $validate_state:ident;)?
+     $(requirements: $requirements:ident;)?
+     $(migrations: [$($migration:ident),* $(,)?];)?
+     $(serialize: $serialize:ident; deserialize: $deserialize:ident;)?
+     $(persistent_range: $persistent_range:ident;)?
+     $(state_layout: $state_layout:expr;)?
+     $(build_tag: $build_tag:expr;)?) => {
+
+        const _: () = assert!(
+            ::live_reload::internals::align_of::<$State>() <= 8,
+            "the State can't need more than 8-byte alignment",
+        );
+
+        $(#[$api_attr])*
+        pub static RELOAD_API: ::live_reload::internals::ReloadApi<$Host> =
+            ::live_reload::internals::ReloadApi
+        {
+            abi_version: ::live_reload::internals::ABI_VERSION,
+            capabilities: 0
+                $(| { let _ = stringify!($is_safe_to_reload); ::live_reload::internals::Capabilities::IS_SAFE_TO_RELOAD.bits() })?
+                $(| { let _ = stringify!($reload_progress); ::live_reload::internals::Capabilities::RELOAD_PROGRESS.bits() })?
+                $(| { let _ = stringify!($Event); ::live_reload::internals::Capabilities::UPDATE_WITH_EVENTS.bits() })?
+                $(| { let _ = stringify!($Extra); ::live_reload::internals::Capabilities::UPDATE_WITH_EXTRA.bits() })?
+                $(| { let _ = stringify!($validate_state); ::live_reload::internals::Capabilities::VALIDATE_STATE.bits() })?
+                $(| { let _ = stringify!($requirements); ::live_reload::internals::Capabilities::REQUIREMENTS.bits() })?
+                $(| { let _ = stringify!($($migration)*); ::live_reload::internals::Capabilities::MIGRATIONS.bits() })?
+                $(| { let _ = stringify!($serialize); ::live_reload::internals::Capabilities::SERIALIZE.bits() })?
+                $(| { let _ = stringify!($persistent_range); ::live_reload::internals::Capabilities::PERSISTENT_RANGE.bits() })?,
+            size: ::live_reload::internals::size_of::<$State>,
+            init: |host, raw_state, sink| {
+                ::live_reload::internals::catch(sink, || {
+                    $init(host, unsafe { &mut *(raw_state as *mut $State) })
+                });
+            },
+            reload: |host, raw_state, sink| {
+                ::live_reload::internals::catch(sink, || {
+                    $reload(host, unsafe { &mut *(raw_state as *mut $State) })
+                });
+            },
+            update: live_reload!(@update $State, $update, [$($Event)?], [$($Extra)?]),
+            unload: |host, raw_state, sink| {
+                ::live_reload::internals::catch(sink, || {
+                    $unload(host, unsafe { &mut *(raw_state as *mut $State) })
+                });
+            },
+            deinit: |host, raw_state, sink| {
+                ::live_reload::internals::catch(sink, || {
+                    $deinit(host, unsafe { &mut *(raw_state as *mut $State) })
+                });
+            },
+            is_safe_to_reload: {
+                let is_safe_to_reload: Option<::live_reload::internals::IsSafeToReload> = None;
+                $(let is_safe_to_reload: Option<::live_reload::internals::IsSafeToReload> =
+                    Some(|raw_state, sink| {
+                        ::live_reload::internals::catch(sink, || {
+                            $is_safe_to_reload(unsafe { &*(raw_state as *const $State) })
+                        }).unwrap_or(false)
+                    });)?
+                is_safe_to_reload
+            },
+            reload_progress: {
+                let reload_progress: Option<::live_reload::internals::ReloadProgress<$Host>> = None;
+                $(let reload_progress: Option<::live_reload::internals::ReloadProgress<$Host>> =
+                    Some(|host, raw_state, progress, sink| {
+                        ::live_reload::internals::catch(sink, || {
+                            $reload_progress(host, unsafe { &mut *(raw_state as *mut $State) }, progress)
+                        });
+                    });)?
+                reload_progress
+            },
+            update_with_events: live_reload!(@update_with_events $Host, $State, $update, [$($Event)?]),
+            update_with_extra: live_reload!(@update_with_extra $Host, $State, $update, [$($Extra)?]),
+            validate_state: {
+                let validate_state: Option<::live_reload::internals::ValidateState> = None;
+                $(let validate_state: Option<::live_reload::internals::ValidateState> =
+                    Some(|raw_state, sink| {
+                        ::live_reload::internals::catch(sink, || {
+                            $validate_state(unsafe { &*(raw_state as *const $State) })
+                        }).unwrap_or(false)
+                    });)?
+                validate_state
+            },
+            requirements: {
+                let requirements: Option<::live_reload::internals::Requirements> = None;
+                $(let requirements: Option<::live_reload::internals::Requirements> =
+                    Some(|sink| ::live_reload::internals::catch(sink, $requirements));)?
+                requirements
+            },
+            state_version: {
+                let state_version: u32 = 0;
+                $(let state_version: u32 = {
+                    let steps: &[fn(&mut $Host, &mut $State, &[u8])] = &[$($migration),*];
+                    steps.len() as u32
+                };)?
+                state_version
+            },
+            migrate: {
+                let migrate: Option<::live_reload::internals::Migrate<$Host>> = None;
+                $(let migrate: Option<::live_reload::internals::Migrate<$Host>> =
+                    Some(|host, raw_state, from, old, sink| {
+                        ::live_reload::internals::catch(sink, || {
+                            let steps: &[fn(&mut $Host, &mut $State, &[u8])] = &[$($migration),*];
+                            steps[from as usize](host, unsafe { &mut *(raw_state as *mut $State) }, old)
+                        });
+                    });)?
+                migrate
+            },
+            serialize: {
+                let serialize: Option<::live_reload::internals::Serialize> = None;
+                $(let serialize: Option<::live_reload::internals::Serialize> =
+                    Some(|raw_state, writer, sink| {
+                        ::live_reload::internals::catch(sink, || {
+                            $serialize(unsafe { &*(raw_state as *const $State) }, writer).is_ok()
+                        }).unwrap_or(false)
+                    });)?
+                serialize
+            },
+            deserialize: {
+                let deserialize: Option<::live_reload::internals::Deserialize> = None;
+                $(let deserialize: Option<::live_reload::internals::Deserialize> =
+                    Some(|raw_state, reader, sink| {
+                        ::live_reload::internals::catch(sink, || {
+                            $deserialize(unsafe { &mut *(raw_state as *mut $State) }, reader).is_ok()
+                        }).unwrap_or(false)
+                    });)?
+                deserialize
+            },
+            persistent_range: {
+                let persistent_range: Option<::live_reload::internals::PersistentRange> = None;
+                $(let persistent_range: Option<::live_reload::internals::PersistentRange> =
+                    Some(|sink| ::live_reload::internals::catch(sink, $persistent_range).unwrap_or((0, 0)));)?
+                persistent_range
+            },
+        };
+
+        $(#[$attr])*
+        pub static RELOAD_BUILD_ID: ::live_reload::internals::BuildId =
+            ::live_reload::internals::BuildId
+        {
+            version: ::live_reload::internals::VERSION,
+            tag: {
+                let build_tag = "";
+                $(let build_tag = $build_tag;)?
+                build_tag
+            },
+        };
+
+        $(#[$attr])*
+        pub static RELOAD_HOST_HASH: u64 = ::live_reload::internals::host_hash::<$Host>();
+
+        $(#[$attr])*
+        pub static RELOAD_PANIC_STRATEGY: u8 = if cfg!(panic = "unwind") {
+            ::live_reload::internals::PanicStrategy::Unwind as u8
+        } else {
+            ::live_reload::internals::PanicStrategy::Abort as u8
+        };
+
+        $(#[$attr])*
+        pub static RELOAD_BUILD_PROFILE: u8 = ::live_reload::internals::Profile::current() as u8;
+
+        $(#[$attr])*
+        pub static RELOAD_STATE_LAYOUT: u64 = {
+            let state_layout: u64 = 0;
+            $(let state_layout: u64 = $state_layout;)?
+            state_layout
+        };
+    };
+
+    (@update $State:ty, $update:ident, [], []) => {
+        |host, raw_state, sink| {
+            ::live_reload::internals::catch(sink, || {
+                $update(host, unsafe { &mut *(raw_state as *mut $State) })
+            }).unwrap_or(::live_reload::ShouldQuit::No)
+        }
+    };
+
+    (@update $State:ty, $update:ident, [$Event:ty], []) => {
+        |host, raw_state, sink| {
+            ::live_reload::internals::catch(sink, || {
+                $update(host, unsafe { &mut *(raw_state as *mut $State) }, &[])
+            }).unwrap_or(::live_reload::ShouldQuit::No)
+        }
+    };
+
+    (@update $State:ty, $update:ident, [], [$Extra:ty]) => {
+        |host, raw_state, sink| {
+            ::live_reload::internals::catch(sink, || {
+                let mut extra = <$Extra as ::std::default::Default>::default();
+                $update(host, unsafe { &mut *(raw_state as *mut $State) }, &mut extra)
+            }).unwrap_or(::live_reload::ShouldQuit::No)
+        }
+    };
+
+    (@update $State:ty, $update:ident, [$Event:ty], [$Extra:ty]) => {
+        compile_error!("`events` and `extra` can't both be given to `live_reload!`")
+    };
+
+    (@update_with_events $Host:ty, $State:ty, $update:ident, []) => { None };
+
+    (@update_with_events $Host:ty, $State:ty, $update:ident, [$Event:ty]) => {{
+        let update: ::live_reload::internals::UpdateWithEvents<$Host> =
+            |host, raw_state, events, len, event_size, sink| {
+                ::live_reload::internals::catch(sink, || {
+                    assert_eq!(
+                        event_size,
+                        ::live_reload::internals::size_of::<$Event>(),
+                        "the host's events aren't the same size as the library's {}",
+                        stringify!($Event),
+                    );
+                    let events = unsafe { ::std::slice::from_raw_parts(events as *const $Event, len) };
+                    $update(host, unsafe { &mut *(raw_state as *mut $State) }, events)
+                }).unwrap_or(::live_reload::ShouldQuit::No)
+            };
+        Some(update)
+    }};
+
+    (@update_with_extra $Host:ty, $State:ty, $update:ident, []) => { None };
+
+    (@update_with_extra $Host:ty, $State:ty, $update:ident, [$Extra:ty]) => {{
+        let update: ::live_reload::internals::UpdateWithExtra<$Host> =
+            |host, raw_state, extra, extra_size, sink| {
+                ::live_reload::internals::catch(sink, || {
+                    assert_eq!(
+                        extra_size,
+                        ::live_reload::internals::size_of::<$Extra>(),
+                        "the host's extra data isn't the same size as the library's {}",
+                        stringify!($Extra),
+                    );
+                    let extra = unsafe { &mut *(extra as *mut $Extra) };
+                    $update(host, unsafe { &mut *(raw_state as *mut $State) }, extra)
+                }).unwrap_or(::live_reload::ShouldQuit::No)
+            };
+        Some(update)
+    }};
+}
+
+/// Declare a `Host` struct along with a builder that won't build it until
+/// every field has been set.
+///
+/// A `Host` is usually a struct of function pointers that the library calls
+/// back into, and a placeholder left in one of them only shows up when the
+/// library calls it. The builder generated by this macro tracks which fields
+/// have been set in its type, so that `build` only exists once all of them
+/// are, and a missing field is a compile error rather than a crash.
+///
+/// Name the builder with `builder: Name;`, and then declare the struct as
+/// usual. Every field has to be `pub`, and each one gets a setter with the
+/// same name. The fields can be set in any order.
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate live_reload;
+/// host_api! {
+///     builder: HostBuilder;
+///
+///     /// The functions the library can call.
+///     pub struct Host {
+///         pub print: fn(&str),
+///         pub log: fn(&str),
+///     }
+/// }
+///
+/// fn print(message: &str) { println!("{}", message); }
+/// fn log(message: &str) { eprintln!("{}", message); }
+///
+/// # fn main() {
+/// let host = Host::builder().log(log).print(print).build();
+/// (host.print)("Hello!");
+/// # }
+/// ```
+///
+/// Leaving a field out doesn't compile:
+///
+/// ```rust,compile_fail
+/// # #[macro_use] extern crate live_reload;
+/// # host_api! {
+/// #     builder: HostBuilder;
+/// #     pub struct Host {
+/// #         pub print: fn(&str),
+/// #         pub log: fn(&str),
+/// #     }
+/// # }
+/// # fn print(message: &str) { println!("{}", message); }
+/// # fn main() {
+/// let host = Host::builder().print(print).build();
+/// # }
+/// ```
+#[macro_export]
+macro_rules! host_api {
+    (builder: $Builder:ident;
+     $(#[$attr:meta])*
+     pub struct $Host:ident {
+         $($(#[$field_attr:meta])* pub $field:ident: $Field:ty),+ $(,)?
+     }) => {
+        $(#[$attr])*
+        pub struct $Host {
+            $($(#[$field_attr])* pub $field: $Field,)+
+        }
 
-        fn cast<'a>(raw_state: *mut ()) -> &'a mut $State {
-            unsafe { &mut *(raw_state as *mut $State) }
+        impl $Host {
+            /// Start building a `Host`. It can only be built once every field
+            /// has been set.
+            pub fn builder() -> $Builder<$(host_api!(@unset $field),)+> {
+                $Builder {
+                    $($field: None,)+
+                    _set: ::std::marker::PhantomData,
+                }
+            }
         }
 
-        fn init_wrapper(host: &mut $Host, raw_state: *mut ()) {
-            $init(host, cast(raw_state))
+        /// Builds a `Host`, keeping track of which fields have been set.
+        #[allow(non_camel_case_types)]
+        pub struct $Builder<$($field,)+> {
+            $($field: Option<$Field>,)+
+            _set: ::std::marker::PhantomData<($($field,)+)>,
         }
 
-        fn reload_wrapper(host: &mut $Host, raw_state: *mut ()) {
-            $reload(host, cast(raw_state))
+        impl $Builder<$(host_api!(@set $field),)+> {
+            /// Build the `Host`, now that every field has been set.
+            pub fn build(self) -> $Host {
+                $Host {
+                    $($field: self.$field.expect("the builder's type says this is set"),)+
+                }
+            }
         }
 
-        fn update_wrapper(host: &mut $Host, raw_state: *mut ())
-            -> ::live_reload::ShouldQuit
+        host_api!(@setters $Builder [] [$($field: $Field,)+]);
+    };
+
+    (@unset $field:ident) => { ::live_reload::internals::Unset };
+    (@set $field:ident) => { ::live_reload::internals::Set };
+
+    (@setters $Builder:ident [$($before:ident,)*] []) => {};
+    (@setters $Builder:ident
+     [$($before:ident,)*]
+     [$field:ident: $Field:ty, $($after:ident: $After:ty,)*]) => {
+        #[allow(non_camel_case_types)]
+        impl<$($before,)* $($after,)*>
+            $Builder<$($before,)* ::live_reload::internals::Unset, $($after,)*>
         {
-            $update(host, cast(raw_state))
+            /// Set this field of the `Host`.
+            pub fn $field(self, $field: $Field)
+                -> $Builder<$($before,)* ::live_reload::internals::Set, $($after,)*>
+            {
+                $Builder {
+                    $($before: self.$before,)*
+                    $field: Some($field),
+                    $($after: self.$after,)*
+                    _set: ::std::marker::PhantomData,
+                }
+            }
+        }
+
+        host_api!(@setters $Builder [$($before,)* $field,] [$($after: $After,)*]);
+    };
+}
+
+/// Declare a `Host` whose services are the methods of a trait, for host
+/// code that keeps state of its own.
+///
+/// A `Host` of plain `fn` pointers can't capture anything, so every service
+/// has to reach its state through a global. This macro declares the trait
+/// instead, and a `#[repr(C)]` `Host` that holds a boxed implementation of
+/// it behind a `*mut ()`, next to a table of `fn` pointers, one per method,
+/// which forward to it. That's the same as a trait object, except that the
+/// layout is spelled out, so it's the same in the host and the library
+/// rather than up to the compiler. Like any `Host`, the declaration has to
+/// be shared by both sides, so put it in the shared module.
+///
+/// Declare the name of the `Host` with `host: Name;`, and then the trait.
+/// Each method takes `&mut self` and named arguments, and the `Host` gets a
+/// method of the same name that calls it, which is what the library uses.
+/// The host builds the `Host` with `Name::new(services)`, from anything
+/// that implements the trait and is `Send`, and can get its implementation
+/// back with `services_mut`. The implementation is dropped with the `Host`.
+/// The trait's methods can't be called `new` or `services_mut`, or start
+/// with `__` like the `Host`'s own fields, but anything else goes, `data`
+/// and `type_id` included.
+///
+/// `services_mut` compares the type it's asked for with the one the `Host`
+/// was built from, which only means anything in the build that built it, so
+/// it's for the host alone. The library's types are unrelated to the host's
+/// even when they share a name.
+///
+/// # Example
+///
+/// ```rust
+/// #[macro_use] extern crate live_reload;
+///
+/// use live_reload::ShouldQuit;
+///
+/// // In the shared module:
+/// host_services! {
+///     host: Host;
+///
+///     /// The services the library can call.
+///     pub trait Services {
+///         /// Show a line of text.
+///         fn print(&mut self, message: &str);
+///         /// Roll a die with `sides` sides.
+///         fn roll(&mut self, sides: u32) -> u32;
+///     }
+/// }
+///
+/// // In the library:
+/// #[repr(C)] pub struct State;
+/// fn update(host: &mut Host, _: &mut State) -> ShouldQuit {
+///     let roll = host.roll(6);
+///     host.print(&format!("rolled {}", roll));
+///     ShouldQuit::No
+/// }
+/// fn nothing(_: &mut Host, _: &mut State) {}
+///
+/// live_reload! {
+///     host: Host;
+///     state: State;
+///     init: nothing;
+///     reload: nothing;
+///     update: update;
+///     unload: nothing;
+///     deinit: nothing;
+/// }
+///
+/// // In the host:
+/// struct Console {
+///     lines: Vec<String>,
+///     next_roll: u32,
+/// }
+///
+/// impl Services for Console {
+///     fn print(&mut self, message: &str) {
+///         self.lines.push(message.to_owned());
+///     }
+///     fn roll(&mut self, sides: u32) -> u32 {
+///         self.next_roll = self.next_roll % sides + 1;
+///         self.next_roll
+///     }
+/// }
+///
+/// # fn main() {
+/// use live_reload::Reloadable;
+///
+/// let host = Host::new(Console { lines: Vec::new(), next_roll: 0 });
+/// let mut app = Reloadable::new_in_process(RELOAD_API, host).unwrap();
+/// app.update();
+/// app.update();
+/// let console = app.host_mut().services_mut::<Console>().unwrap();
+/// assert_eq!(console.lines, ["rolled 1", "rolled 2"]);
+/// # }
+/// ```
+///
+/// So a trait can have methods called `data`, `type_id` or `drop`:
+///
+/// ```rust
+/// # #[macro_use] extern crate live_reload;
+/// host_services! {
+///     host: Host;
+///
+///     pub trait Services {
+///         fn data(&mut self) -> u32;
+///         fn type_id(&mut self) -> u32;
+///         fn drop(&mut self);
+///     }
+/// }
+///
+/// struct Counts(u32);
+///
+/// impl Services for Counts {
+///     fn data(&mut self) -> u32 { self.0 }
+///     fn type_id(&mut self) -> u32 { self.0 + 1 }
+///     fn drop(&mut self) { self.0 = 0; }
+/// }
+///
+/// # fn main() {
+/// let mut host = Host::new(Counts(1));
+/// assert_eq!((host.data(), host.type_id()), (1, 2));
+/// host.drop();
+/// assert_eq!(host.data(), 0);
+/// # }
+/// ```
+#[macro_export]
+macro_rules! host_services {
+    (host: $Host:ident;
+     $(#[$attr:meta])*
+     pub trait $Services:ident {
+         $($(#[$method_attr:meta])*
+           fn $method:ident(&mut self $(, $arg:ident: $Arg:ty)* $(,)?) $(-> $Ret:ty)?;)+
+     }) => {
+        $(#[$attr])*
+        pub trait $Services {
+            $($(#[$method_attr])* fn $method(&mut self $(, $arg: $Arg)*) $(-> $Ret)?;)+
+        }
+
+        /// The services, as a `Host` that a library can call them through.
+        #[repr(C)]
+        pub struct $Host {
+            __data: *mut (),
+            __drop: fn(*mut ()),
+            __type_id: fn() -> ::std::any::TypeId,
+            $($method: fn(*mut () $(, $Arg)*) $(-> $Ret)?,)+
+        }
+
+        // The services were `Send` when the `Host` was built, and the `Host`
+        // owns them.
+        unsafe impl Send for $Host {}
+
+        impl $Host {
+            /// Build a `Host` that owns `services`, and calls them.
+            pub fn new<T: $Services + Send + 'static>(services: T) -> Self {
+                fn __drop<T>(data: *mut ()) {
+                    unsafe { ::std::mem::drop(::std::boxed::Box::from_raw(data as *mut T)) }
+                }
+                fn __type_id<T: 'static>() -> ::std::any::TypeId {
+                    ::std::any::TypeId::of::<T>()
+                }
+                $(fn $method<T: $Services>(data: *mut () $(, $arg: $Arg)*) $(-> $Ret)? {
+                    unsafe { &mut *(data as *mut T) }.$method($($arg),*)
+                })+
+                $Host {
+                    __data: ::std::boxed::Box::into_raw(::std::boxed::Box::new(services)) as *mut (),
+                    __drop: __drop::<T>,
+                    __type_id: __type_id::<T>,
+                    $($method: $method::<T>,)+
+                }
+            }
+
+            /// The services the `Host` was built from, if they're a `T`.
+            ///
+            /// This is only for the host that built the `Host`.
+            pub fn services_mut<T: $Services + 'static>(&mut self) -> Option<&mut T> {
+                if (self.__type_id)() == ::std::any::TypeId::of::<T>() {
+                    Some(unsafe { &mut *(self.__data as *mut T) })
+                } else {
+                    None
+                }
+            }
+
+            $($(#[$method_attr])*
+            pub fn $method(&mut self $(, $arg: $Arg)*) $(-> $Ret)? {
+                (self.$method)(self.__data $(, $arg)*)
+            })+
+        }
+
+        impl Drop for $Host {
+            fn drop(&mut self) {
+                (self.__drop)(self.__data)
+            }
         }
+    };
+}
+
+/// Let the calls a library makes into a `Host` be recorded and replayed
+/// with a [`RecordingHost`][].
+///
+/// List each `fn` field of the `Host` that should be recorded, with its
+/// arguments named, the way a function is declared. The fields left out,
+/// whether functions or data, are passed through untouched. Each argument
+/// has to implement `Debug`, and each result `Clone`, `Debug`, `Send` and
+/// `Sync`, which the results of `fn`s handed across to a library usually
+/// do.
+///
+/// This is for the host: hand the `Host` returned by
+/// [`RecordingHost::record`][] to a [`Reloadable`][] as usual, and every call
+/// the library makes into it is kept, to replay later with
+/// [`RecordingHost::replay`][].
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate live_reload;
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use live_reload::RecordingHost;
+///
+/// pub struct Host {
+///     pub roll: fn(u32) -> u32,
+///     pub log: fn(&str),
+///     pub seed: u64,
+/// }
+///
+/// recording_host! {
+///     host: Host;
+///     fn roll(sides: u32) -> u32;
+///     fn log(message: &str);
+/// }
+///
+/// static ROLLS: AtomicU32 = AtomicU32::new(0);
+///
+/// fn roll(sides: u32) -> u32 {
+///     ROLLS.fetch_add(1, Ordering::Relaxed) % sides + 1
+/// }
+///
+/// # fn main() {
+/// let host = Host { roll, log: |_| {}, seed: 7 };
+/// let (host, recording) = RecordingHost::record(host);
+/// // The library would make these calls, from `update`.
+/// let first = (host.roll)(6);
+/// (host.log)("rolled");
+/// let calls = recording.finish();
+/// assert_eq!(calls[0].to_string(), format!("roll(6) -> {}", first));
+/// assert_eq!(calls[1].to_string(), "log(\"rolled\") -> ()");
+///
+/// let (host, replay) = RecordingHost::replay(host, calls);
+/// assert_eq!((host.roll)(6), first);
+/// (host.log)("rolled");
+/// assert!(replay.finish().is_empty());
+/// assert_eq!(host.seed, 7);
+/// # }
+/// ```
+///
+/// [`RecordingHost`]: struct.RecordingHost.html
+/// [`RecordingHost::record`]: struct.RecordingHost.html#method.record
+/// [`RecordingHost::replay`]: struct.RecordingHost.html#method.replay
+/// [`Reloadable`]: struct.Reloadable.html
+#[macro_export]
+macro_rules! recording_host {
+    (host: $Host:ty;
+     $(fn $function:ident($($arg:ident: $Arg:ty),* $(,)?) $(-> $Ret:ty)?;)+) => {
+        const _: () = {
+            use ::std::cell::{Cell, RefCell};
+            use ::live_reload::internals::{record_call, Recorder};
 
-        fn unload_wrapper(host: &mut $Host, raw_state: *mut ()) {
-            $unload(host, cast(raw_state))
+            /// The functions the `Host` had before it was wrapped.
+            #[derive(Clone, Copy)]
+            struct Originals {
+                $($function: fn($($Arg),*) $(-> $Ret)?,)+
+            }
+
+            thread_local! {
+                static ORIGINALS: Cell<Option<Originals>> = const { Cell::new(None) };
+                static RECORDER: RefCell<Recorder> = RefCell::new(Recorder::default());
+            }
+
+            fn originals() -> Originals {
+                ORIGINALS.with(Cell::get).expect("a recorded Host was called on another thread than it was recorded on")
+            }
+
+            $(
+                fn $function($($arg: $Arg),*) $(-> $Ret)? {
+                    let original = originals().$function;
+                    let args = if RECORDER.with(|recorder| recorder.borrow().is_active()) {
+                        let args: &[String] = &[$(format!("{:?}", $arg)),*];
+                        Some(args.join(", "))
+                    } else {
+                        None
+                    };
+                    record_call(&RECORDER, stringify!($function), args, move || original($($arg),*))
+                }
+            )+
+
+            impl ::live_reload::RecordHost for $Host {
+                fn wrap(mut self) -> Self {
+                    // Keep the originals from before any earlier wrapping, so
+                    // that a wrapper never ends up calling itself.
+                    let mut originals = ORIGINALS.with(Cell::get).unwrap_or(Originals {
+                        $($function: self.$function,)+
+                    });
+                    $(
+                        let wrapper: fn($($Arg),*) $(-> $Ret)? = $function;
+                        if self.$function as usize != wrapper as usize {
+                            originals.$function = self.$function;
+                        }
+                        self.$function = wrapper;
+                    )+
+                    ORIGINALS.with(|cell| cell.set(Some(originals)));
+                    self
+                }
+
+                fn recorder() -> &'static ::std::thread::LocalKey<RefCell<Recorder>> {
+                    &RECORDER
+                }
+            }
+        };
+    };
+}
+
+/// Declare a `State` struct along with a hash of its layout.
+///
+/// The struct is declared as usual, but always `#[repr(C)]`, so its layout
+/// only depends on its fields, in order. So don't give it a `repr` of your
+/// own. It gets a `LAYOUT_HASH` constant, which hashes the name, type, size
+/// and alignment of each field. Adding, removing, reordering, renaming or
+/// retyping a field changes the hash, without a version number to keep
+/// bumping by hand. Pass it to [`live_reload!`][] as `state_layout`, so
+/// that the host can see it.
+///
+/// A field's type is hashed by how it's written, along with its size and
+/// alignment, so a change inside another struct that keeps those the same
+/// isn't noticed.
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate live_reload;
+/// mod before {
+///     reloadable_state! {
+///         pub struct State {
+///             pub counter: u32,
+///         }
+///     }
+/// }
+///
+/// mod after {
+///     reloadable_state! {
+///         /// The counter, now with more room.
+///         pub struct State {
+///             pub counter: u64,
+///         }
+///     }
+/// }
+///
+/// # fn main() {
+/// assert_ne!(before::State::LAYOUT_HASH, after::State::LAYOUT_HASH);
+/// # }
+/// ```
+///
+/// [`live_reload!`]: macro.live_reload.html
+#[macro_export]
+macro_rules! reloadable_state {
+    ($(#[$attr:meta])*
+     $vis:vis struct $State:ident {
+         $($(#[$field_attr:meta])* $field_vis:vis $field:ident: $Field:ty),* $(,)?
+     }) => {
+        $(#[$attr])*
+        #[repr(C)]
+        $vis struct $State {
+            $($(#[$field_attr])* $field_vis $field: $Field,)*
         }
 
-        fn deinit_wrapper(host: &mut $Host, raw_state: *mut ()) {
-            $deinit(host, cast(raw_state))
+        impl $State {
+            /// A hash of the fields of this struct, which changes whenever
+            /// its layout does.
+            #[allow(dead_code)]
+            pub const LAYOUT_HASH: u64 = {
+                let hash = ::live_reload::internals::LAYOUT_HASH_SEED;
+                $(
+                    let hash = ::live_reload::internals::hash_str(hash, stringify!($field));
+                    let hash = ::live_reload::internals::hash_str(hash, stringify!($Field));
+                    let hash = ::live_reload::internals::hash_usize(hash, ::live_reload::internals::size_of::<$Field>());
+                    let hash = ::live_reload::internals::hash_usize(hash, ::live_reload::internals::align_of::<$Field>());
+                )*
+                hash
+            };
         }
+    };
+}
+
+/// Declare several independent live-reloadable modules in one library.
+///
+/// Each module has its own `State` and its own lifecycle functions, just like
+/// with [`live_reload!`][], and they all share the same `Host`. Load the
+/// library with a [`MultiReloadable`][], which keeps a separate state for each
+/// module, and matches the modules up by name across reloads. Next to the
+/// table, the library exports the panic strategy it was built with, which
+/// `MultiReloadable` checks like [`Builder::check_panic_strategy`][] does.
+///
+/// As with `live_reload!`, each module's `State` can't need more than 8-byte
+/// alignment:
+///
+/// ```rust,compile_fail
+/// # #[macro_use] extern crate live_reload;
+/// # use live_reload::ShouldQuit;
+/// #[repr(C, align(16))]
+/// pub struct Lanes { lanes: [f32; 4] }
+/// # fn nothing(_: &mut (), _: &mut Lanes) {}
+/// # fn update(_: &mut (), _: &mut Lanes) -> ShouldQuit { ShouldQuit::No }
+///
+/// live_reload_multi! {
+///     host: ();
+///     simd {
+///         state: Lanes;
+///         init: nothing;
+///         reload: nothing;
+///         update: update;
+///         unload: nothing;
+///         deinit: nothing;
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// # Example
+///
+/// ```rust
+/// # #[macro_use] extern crate live_reload;
+/// # mod host_api { pub struct Host; }
+/// # use host_api::Host;
+/// use live_reload::ShouldQuit;
+///
+/// struct Physics { steps: u64 }
+/// struct Audio { volume: f32 }
+///
+/// fn physics_init(_: &mut Host, physics: &mut Physics) { physics.steps = 0; }
+/// fn physics_update(_: &mut Host, physics: &mut Physics) -> ShouldQuit {
+///     physics.steps += 1;
+///     ShouldQuit::No
+/// }
+/// fn audio_init(_: &mut Host, audio: &mut Audio) { audio.volume = 1.0; }
+/// fn audio_update(_: &mut Host, _: &mut Audio) -> ShouldQuit { ShouldQuit::No }
+/// fn nothing<T>(_: &mut Host, _: &mut T) {}
+///
+/// live_reload_multi! {
+///     host: Host;
+///     physics {
+///         state: Physics;
+///         init: physics_init;
+///         reload: nothing;
+///         update: physics_update;
+///         unload: nothing;
+///         deinit: nothing;
+///     }
+///     audio {
+///         state: Audio;
+///         init: audio_init;
+///         reload: nothing;
+///         update: audio_update;
+///         unload: nothing;
+///         deinit: nothing;
+///     }
+/// }
+///
+/// # fn main() {
+/// let names: Vec<_> = RELOAD_API_TABLE.entries.iter().map(|entry| entry.name).collect();
+/// assert_eq!(names, ["physics", "audio"]);
+/// # assert_eq!(RELOAD_PANIC_STRATEGY, live_reload::internals::PanicStrategy::current() as u8);
+/// # }
+/// ```
+///
+/// [`live_reload!`]: macro.live_reload.html
+/// [`MultiReloadable`]: struct.MultiReloadable.html
+/// [`Builder::check_panic_strategy`]: struct.Builder.html#method.check_panic_strategy
+#[macro_export]
+macro_rules! live_reload_multi {
+    (host: $Host:ty;
+     $($name:ident {
+         state: $State:ty;
+         init: $init:path;
+         reload: $reload:path;
+         update: $update:path;
+         unload: $unload:path;
+         deinit: $deinit:path;
+         $(is_safe_to_reload: $is_safe_to_reload:path;)?
+     })+) => {
 
-        #[cfg(feature = "std")]
-        use ::std::mem;
-        #[cfg(not(feature = "std"))]
-        use ::core::mem;
+        $(const _: () = assert!(
+            ::live_reload::internals::align_of::<$State>() <= 8,
+            concat!("the State of `", stringify!($name), "` can't need more than 8-byte alignment"),
+        );)+
 
         #[no_mangle]
-        pub static RELOAD_API: ::live_reload::internals::ReloadApi<$Host> =
-            ::live_reload::internals::ReloadApi
+        pub static RELOAD_PANIC_STRATEGY: u8 = if cfg!(panic = "unwind") {
+            ::live_reload::internals::PanicStrategy::Unwind as u8
+        } else {
+            ::live_reload::internals::PanicStrategy::Abort as u8
+        };
+
+        #[no_mangle]
+        pub static RELOAD_API_TABLE: ::live_reload::internals::ReloadApiTable<$Host> =
+            ::live_reload::internals::ReloadApiTable
         {
-            size: mem::size_of::<$State>,
-            init: init_wrapper,
-            reload: reload_wrapper,
-            update: update_wrapper,
-            unload: unload_wrapper,
-            deinit: deinit_wrapper,
+            abi_version: ::live_reload::internals::ABI_VERSION,
+            entries: &[$(
+                ::live_reload::internals::ReloadApiEntry {
+                    name: stringify!($name),
+                    api: ::live_reload::internals::ReloadApi {
+                        abi_version: ::live_reload::internals::ABI_VERSION,
+                        capabilities: 0
+                            $(| { let _ = stringify!($is_safe_to_reload); ::live_reload::internals::Capabilities::IS_SAFE_TO_RELOAD.bits() })?,
+                        size: ::live_reload::internals::size_of::<$State>,
+                        init: |host, raw_state, sink| {
+                            ::live_reload::internals::catch(sink, || {
+                                $init(host, unsafe { &mut *(raw_state as *mut $State) })
+                            });
+                        },
+                        reload: |host, raw_state, sink| {
+                            ::live_reload::internals::catch(sink, || {
+                                $reload(host, unsafe { &mut *(raw_state as *mut $State) })
+                            });
+                        },
+                        update: |host, raw_state, sink| {
+                            ::live_reload::internals::catch(sink, || {
+                                $update(host, unsafe { &mut *(raw_state as *mut $State) })
+                            }).unwrap_or(::live_reload::ShouldQuit::No)
+                        },
+                        unload: |host, raw_state, sink| {
+                            ::live_reload::internals::catch(sink, || {
+                                $unload(host, unsafe { &mut *(raw_state as *mut $State) })
+                            });
+                        },
+                        deinit: |host, raw_state, sink| {
+                            ::live_reload::internals::catch(sink, || {
+                                $deinit(host, unsafe { &mut *(raw_state as *mut $State) })
+                            });
+                        },
+                        is_safe_to_reload: {
+                            let is_safe_to_reload: Option<::live_reload::internals::IsSafeToReload> = None;
+                            $(let is_safe_to_reload: Option<::live_reload::internals::IsSafeToReload> =
+                                Some(|raw_state, sink| {
+                                    ::live_reload::internals::catch(sink, || {
+                                        $is_safe_to_reload(unsafe { &*(raw_state as *const $State) })
+                                    }).unwrap_or(false)
+                                });)?
+                            is_safe_to_reload
+                        },
+                        reload_progress: None,
+                        update_with_events: None,
+                        update_with_extra: None,
+                        validate_state: None,
+                        requirements: None,
+                        state_version: 0,
+                        migrate: None,
+                        serialize: None,
+                        deserialize: None,
+                        persistent_range: None,
+                    },
+                },
+            )+],
         };
     }
 }
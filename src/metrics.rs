@@ -0,0 +1,76 @@
+use std::time::{Duration, Instant};
+
+/// Totals over the reloads a [`Reloadable`][] has done so far.
+///
+/// This is returned by [`Reloadable::metrics`][], and passed to the sink set
+/// with [`Reloadable::set_metrics_sink`][] after each reload, to feed into
+/// whatever metrics system the host already has. Every reload counts, whether
+/// it's from [`reload`][] noticing a change or from [`reload_now`][].
+///
+/// [`Reloadable`]: struct.Reloadable.html
+/// [`Reloadable::metrics`]: struct.Reloadable.html#method.metrics
+/// [`Reloadable::set_metrics_sink`]: struct.Reloadable.html#method.set_metrics_sink
+/// [`reload`]: struct.Reloadable.html#method.reload
+/// [`reload_now`]: struct.Reloadable.html#method.reload_now
+#[derive(Debug, Clone, Default)]
+pub struct ReloadMetrics {
+    reloads: u64,
+    failures: u64,
+    /// How long the successful reloads took, all together.
+    reload_time: Duration,
+    last_reload_time: Option<Duration>,
+    last_reload: Option<Instant>,
+}
+
+impl ReloadMetrics {
+    /// The number of successful reloads.
+    pub fn reloads(&self) -> u64 {
+        self.reloads
+    }
+
+    /// The number of reloads that returned an error.
+    pub fn failures(&self) -> u64 {
+        self.failures
+    }
+
+    /// How long the successful reloads took, all together.
+    pub fn total_reload_time(&self) -> Duration {
+        self.reload_time
+    }
+
+    /// How long a successful reload took on average, or `None` before the
+    /// first one.
+    pub fn average_reload_time(&self) -> Option<Duration> {
+        if self.reloads == 0 {
+            return None;
+        }
+        Some(Duration::from_nanos((self.reload_time.as_nanos() / u128::from(self.reloads)) as u64))
+    }
+
+    /// How long the last successful reload took.
+    pub fn last_reload_time(&self) -> Option<Duration> {
+        self.last_reload_time
+    }
+
+    /// When the last successful reload finished.
+    pub fn last_reload(&self) -> Option<Instant> {
+        self.last_reload
+    }
+
+    /// How long ago the last successful reload finished, as of now.
+    pub fn time_since_last_reload(&self) -> Option<Duration> {
+        self.last_reload.map(|at| at.elapsed())
+    }
+
+    /// Count a reload that took `took`.
+    pub(crate) fn record(&mut self, took: Duration, succeeded: bool) {
+        if !succeeded {
+            self.failures += 1;
+            return;
+        }
+        self.reloads += 1;
+        self.reload_time += took;
+        self.last_reload_time = Some(took);
+        self.last_reload = Some(Instant::now());
+    }
+}
@@ -0,0 +1,177 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr;
+use std::slice;
+
+use ::libc;
+
+/// A `State` buffer that lives in a memory-mapped file.
+///
+/// The mapping is `MAP_SHARED`, so every write the library makes to its state
+/// lands in the page cache for the file and survives the host process
+/// crashing. It does *not* survive the machine losing power unless the pages
+/// have been flushed; see [`flush`](#method.flush).
+///
+/// The file holds the raw, native-endian bytes of the `State` struct, so it is
+/// only meaningful to a library with the same `State` layout, on the same
+/// kind of machine. Two processes should never map the same state file at
+/// once.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate live_reload;
+/// # fn main() {
+/// # if cfg!(miri) { return; } // Miri can't map files.
+/// use live_reload::MappedState;
+///
+/// let path = std::env::temp_dir().join("live-reload-mapped-state-doctest");
+/// # let _ = std::fs::remove_file(&path);
+/// {
+///     let mut state = MappedState::open(&path).unwrap();
+///     state.resize(2).unwrap();
+///     state.as_mut_slice().copy_from_slice(&[17, 42]);
+/// }
+/// // Reopening the file maps the state that was left behind.
+/// let state = MappedState::open(&path).unwrap();
+/// assert_eq!(state.as_slice(), &[17, 42]);
+/// # drop(state);
+/// # std::fs::remove_file(&path).unwrap();
+/// # }
+/// ```
+pub struct MappedState {
+    file: File,
+    ptr: *mut u64,
+    /// The length of the mapping in `u64`s.
+    len: usize,
+}
+
+impl MappedState {
+    /// Open (or create) the state file and map whatever it already contains.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        Self::from_file(file)
+    }
+
+    /// Map whatever `file` already contains.
+    pub(crate) fn from_file(file: File) -> io::Result<Self> {
+        let bytes = file.metadata()?.len() as usize;
+        let mut mapped = MappedState {
+            file,
+            ptr: ptr::NonNull::dangling().as_ptr(),
+            len: 0,
+        };
+        // A file with a partial trailing unit is rounded up so that the mapping
+        // always covers a whole number of `u64`s.
+        mapped.resize(bytes.div_ceil(8))?;
+        Ok(mapped)
+    }
+
+    /// The number of `u64`s currently mapped.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether nothing is mapped yet, which is the case for a new state file.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Grow the file and the mapping to hold at least `len` `u64`s.
+    ///
+    /// The mapping never shrinks, so that bytes past the end of a smaller
+    /// `State` are still there if a later build grows it again. Neither does
+    /// the file, in case something else has it mapped further. Growing the
+    /// mapping moves it, so pointers from [`as_mut_ptr`](#method.as_mut_ptr)
+    /// are invalidated.
+    pub fn resize(&mut self, len: usize) -> io::Result<()> {
+        if len <= self.len {
+            return Ok(());
+        }
+        let bytes = len * 8;
+        if self.file.metadata()?.len() < bytes as u64 {
+            self.file.set_len(bytes as u64)?;
+        }
+        let ptr = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                bytes,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                self.file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        self.unmap();
+        self.ptr = ptr as *mut u64;
+        self.len = len;
+        Ok(())
+    }
+
+    /// Get a pointer to the start of the mapping.
+    ///
+    /// Mappings are page-aligned, so this is suitably aligned for any `State`
+    /// with an alignment no greater than the page size.
+    pub fn as_mut_ptr(&mut self) -> *mut u64 {
+        self.ptr
+    }
+
+    /// View the mapping as a slice of `u64`s.
+    pub fn as_slice(&self) -> &[u64] {
+        unsafe { slice::from_raw_parts(self.ptr, self.len) }
+    }
+
+    /// View the mapping as a mutable slice of `u64`s.
+    pub fn as_mut_slice(&mut self) -> &mut [u64] {
+        unsafe { slice::from_raw_parts_mut(self.ptr, self.len) }
+    }
+
+    /// The file that is mapped.
+    pub(crate) fn file(&self) -> &File {
+        &self.file
+    }
+
+    /// Synchronously write the mapped pages back to the file.
+    pub fn flush(&self) -> io::Result<()> {
+        if self.len == 0 {
+            return Ok(());
+        }
+        let ret = unsafe {
+            libc::msync(self.ptr as *mut libc::c_void, self.len * 8, libc::MS_SYNC)
+        };
+        if ret == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    fn unmap(&mut self) {
+        if self.len != 0 {
+            unsafe {
+                libc::munmap(self.ptr as *mut libc::c_void, self.len * 8);
+            }
+        }
+    }
+}
+
+// The mapping is owned by the `MappedState`, like the buffer of a `Vec`, and
+// it's only written through `&mut self`.
+unsafe impl Send for MappedState {}
+unsafe impl Sync for MappedState {}
+
+impl Drop for MappedState {
+    fn drop(&mut self) {
+        self.unmap();
+    }
+}
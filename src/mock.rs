@@ -0,0 +1,197 @@
+//! A scriptable stand-in for a library, for testing host code.
+
+use std::collections::VecDeque;
+
+use super::ShouldQuit;
+use guard::{self, PanicSink};
+use internals::{self, ReloadApi};
+
+type Stage<Host> = Box<dyn FnMut(&mut Host) + Send>;
+type Update<Host> = Box<dyn FnMut(&mut Host) -> ShouldQuit + Send>;
+
+/// A fake library whose lifecycle functions are closures, for testing the
+/// host code that drives a [`Reloadable`][].
+///
+/// Give each lifecycle function the closure it should run, with the
+/// `on_*` methods, and hand the mock to [`Reloadable::with_mock`][]. The
+/// closures only get the `Host`: the mock has no `State` of its own, so
+/// anything it needs to remember can be captured by the closures. Any
+/// function without a closure does nothing, and `update` returns
+/// `ShouldQuit::No`, after going through the results set with
+/// [`script_updates`](#method.script_updates). A closure that panics is
+/// reported as [`Error::LibraryPanicked`][], like a library that panics.
+///
+/// This is only available with the `testing` feature enabled.
+///
+/// ```rust
+/// # extern crate live_reload;
+/// # fn main() {
+/// use live_reload::{Error, MockLibrary, Reloadable, ShouldQuit};
+///
+/// struct Host {
+///     log: Vec<&'static str>,
+/// }
+///
+/// let mock = MockLibrary::new()
+///     .on_init(|host: &mut Host| host.log.push("init"))
+///     .on_reload(|host: &mut Host| host.log.push("reload"))
+///     .on_update(|_: &mut Host| panic!("out of script"))
+///     .script_updates(vec![ShouldQuit::No, ShouldQuit::Yes]);
+///
+/// let mut app = Reloadable::with_mock(Host { log: Vec::new() }, mock).unwrap();
+/// assert_eq!(app.update(), ShouldQuit::No);
+/// app.reload_now().unwrap();
+/// assert_eq!(app.update(), ShouldQuit::Yes);
+/// assert!(matches!(app.try_update(), Err(Error::LibraryPanicked { .. })));
+/// assert_eq!(app.host().log, ["init", "reload"]);
+/// # }
+/// ```
+///
+/// [`Reloadable`]: struct.Reloadable.html
+/// [`Reloadable::with_mock`]: struct.Reloadable.html#method.with_mock
+/// [`Error::LibraryPanicked`]: enum.Error.html#variant.LibraryPanicked
+pub struct MockLibrary<Host> {
+    init: Option<Stage<Host>>,
+    reload: Option<Stage<Host>>,
+    update: Option<Update<Host>>,
+    unload: Option<Stage<Host>>,
+    deinit: Option<Stage<Host>>,
+    /// The results the next updates return, before `update` is called.
+    script: VecDeque<ShouldQuit>,
+}
+
+impl<Host> Default for MockLibrary<Host> {
+    fn default() -> Self {
+        MockLibrary {
+            init: None,
+            reload: None,
+            update: None,
+            unload: None,
+            deinit: None,
+            script: VecDeque::new(),
+        }
+    }
+}
+
+impl<Host> MockLibrary<Host> {
+    /// A mock whose lifecycle functions all do nothing.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Run `init` when the library is started.
+    pub fn on_init<F: FnMut(&mut Host) + Send + 'static>(mut self, init: F) -> Self {
+        self.init = Some(Box::new(init));
+        self
+    }
+
+    /// Run `reload` each time the library is reloaded.
+    pub fn on_reload<F: FnMut(&mut Host) + Send + 'static>(mut self, reload: F) -> Self {
+        self.reload = Some(Box::new(reload));
+        self
+    }
+
+    /// Run `update` for each update, once the scripted results have run
+    /// out.
+    pub fn on_update<F: FnMut(&mut Host) -> ShouldQuit + Send + 'static>(mut self, update: F) -> Self {
+        self.update = Some(Box::new(update));
+        self
+    }
+
+    /// Run `unload` before each reload.
+    pub fn on_unload<F: FnMut(&mut Host) + Send + 'static>(mut self, unload: F) -> Self {
+        self.unload = Some(Box::new(unload));
+        self
+    }
+
+    /// Run `deinit` when the `Reloadable` shuts down.
+    pub fn on_deinit<F: FnMut(&mut Host) + Send + 'static>(mut self, deinit: F) -> Self {
+        self.deinit = Some(Box::new(deinit));
+        self
+    }
+
+    /// Have the next updates return `results`, in order, without calling the
+    /// `update` closure.
+    pub fn script_updates<I: IntoIterator<Item = ShouldQuit>>(mut self, results: I) -> Self {
+        self.script.extend(results);
+        self
+    }
+
+    /// The `ReloadApi` that runs the mock behind the pointer it's handed in
+    /// place of a `State`.
+    pub(crate) fn api() -> ReloadApi<Host> {
+        ReloadApi {
+            abi_version: internals::ABI_VERSION,
+            capabilities: 0,
+            size: internals::size_of::<()>,
+            init: |host, state, sink| run_stage(state, sink, |mock| &mut mock.init, host),
+            reload: |host, state, sink| run_stage(state, sink, |mock| &mut mock.reload, host),
+            update: |host, state, sink| {
+                guard::catch(sink, || {
+                    let mock = unsafe { mock_at::<Host>(state) };
+                    match mock.script.pop_front() {
+                        Some(quit) => quit,
+                        None => mock.update.as_mut().map_or(ShouldQuit::No, |update| update(host)),
+                    }
+                }).unwrap_or(ShouldQuit::No)
+            },
+            unload: |host, state, sink| run_stage(state, sink, |mock| &mut mock.unload, host),
+            deinit: |host, state, sink| run_stage(state, sink, |mock| &mut mock.deinit, host),
+            is_safe_to_reload: None,
+            reload_progress: None,
+            update_with_events: None,
+            update_with_extra: None,
+            validate_state: None,
+            requirements: None,
+            state_version: 0,
+            migrate: None,
+            serialize: None,
+            deserialize: None,
+            persistent_range: None,
+        }
+    }
+}
+
+/// Run the closure that `stage` picks out of the mock, if it's set.
+fn run_stage<Host>(
+    state: *mut (),
+    sink: &mut PanicSink,
+    stage: fn(&mut MockLibrary<Host>) -> &mut Option<Stage<Host>>,
+    host: &mut Host,
+) {
+    guard::catch(sink, || {
+        let mock = unsafe { mock_at::<Host>(state) };
+        if let Some(ref mut run) = *stage(mock) {
+            run(host);
+        }
+    });
+}
+
+/// Get the mock that `state` points to.
+///
+/// The `Reloadable` that owns the mock hands its lifecycle functions a
+/// pointer to it in place of a `State`, and outlives every call into it.
+unsafe fn mock_at<'a, Host>(state: *mut ()) -> &'a mut MockLibrary<Host> {
+    &mut *(state as *mut MockLibrary<Host>)
+}
+
+/// Owns a mock that's reached through the pointer in its `State`, so that it
+/// can't be reached through a `Box` at the same time.
+pub(crate) struct OwnedMock<Host>(*mut MockLibrary<Host>);
+
+impl<Host> OwnedMock<Host> {
+    pub(crate) fn new(mock: MockLibrary<Host>) -> Self {
+        OwnedMock(Box::into_raw(Box::new(mock)))
+    }
+
+    /// The pointer to hand the mock's lifecycle functions.
+    pub(crate) fn as_ptr(&self) -> *mut MockLibrary<Host> {
+        self.0
+    }
+}
+
+impl<Host> Drop for OwnedMock<Host> {
+    fn drop(&mut self) {
+        drop(unsafe { Box::from_raw(self.0) });
+    }
+}
@@ -0,0 +1,459 @@
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::channel;
+use std::time::Duration;
+
+use ::libloading::Library;
+use ::notify::{self, RecommendedWatcher, Watcher};
+
+use super::{Error, ShouldQuit};
+use super::internals::{self, ReloadApi, ReloadApiEntry, ReloadApiTable};
+use builder::Config;
+use events::{self, Events};
+use guard::{self, LifecyclePhase};
+use with_std::{check_panic_strategy, watched_dir};
+
+struct TableSym<Host: 'static> {
+    /// This needs to be present so that the library will be closed on drop.
+    /// It's `None` for a table that's part of the host itself.
+    _lib: Option<Library>,
+    table: *const ReloadApiTable<Host>,
+}
+
+// The table is a static, in the loaded library or the host, which is never
+// written, and stays put for as long as the library is loaded.
+unsafe impl<Host> Send for TableSym<Host> {}
+unsafe impl<Host> Sync for TableSym<Host> {}
+
+impl<Host> TableSym<Host> {
+    fn new(path: &Path) -> Result<Self, Error> {
+        let library = Library::new(path)?;
+        let table: *const ReloadApiTable<Host> = unsafe {
+            *library.get::<*mut ReloadApiTable<Host>>(b"RELOAD_API_TABLE")?
+        };
+        let found = unsafe { (*table).abi_version };
+        if found != internals::ABI_VERSION {
+            return Err(Error::AbiMismatch {
+                expected: internals::ABI_VERSION,
+                found,
+            });
+        }
+        unsafe { check_panic_strategy(&library)? };
+        for entry in unsafe { (*table).entries } {
+            unsafe { ReloadApi::validate(&entry.api)? };
+        }
+        Ok(TableSym {
+            _lib: Some(library),
+            table,
+        })
+    }
+
+    #[cfg(feature = "in-process")]
+    fn in_process(table: &'static ReloadApiTable<Host>) -> Self {
+        TableSym {
+            _lib: None,
+            table,
+        }
+    }
+
+    fn entries(&self) -> &[ReloadApiEntry<Host>] {
+        unsafe { (*self.table).entries }
+    }
+}
+
+/// One of the modules exported by a library, and its state.
+struct Module {
+    name: String,
+    state: Vec<u64>,
+}
+
+impl Module {
+    fn state_ptr(&mut self) -> *mut () {
+        self.state.as_mut_ptr() as *mut ()
+    }
+}
+
+/// The indices of `modules`, with those named in `order` first, in that
+/// order, and the rest after them in the order the library declares them.
+fn ordered(modules: &[Module], order: &[String]) -> Vec<usize> {
+    let named = order.iter().filter_map(|name| modules.iter().position(|module| module.name == *name));
+    let mut indices: Vec<usize> = Vec::with_capacity(modules.len());
+    for index in named.chain(0..modules.len()) {
+        if !indices.contains(&index) {
+            indices.push(index);
+        }
+    }
+    indices
+}
+
+/// A handle to a library that exports several independent reloadable modules.
+///
+/// This is the host side of [`live_reload_multi!`][]. Each module exported by
+/// the library gets its own `State` buffer, and all of them share the `Host`.
+/// Every lifecycle call is made on each module in turn, in the order the
+/// modules are declared in the library, unless another order is set with
+/// [`set_update_order`][] or [`set_reload_order`][].
+///
+/// When the library is reloaded, modules are matched up with the previous
+/// build by name. A module that kept its name keeps its state and gets
+/// `reload`, a new module gets a fresh zeroed state and `init`, and the state
+/// of a module that was removed is dropped, without `deinit`, since its code
+/// is already gone.
+///
+/// ```rust,no_run
+/// # extern crate live_reload;
+/// # fn main() {
+/// # struct Host;
+/// use live_reload::{MultiReloadable, ShouldQuit};
+///
+/// let mut app = MultiReloadable::new("target/debug/libplugin.so", Host)
+///     .expect("Should successfully load");
+/// while app.update() == ShouldQuit::No {
+///     app.reload().expect("Should successfully reload");
+/// }
+/// # }
+/// ```
+///
+/// [`live_reload_multi!`]: macro.live_reload_multi.html
+/// [`set_update_order`]: #method.set_update_order
+/// [`set_reload_order`]: #method.set_reload_order
+pub struct MultiReloadable<Host: 'static> {
+    /// The canonical path of the library, as of the last successful load.
+    path: PathBuf,
+    /// The absolute path the library was asked for, which might be a symlink.
+    request_path: PathBuf,
+    sym: Option<TableSym<Host>>,
+    /// The modules of the loaded library, in the same order as its table.
+    modules: Vec<Module>,
+    /// Whether a change has been seen that hasn't been reloaded yet.
+    pending_reload: bool,
+    /// The names of the modules to update first, in order.
+    update_order: Vec<String>,
+    /// The names of the modules to reload first, if not the same as
+    /// `update_order`.
+    reload_order: Option<Vec<String>>,
+    /// The table a library run in-process exports, in place of loading one.
+    #[cfg(feature = "in-process")]
+    in_process: Option<&'static ReloadApiTable<Host>>,
+    _watcher: Option<RecommendedWatcher>,
+    events: Events,
+    host: Host,
+}
+
+impl<Host> MultiReloadable<Host> {
+    /// Load a library that exports its modules with [`live_reload_multi!`][],
+    /// and `init` each of them.
+    ///
+    /// Like [`Reloadable`][], this refuses a library built with a different
+    /// panic strategy than the host with [`Error::PanicStrategyMismatch`][],
+    /// here and on every reload, with no way to turn the check off.
+    ///
+    /// [`live_reload_multi!`]: macro.live_reload_multi.html
+    /// [`Reloadable`]: struct.Reloadable.html
+    /// [`Error::PanicStrategyMismatch`]: enum.Error.html#variant.PanicStrategyMismatch
+    pub fn new<P: AsRef<Path>>(path: P, host: Host) -> Result<Self, Error> {
+        let request_path = Config::<Host>::default().resolve(path.as_ref())?;
+        let sym = TableSym::new(&request_path)?;
+        let (tx, rx) = channel();
+        let mut watcher = notify::watcher(tx, Duration::from_secs(1))?;
+        watcher.watch(
+            watched_dir(&request_path),
+            notify::RecursiveMode::NonRecursive,
+        )?;
+        let mut app = MultiReloadable {
+            path: request_path.canonicalize()?,
+            request_path,
+            sym: None,
+            modules: Vec::new(),
+            pending_reload: false,
+            update_order: Vec::new(),
+            reload_order: None,
+            #[cfg(feature = "in-process")]
+            in_process: None,
+            _watcher: Some(watcher),
+            events: Events::new(rx),
+            host,
+        };
+        app.install(sym);
+        Ok(app)
+    }
+
+    /// Run the modules of a [`live_reload_multi!`][] table that is compiled
+    /// into the host itself, rather than loaded from a dynamic library.
+    ///
+    /// Like [`Reloadable::new_in_process`][], nothing is watched, and
+    /// [`reload_now`][] unloads and reloads the same modules, which makes it
+    /// handy for testing them. See [`set_update_order`][] for an example.
+    ///
+    /// [`live_reload_multi!`]: macro.live_reload_multi.html
+    /// [`Reloadable::new_in_process`]: struct.Reloadable.html#method.new_in_process
+    /// [`reload_now`]: #method.reload_now
+    /// [`set_update_order`]: #method.set_update_order
+    #[cfg(feature = "in-process")]
+    pub fn new_in_process(table: &'static ReloadApiTable<Host>, host: Host) -> Result<Self, Error> {
+        let mut app = MultiReloadable {
+            path: PathBuf::new(),
+            request_path: PathBuf::new(),
+            sym: None,
+            modules: Vec::new(),
+            pending_reload: false,
+            update_order: Vec::new(),
+            reload_order: None,
+            in_process: Some(table),
+            _watcher: None,
+            events: Events::closed(),
+            host,
+        };
+        app.install(TableSym::in_process(table));
+        Ok(app)
+    }
+
+    /// Load the library, or take the table run in-process.
+    fn load(&self) -> Result<TableSym<Host>, Error> {
+        #[cfg(feature = "in-process")]
+        {
+            if let Some(table) = self.in_process {
+                return Ok(TableSym::in_process(table));
+            }
+        }
+        TableSym::new(&self.request_path)
+    }
+
+    /// Reload the library if it has changed, otherwise do nothing.
+    ///
+    /// See [`Reloadable::reload`](struct.Reloadable.html#method.reload). The
+    /// reload is put off while any module's `is_safe_to_reload` returns false.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        while let Some(evt) = self.events.try_recv() {
+            if events::is_change(&evt, &self.path, &self.request_path, events::SIDECAR_EXTENSIONS) {
+                self.pending_reload = true;
+            }
+        }
+        if self.sym.is_none() || (self.pending_reload && self.modules_allow_reload()) {
+            self.reload_now()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Ask every module whether its state can be reloaded right now.
+    fn modules_allow_reload(&mut self) -> bool {
+        match self.sym {
+            Some(ref sym) => sym
+                .entries()
+                .iter()
+                .zip(&mut self.modules)
+                .all(|(entry, module)| entry.api.allows_reload(module.state_ptr())),
+            None => true,
+        }
+    }
+
+    /// Immediately reload the library without checking whether it has changed.
+    ///
+    /// This calls `unload` on every module, unloads the library, loads it
+    /// again, and then calls `reload` (or `init`, for new modules) on every
+    /// module. If the new library fails to load, this returns an `Err` and
+    /// leaves no library loaded. If `unload` panics, this returns
+    /// [`Error::LibraryPanicked`][] and the old library stays loaded.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `reload` or `init` panics, with the library's message.
+    ///
+    /// [`Error::LibraryPanicked`]: enum.Error.html#variant.LibraryPanicked
+    pub fn reload_now(&mut self) -> Result<(), Error> {
+        self.pending_reload = false;
+        if let Some(ref sym) = self.sym {
+            let order = ordered(&self.modules, self.reload_order());
+            let host = &mut self.host;
+            for index in order {
+                let state = self.modules[index].state_ptr();
+                let unload = sym.entries()[index].api.unload;
+                guard::call(LifecyclePhase::Unload, |sink| unload(host, state, sink))?;
+            }
+        }
+        self.sym = None;
+        let sym = self.load()?;
+        // A table run in-process has no path, and nothing to resolve.
+        if !self.request_path.as_os_str().is_empty() {
+            if let Ok(path) = self.request_path.canonicalize() {
+                self.path = path;
+            }
+        }
+        self.install(sym);
+        Ok(())
+    }
+
+    /// Match up the modules of a freshly loaded library with the existing
+    /// ones, and start them up.
+    fn install(&mut self, sym: TableSym<Host>) {
+        let mut old = ::std::mem::take(&mut self.modules);
+        let mut kept = Vec::new();
+        for entry in sym.entries() {
+            let existing = old.iter().position(|module| module.name == entry.name);
+            let mut module = match existing {
+                Some(index) => old.swap_remove(index),
+                None => Module {
+                    name: entry.name.to_owned(),
+                    state: Vec::new(),
+                },
+            };
+            module.state.resize((entry.api.size)().div_ceil(8), 0);
+            kept.push(existing.is_some());
+            self.modules.push(module);
+        }
+        for index in ordered(&self.modules, self.reload_order()) {
+            let entry = &sym.entries()[index];
+            let (phase, start) = if kept[index] {
+                (LifecyclePhase::Reload, entry.api.reload)
+            } else {
+                (LifecyclePhase::Init, entry.api.init)
+            };
+            let state = self.modules[index].state_ptr();
+            guard::call_or_panic(phase, |sink| start(&mut self.host, state, sink));
+        }
+        self.sym = Some(sym);
+    }
+
+    /// Call the update method of every module, in update order.
+    ///
+    /// This returns `ShouldQuit::Yes` if any module asked to quit. Every
+    /// module is updated regardless. If no library is currently loaded, this
+    /// does nothing and returns `ShouldQuit::No`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a module panics, with the library's message and location.
+    pub fn update(&mut self) -> ShouldQuit {
+        let mut quit = ShouldQuit::No;
+        if let Some(ref sym) = self.sym {
+            let host = &mut self.host;
+            for index in ordered(&self.modules, &self.update_order) {
+                let state = self.modules[index].state_ptr();
+                let update = sym.entries()[index].api.update;
+                quit = quit.combine(guard::call_or_panic(LifecyclePhase::Update, |sink| update(host, state, sink)));
+            }
+        }
+        quit
+    }
+
+    /// The names of the modules of the loaded library, in the order the
+    /// library declares them.
+    pub fn module_names(&self) -> Vec<&str> {
+        self.modules.iter().map(|module| &*module.name).collect()
+    }
+
+    /// Update the modules named in `names` first, in that order, and the
+    /// rest after them, in the order the library declares them.
+    ///
+    /// This makes the order a pipeline can depend on explicit, such as
+    /// physics before rendering, rather than leaving it to the order the
+    /// library happens to declare its modules in. Names that don't match a
+    /// module are kept but skipped, so that a module that's added by a later
+    /// build falls into place once it's there. Until [`set_reload_order`][]
+    /// is called, this is the reload order too.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{MultiReloadable, ShouldQuit};
+    ///
+    /// pub struct Host { log: Vec<&'static str> }
+    /// pub struct Nothing;
+    ///
+    /// fn physics(host: &mut Host, _: &mut Nothing) -> ShouldQuit {
+    ///     host.log.push("physics");
+    ///     ShouldQuit::No
+    /// }
+    /// fn render(host: &mut Host, _: &mut Nothing) -> ShouldQuit {
+    ///     host.log.push("render");
+    ///     ShouldQuit::No
+    /// }
+    /// fn reload_audio(host: &mut Host, _: &mut Nothing) { host.log.push("reload audio") }
+    /// fn reload_render(host: &mut Host, _: &mut Nothing) { host.log.push("reload render") }
+    /// fn nothing(_: &mut Host, _: &mut Nothing) {}
+    /// fn no_update(_: &mut Host, _: &mut Nothing) -> ShouldQuit { ShouldQuit::No }
+    ///
+    /// live_reload_multi! {
+    ///     host: Host;
+    ///     render {
+    ///         state: Nothing; init: nothing; reload: reload_render; update: render;
+    ///         unload: nothing; deinit: nothing;
+    ///     }
+    ///     audio {
+    ///         state: Nothing; init: nothing; reload: reload_audio; update: no_update;
+    ///         unload: nothing; deinit: nothing;
+    ///     }
+    ///     physics {
+    ///         state: Nothing; init: nothing; reload: nothing; update: physics;
+    ///         unload: nothing; deinit: nothing;
+    ///     }
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut app = MultiReloadable::new_in_process(&RELOAD_API_TABLE, Host { log: Vec::new() }).unwrap();
+    /// app.set_update_order(&["physics", "render"]);
+    /// assert_eq!(app.update_order(), ["physics", "render", "audio"]);
+    /// app.update();
+    /// assert_eq!(app.host().log, ["physics", "render"]);
+    ///
+    /// app.set_reload_order(&["audio"]);
+    /// app.host_mut().log.clear();
+    /// app.reload_now().unwrap();
+    /// assert_eq!(app.host().log, ["reload audio", "reload render"]);
+    /// # }
+    /// ```
+    ///
+    /// [`set_reload_order`]: #method.set_reload_order
+    pub fn set_update_order(&mut self, names: &[&str]) {
+        self.update_order = names.iter().map(|&name| name.to_owned()).collect();
+    }
+
+    /// The names of the modules of the loaded library, in the order they're
+    /// updated.
+    pub fn update_order(&self) -> Vec<&str> {
+        let order = ordered(&self.modules, &self.update_order);
+        order.into_iter().map(|index| &*self.modules[index].name).collect()
+    }
+
+    /// Make `unload`, `reload`, `init` and `deinit` calls on the modules
+    /// named in `names` first, in that order, and on the rest after them, in
+    /// the order the library declares them.
+    ///
+    /// By default, this is the order set with [`set_update_order`][]. Use
+    /// this when handing resources over between modules needs another, say
+    /// an asset module that has to reload before the modules that use it.
+    ///
+    /// [`set_update_order`]: #method.set_update_order
+    pub fn set_reload_order(&mut self, names: &[&str]) {
+        self.reload_order = Some(names.iter().map(|&name| name.to_owned()).collect());
+    }
+
+    /// The names of the modules to reload first, in order.
+    fn reload_order(&self) -> &[String] {
+        self.reload_order.as_ref().unwrap_or(&self.update_order)
+    }
+
+    /// Get a reference to the `Host` struct.
+    pub fn host(&self) -> &Host { &self.host }
+
+    /// Get a mutable reference to the `Host` struct.
+    pub fn host_mut(&mut self) -> &mut Host { &mut self.host }
+}
+
+impl<Host> Drop for MultiReloadable<Host> {
+    fn drop(&mut self) {
+        if let Some(ref sym) = self.sym {
+            let order = ordered(&self.modules, self.reload_order());
+            let host = &mut self.host;
+            for index in order {
+                let state = self.modules[index].state_ptr();
+                let deinit = sym.entries()[index].api.deinit;
+                let deinited = guard::call(LifecyclePhase::Deinit, |sink| deinit(host, state, sink));
+                if let Err(err) = deinited {
+                    if !::std::thread::panicking() {
+                        panic!("{}", err);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+use super::{Error, Reloadable};
+
+/// Hears about each step in the lifecycle of a [`Reloadable`][].
+///
+/// Install one with [`Reloadable::set_observer`][]. Each method is called
+/// right after the step it's named for has happened, with the `Reloadable`
+/// as it is then, so that it can look at the host, the state and the
+/// [`library_info`][]. Every method does nothing by default, so an observer
+/// only needs to implement the ones it cares about.
+///
+/// In a reload, the steps are `on_unload`, then `on_migrate` if the `State`
+/// changed its layout, then `on_state_checksums` if they're traced, then
+/// `on_reload`, once the new version's `reload` has run. A step that fails calls `on_error` instead, and the steps after it
+/// don't happen.
+///
+/// ```rust
+/// #[macro_use] extern crate live_reload;
+///
+/// use live_reload::{Reloadable, ReloadObserver, ShouldQuit};
+///
+/// pub struct Host;
+/// #[repr(C)] pub struct State;
+/// fn nothing(_: &mut Host, _: &mut State) {}
+/// fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+///
+/// live_reload! {
+///     host: Host;
+///     state: State;
+///     init: nothing;
+///     reload: nothing;
+///     update: update;
+///     unload: nothing;
+///     deinit: nothing;
+/// }
+///
+/// struct Log(std::sync::Arc<std::sync::Mutex<Vec<String>>>);
+///
+/// impl ReloadObserver<Host> for Log {
+///     fn on_reload(&mut self, app: &Reloadable<Host>) {
+///         self.0.lock().unwrap().push(format!("reload {}", app.reload_count()));
+///     }
+///     fn on_unload(&mut self, _: &Reloadable<Host>) {
+///         self.0.lock().unwrap().push("unload".into());
+///     }
+/// }
+///
+/// # fn main() {
+/// let log = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+/// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+/// app.set_observer(Box::new(Log(log.clone())));
+/// app.reload_now().unwrap();
+/// assert_eq!(*log.lock().unwrap(), ["unload", "reload 1"]);
+/// # }
+/// ```
+///
+/// [`Reloadable`]: struct.Reloadable.html
+/// [`Reloadable::set_observer`]: struct.Reloadable.html#method.set_observer
+/// [`library_info`]: struct.Reloadable.html#method.library_info
+pub trait ReloadObserver<Host>: Send {
+    /// The library's `init` was called.
+    ///
+    /// The first `init` usually runs while the `Reloadable` is being made,
+    /// before there's an observer to hear about it. Build it with
+    /// [`Builder::lazy_init`][] to put `init` off until there is.
+    ///
+    /// [`Builder::lazy_init`]: struct.Builder.html#method.lazy_init
+    fn on_init(&mut self, _app: &Reloadable<Host>) {}
+
+    /// A new version of the library was loaded, and its `reload` called if
+    /// the library had been started.
+    fn on_reload(&mut self, _app: &Reloadable<Host>) {}
+
+    /// The old version of the library was sent `unload`, ahead of a reload.
+    fn on_unload(&mut self, _app: &Reloadable<Host>) {}
+
+    /// The new version of the library declares a different `State` layout
+    /// than the last one that loaded, going from the layout hash `old` to
+    /// `new`.
+    ///
+    /// This is only known for libraries that pass a layout hash to
+    /// `live_reload!`, usually from [`reloadable_state!`][]. By the time
+    /// this is called, the new version's `reload` has had its chance to
+    /// migrate the state.
+    ///
+    /// [`reloadable_state!`]: macro.reloadable_state.html
+    fn on_migrate(&mut self, _app: &Reloadable<Host>, _old: u64, _new: u64) {}
+
+    /// The [checksum][] of the `State` was `before` just ahead of the old
+    /// version's `unload`, and is `after` now that the new version's
+    /// `reload` has run.
+    ///
+    /// This is only called with [`Builder::trace_state_checksums`][] on.
+    ///
+    /// [checksum]: struct.Reloadable.html#method.state_checksum
+    /// [`Builder::trace_state_checksums`]: struct.Builder.html#method.trace_state_checksums
+    fn on_state_checksums(&mut self, _app: &Reloadable<Host>, _before: u64, _after: u64) {}
+
+    /// The library's `deinit` was called, while shutting down or in
+    /// [`Reloadable::restart`][].
+    ///
+    /// When shutting down, the library is already unloaded by then, so
+    /// `library_info` is `None`.
+    ///
+    /// [`Reloadable::restart`]: struct.Reloadable.html#method.restart
+    fn on_deinit(&mut self, _app: &Reloadable<Host>) {}
+
+    /// An update returned `ShouldQuit::Yes`, where the one before it didn't.
+    ///
+    /// Updates that keep asking to quit after that aren't reported again.
+    /// See [`Reloadable::set_on_quit_requested`][] for a hook that can get
+    /// at the `Host`.
+    ///
+    /// [`Reloadable::set_on_quit_requested`]: struct.Reloadable.html#method.set_on_quit_requested
+    fn on_quit_requested(&mut self, _app: &Reloadable<Host>) {}
+
+    /// A reload, an update or `deinit` failed with `err`, which is also
+    /// returned to the caller.
+    fn on_error(&mut self, _app: &Reloadable<Host>, _err: &Error) {}
+}
@@ -0,0 +1,115 @@
+use std::io;
+use std::path::Path;
+
+use ::libloading::Library;
+#[cfg(unix)]
+use ::libc;
+
+/// How the platform's dynamic loader should open the library.
+///
+/// By default the library is opened with `RTLD_NOW | RTLD_LOCAL` on Unix, so
+/// that a missing symbol fails the load straight away, and the library's own
+/// symbols stay out of the way of everything else. Set other flags with
+/// [`Builder::open_flags`][].
+///
+/// Windows has no equivalent of these flags for `LoadLibrary`: every DLL
+/// resolves its imports when it's loaded, and its exports are only ever
+/// found through its own handle. There, the flags are accepted and ignored.
+///
+/// # Flags and reloading
+///
+/// Not every flag works with live reloading, so [`global`](#method.global)
+/// and [`lazy`](#method.lazy) cover the useful ones portably:
+///
+/// - `RTLD_GLOBAL` is fine, and needed when libraries loaded after yours
+///   must see its symbols, as with some plugin systems. The old version is
+///   always closed before the new one is opened, so the two versions'
+///   symbols never clash. That's not so with `Builder::retain_old_images`,
+///   which keeps the old version loaded, so libraries loaded later might
+///   bind to its symbols instead of the new version's.
+/// - `RTLD_LAZY` works, but a symbol that can't be resolved only fails when
+///   it's first called, which crashes the host instead of failing the load.
+/// - `RTLD_NODELETE` breaks reloading: the old version is never unloaded,
+///   so opening the library again just returns the old version.
+/// - `RTLD_NOLOAD` only finds a library that's already loaded, so it can
+///   never load a new version.
+///
+/// [`Builder::open_flags`]: struct.Builder.html#method.open_flags
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OpenFlags {
+    global: bool,
+    lazy: bool,
+    #[cfg(unix)]
+    raw: Option<libc::c_int>,
+}
+
+impl OpenFlags {
+    /// The default flags, `RTLD_NOW | RTLD_LOCAL` on Unix.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make the library's symbols available to libraries that are loaded
+    /// after it, like `RTLD_GLOBAL`.
+    pub fn global(mut self, global: bool) -> Self {
+        self.global = global;
+        self
+    }
+
+    /// Resolve the library's symbols when they're first used, rather than
+    /// when it's loaded, like `RTLD_LAZY`.
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+
+    /// Pass exactly `flags` to `dlopen`, ignoring [`global`](#method.global)
+    /// and [`lazy`](#method.lazy).
+    ///
+    /// This is an escape hatch for flags that have no portable equivalent,
+    /// like `RTLD_DEEPBIND`. See above for which flags break reloading.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # extern crate libc;
+    /// # fn main() {
+    /// # #[cfg(target_os = "linux")] {
+    /// use live_reload::{Builder, OpenFlags};
+    ///
+    /// let flags = OpenFlags::from_raw(libc::RTLD_NOW | libc::RTLD_DEEPBIND);
+    /// let builder = Builder::<()>::new("target/debug/libplugin.so").open_flags(flags);
+    /// # let _ = builder;
+    /// # }
+    /// # }
+    /// ```
+    #[cfg(unix)]
+    pub fn from_raw(flags: libc::c_int) -> Self {
+        OpenFlags {
+            raw: Some(flags),
+            ..Self::default()
+        }
+    }
+
+    /// The flags to pass to `dlopen`.
+    #[cfg(unix)]
+    fn to_raw(self) -> libc::c_int {
+        if let Some(raw) = self.raw {
+            return raw;
+        }
+        let binding = if self.lazy { libc::RTLD_LAZY } else { libc::RTLD_NOW };
+        let visibility = if self.global { libc::RTLD_GLOBAL } else { libc::RTLD_LOCAL };
+        binding | visibility
+    }
+}
+
+/// Open the library at `path` with `flags`.
+#[cfg(unix)]
+pub(crate) fn open(path: &Path, flags: OpenFlags) -> io::Result<Library> {
+    ::libloading::os::unix::Library::open(Some(path), flags.to_raw()).map(Library::from)
+}
+
+/// Open the library at `path` with `flags`.
+#[cfg(not(unix))]
+pub(crate) fn open(path: &Path, _flags: OpenFlags) -> io::Result<Library> {
+    Library::new(path)
+}
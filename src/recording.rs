@@ -0,0 +1,228 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt::{self, Debug};
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::thread::LocalKey;
+
+/// One call the library made into the `Host`, as a [`RecordingHost`][] saw
+/// it.
+///
+/// The arguments and result are kept as their `Debug` text, to look at, and
+/// the result also as its value, to hand back when the call is replayed.
+///
+/// [`RecordingHost`]: struct.RecordingHost.html
+#[derive(Clone)]
+pub struct HostCall {
+    function: &'static str,
+    args: String,
+    result: String,
+    value: Arc<dyn Any + Send + Sync>,
+}
+
+impl HostCall {
+    /// The name of the `Host`'s function that was called.
+    pub fn function(&self) -> &'static str {
+        self.function
+    }
+
+    /// The arguments, as `Debug` shows them, separated by commas.
+    pub fn args(&self) -> &str {
+        &self.args
+    }
+
+    /// The result, as `Debug` shows it.
+    pub fn result(&self) -> &str {
+        &self.result
+    }
+}
+
+/// Shows the call like `roll(6) -> 4`.
+impl fmt::Display for HostCall {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "{}({}) -> {}", self.function, self.args, self.result)
+    }
+}
+
+impl Debug for HostCall {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self, fmt)
+    }
+}
+
+/// A `Host` whose functions can be recorded and replayed, as declared with
+/// [`recording_host!`][].
+///
+/// [`recording_host!`]: macro.recording_host.html
+pub trait RecordHost: Sized {
+    /// Swap the functions of `self` for ones that go through the recorder,
+    /// keeping the originals to call.
+    #[doc(hidden)]
+    fn wrap(self) -> Self;
+
+    /// The recorder of this `Host`'s calls on the current thread.
+    #[doc(hidden)]
+    fn recorder() -> &'static LocalKey<RefCell<Recorder>>;
+}
+
+/// Records the calls the library makes into a `Host`, or replays them.
+///
+/// A `Host` is a struct of `fn` pointers that the host fills in, so a `Host`
+/// declared with [`recording_host!`][] can have them swapped for ones that
+/// note each call before going on to the original. [`record`][] does that,
+/// and the calls can then be taken with [`finish`][]. [`replay`][] swaps in
+/// functions that hand back the recorded results in order instead, without
+/// calling the originals at all, so a run that went wrong can be run again
+/// exactly, say to find where two runs of the library stop agreeing. A call
+/// that doesn't match the next recorded one, by function or arguments,
+/// panics, and so does one past the end of the recording. The library's
+/// panic guard reports that as a panic in the library.
+///
+/// The recording lives on the thread that started it, so the `Host` has to
+/// be called from that thread, which is the one that calls the library. Once
+/// the `RecordingHost` is finished or dropped, the `Host`'s functions go
+/// straight to the originals again.
+///
+/// See [`recording_host!`][] for an example.
+///
+/// [`recording_host!`]: macro.recording_host.html
+/// [`record`]: #method.record
+/// [`finish`]: #method.finish
+/// [`replay`]: #method.replay
+pub struct RecordingHost<Host: RecordHost> {
+    _host: PhantomData<*const Host>,
+}
+
+impl<Host: RecordHost> RecordingHost<Host> {
+    /// Start recording the calls into `host`, and return it ready to hand to
+    /// the library.
+    pub fn record(host: Host) -> (Host, Self) {
+        Self::start(host, Mode::Record, Vec::new())
+    }
+
+    /// Start replaying `calls`, and return `host` ready to hand to the
+    /// library.
+    pub fn replay(host: Host, calls: Vec<HostCall>) -> (Host, Self) {
+        Self::start(host, Mode::Replay, calls)
+    }
+
+    fn start(host: Host, mode: Mode, calls: Vec<HostCall>) -> (Host, Self) {
+        Host::recorder().with(|recorder| {
+            *recorder.borrow_mut() = Recorder { mode, calls, next: 0 };
+        });
+        (host.wrap(), RecordingHost { _host: PhantomData })
+    }
+
+    /// The calls recorded so far, or those still left to replay.
+    pub fn calls(&self) -> Vec<HostCall> {
+        Host::recorder().with(|recorder| recorder.borrow().remaining().to_vec())
+    }
+
+    /// Stop recording or replaying, and take the calls recorded, or those
+    /// that were never replayed.
+    pub fn finish(self) -> Vec<HostCall> {
+        Host::recorder().with(|recorder| {
+            let mut recorder = recorder.borrow_mut();
+            let next = recorder.next;
+            recorder.mode = Mode::Off;
+            recorder.calls.split_off(next)
+        })
+    }
+}
+
+impl<Host: RecordHost> Drop for RecordingHost<Host> {
+    fn drop(&mut self) {
+        // The thread-local may already be gone if the thread is exiting.
+        let _ = Host::recorder().try_with(|recorder| {
+            *recorder.borrow_mut() = Recorder::default();
+        });
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    Off,
+    Record,
+    Replay,
+}
+
+/// The calls of one `Host` type on one thread, for `recording_host!`.
+#[doc(hidden)]
+pub struct Recorder {
+    mode: Mode,
+    calls: Vec<HostCall>,
+    /// The next call to replay.
+    next: usize,
+}
+
+impl Default for Recorder {
+    fn default() -> Self {
+        Recorder { mode: Mode::Off, calls: Vec::new(), next: 0 }
+    }
+}
+
+impl Recorder {
+    /// Whether calls are being recorded or replayed, and so need their
+    /// arguments shown.
+    pub fn is_active(&self) -> bool {
+        self.mode != Mode::Off
+    }
+
+    fn remaining(&self) -> &[HostCall] {
+        &self.calls[self.next..]
+    }
+
+    /// Hand back the result of the next recorded call, which has to be to
+    /// `function` with `args`.
+    fn replay<R: Clone + 'static>(&mut self, function: &'static str, args: &str) -> R {
+        let call = match self.calls.get(self.next) {
+            Some(call) => call,
+            None => panic!("the replay ran out of calls at {}({})", function, args),
+        };
+        if call.function != function || call.args != args {
+            panic!("the replay diverged: expected {}, got {}({})", call, function, args);
+        }
+        let value = match call.value.downcast_ref::<R>() {
+            Some(value) => value.clone(),
+            None => panic!("the replay of {} has a result of the wrong type", call),
+        };
+        self.next += 1;
+        value
+    }
+}
+
+/// Make one call into a recorded `Host` function: `call` runs the original,
+/// and `args` are the arguments as `Debug` shows them, if the recorder is
+/// active.
+#[doc(hidden)]
+pub fn record_call<R, F>(
+    recorder: &'static LocalKey<RefCell<Recorder>>,
+    function: &'static str,
+    args: Option<String>,
+    call: F,
+) -> R
+where
+    R: Clone + Debug + Send + Sync + 'static,
+    F: FnOnce() -> R,
+{
+    let args = match args {
+        Some(args) => args,
+        None => return call(),
+    };
+    let replaying = recorder.with(|recorder| recorder.borrow().mode == Mode::Replay);
+    if replaying {
+        return recorder.with(|recorder| recorder.borrow_mut().replay(function, &args));
+    }
+    // Don't hold the recorder while the original runs, in case it calls
+    // another recorded function.
+    let result = call();
+    recorder.with(|recorder| {
+        recorder.borrow_mut().calls.push(HostCall {
+            function,
+            args,
+            result: format!("{:?}", result),
+            value: Arc::new(result.clone()),
+        })
+    });
+    result
+}
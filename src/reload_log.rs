@@ -0,0 +1,82 @@
+use std::time::SystemTime;
+
+/// What an entry in the reload log is about.
+///
+/// Each kind matches a method of [`ReloadObserver`][].
+///
+/// [`ReloadObserver`]: trait.ReloadObserver.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadLogKind {
+    /// The library's `init` was called.
+    Init,
+    /// The library's `unload` was called, before a reload.
+    Unload,
+    /// The `State` changed its layout in a reload.
+    Migrate,
+    /// A reload finished.
+    Reload,
+    /// The library's `update` asked to quit.
+    QuitRequested,
+    /// The library's `deinit` was called.
+    Deinit,
+    /// A lifecycle step failed.
+    Error,
+}
+
+/// One entry in the log kept with [`Builder::reload_log_capacity`][].
+///
+/// [`Builder::reload_log_capacity`]: struct.Builder.html#method.reload_log_capacity
+#[derive(Debug, Clone)]
+pub struct ReloadLogEntry {
+    time: SystemTime,
+    kind: ReloadLogKind,
+    detail: String,
+}
+
+impl ReloadLogEntry {
+    /// When it happened.
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    /// What happened.
+    pub fn kind(&self) -> ReloadLogKind {
+        self.kind
+    }
+
+    /// A line to show with it: the library's path for a load or reload, the
+    /// old and new layout hashes for a migration, and the message of an
+    /// error. It can be empty.
+    pub fn detail(&self) -> &str {
+        &self.detail
+    }
+}
+
+/// The last few entries of the reload log, oldest first.
+///
+/// This is a `Vec` rather than a ring buffer so that it can be read as a
+/// slice. It's only ever as long as a dev console can show, so moving the
+/// entries down when the oldest is dropped costs nothing that matters.
+pub(crate) struct ReloadLog {
+    entries: Vec<ReloadLogEntry>,
+}
+
+impl ReloadLog {
+    pub(crate) fn new() -> Self {
+        ReloadLog { entries: Vec::new() }
+    }
+
+    /// Add an entry, dropping the oldest ones past `capacity`.
+    pub(crate) fn push(&mut self, capacity: usize, kind: ReloadLogKind, detail: String) {
+        if capacity == 0 {
+            return;
+        }
+        let excess = (self.entries.len() + 1).saturating_sub(capacity);
+        self.entries.drain(..excess);
+        self.entries.push(ReloadLogEntry { time: SystemTime::now(), kind, detail });
+    }
+
+    pub(crate) fn entries(&self) -> &[ReloadLogEntry] {
+        &self.entries
+    }
+}
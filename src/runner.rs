@@ -0,0 +1,178 @@
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use super::{Error, Reloadable, ShouldQuit};
+
+/// Set once an interrupt asks the running loop to stop.
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+/// Load the library at `path`, and run it until it asks to quit.
+///
+/// This is the usual host `main` loop, done once: every `tick`, it reloads
+/// the library if it has changed and then calls `update`. A reload that
+/// fails, say because a build was broken, is printed to stderr and retried on
+/// the next change, rather than ending the run. On Unix, the first Ctrl-C (or
+/// `SIGTERM`) stops the loop so that `deinit` still runs, and a second one
+/// kills the process as usual.
+///
+/// This returns once the library's `update` returns `ShouldQuit::Yes`, or the
+/// loop is interrupted, with the result of the final `deinit`. It returns an
+/// error straight away if the library can't be loaded at first, or if the
+/// library panics.
+///
+/// ```rust,no_run
+/// # extern crate live_reload;
+/// # struct Host;
+/// # fn main() {
+/// use std::time::Duration;
+///
+/// let tick = Duration::from_millis(16);
+/// live_reload::run("target/debug/libgame.so", Host, tick).expect("the game failed");
+/// # }
+/// ```
+pub fn run<Host, P: AsRef<Path>>(path: P, host: Host, tick: Duration) -> Result<(), Error> {
+    let mut app = Reloadable::new(path, host)?;
+    let _interrupt = Interrupt::install();
+    let mut next_tick = Instant::now();
+    while !take_interrupt() {
+        if let Err(err) = app.reload() {
+            eprintln!("live-reload: {}", err);
+        }
+        if app.try_update()? == ShouldQuit::Yes {
+            break;
+        }
+        wait_for_tick(&mut next_tick, tick);
+    }
+    app.shutdown()
+}
+
+/// Ask the loop in [`run`][] or [`Reloadable::update_until_quit`][] to stop,
+/// the way Ctrl-C does.
+///
+/// The loop checks for this before each frame, and stops without calling
+/// `update` again, leaving `deinit` to run as usual. This is safe to call
+/// from a signal handler, for a host that handles its signals itself, as it
+/// does unless it turns on the loop's own handling with
+/// [`Builder::handle_interrupts`][]. An interrupt is used up by the loop that
+/// sees it, and one asked for before a loop with its own handling starts is
+/// dropped.
+///
+/// ```rust
+/// #[macro_use] extern crate live_reload;
+///
+/// use live_reload::ShouldQuit;
+///
+/// pub struct Host;
+///
+/// struct State { frames: u64, deinit: bool }
+///
+/// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+///     state.frames += 1;
+///     if state.frames == 2 {
+///         // Stands in for the host's own SIGINT handler.
+///         live_reload::interrupt();
+///     }
+///     ShouldQuit::No
+/// }
+/// fn deinit(_: &mut Host, state: &mut State) {
+///     state.deinit = true;
+/// }
+/// fn nothing(_: &mut Host, _: &mut State) {}
+///
+/// live_reload! {
+///     host: Host;
+///     state: State;
+///     init: nothing;
+///     reload: nothing;
+///     update: update;
+///     unload: nothing;
+///     deinit: deinit;
+/// }
+///
+/// # fn main() {
+/// use std::time::Duration;
+/// use live_reload::Builder;
+///
+/// let mut app = Builder::new("")
+///     .build_in_process(RELOAD_API, Host)
+///     .unwrap();
+/// app.update_until_quit(Duration::from_millis(1)).unwrap();
+/// assert_eq!(app.state_bytes()[..8], 2u64.to_ne_bytes());
+/// app.shutdown().unwrap();
+/// # }
+/// ```
+///
+/// [`run`]: fn.run.html
+/// [`Reloadable::update_until_quit`]: struct.Reloadable.html#method.update_until_quit
+/// [`Builder::handle_interrupts`]: struct.Builder.html#method.handle_interrupts
+pub fn interrupt() {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Whether the running loop was interrupted, clearing the interrupt if so.
+pub(crate) fn take_interrupt() -> bool {
+    INTERRUPTED.swap(false, Ordering::SeqCst)
+}
+
+/// Sleep until `tick` after `next_tick`, and move `next_tick` along.
+///
+/// If that time has already passed, the missed ticks are dropped, rather
+/// than run back to back to catch up.
+pub(crate) fn wait_for_tick(next_tick: &mut Instant, tick: Duration) {
+    *next_tick += tick;
+    let now = Instant::now();
+    if *next_tick > now {
+        ::std::thread::sleep(*next_tick - now);
+    } else {
+        *next_tick = now;
+    }
+}
+
+/// Routes interrupts to `INTERRUPTED` while it's alive.
+pub(crate) struct Interrupt {
+    #[cfg(unix)]
+    previous: [::libc::sighandler_t; 2],
+}
+
+#[cfg(unix)]
+const SIGNALS: [::libc::c_int; 2] = [::libc::SIGINT, ::libc::SIGTERM];
+
+#[cfg(unix)]
+extern "C" fn interrupted(signal: ::libc::c_int) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+    // Let a second interrupt kill the process, in case `deinit` hangs.
+    unsafe {
+        ::libc::signal(signal, ::libc::SIG_DFL);
+    }
+}
+
+impl Interrupt {
+    #[cfg(unix)]
+    pub(crate) fn install() -> Self {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        let handler = interrupted as extern "C" fn(::libc::c_int) as ::libc::sighandler_t;
+        let mut previous = [::libc::SIG_DFL; 2];
+        for (signal, previous) in SIGNALS.iter().zip(&mut previous) {
+            *previous = unsafe { ::libc::signal(*signal, handler) };
+        }
+        Interrupt { previous }
+    }
+
+    #[cfg(not(unix))]
+    pub(crate) fn install() -> Self {
+        INTERRUPTED.store(false, Ordering::SeqCst);
+        Interrupt {}
+    }
+}
+
+#[cfg(unix)]
+impl Drop for Interrupt {
+    fn drop(&mut self) {
+        for (signal, previous) in SIGNALS.iter().zip(&self.previous) {
+            unsafe {
+                ::libc::signal(*signal, *previous);
+            }
+        }
+    }
+}
@@ -0,0 +1,137 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use diff::StateDiff;
+use store::units_as_bytes;
+
+/// A saved copy of the state
+///
+/// This only lives in memory, so it's always loaded back on the machine that
+/// saved it. Use [`Reloadable::snapshot_to_path`][] to keep the state in a
+/// file.
+///
+/// [`Reloadable::snapshot_to_path`]: struct.Reloadable.html#method.snapshot_to_path
+pub struct SaveState {
+    pub(crate) state: Vec<u64>,
+    /// The size in bytes of the `State` when it was saved.
+    pub(crate) size: usize,
+    pub(crate) generation: Generation,
+    /// The build id of the library that was loaded when it was saved.
+    pub(crate) build_id: Option<String>,
+    /// The layout hash of the `State` when it was saved, if it was known.
+    pub(crate) state_layout: Option<u64>,
+    /// The `State` as the library's `serialize` wrote it, if it has one.
+    pub(crate) serialized: Option<Vec<u8>>,
+}
+
+impl SaveState {
+    /// Make a save of a `State` whose bytes are `bytes`.
+    ///
+    /// This is for code that manages a `State` itself, without a
+    /// [`Reloadable`][], such as a loader for a platform without `std`. The
+    /// save has the first generation, and no build id or layout hash.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// use live_reload::SaveState;
+    ///
+    /// let base = SaveState::from_bytes(&[0; 12]);
+    /// let save = SaveState::from_bytes(&[0, 0, 0, 0, 7, 0, 0, 0, 0, 0, 0, 0]);
+    /// assert_eq!(save.state_size(), 12);
+    /// assert_eq!(save.bytes()[4], 7);
+    /// assert_eq!(save.diff(&base).changed_bytes(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`Reloadable`]: struct.Reloadable.html
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let state = bytes
+            .chunks(8)
+            .map(|chunk| {
+                let mut unit = [0; 8];
+                unit[..chunk.len()].copy_from_slice(chunk);
+                u64::from_ne_bytes(unit)
+            })
+            .collect();
+        SaveState {
+            state,
+            size: bytes.len(),
+            generation: Generation(0),
+            build_id: None,
+            state_layout: None,
+            serialized: None,
+        }
+    }
+
+    /// Make an empty save, with room for a `State` of `bytes` bytes, to save
+    /// into with [`Reloadable::save_state_into`][] without allocating.
+    ///
+    /// Until then, its `State` is empty, and it has the first generation.
+    ///
+    /// [`Reloadable::save_state_into`]: struct.Reloadable.html#method.save_state_into
+    pub fn with_capacity(bytes: usize) -> Self {
+        SaveState {
+            state: Vec::with_capacity(bytes.div_ceil(8)),
+            size: 0,
+            generation: Generation(0),
+            build_id: None,
+            state_layout: None,
+            serialized: None,
+        }
+    }
+
+    /// The bytes of the `State` when it was saved.
+    pub fn bytes(&self) -> &[u8] {
+        &units_as_bytes(&self.state)[..self.size]
+    }
+
+    /// The `State` as the library's own `serialize` wrote it, if the library
+    /// that was loaded when it was saved has one (see [`live_reload!`][]).
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    pub fn serialized(&self) -> Option<&[u8]> {
+        self.serialized.as_ref().map(|bytes| &bytes[..])
+    }
+
+    /// The generation of the save, which orders it among the other saves of
+    /// the same `Reloadable`.
+    pub fn generation(&self) -> Generation {
+        self.generation
+    }
+
+    /// The size in bytes of the `State` when it was saved.
+    pub fn state_size(&self) -> usize {
+        self.size
+    }
+
+    /// Work out how this save differs from `base`, an earlier save of the
+    /// same state.
+    ///
+    /// Keep one full save and a diff for each step after it, and any step can
+    /// be rebuilt with [`Reloadable::apply_diff`][], for a long undo history
+    /// or to send small updates of the state over a network. See
+    /// [`StateDiff`][] for what's kept.
+    ///
+    /// [`Reloadable::apply_diff`]: struct.Reloadable.html#method.apply_diff
+    /// [`StateDiff`]: struct.StateDiff.html
+    pub fn diff(&self, base: &SaveState) -> StateDiff {
+        StateDiff::between(units_as_bytes(&base.state), base.size, units_as_bytes(&self.state), self.size)
+    }
+}
+
+/// Numbers the saves of a `Reloadable` in the order they were taken.
+///
+/// Each [`Reloadable::save_state`][] gets a later generation than the one
+/// before it, so generations can key and order a collection of saves.
+///
+/// [`Reloadable::save_state`]: struct.Reloadable.html#method.save_state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Generation(pub(crate) u64);
+
+impl Generation {
+    /// The generation as a number, counting up from zero.
+    pub fn get(self) -> u64 {
+        self.0
+    }
+}
@@ -0,0 +1,169 @@
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use super::{Error, Reloadable, SaveState, ShouldQuit};
+
+/// Something that can save its state from any thread, without knowing its
+/// `Host`.
+trait SaveSource: Send + Sync {
+    fn save_state_into(&self, out: &mut SaveState);
+}
+
+impl<Host: Send> SaveSource for Mutex<Reloadable<Host>> {
+    fn save_state_into(&self, out: &mut SaveState) {
+        lock(self).save_state_into(out);
+    }
+}
+
+fn lock<Host>(app: &Mutex<Reloadable<Host>>) -> MutexGuard<'_, Reloadable<Host>> {
+    // A panic that poisoned the lock has been caught and reported already,
+    // and the `Reloadable` doesn't hold the lock across anything it could
+    // leave half done.
+    app.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<Host: Send + 'static> Reloadable<Host> {
+    /// Split the `Reloadable` into a handle that drives it and a handle that
+    /// saves its state, for saving from another thread.
+    ///
+    /// The `Reloadable` moves behind a mutex that both handles share. The
+    /// [`UpdateHandle`][] holds the lock for the whole of each call it makes,
+    /// like an [`update`][] or a [`reload`][], and the [`SaveHandle`][]
+    /// holds it for as long as it takes to copy the `State` out. So a save
+    /// always sees the `State` between two calls into the library, never
+    /// halfway through one, and the two threads only ever wait on each other
+    /// for the length of a copy or a call. The `SaveHandle` can be cloned
+    /// and sent to as many threads as needed, and it doesn't know about the
+    /// `Host`, so the `Host` only has to be `Send`.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { frames: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    /// #     state.frames += 1;
+    /// #     ShouldQuit::No
+    /// # }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let (app, saver) = app.shared();
+    ///
+    /// let autosave = std::thread::spawn(move || {
+    ///     (0..10).map(|_| saver.save_state()).collect::<Vec<_>>()
+    /// });
+    /// for _ in 0..100 {
+    ///     app.update();
+    /// }
+    /// let frames = |save: &live_reload::SaveState| {
+    ///     let mut bytes = [0; 8];
+    ///     bytes.copy_from_slice(&save.bytes()[..8]);
+    ///     u64::from_ne_bytes(bytes)
+    /// };
+    /// let saves = autosave.join().unwrap();
+    /// // Each save caught the state between two updates.
+    /// assert!(saves.windows(2).all(|pair| frames(&pair[0]) <= frames(&pair[1])));
+    /// assert!(frames(&saves[9]) <= 100);
+    ///
+    /// // With every `SaveHandle` gone, the `Reloadable` can be taken back.
+    /// let app = app.into_inner().ok().unwrap();
+    /// assert_eq!(frames(&app.save_state()), 100);
+    /// # }
+    /// ```
+    ///
+    /// [`UpdateHandle`]: struct.UpdateHandle.html
+    /// [`SaveHandle`]: struct.SaveHandle.html
+    /// [`update`]: struct.Reloadable.html#method.update
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    pub fn shared(self) -> (UpdateHandle<Host>, SaveHandle) {
+        let app = Arc::new(Mutex::new(self));
+        let saver = SaveHandle { app: app.clone() };
+        (UpdateHandle { app }, saver)
+    }
+}
+
+/// The half of a [`Reloadable::shared`][] split that drives the library.
+///
+/// Each method locks the `Reloadable` for the length of the call, which is
+/// what keeps a [`SaveHandle`][] from seeing it in the middle of one. For
+/// anything else, [`lock`](#method.lock) it, and keep the guard for as long
+/// as the saves should wait.
+///
+/// [`Reloadable::shared`]: struct.Reloadable.html#method.shared
+/// [`SaveHandle`]: struct.SaveHandle.html
+pub struct UpdateHandle<Host> {
+    app: Arc<Mutex<Reloadable<Host>>>,
+}
+
+impl<Host> UpdateHandle<Host> {
+    /// Lock the `Reloadable`, holding off saves until the guard is dropped.
+    pub fn lock(&self) -> MutexGuard<'_, Reloadable<Host>> {
+        lock(&self.app)
+    }
+
+    /// Call [`Reloadable::update`](struct.Reloadable.html#method.update)
+    /// under the lock.
+    pub fn update(&self) -> ShouldQuit {
+        self.lock().update()
+    }
+
+    /// Call [`Reloadable::try_update`](struct.Reloadable.html#method.try_update)
+    /// under the lock.
+    pub fn try_update(&self) -> Result<ShouldQuit, Error> {
+        self.lock().try_update()
+    }
+
+    /// Call [`Reloadable::reload`](struct.Reloadable.html#method.reload)
+    /// under the lock.
+    pub fn reload(&self) -> Result<(), Error> {
+        self.lock().reload()
+    }
+
+    /// Take the `Reloadable` back, once every [`SaveHandle`][] for it has
+    /// been dropped, or get the handle back if one is still around.
+    ///
+    /// [`SaveHandle`]: struct.SaveHandle.html
+    pub fn into_inner(self) -> Result<Reloadable<Host>, Self> {
+        match Arc::try_unwrap(self.app) {
+            Ok(app) => Ok(app.into_inner().unwrap_or_else(PoisonError::into_inner)),
+            Err(app) => Err(UpdateHandle { app }),
+        }
+    }
+}
+
+/// The half of a [`Reloadable::shared`][] split that saves the state, from
+/// any thread.
+///
+/// [`Reloadable::shared`]: struct.Reloadable.html#method.shared
+#[derive(Clone)]
+pub struct SaveHandle {
+    app: Arc<dyn SaveSource>,
+}
+
+impl SaveHandle {
+    /// Save a copy of the state, like
+    /// [`Reloadable::save_state`](struct.Reloadable.html#method.save_state),
+    /// waiting for any call into the library to finish first.
+    pub fn save_state(&self) -> SaveState {
+        let mut save = SaveState::with_capacity(0);
+        self.save_state_into(&mut save);
+        save
+    }
+
+    /// Save a copy of the state into `out`, reusing its allocations, like
+    /// [`Reloadable::save_state_into`](struct.Reloadable.html#method.save_state_into).
+    pub fn save_state_into(&self, out: &mut SaveState) {
+        self.app.save_state_into(out);
+    }
+}
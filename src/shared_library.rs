@@ -0,0 +1,159 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+
+use ::notify::RecommendedWatcher;
+
+use super::{Error, Reloadable};
+use builder::Config;
+use events::{self, Events};
+use with_std::AppSym;
+
+/// The version of the library that `Reloadable`s should be running.
+struct Current<Host> {
+    sym: Arc<AppSym<Host>>,
+    /// Bumped every time a new version is loaded.
+    generation: u64,
+}
+
+/// A library that is loaded once, and run by several `Reloadable`s.
+///
+/// Each `Reloadable` made with [`Reloadable::from_shared`][] has its own
+/// `Host` and its own `State`, but they all run the same copy of the library,
+/// so its code and its statics are only loaded once. The `SharedLibrary`
+/// watches the library, and [`reload`](#method.reload) loads a new version
+/// when it changes. Each `Reloadable` then swaps to the new version on its
+/// own next [`Reloadable::reload`][], calling `unload` and `reload` on its
+/// own state, and the old version stays loaded until the last of them has
+/// swapped away from it.
+///
+/// ```rust,no_run
+/// # extern crate live_reload;
+/// # fn main() {
+/// # struct Host { id: u32 }
+/// use live_reload::{Reloadable, SharedLibrary};
+///
+/// let mut library = SharedLibrary::new("target/debug/libplugin.so")
+///     .expect("Should successfully load");
+/// let mut instances: Vec<_> = (0..4)
+///     .map(|id| Reloadable::from_shared(&library, Host { id }).unwrap())
+///     .collect();
+/// loop {
+///     library.reload().expect("Should successfully reload");
+///     for instance in &mut instances {
+///         instance.reload().expect("Should successfully reload");
+///         instance.update();
+///     }
+/// }
+/// # }
+/// ```
+///
+/// [`Reloadable::from_shared`]: struct.Reloadable.html#method.from_shared
+/// [`Reloadable::reload`]: struct.Reloadable.html#method.reload
+pub struct SharedLibrary<Host> {
+    /// The canonical path of the library, as of the last successful load.
+    path: PathBuf,
+    /// The absolute path the library was asked for, which might be a symlink.
+    request_path: PathBuf,
+    current: Arc<Mutex<Current<Host>>>,
+    config: Config<Host>,
+    /// Whether a change has been seen that hasn't been reloaded yet.
+    pending_reload: bool,
+    _watcher: RecommendedWatcher,
+    events: Events,
+}
+
+impl<Host> SharedLibrary<Host> {
+    /// Load the library, and start watching it for changes.
+    ///
+    /// Nothing is called in the library until a `Reloadable` is made from it.
+    pub fn new<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
+        let config = Config::default();
+        let request_path = config.resolve(path.as_ref())?;
+        let sym = AppSym::new(&request_path, &config)?;
+        let (watcher, events) = Reloadable::watch(&request_path, &config)?;
+        Ok(SharedLibrary {
+            path: request_path.canonicalize()?,
+            request_path,
+            current: Arc::new(Mutex::new(Current {
+                sym: Arc::new(sym),
+                generation: 0,
+            })),
+            config,
+            pending_reload: false,
+            _watcher: watcher,
+            events,
+        })
+    }
+
+    /// Load a new version of the library if it has changed.
+    ///
+    /// The `Reloadable`s made from this library keep running the old version
+    /// until their own next `reload`.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        while let Some(evt) = self.events.try_recv() {
+            let ignored = &self.config.ignored_extensions;
+            if events::is_change(&evt, &self.path, &self.request_path, ignored) {
+                self.pending_reload = true;
+            }
+        }
+        if self.pending_reload {
+            self.reload_now()?;
+        }
+        Ok(())
+    }
+
+    /// Load a new version of the library without checking whether it has
+    /// changed.
+    ///
+    /// If the library fails to load, the `Reloadable`s keep running the
+    /// version they have.
+    pub fn reload_now(&mut self) -> Result<(), Error> {
+        self.pending_reload = false;
+        let sym = AppSym::new(&self.request_path, &self.config)?;
+        if let Ok(path) = self.request_path.canonicalize() {
+            self.path = path;
+        }
+        let mut current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        current.sym = Arc::new(sym);
+        current.generation += 1;
+        Ok(())
+    }
+
+    /// The canonical path of the library.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn request_path(&self) -> &Path {
+        &self.request_path
+    }
+
+    pub(crate) fn handle(&self) -> SharedHandle<Host> {
+        SharedHandle {
+            current: self.current.clone(),
+            generation: 0,
+        }
+    }
+}
+
+/// A `Reloadable`'s link to the `SharedLibrary` it was made from.
+pub(crate) struct SharedHandle<Host> {
+    current: Arc<Mutex<Current<Host>>>,
+    /// The generation of the version the `Reloadable` last loaded.
+    generation: u64,
+}
+
+impl<Host> SharedHandle<Host> {
+    /// Get the latest version of the library, and remember having loaded it.
+    pub(crate) fn latest(&mut self) -> Arc<AppSym<Host>> {
+        let current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        self.generation = current.generation;
+        current.sym.clone()
+    }
+
+    /// Has a newer version been loaded since the last call to `latest`?
+    pub(crate) fn is_stale(&self) -> bool {
+        let current = self.current.lock().unwrap_or_else(PoisonError::into_inner);
+        current.generation != self.generation
+    }
+}
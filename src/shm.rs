@@ -0,0 +1,207 @@
+use std::ffi::CString;
+use std::fs::File;
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use ::libc;
+
+use mmap::MappedState;
+
+/// The number of `u64`s at the start of the segment that hold its header,
+/// rather than the `State`. This keeps the `State` 64-byte aligned.
+const HEADER_LEN: usize = 8;
+
+/// Written to the first word of the header once the creator has initialized
+/// the `State`.
+const READY: u64 = 0x6c69_7665_7265_6479;
+
+/// How long to wait for the creator of a segment to initialize it.
+const READY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A `State` buffer in a named shared-memory segment, which several host
+/// processes can map at once.
+///
+/// The first process to open a name creates the segment, and is the one that
+/// calls `init` on the `State`. Any process that opens the name after that
+/// waits until the creator has finished, and then treats the `State` as
+/// recovered, calling `reload` on it instead. The segment only ever grows, so
+/// when a reload changes the size of the `State`, each process grows its
+/// mapping as it reloads, and nobody's mapping is cut short.
+///
+/// Every process writes to the same memory, with no locking, so the library
+/// has to be written with that in mind. The segment outlives the processes
+/// that use it, until it's removed with [`unlink`](#method.unlink).
+///
+/// This is only available on Unix, with the `shm` feature enabled.
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate live_reload;
+/// # fn main() {
+/// # if cfg!(miri) { return; } // Miri can't open shared memory.
+/// use live_reload::SharedState;
+///
+/// let name = format!("live-reload-doctest-{}", std::process::id());
+/// let mut first = SharedState::open(&name).unwrap();
+/// assert!(first.is_creator());
+/// first.resize(1).unwrap();
+/// first.as_mut_slice()[0] = 42;
+/// first.mark_ready();
+///
+/// let second = SharedState::open(&name).unwrap();
+/// assert!(!second.is_creator());
+/// assert_eq!(second.as_slice(), &[42]);
+/// # drop((first, second));
+/// SharedState::unlink(&name).unwrap();
+/// # }
+/// ```
+pub struct SharedState {
+    map: MappedState,
+    creator: bool,
+}
+
+impl SharedState {
+    /// Open the segment called `name`, creating it if it doesn't exist yet.
+    ///
+    /// If another process created the segment, this waits for it to call
+    /// [`mark_ready`](#method.mark_ready), and fails with `TimedOut` if that
+    /// doesn't happen within ten seconds (say, because the creator crashed).
+    pub fn open(name: &str) -> io::Result<Self> {
+        let name = segment_name(name)?;
+        let mode = 0o600 as libc::c_uint;
+        let fd = unsafe {
+            libc::shm_open(name.as_ptr(), libc::O_RDWR | libc::O_CREAT | libc::O_EXCL, mode)
+        };
+        let (fd, creator) = if fd >= 0 {
+            (fd, true)
+        } else {
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::AlreadyExists {
+                return Err(err);
+            }
+            let fd = unsafe { libc::shm_open(name.as_ptr(), libc::O_RDWR, mode) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            (fd, false)
+        };
+        let file = unsafe { File::from_raw_fd(fd) };
+        let mut shared = SharedState {
+            map: MappedState::from_file(file)?,
+            creator,
+        };
+        if creator {
+            shared.map.resize(HEADER_LEN)?;
+        } else {
+            shared.wait_until_ready()?;
+        }
+        Ok(shared)
+    }
+
+    /// Remove the segment called `name`.
+    ///
+    /// Processes that have it open keep using it, but the next process to
+    /// open the name creates a fresh one.
+    pub fn unlink(name: &str) -> io::Result<()> {
+        let name = segment_name(name)?;
+        if unsafe { libc::shm_unlink(name.as_ptr()) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    /// Whether this process created the segment, and so should initialize it.
+    pub fn is_creator(&self) -> bool {
+        self.creator
+    }
+
+    /// Tell the processes waiting in [`open`](#method.open) that the `State`
+    /// has been initialized.
+    pub fn mark_ready(&self) {
+        self.header().store(READY, Ordering::Release);
+    }
+
+    /// The number of `u64`s of `State` currently mapped.
+    pub fn len(&self) -> usize {
+        self.map.len() - HEADER_LEN
+    }
+
+    /// Whether no `State` is mapped yet, which is the case for a new segment.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Grow the segment and the mapping to hold at least `len` `u64`s of
+    /// `State`.
+    ///
+    /// Like [`MappedState::resize`][], this never shrinks anything, and moves
+    /// the mapping when it grows it.
+    ///
+    /// [`MappedState::resize`]: struct.MappedState.html#method.resize
+    pub fn resize(&mut self, len: usize) -> io::Result<()> {
+        let fd = self.map.file().as_raw_fd();
+        // Serialize growing the segment with the other processes, so that one
+        // can't shrink it back to a smaller size it saw before another grew it.
+        if unsafe { libc::flock(fd, libc::LOCK_EX) } != 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let resized = self.map.resize(HEADER_LEN + len);
+        unsafe {
+            libc::flock(fd, libc::LOCK_UN);
+        }
+        resized
+    }
+
+    /// Get a pointer to the start of the `State`.
+    pub fn as_mut_ptr(&mut self) -> *mut u64 {
+        unsafe { self.map.as_mut_ptr().add(HEADER_LEN) }
+    }
+
+    /// View the `State` as a slice of `u64`s.
+    pub fn as_slice(&self) -> &[u64] {
+        &self.map.as_slice()[HEADER_LEN..]
+    }
+
+    /// View the `State` as a mutable slice of `u64`s.
+    pub fn as_mut_slice(&mut self) -> &mut [u64] {
+        &mut self.map.as_mut_slice()[HEADER_LEN..]
+    }
+
+    /// The word of the header that says whether the segment is ready.
+    fn header(&self) -> &AtomicU64 {
+        unsafe { &*(self.map.as_slice().as_ptr() as *const AtomicU64) }
+    }
+
+    fn wait_until_ready(&mut self) -> io::Result<()> {
+        let start = Instant::now();
+        loop {
+            let bytes = self.map.file().metadata()?.len() as usize;
+            self.map.resize(bytes / 8)?;
+            if self.map.len() >= HEADER_LEN && self.header().load(Ordering::Acquire) == READY {
+                return Ok(());
+            }
+            if start.elapsed() > READY_TIMEOUT {
+                return Err(io::Error::new(
+                    io::ErrorKind::TimedOut,
+                    "the shared state was never initialized by the process that created it",
+                ));
+            }
+            thread::sleep(Duration::from_millis(10));
+        }
+    }
+}
+
+/// Turn a segment name into the form `shm_open` expects, with a leading slash.
+fn segment_name(name: &str) -> io::Result<CString> {
+    let name = if name.starts_with('/') {
+        name.to_owned()
+    } else {
+        format!("/{}", name)
+    };
+    CString::new(name).map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))
+}
@@ -0,0 +1,128 @@
+use std::io::{self, Read, Write};
+
+use super::Error;
+
+/// The first bytes of every snapshot file.
+const MAGIC: &[u8; 8] = b"LRSNAP\0\0";
+
+/// The version of the snapshot format, bumped whenever it changes.
+///
+/// Version 2 added the layout hash. Version 1 snapshots are still read, as
+/// having none.
+const FORMAT_VERSION: u32 = 2;
+
+/// Written in native byte order, so that it reads back as itself only on a
+/// machine with the same byte order.
+const BYTE_ORDER_MARK: u32 = 1;
+
+/// A snapshot read back by `read`.
+pub(crate) struct Snapshot {
+    /// The size of the `State`.
+    pub(crate) size: usize,
+    /// The layout hash of the `State`, if its library reported one.
+    pub(crate) layout: Option<u64>,
+    /// The `State` itself.
+    pub(crate) units: Vec<u64>,
+}
+
+/// Write a snapshot of a `State` of `size` bytes and layout hash `layout`,
+/// held in `units`.
+///
+/// The header is the magic bytes, the format version, a byte-order marker,
+/// the size of the `State`, its layout hash (0 for none), and the number of
+/// units that follow, all little-endian. The units themselves are written
+/// as they are in memory.
+pub(crate) fn write<W: Write>(
+    mut out: W,
+    size: usize,
+    layout: Option<u64>,
+    units: &[u64],
+) -> io::Result<()> {
+    out.write_all(MAGIC)?;
+    out.write_all(&FORMAT_VERSION.to_le_bytes())?;
+    out.write_all(&BYTE_ORDER_MARK.to_ne_bytes())?;
+    out.write_all(&(size as u64).to_le_bytes())?;
+    out.write_all(&layout.unwrap_or(0).to_le_bytes())?;
+    out.write_all(&(units.len() as u64).to_le_bytes())?;
+    for unit in units {
+        out.write_all(&unit.to_ne_bytes())?;
+    }
+    out.flush()
+}
+
+/// Write `bytes` as a hexdump, in the layout of `hexdump -C`: each line has
+/// the offset, up to 16 bytes in hex, split into two groups of 8, and the
+/// same bytes as ASCII, with a `.` for anything that isn't printable. The
+/// last line is the total length.
+pub(crate) fn write_hex<W: Write>(mut out: W, bytes: &[u8]) -> io::Result<()> {
+    for (line, chunk) in bytes.chunks(16).enumerate() {
+        write!(out, "{:08x} ", line * 16)?;
+        for column in 0..16 {
+            if column % 8 == 0 {
+                out.write_all(b" ")?;
+            }
+            match chunk.get(column) {
+                Some(byte) => write!(out, "{:02x} ", byte)?,
+                None => out.write_all(b"   ")?,
+            }
+        }
+        out.write_all(b" |")?;
+        for &byte in chunk {
+            let shown = if byte.is_ascii_graphic() || byte == b' ' { byte } else { b'.' };
+            out.write_all(&[shown])?;
+        }
+        out.write_all(b"|\n")?;
+    }
+    writeln!(out, "{:08x}", bytes.len())?;
+    out.flush()
+}
+
+/// Read a snapshot back.
+///
+/// A snapshot taken on a machine with the other byte order is refused with
+/// `Error::EndianMismatch`, since its units can't be swapped back without
+/// knowing the types in the `State`.
+pub(crate) fn read<R: Read>(mut input: R) -> Result<Snapshot, Error> {
+    let mut magic = [0; 8];
+    input.read_exact(&mut magic)?;
+    if &magic != MAGIC {
+        return Err(invalid("not a live-reload snapshot").into());
+    }
+    let version = u32::from_le_bytes(read_array(&mut input)?);
+    if version != 1 && version != FORMAT_VERSION {
+        return Err(invalid("unsupported snapshot format version").into());
+    }
+    match u32::from_ne_bytes(read_array(&mut input)?) {
+        BYTE_ORDER_MARK => {}
+        mark if mark == BYTE_ORDER_MARK.swap_bytes() => return Err(Error::EndianMismatch),
+        _ => return Err(invalid("snapshot is corrupt").into()),
+    }
+    let size = u64::from_le_bytes(read_array(&mut input)?) as usize;
+    let layout = match version {
+        1 => 0,
+        _ => u64::from_le_bytes(read_array(&mut input)?),
+    };
+    let len = u64::from_le_bytes(read_array(&mut input)?) as usize;
+    if len != size.div_ceil(8) {
+        return Err(invalid("snapshot is corrupt").into());
+    }
+    let mut units = Vec::with_capacity(len);
+    for _ in 0..len {
+        units.push(u64::from_ne_bytes(read_array(&mut input)?));
+    }
+    Ok(Snapshot {
+        size,
+        layout: if layout == 0 { None } else { Some(layout) },
+        units,
+    })
+}
+
+fn read_array<R: Read, T: Default + AsMut<[u8]>>(input: &mut R) -> io::Result<T> {
+    let mut bytes = T::default();
+    input.read_exact(bytes.as_mut())?;
+    Ok(bytes)
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
@@ -0,0 +1,294 @@
+//! The `static` backend, for shipping the library linked into the host.
+
+use std::fmt;
+use std::path::Path;
+
+use super::ShouldQuit;
+use guard::{self, LifecyclePhase, PanicLocation};
+use internals::{self, ReloadApi};
+
+extern "Rust" {
+    /// The `RELOAD_API` exported by `live_reload!` in the linked library.
+    ///
+    /// The layout of a `ReloadApi` doesn't depend on its `Host`, so it can be
+    /// declared with any `Host` here, and cast to the right one.
+    #[link_name = "RELOAD_API"]
+    static LINKED_API: ReloadApi<()>;
+}
+
+/// The errors that can occur while working with a `Reloadable` object.
+///
+/// With the `static` feature, nothing is loaded at runtime, so only the
+/// errors that the linked library itself can cause are left.
+#[derive(Debug)]
+pub enum Error {
+    /// The library was built against a version of `live-reload` with a
+    /// different `ReloadApi` layout than the host.
+    AbiMismatch {
+        /// The ABI version the host understands.
+        expected: u32,
+        /// The ABI version the library reported.
+        found: u32,
+    },
+    /// The library's `ReloadApi` has a null function pointer.
+    InvalidApi {
+        /// The name of the first `ReloadApi` function found to be null.
+        function: &'static str,
+    },
+    /// The library panicked during a call made by the `Reloadable`.
+    LibraryPanicked {
+        /// The lifecycle function that panicked.
+        phase: LifecyclePhase,
+        /// The panic message, if the payload was a string.
+        message: String,
+        /// Where the library panicked, if it could be found out.
+        location: Option<PanicLocation>,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::AbiMismatch { expected, found } => write!(
+                fmt,
+                "the library was built with live-reload ABI version {}, but the host expects {}",
+                found, expected,
+            ),
+            Error::InvalidApi { function } => write!(fmt, "the library's ReloadApi has a null {} function", function),
+            Error::LibraryPanicked { phase, ref message, location: Some(ref location) } => {
+                write!(fmt, "the library's {} panicked at {}: {}", phase, location, message)
+            }
+            Error::LibraryPanicked { phase, ref message, location: None } => {
+                write!(fmt, "the library's {} panicked: {}", phase, message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The library, linked into the host, driven the same way as a reloadable
+/// one.
+///
+/// With the `std` feature off and the `static` feature on, this stands in for
+/// the usual `Reloadable`, with the same methods for driving the library, but
+/// no dynamic loading, no filesystem watcher, and no dependency on
+/// `libloading` or `notify`. [`new`](#method.new) binds to the `RELOAD_API`
+/// that [`live_reload!`][] exported from the library crate linked into the
+/// host, and [`reload`](#method.reload) never has anything to do. So the
+/// same host code can hot-reload in development and ship a single binary,
+/// with only a difference in features:
+///
+/// ```toml
+/// [dependencies]
+/// live-reload = { version = "0.2", default-features = false }
+/// # Built as a `cdylib` for development, and an `rlib` for release.
+/// game = { path = "game", optional = true }
+///
+/// [features]
+/// default = ["live-reload/std"]
+/// release = ["live-reload/static", "game"]
+/// ```
+///
+/// The library crate has to be linked for its `RELOAD_API` to be found, so
+/// the host needs an `extern crate game;` behind the same feature. Without
+/// it, linking fails with an undefined `RELOAD_API`.
+///
+/// Calls into the library go through the same panic guards as with a dynamic
+/// library, so panics are reported the same way, and `init` and `deinit`
+/// still bracket the run.
+///
+/// [`live_reload!`]: macro.live_reload.html
+pub struct Reloadable<Host: 'static> {
+    api: &'static ReloadApi<Host>,
+    state: Vec<u64>,
+    /// Whether `deinit` has run, or shouldn't, because `init` failed.
+    finished: bool,
+    host: Host,
+}
+
+impl<Host> Reloadable<Host> {
+    /// Bind to the linked library's `RELOAD_API`, and call its `init`.
+    ///
+    /// `path` is ignored, since the library is already linked in. It's only
+    /// taken so that the same call works with and without the `static`
+    /// feature.
+    pub fn new<P: AsRef<Path>>(path: P, host: Host) -> Result<Self, Error> {
+        let _ = path;
+        // The linked `RELOAD_API` was declared by `live_reload!` for the same
+        // `Host` the library was built with, which has to be the host's.
+        let api = unsafe { &*(&LINKED_API as *const ReloadApi<()> as *const ReloadApi<Host>) };
+        if api.abi_version != internals::ABI_VERSION {
+            return Err(Error::AbiMismatch {
+                expected: internals::ABI_VERSION,
+                found: api.abi_version,
+            });
+        }
+        unsafe { ReloadApi::validate(api)? };
+        let mut app = Reloadable {
+            api,
+            state: vec![0; (api.size)().div_ceil(8)],
+            finished: false,
+            host,
+        };
+        let init = app.api.init;
+        let state = app.state_ptr();
+        if let Err(err) = guard::call(LifecyclePhase::Init, |sink| init(&mut app.host, state, sink)) {
+            // Don't let `drop` call `deinit` on a state that never started.
+            app.finished = true;
+            return Err(err);
+        }
+        Ok(app)
+    }
+
+    /// Does nothing, since a linked library never changes.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Does nothing, since a linked library never changes.
+    pub fn reload_now(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Call the update method on the library.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the library panics, with the library's message and location.
+    /// Use [`try_update`](#method.try_update) to get an error instead.
+    pub fn update(&mut self) -> ShouldQuit {
+        let update = self.api.update;
+        let state = self.state_ptr();
+        let host = &mut self.host;
+        guard::call_or_panic(LifecyclePhase::Update, |sink| update(host, state, sink))
+    }
+
+    /// Call the update method on the library, returning
+    /// `Error::LibraryPanicked` if it panics.
+    pub fn try_update(&mut self) -> Result<ShouldQuit, Error> {
+        let update = self.api.update;
+        let state = self.state_ptr();
+        let host = &mut self.host;
+        guard::call(LifecyclePhase::Update, |sink| update(host, state, sink))
+    }
+
+    /// Call the update method on the library, passing it `events`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the library panics, with the library's message and location.
+    /// Use [`try_update_with_events`](#method.try_update_with_events) to get
+    /// an error instead.
+    ///
+    /// # Safety
+    ///
+    /// `E` must have the same layout as the library's event type, as for
+    /// [`Reloadable::update_with_events`](struct.Reloadable.html#method.update_with_events).
+    pub unsafe fn update_with_events<E>(&mut self, events: &[E]) -> ShouldQuit {
+        let api = self.api;
+        let state = self.state_ptr();
+        let host = &mut self.host;
+        guard::call_or_panic(LifecyclePhase::Update, |sink| api.call_update(host, state, events, sink))
+    }
+
+    /// Call the update method on the library, passing it `events`, and
+    /// returning `Error::LibraryPanicked` if it panics.
+    ///
+    /// # Safety
+    ///
+    /// `E` must have the same layout as the library's event type.
+    pub unsafe fn try_update_with_events<E>(&mut self, events: &[E]) -> Result<ShouldQuit, Error> {
+        let api = self.api;
+        let state = self.state_ptr();
+        let host = &mut self.host;
+        guard::call(LifecyclePhase::Update, |sink| api.call_update(host, state, events, sink))
+    }
+
+    /// Call the update method on the library, lending it `extra` for the
+    /// call.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the library panics, with the library's message and location.
+    /// Use [`try_update_with`](#method.try_update_with) to get an error
+    /// instead.
+    ///
+    /// # Safety
+    ///
+    /// `T` must have the same layout as the library's extra type, as for
+    /// [`Reloadable::update_with`](struct.Reloadable.html#method.update_with).
+    pub unsafe fn update_with<T>(&mut self, extra: &mut T) -> ShouldQuit {
+        let api = self.api;
+        let state = self.state_ptr();
+        let host = &mut self.host;
+        guard::call_or_panic(LifecyclePhase::Update, |sink| api.call_update_with(host, state, extra, sink))
+    }
+
+    /// Call the update method on the library, lending it `extra` for the
+    /// call, and returning `Error::LibraryPanicked` if it panics.
+    ///
+    /// # Safety
+    ///
+    /// `T` must have the same layout as the library's extra type.
+    pub unsafe fn try_update_with<T>(&mut self, extra: &mut T) -> Result<ShouldQuit, Error> {
+        let api = self.api;
+        let state = self.state_ptr();
+        let host = &mut self.host;
+        guard::call(LifecyclePhase::Update, |sink| api.call_update_with(host, state, extra, sink))
+    }
+
+    /// Call `deinit`, and report whether it panicked.
+    pub fn shutdown(mut self) -> Result<(), Error> {
+        self.finalize()
+    }
+
+    /// Run `deinit`, so that it happens only once.
+    fn finalize(&mut self) -> Result<(), Error> {
+        if self.finished {
+            return Ok(());
+        }
+        self.finished = true;
+        let deinit = self.api.deinit;
+        let state = self.state_ptr();
+        let host = &mut self.host;
+        guard::call(LifecyclePhase::Deinit, |sink| deinit(host, state, sink))
+    }
+
+    /// The size in bytes of the library's `State`.
+    pub fn state_size(&self) -> usize {
+        (self.api.size)()
+    }
+
+    /// View the live bytes of the `State`, without copying them.
+    pub fn state_bytes(&self) -> &[u8] {
+        let bytes = unsafe { std::slice::from_raw_parts(self.state.as_ptr() as *const u8, self.state.len() * 8) };
+        &bytes[..self.state_size().min(bytes.len())]
+    }
+
+    /// Hash the live bytes of the `State`, the same way as with the `std`
+    /// feature.
+    pub fn state_checksum(&self) -> u64 {
+        internals::fnv1a(self.state_bytes())
+    }
+
+    /// Get a reference to the `Host` struct.
+    pub fn host(&self) -> &Host { &self.host }
+
+    /// Get a mutable reference to the `Host` struct.
+    pub fn host_mut(&mut self) -> &mut Host { &mut self.host }
+
+    fn state_ptr(&mut self) -> *mut () {
+        self.state.as_mut_ptr() as *mut ()
+    }
+}
+
+impl<Host> Drop for Reloadable<Host> {
+    fn drop(&mut self) {
+        if let Err(err) = self.finalize() {
+            if !std::thread::panicking() {
+                panic!("{}", err);
+            }
+        }
+    }
+}
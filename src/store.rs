@@ -0,0 +1,194 @@
+use alloc::slice;
+use alloc::vec::Vec;
+
+/// Storage for the `State` of a `Reloadable`.
+///
+/// By default the `State` lives in a `Vec<u64>`, which is reallocated on the
+/// global heap whenever a reload changes its size. Implement this trait to
+/// keep it somewhere else instead, such as an arena shared with the rest of
+/// your engine, and hand it to [`Builder::state_store`][].
+///
+/// # Example
+///
+/// ```rust
+/// # extern crate live_reload;
+/// # fn main() {
+/// use live_reload::StateStore;
+///
+/// let mut store: Vec<u64> = Vec::new();
+/// store.ensure(12, 8);
+/// assert_eq!(store.bytes(), &[0; 16][..]);
+/// assert_eq!(store.ptr() as *const u8, store.bytes().as_ptr());
+/// # }
+/// ```
+///
+/// [`Builder::state_store`]: struct.Builder.html#method.state_store
+///
+/// # Safety
+///
+/// The library reads and writes its `State` through [`ptr`](#tymethod.ptr)
+/// without any checks, so an implementation must keep the promises made on
+/// each method: after [`ensure`](#tymethod.ensure), `ptr` must point to at
+/// least `size` bytes that are aligned to `align`, that stay valid until the
+/// next call to `ensure`, and that are the same bytes [`bytes`](#tymethod.bytes)
+/// returns.
+pub unsafe trait StateStore {
+    /// Make room for at least `size` bytes, aligned to at least `align`.
+    ///
+    /// The existing contents must be kept (up to `size`), and any bytes that
+    /// are added must be zeroed. This is called whenever a library is loaded,
+    /// with the size of its `State`. The store may move, so the library is
+    /// handed [`ptr`](#tymethod.ptr) afresh after every call. `align` is
+    /// currently always 8.
+    fn ensure(&mut self, size: usize, align: usize);
+
+    /// The most bytes the store can hold, if it can't grow past a limit.
+    ///
+    /// A `State` that's larger is refused with an [`Error::StateTooLarge`][]
+    /// before [`ensure`](#tymethod.ensure) is called. By default there's no
+    /// limit.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { counters: [u64; 4] }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::{Builder, Error, StateStore};
+    ///
+    /// /// A store that can never hold more than 16 bytes.
+    /// struct Fixed(Vec<u64>);
+    ///
+    /// unsafe impl StateStore for Fixed {
+    ///     fn ensure(&mut self, size: usize, align: usize) {
+    ///         assert!(size <= 16);
+    ///         self.0.ensure(size, align);
+    ///     }
+    ///     fn capacity(&self) -> Option<usize> {
+    ///         Some(16)
+    ///     }
+    ///     fn ptr(&mut self) -> *mut () {
+    ///         self.0.ptr()
+    ///     }
+    ///     fn bytes(&self) -> &[u8] {
+    ///         self.0.bytes()
+    ///     }
+    /// }
+    ///
+    /// match Builder::new("unused").state_store(Fixed(Vec::new())).build_in_process(RELOAD_API, Host) {
+    ///     Err(Error::StateTooLarge { reported: 32, max: 16 }) => {}
+    ///     _ => panic!("a 32-byte State shouldn't fit in 16 bytes"),
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`Error::StateTooLarge`]: enum.Error.html#variant.StateTooLarge
+    fn capacity(&self) -> Option<usize> {
+        None
+    }
+
+    /// A pointer to the start of the store.
+    fn ptr(&mut self) -> *mut ();
+
+    /// The whole store as bytes, which is at least as long as the last `size`
+    /// passed to [`ensure`](#tymethod.ensure).
+    fn bytes(&self) -> &[u8];
+}
+
+unsafe impl StateStore for Vec<u64> {
+    /// Resize the vector to exactly fit `size`, rounded up to whole `u64`s.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `align` is more than 8, which a `Vec<u64>` can't provide.
+    fn ensure(&mut self, size: usize, align: usize) {
+        assert!(align <= 8, "a Vec<u64> can't be aligned to {} bytes", align);
+        self.resize(size.div_ceil(8), 0);
+    }
+
+    fn ptr(&mut self) -> *mut () {
+        self.as_mut_ptr() as *mut ()
+    }
+
+    fn bytes(&self) -> &[u8] {
+        units_as_bytes(self)
+    }
+}
+
+/// View a buffer of `u64`s as bytes.
+pub(crate) fn units_as_bytes(units: &[u64]) -> &[u8] {
+    unsafe { slice::from_raw_parts(units.as_ptr() as *const u8, units.len() * 8) }
+}
+
+/// A store with a fixed capacity, allocated up front, so that it never moves.
+///
+/// This is what [`Builder::reserve_state`][] keeps the `State` in.
+///
+/// [`Builder::reserve_state`]: struct.Builder.html#method.reserve_state
+#[cfg(feature = "std")]
+pub(crate) struct ReservedStore {
+    units: Vec<u64>,
+    /// How many of `units` are in use.
+    len: usize,
+}
+
+#[cfg(feature = "std")]
+impl ReservedStore {
+    /// Allocate room for `capacity` bytes, rounded up to whole `u64`s.
+    pub(crate) fn new(capacity: usize) -> Self {
+        ReservedStore {
+            units: vec![0; capacity.div_ceil(8)],
+            len: 0,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+unsafe impl StateStore for ReservedStore {
+    /// Change the length in use, without ever reallocating.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is more than the capacity, which a `Reloadable`
+    /// checks first, or `align` is more than 8.
+    fn ensure(&mut self, size: usize, align: usize) {
+        assert!(align <= 8, "a reserved state can't be aligned to {} bytes", align);
+        let len = size.div_ceil(8);
+        assert!(
+            len <= self.units.len(),
+            "a State of {} bytes doesn't fit in the {} bytes reserved",
+            size,
+            self.units.len() * 8,
+        );
+        // The units past the old length may hold a larger, older State.
+        if len > self.len {
+            for unit in &mut self.units[self.len..len] {
+                *unit = 0;
+            }
+        }
+        self.len = len;
+    }
+
+    fn capacity(&self) -> Option<usize> {
+        Some(self.units.len() * 8)
+    }
+
+    fn ptr(&mut self) -> *mut () {
+        self.units.as_mut_ptr() as *mut ()
+    }
+
+    fn bytes(&self) -> &[u8] {
+        unsafe { slice::from_raw_parts(self.units.as_ptr() as *const u8, self.len * 8) }
+    }
+}
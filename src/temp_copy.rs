@@ -0,0 +1,144 @@
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+#[cfg(unix)]
+use std::convert::TryFrom;
+
+#[cfg(unix)]
+use ::libc;
+
+/// The prefix of the file name of every temporary copy of a library.
+///
+/// Copies are named `live-reload-<pid>-<n>-<name>`, where `<pid>` is the id of
+/// the host process that made the copy, `<n>` counts up with every copy made
+/// by that process, and `<name>` is chosen by the naming function given to
+/// [`Builder::copy_name`][] (the library's own file name by default).
+///
+/// A copy is removed when its library is unloaded, so one whose process is
+/// no longer running was left behind by a crash, and is safe to delete. A
+/// `Reloadable` deletes those in its copy directory when it's built, and
+/// [`Reloadable::cleanup_temp_copies`][] does the same on demand, but any
+/// other tool can too, going by the process id in the name.
+///
+/// [`Builder::copy_name`]: struct.Builder.html#method.copy_name
+/// [`Reloadable::cleanup_temp_copies`]: struct.Reloadable.html#method.cleanup_temp_copies
+pub const TEMP_COPY_PREFIX: &str = "live-reload-";
+
+/// Counts the copies made by this process, so that each has a unique name.
+static COPY_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Where and under what name to copy a library before loading it.
+#[derive(Clone)]
+pub(crate) struct CopyConfig {
+    pub(crate) dir: PathBuf,
+    pub(crate) name: fn(&Path) -> PathBuf,
+}
+
+impl Default for CopyConfig {
+    fn default() -> Self {
+        CopyConfig {
+            dir: ::std::env::temp_dir(),
+            name: default_name,
+        }
+    }
+}
+
+/// Name the copy after the library itself.
+fn default_name(path: &Path) -> PathBuf {
+    path.file_name().map(PathBuf::from).unwrap_or_default()
+}
+
+/// A copy of a library file, which is deleted again when dropped.
+pub(crate) struct TempCopy {
+    path: PathBuf,
+}
+
+impl TempCopy {
+    /// Copy the library at `original` according to `config`.
+    pub(crate) fn create(original: &Path, config: &CopyConfig) -> io::Result<Self> {
+        let suffix = (config.name)(original);
+        loop {
+            let count = COPY_COUNTER.fetch_add(1, Ordering::Relaxed);
+            let mut name = OsString::from(format!("{}{}-{}-", TEMP_COPY_PREFIX, process::id(), count));
+            name.push(&suffix);
+            let path = config.dir.join(name);
+            // Claim the name before copying, since a host that is itself a
+            // dynamic library has its own copy of this crate, and so its own
+            // counter, in the same process as its parent.
+            match OpenOptions::new().write(true).create_new(true).open(&path) {
+                Ok(_) => {}
+                Err(ref err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(err) => return Err(err),
+            }
+            let copy = TempCopy { path };
+            fs::copy(original, &copy.path)?;
+            return Ok(copy);
+        }
+    }
+
+    /// The path of the copy.
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempCopy {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Remove the copies left in `dir` by host processes that are no longer
+/// running, returning their paths.
+///
+/// Copies are normally removed when their library is unloaded, so any that
+/// belong to a process that's gone were left behind by a crash. Copies of
+/// running processes are left alone on Unix. Elsewhere, whether a process is
+/// running isn't checked, but a copy that's still loaded can't be removed
+/// there anyway. A copy that can't be removed is skipped.
+pub(crate) fn remove_stale(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut removed = Vec::new();
+    for entry in fs::read_dir(dir)?.filter_map(Result::ok) {
+        let stale = match copy_owner(&entry.file_name().to_string_lossy()) {
+            Some(pid) => pid != process::id() && !is_running(pid),
+            None => false,
+        };
+        if stale && fs::remove_file(entry.path()).is_ok() {
+            removed.push(entry.path());
+        }
+    }
+    Ok(removed)
+}
+
+/// Whether the process with id `pid` is running.
+#[cfg(unix)]
+fn is_running(pid: u32) -> bool {
+    match libc::pid_t::try_from(pid) {
+        // Signal 0 only checks that the process exists. `EPERM` means it
+        // does, but belongs to another user.
+        Ok(pid) if pid > 0 => {
+            let exists = unsafe { libc::kill(pid, 0) == 0 };
+            exists || io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+        }
+        _ => false,
+    }
+}
+
+/// Whether the process with id `pid` is running, which isn't checked here.
+#[cfg(not(unix))]
+fn is_running(_pid: u32) -> bool {
+    false
+}
+
+/// Get the id of the process that made a copy from the copy's file name, or
+/// `None` if it isn't named like a copy.
+fn copy_owner(name: &str) -> Option<u32> {
+    let mut parts = name.strip_prefix(TEMP_COPY_PREFIX)?.splitn(3, '-');
+    let pid = parts.next()?.parse().ok()?;
+    parts.next()?.parse::<usize>().ok()?;
+    parts.next()?;
+    Some(pid)
+}
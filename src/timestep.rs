@@ -0,0 +1,151 @@
+use std::time::Duration;
+
+use {Error, Reloadable, ShouldQuit};
+
+/// An accumulator for running `update` on a fixed timestep.
+///
+/// Each frame, the real time the frame took goes in with [`advance`][], and
+/// comes back out as the number of fixed steps to simulate. Whatever is left
+/// over, less than one step, is kept for the next frame, and [`alpha`][] is
+/// how far it is into the next step, in `[0, 1)`, for the host to interpolate
+/// between the last two states when it renders. [`update`][] does both at
+/// once, calling [`Reloadable::try_update`][] once per step. The library
+/// should advance its simulation by [`step`][] on each call, which it can
+/// know as a constant, or read from the `Host`.
+///
+/// A frame that takes so long that it owes more than [`max_steps`][] steps
+/// only runs that many, and forgets the rest of the backlog, rather than
+/// falling further behind each frame as the catch-up gets slower.
+///
+/// ```rust
+/// use live_reload::FixedTimestep;
+/// use std::time::Duration;
+///
+/// let mut timestep = FixedTimestep::new(Duration::from_millis(10)).max_steps(3);
+/// assert_eq!(timestep.advance(Duration::from_millis(25)), 2);
+/// assert_eq!(timestep.alpha(), 0.5);
+/// assert_eq!(timestep.advance(Duration::from_millis(5)), 1);
+/// assert_eq!(timestep.alpha(), 0.0);
+///
+/// // A long stall only catches up by three steps.
+/// assert_eq!(timestep.advance(Duration::from_millis(1004)), 3);
+/// assert!((timestep.alpha() - 0.4).abs() < 1e-6);
+/// ```
+///
+/// [`advance`]: struct.FixedTimestep.html#method.advance
+/// [`alpha`]: struct.FixedTimestep.html#method.alpha
+/// [`update`]: struct.FixedTimestep.html#method.update
+/// [`step`]: struct.FixedTimestep.html#method.step
+/// [`max_steps`]: struct.FixedTimestep.html#method.max_steps
+/// [`Reloadable::try_update`]: struct.Reloadable.html#method.try_update
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    step: Duration,
+    max_steps: u32,
+    accumulator: Duration,
+}
+
+impl FixedTimestep {
+    /// Create an accumulator that steps by `step`, catching up by at most 8
+    /// steps a frame.
+    ///
+    /// # Panics
+    ///
+    /// If `step` is zero.
+    pub fn new(step: Duration) -> Self {
+        assert!(step > Duration::from_secs(0), "a fixed timestep can't be zero");
+        FixedTimestep {
+            step,
+            max_steps: 8,
+            accumulator: Duration::from_secs(0),
+        }
+    }
+
+    /// Set the most steps a single frame can run, to catch up after a slow
+    /// frame. This is at least 1.
+    pub fn max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps.max(1);
+        self
+    }
+
+    /// The fixed step each `update` simulates.
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Add a frame's worth of real time, and return how many steps to run.
+    pub fn advance(&mut self, frame: Duration) -> u32 {
+        self.accumulator += frame;
+        let owed = self.accumulator.as_nanos() / self.step.as_nanos();
+        let leftover = self.accumulator.as_nanos() % self.step.as_nanos();
+        self.accumulator = Duration::from_nanos(leftover as u64);
+        if owed > u128::from(self.max_steps) {
+            self.max_steps
+        } else {
+            owed as u32
+        }
+    }
+
+    /// How far the time left over is into the next step, in `[0, 1)`.
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator.as_nanos() as f64 / self.step.as_nanos() as f64) as f32
+    }
+
+    /// Add a frame's worth of real time, and call `update` on `app` once for
+    /// each step owed, returning [`alpha`][] afterwards.
+    ///
+    /// This stops early, returning `ShouldQuit::Yes`, as soon as an `update`
+    /// asks to quit, and stops at the first error. Either way, the steps it
+    /// didn't get to are dropped.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host { ticks: u32 }
+    /// #[repr(C)] pub struct State;
+    ///
+    /// fn update(host: &mut Host, _: &mut State) -> ShouldQuit {
+    ///     host.ticks += 1;
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::{FixedTimestep, Reloadable};
+    /// use std::time::Duration;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host { ticks: 0 }).unwrap();
+    /// let mut timestep = FixedTimestep::new(Duration::from_millis(20));
+    /// let (quit, alpha) = timestep.update(&mut app, Duration::from_millis(50)).unwrap();
+    /// assert_eq!(quit, ShouldQuit::No);
+    /// assert_eq!(alpha, 0.5);
+    /// assert_eq!(app.host().ticks, 2);
+    /// # }
+    /// ```
+    ///
+    /// [`alpha`]: struct.FixedTimestep.html#method.alpha
+    pub fn update<Host>(
+        &mut self,
+        app: &mut Reloadable<Host>,
+        frame: Duration,
+    ) -> Result<(ShouldQuit, f32), Error> {
+        for _ in 0..self.advance(frame) {
+            if app.try_update()? == ShouldQuit::Yes {
+                return Ok((ShouldQuit::Yes, self.alpha()));
+            }
+        }
+        Ok((ShouldQuit::No, self.alpha()))
+    }
+}
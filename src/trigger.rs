@@ -0,0 +1,53 @@
+/// Decides when a [`Reloadable`][] reloads its library, in place of watching
+/// the library's file.
+///
+/// By default, a `Reloadable` watches the library's directory, and
+/// [`reload`][] reloads once the library has changed. A trigger set with
+/// [`Builder::trigger`][] takes over from that watcher, so that reloads can
+/// come from anything else: a network message, a key press or a command in a
+/// console. Each [`reload`][] asks the trigger, and a `true` is treated just
+/// like a change seen by the watcher, so the reload still waits for the
+/// library's `is_safe_to_reload`, the reload gate and so on.
+///
+/// Any `FnMut() -> bool` closure is a trigger.
+///
+/// ```rust,no_run
+/// # extern crate live_reload;
+/// # fn main() {
+/// # struct Host;
+/// use std::sync::Arc;
+/// use std::sync::atomic::{AtomicBool, Ordering};
+/// use live_reload::Builder;
+///
+/// let requested = Arc::new(AtomicBool::new(false));
+/// let trigger = requested.clone();
+/// let mut app = Builder::new("target/debug/libgame.so")
+///     .trigger(move || trigger.swap(false, Ordering::Relaxed))
+///     .build(Host)
+///     .unwrap();
+///
+/// // Somewhere else, say when F5 is pressed:
+/// requested.store(true, Ordering::Relaxed);
+///
+/// app.reload().unwrap();
+/// # }
+/// ```
+///
+/// [`Reloadable`]: struct.Reloadable.html
+/// [`reload`]: struct.Reloadable.html#method.reload
+/// [`Builder::trigger`]: struct.Builder.html#method.trigger
+pub trait ReloadTrigger: Send {
+    /// Whether the library should be reloaded now.
+    ///
+    /// This is called once by each [`reload`][], and not while watching is
+    /// paused.
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    fn should_reload(&mut self) -> bool;
+}
+
+impl<F: FnMut() -> bool + Send> ReloadTrigger for F {
+    fn should_reload(&mut self) -> bool {
+        self()
+    }
+}
@@ -0,0 +1,179 @@
+use std::marker::PhantomData;
+use std::mem::{align_of, size_of};
+use std::ops::{Deref, DerefMut};
+
+use super::{Error, Reloadable};
+
+/// A host-side copy of the declaration of a library's `State`, for
+/// [`TypedReloadable`][].
+///
+/// The simplest way to keep the copy faithful is to declare the `State` with
+/// [`reloadable_state!`][] in a file that both the library and the host
+/// include, and use its `LAYOUT_HASH` here, which the library also passes to
+/// [`live_reload!`][] as `state_layout`.
+///
+/// # Safety
+///
+/// Implementing this promises that `Self` is declared exactly like the
+/// library's `State`, field for field, with `#[repr(C)]`, and that
+/// `LAYOUT_HASH` is the hash the library reports for it, so that it changes
+/// with the declaration. All-zero bytes must also be a valid `Self`, since
+/// that's what the state holds before `init`.
+///
+/// [`TypedReloadable`]: struct.TypedReloadable.html
+/// [`reloadable_state!`]: macro.reloadable_state.html
+/// [`live_reload!`]: macro.live_reload.html
+pub unsafe trait StateMirror: Sized {
+    /// The layout hash of the `State` this mirrors.
+    const LAYOUT_HASH: u64;
+}
+
+/// A [`Reloadable`][] whose `State` the host can read and write as a
+/// [`StateMirror`][] `S`, without any `unsafe`.
+///
+/// Each call to [`state`](#method.state) or [`state_mut`](#method.state_mut)
+/// checks `S` against the library that's loaded: the library's `State` has
+/// to have `S`'s size and the layout hash `S` declares, and `S` mustn't need
+/// more than 8-byte alignment. Once a reload brings in a `State` that no
+/// longer matches, access is refused with [`Error::StateLayoutChanged`][]
+/// until the host is rebuilt with an updated mirror, or the library goes
+/// back to the old layout. [`reload`](#method.reload) reports that straight
+/// away. A library that doesn't report a layout is refused too, unless it's
+/// run in-process, where only the size is compared.
+///
+/// Everything else goes through to the `Reloadable`, which this derefs to.
+///
+/// ```rust
+/// # #[macro_use] extern crate live_reload;
+/// use live_reload::{Error, Reloadable, ShouldQuit, StateMirror, TypedReloadable};
+///
+/// // In a file shared by the library and the host.
+/// reloadable_state! {
+///     pub struct State {
+///         pub frames: u64,
+///     }
+/// }
+///
+/// // In the host.
+/// unsafe impl StateMirror for State {
+///     const LAYOUT_HASH: u64 = State::LAYOUT_HASH;
+/// }
+/// # pub struct Host;
+/// # fn nothing(_: &mut Host, _: &mut State) {}
+/// # fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+/// #     state.frames += 1;
+/// #     ShouldQuit::No
+/// # }
+/// # live_reload! {
+/// #     host: Host;
+/// #     state: State;
+/// #     init: nothing;
+/// #     reload: nothing;
+/// #     update: update;
+/// #     unload: nothing;
+/// #     deinit: nothing;
+/// # }
+///
+/// # fn main() {
+/// let app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+/// let mut app = TypedReloadable::<_, State>::new(app);
+/// app.update();
+/// app.update();
+/// assert_eq!(app.state().unwrap().frames, 2);
+/// app.state_mut().unwrap().frames = 10;
+/// app.update();
+/// assert_eq!(app.state().unwrap().frames, 11);
+///
+/// // A mirror that's out of date is refused.
+/// #[repr(C)]
+/// struct Stale { frames: u32 }
+/// unsafe impl StateMirror for Stale {
+///     const LAYOUT_HASH: u64 = 0;
+/// }
+/// let app = TypedReloadable::<_, Stale>::new(app.into_inner());
+/// assert!(matches!(app.state(), Err(Error::StateLayoutChanged { .. })));
+/// # }
+/// ```
+///
+/// [`Reloadable`]: struct.Reloadable.html
+/// [`StateMirror`]: trait.StateMirror.html
+/// [`Error::StateLayoutChanged`]: enum.Error.html#variant.StateLayoutChanged
+pub struct TypedReloadable<Host, S> {
+    app: Reloadable<Host>,
+    _state: PhantomData<S>,
+}
+
+impl<Host, S: StateMirror> TypedReloadable<Host, S> {
+    /// Wrap `app`, to get at its state as an `S`.
+    pub fn new(app: Reloadable<Host>) -> Self {
+        TypedReloadable {
+            app,
+            _state: PhantomData,
+        }
+    }
+
+    /// Check that `S` still mirrors the `State` of the loaded library.
+    ///
+    /// This returns [`Error::StateLayoutChanged`][] if it doesn't.
+    ///
+    /// [`Error::StateLayoutChanged`]: enum.Error.html#variant.StateLayoutChanged
+    pub fn check(&self) -> Result<(), Error> {
+        let expected = S::LAYOUT_HASH;
+        let found = self.app.loaded_state_layout();
+        let layout_matches = match found {
+            Some(found) => found == expected,
+            None => self.app.is_in_process(),
+        };
+        let layout = self.app.state_alloc_layout();
+        if !layout_matches || size_of::<S>() != layout.size() || align_of::<S>() > layout.align() {
+            return Err(Error::StateLayoutChanged { expected, found });
+        }
+        Ok(())
+    }
+
+    /// View the state as an `S`, if it still matches.
+    pub fn state(&self) -> Result<&S, Error> {
+        self.check()?;
+        let bytes = self.app.state_bytes();
+        // `check` made sure the library's `State` is an `S`, and that the
+        // buffer is aligned enough for it.
+        Ok(unsafe { &*(bytes.as_ptr() as *const S) })
+    }
+
+    /// Mutably view the state as an `S`, if it still matches.
+    ///
+    /// Whatever is written here is what the library sees on its next call.
+    pub fn state_mut(&mut self) -> Result<&mut S, Error> {
+        self.check()?;
+        let bytes = unsafe { self.app.state_bytes_mut() };
+        Ok(unsafe { &mut *(bytes.as_mut_ptr() as *mut S) })
+    }
+
+    /// Reload the library if it changed, like [`Reloadable::reload`][], and
+    /// then check that `S` still matches its `State`.
+    ///
+    /// [`Reloadable::reload`]: struct.Reloadable.html#method.reload
+    pub fn reload(&mut self) -> Result<(), Error> {
+        self.app.reload()?;
+        self.check()
+    }
+
+    /// Take back the `Reloadable`.
+    pub fn into_inner(self) -> Reloadable<Host> {
+        self.app
+    }
+}
+
+impl<Host, S> Deref for TypedReloadable<Host, S> {
+    type Target = Reloadable<Host>;
+
+    fn deref(&self) -> &Reloadable<Host> {
+        &self.app
+    }
+}
+
+impl<Host, S> DerefMut for TypedReloadable<Host, S> {
+    fn deref_mut(&mut self) -> &mut Reloadable<Host> {
+        &mut self.app
+    }
+}
@@ -0,0 +1,22 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Checks a library's bytes against its detached signature.
+pub(crate) type CheckSignature = Box<dyn Fn(&[u8], &[u8]) -> bool + Send + Sync>;
+
+/// Where a library's signature is, and how to check it.
+pub(crate) struct Verifier {
+    pub(crate) signature: PathBuf,
+    pub(crate) check: CheckSignature,
+}
+
+impl Verifier {
+    /// Check the library file at `path` against the signature as it is on
+    /// disk now, returning `Ok(false)` if they don't match.
+    pub(crate) fn verify(&self, path: &Path) -> io::Result<bool> {
+        let library = fs::read(path)?;
+        let signature = fs::read(&self.signature)?;
+        Ok((self.check)(&library, &signature))
+    }
+}
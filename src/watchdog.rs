@@ -0,0 +1,118 @@
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, PoisonError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use guard::{self, LifecyclePhase, PanicSink};
+use super::Error;
+
+/// Hears about a lifecycle call that's taking too long.
+pub(crate) type OnHang = Box<dyn FnMut(LifecyclePhase) + Send>;
+
+/// Watches the calls into the library from a thread of its own, and reports
+/// any that runs longer than the timeout, once per call.
+pub(crate) struct Watchdog {
+    shared: Arc<Shared>,
+    worker: Option<JoinHandle<()>>,
+}
+
+struct Shared {
+    watched: Mutex<Watched>,
+    /// Signalled whenever a call starts or ends, or the watchdog stops.
+    changed: Condvar,
+}
+
+struct Watched {
+    /// The call in progress, with when it started, and whether it's been
+    /// reported yet.
+    call: Option<(LifecyclePhase, Instant, bool)>,
+    stopped: bool,
+}
+
+impl Watchdog {
+    pub(crate) fn spawn(timeout: Duration, mut on_hang: OnHang) -> io::Result<Self> {
+        let shared = Arc::new(Shared {
+            watched: Mutex::new(Watched { call: None, stopped: false }),
+            changed: Condvar::new(),
+        });
+        let worker = {
+            let shared = shared.clone();
+            thread::Builder::new()
+                .name("live-reload-watchdog".into())
+                .spawn(move || {
+                    let mut watched = lock(&shared);
+                    while !watched.stopped {
+                        let wait = match watched.call {
+                            Some((phase, started, false)) => {
+                                let elapsed = started.elapsed();
+                                if elapsed >= timeout {
+                                    watched.call = Some((phase, started, true));
+                                    // Let the call finish while the hook runs.
+                                    drop(watched);
+                                    on_hang(phase);
+                                    watched = lock(&shared);
+                                    continue;
+                                }
+                                Some(timeout - elapsed)
+                            }
+                            _ => None,
+                        };
+                        watched = match wait {
+                            Some(wait) => shared.changed
+                                .wait_timeout(watched, wait)
+                                .unwrap_or_else(PoisonError::into_inner)
+                                .0,
+                            None => shared.changed.wait(watched).unwrap_or_else(PoisonError::into_inner),
+                        };
+                    }
+                })?
+        };
+        Ok(Watchdog {
+            shared,
+            worker: Some(worker),
+        })
+    }
+
+    /// Note that a call of `phase` is starting, until the guard is dropped.
+    fn watch(&self, phase: LifecyclePhase) -> Watch<'_> {
+        lock(&self.shared).call = Some((phase, Instant::now(), false));
+        self.shared.changed.notify_all();
+        Watch { watchdog: self }
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        lock(&self.shared).stopped = true;
+        self.shared.changed.notify_all();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Marks the end of a watched call when dropped.
+struct Watch<'a> {
+    watchdog: &'a Watchdog,
+}
+
+impl<'a> Drop for Watch<'a> {
+    fn drop(&mut self) {
+        lock(&self.watchdog.shared).call = None;
+        self.watchdog.shared.changed.notify_all();
+    }
+}
+
+/// Make a call into the library like `guard::call`, under the watchdog if
+/// there is one.
+pub(crate) fn call<R, F>(watchdog: Option<&Watchdog>, phase: LifecyclePhase, call: F) -> Result<R, Error>
+where
+    F: FnOnce(&mut PanicSink) -> R,
+{
+    let _watch = watchdog.map(|watchdog| watchdog.watch(phase));
+    guard::call(phase, call)
+}
+
+fn lock(shared: &Shared) -> MutexGuard<'_, Watched> {
+    shared.watched.lock().unwrap_or_else(PoisonError::into_inner)
+}
@@ -1,49 +1,821 @@
 use ::std;
+use std::alloc::Layout;
+use std::collections::VecDeque;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
-use std::sync::mpsc::{channel, Receiver};
+use std::time::{Duration, Instant, SystemTime};
+use std::sync::mpsc::channel;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
 
 use ::notify;
 use ::notify::{Watcher, RecommendedWatcher};
 use ::libloading;
 use ::libloading::Library;
 
-use super::ShouldQuit;
+use super::{Builder, ShouldQuit};
+use builder::Config;
+use temp_copy::{self, TempCopy};
+use debugger;
+use history::History;
+use reload_log::{ReloadLog, ReloadLogEntry, ReloadLogKind};
+use diff::StateDiff;
+use save::{Generation, SaveState};
+use events::{self, Events};
+use store::StateStore;
+use store::units_as_bytes;
+use guard::{LifecyclePhase, PanicLocation, PanicSink};
+use snapshot;
+use shared_library::{SharedHandle, SharedLibrary};
+use complete;
+use runner;
+use observer::ReloadObserver;
+use build_command::BuildTrigger;
+use metrics::ReloadMetrics;
+use generational::Generational;
+use watchdog::{self, Watchdog};
+use internals;
+use super::{Capabilities, HostRequirements, PanicStrategy, Profile};
+use open_flags;
+#[cfg(feature = "testing")]
+use mock::{MockLibrary, OwnedMock};
+#[cfg(all(feature = "mmap", unix))]
+use super::MappedState;
+#[cfg(all(feature = "shm", unix))]
+use super::SharedState;
 
 #[cfg(unix)]
 type Symbol<T> = libloading::os::unix::Symbol<T>;
 #[cfg(windows)]
 type Symbol<T> = libloading::os::windows::Symbol<T>;
 
-struct AppSym<Host> {
+pub(crate) struct AppSym<Host> {
     /// This needs to be present so that the library will be closed on drop
+    lib: Option<Library>,
+    /// The companion libraries, in the reverse of the order they were
+    /// loaded. This comes after `lib`, so that they're closed after it, back
+    /// to front.
+    companions: Vec<Companion>,
+    api: Api<Host>,
+    /// The build id the library exported, if it did.
+    build_id: Option<internals::BuildId>,
+    /// The layout hash of the library's `State`, if it exported one.
+    state_layout: Option<u64>,
+    /// The profile the library was built with, if it exported one.
+    profile: Option<Profile>,
+    /// The name the `ReloadApi` was found under, for a library file.
+    api_symbol: Option<String>,
+    /// The file the library was loaded from, which is the copy if there is
+    /// one.
+    file: Option<PathBuf>,
+    /// The copy that was loaded instead of the library itself, if any. This
+    /// comes after `lib` so that it's only removed once the library is closed.
+    _copy: Option<TempCopy>,
+}
+
+/// A library loaded along with the main one, from `Builder::companion_library`.
+struct Companion {
     _lib: Library,
-    api: Symbol<*mut internals::ReloadApi<Host>>,
+    /// Removed once the library is closed, like `AppSym::_copy`.
+    _copy: Option<TempCopy>,
+}
+
+// A loaded library is immutable, and stays put until the `AppSym` is dropped,
+// so it can be shared between `Reloadable`s on different threads.
+unsafe impl<Host> Send for AppSym<Host> {}
+unsafe impl<Host> Sync for AppSym<Host> {}
+
+/// Decides whether a pending reload may happen now.
+type ReloadGate<Host> = Box<dyn FnMut(&Host) -> bool + Send>;
+
+/// Hears about the metrics after each reload.
+type MetricsSink = Box<dyn FnMut(&ReloadMetrics) + Send>;
+
+/// Hears about the `State` moving, with its old and new addresses.
+type StateMovedHook = Box<dyn FnMut(*mut (), *mut ()) + Send>;
+
+/// Hears about the library asking to quit.
+type QuitHook<Host> = Box<dyn FnMut(&mut Host) + Send>;
+
+/// Opens each new version of a library adopted by `from_library`.
+type LibraryLoader = Box<dyn FnMut() -> Result<Library, Error> + Send>;
+
+/// Where the `ReloadApi` of the loaded library lives.
+enum Api<Host> {
+    /// The `RELOAD_API` symbol of a dynamic library.
+    Symbol(Symbol<*mut internals::ReloadApi<Host>>),
+    /// A `ReloadApi` that is part of the host itself.
+    #[cfg(feature = "in-process")]
+    InProcess(internals::ReloadApi<Host>),
+    /// A library loaded once by a `SharedLibrary`, for several `Reloadable`s.
+    Shared(Arc<AppSym<Host>>),
 }
 
 // @Todo: Flesh out this documentation
 /// A `Reloadable` represents a handle to library that can be live reloaded.
+///
+/// A `Reloadable` is `Send` when its `Host` is, so it can be handed to a
+/// worker thread. To save its state from another thread while it runs, see
+/// [`shared`](#method.shared).
+///
+/// ```rust
+/// # extern crate live_reload;
+/// # fn main() {
+/// use live_reload::Reloadable;
+///
+/// struct Host {
+///     frames: u64,
+/// }
+///
+/// fn assert_send<T: Send>() {}
+/// assert_send::<Reloadable<Host>>();
+/// # }
+/// ```
 pub struct Reloadable<Host> {
+    /// The canonical path of the library, as of the last successful load.
     path: PathBuf,
+    /// The absolute path the library was asked for, which might be a symlink.
+    request_path: PathBuf,
     sym: Option<AppSym<Host>>,
-    state: Vec<u64>,
-    _watcher: RecommendedWatcher,
-    rx: Receiver<notify::DebouncedEvent>,
+    state: StateBuffer,
+    /// The size in bytes of the `State` reported by the last library loaded.
+    state_size: usize,
+    /// The modification times of the library file and then of each companion
+    /// library, when they were last loaded.
+    loaded_mtimes: Vec<Option<SystemTime>>,
+    /// The number of successful reloads so far.
+    reload_count: u64,
+    config: Config<Host>,
+    history: Option<History>,
+    /// Whether a change has been seen that hasn't been reloaded yet.
+    pending_reload: bool,
+    /// Whether the library's `init` (or `reload`, for a recovered state) has
+    /// been called, which `lazy_init` puts off until the first update.
+    initialized: bool,
+    /// Whether the `Reloadable` was opened with `open_inert`, and
+    /// `ensure_init` hasn't been called since.
+    inert: bool,
+    /// Whether the library didn't exist yet when the `Reloadable` was made,
+    /// and hasn't been loaded since.
+    deferred: bool,
+    /// Whether watching was paused with `pause_watching`.
+    paused: bool,
+    /// When the recent failed reloads happened, for `detect_reload_loops`.
+    reload_failures: VecDeque<Instant>,
+    /// Set while reloading is stopped by a reload loop, with the hash of the
+    /// contents of the library and its companions when it stopped.
+    reload_loop: Option<Option<u64>>,
+    /// The hashes of the contents of libraries that failed to load, with
+    /// quarantine on.
+    quarantine: Vec<u64>,
+    /// Whether the library's directory was removed, taking the watch with it.
+    dir_removed: bool,
+    reload_gate: Option<ReloadGate<Host>>,
+    /// Where the library's `reload_progress` reports to, if anywhere.
+    reload_progress: Option<fn(f32)>,
+    observer: Option<Box<dyn ReloadObserver<Host>>>,
+    /// The last few lifecycle steps, for `Builder::reload_log_capacity`.
+    reload_log: ReloadLog,
+    metrics: ReloadMetrics,
+    metrics_sink: Option<MetricsSink>,
+    on_state_moved: Option<StateMovedHook>,
+    on_quit_requested: Option<QuitHook<Host>>,
+    /// Whether the last update asked to quit.
+    quit_requested: bool,
+    /// Opens the library in place of a path, for `from_library`.
+    loader: Option<LibraryLoader>,
+    watchdog: Option<Watchdog>,
+    /// Counts the times the library was swapped out or the `State` moved.
+    load_generation: u64,
+    /// The version of the `State`, as the number of migrations it's been
+    /// through.
+    state_version: u32,
+    /// The layout hash of the `State` of the last library that loaded, to
+    /// tell the observer about migrations.
+    state_layout: Option<u64>,
+    /// The generation the next `SaveState` gets.
+    next_generation: AtomicU64,
+    watcher: Option<RecommendedWatcher>,
+    /// The `ReloadApi` that is "reloaded" in place of a dynamic library.
+    #[cfg(feature = "in-process")]
+    in_process: Option<internals::ReloadApi<Host>>,
+    events: Events,
+    /// The `SharedLibrary` the library comes from, if any.
+    shared: Option<SharedHandle<Host>>,
+    /// Replaced versions of the library that `retain_old_images` keeps
+    /// loaded, oldest first.
+    old_images: VecDeque<AppSym<Host>>,
+    /// The mock that `with_mock` runs, which has to outlive every call into
+    /// it.
+    #[cfg(feature = "testing")]
+    mock: Option<OwnedMock<Host>>,
     host: Host,
 }
 
+/// Whether a call that reloads on change actually performed a reload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReloadOutcome {
+    /// The library had changed and was reloaded.
+    Reloaded,
+    /// The library hadn't changed, so nothing happened.
+    Unchanged,
+}
+
+/// What [`drain_and_coalesce`][] found in the watcher's queued events.
+///
+/// [`drain_and_coalesce`]: struct.Reloadable.html#method.drain_and_coalesce
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChangeSummary {
+    code_changed: bool,
+    assets_changed: Vec<PathBuf>,
+}
+
+impl ChangeSummary {
+    /// Whether the library changed, so that a reload is waiting.
+    pub fn code_changed(&self) -> bool {
+        self.code_changed
+    }
+
+    /// The other files that were written, created or renamed into place,
+    /// each once, in the order they first changed.
+    pub fn assets_changed(&self) -> &[PathBuf] {
+        &self.assets_changed
+    }
+
+    /// Whether nothing changed at all.
+    pub fn is_empty(&self) -> bool {
+        !self.code_changed && self.assets_changed.is_empty()
+    }
+}
+
+/// What [`resume_watching`][] does about changes made while watching was
+/// paused.
+///
+/// [`resume_watching`]: struct.Reloadable.html#method.resume_watching
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissedChanges {
+    /// Reload on the next [`reload`][] if the library changed while paused.
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    Reload,
+    /// Forget about any changes, and keep running the loaded library until
+    /// it changes again.
+    Ignore,
+}
+
+/// A phase of work that a `Reloadable` does, as seen by the hook set with
+/// [`Builder::instrument`][].
+///
+/// A phase can span several calls into the library. To say which of the
+/// library's functions was running, say for a panic, there's
+/// [`LifecyclePhase`][].
+///
+/// [`Builder::instrument`]: struct.Builder.html#method.instrument
+/// [`LifecyclePhase`]: enum.LifecyclePhase.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Phase {
+    /// Reloading the library, in [`reload_now`][] (and so in [`reload`][]).
+    ///
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    Reload,
+    /// Calling the library's `update`, in [`update`][] or [`try_update`][].
+    ///
+    /// [`update`]: struct.Reloadable.html#method.update
+    /// [`try_update`]: struct.Reloadable.html#method.try_update
+    Update,
+}
+
+/// Describes a phase of work that is about to run.
+#[derive(Debug, Clone)]
+pub struct PhaseInfo {
+    phase: Phase,
+    reload_count: u64,
+    state_size: usize,
+}
+
+impl PhaseInfo {
+    /// Which phase this is.
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    /// The number of successful reloads before this phase.
+    pub fn reload_count(&self) -> u64 {
+        self.reload_count
+    }
+
+    /// The size in bytes of the `State` before this phase.
+    pub fn state_size(&self) -> usize {
+        self.state_size
+    }
+}
+
+/// Both sides of a reload, as returned by [`Reloadable::reload_now`][].
+///
+/// [`Reloadable::reload_now`]: struct.Reloadable.html#method.reload_now
+#[derive(Debug, Clone)]
+pub struct ReloadTransition {
+    before: Option<LibraryInfo>,
+    after: LibraryInfo,
+}
+
+impl ReloadTransition {
+    /// The library that was replaced, or `None` if none was loaded.
+    pub fn before(&self) -> Option<&LibraryInfo> {
+        self.before.as_ref()
+    }
+
+    /// The library that's loaded now.
+    pub fn after(&self) -> &LibraryInfo {
+        &self.after
+    }
+}
+
+/// What's known about a library.
+///
+/// This is passed to the hook set with [`Builder::before_reload`][], about a
+/// changed library before it's loaded, and returned by
+/// [`Reloadable::library_info`][], about the library that's loaded, and by
+/// [`Reloadable::inspect`][], about any library. What the library itself
+/// reports is only known once it's loaded, so it's `None` in the first.
+///
+/// [`Builder::before_reload`]: struct.Builder.html#method.before_reload
+/// [`Reloadable::library_info`]: struct.Reloadable.html#method.library_info
+/// [`Reloadable::inspect`]: struct.Reloadable.html#method.inspect
+#[derive(Debug, Clone)]
+pub struct LibraryInfo {
+    path: PathBuf,
+    metadata: Option<std::fs::Metadata>,
+    abi_version: Option<u32>,
+    state_size: Option<usize>,
+    build_id: Option<String>,
+    capabilities: Option<Capabilities>,
+    state_layout: Option<u64>,
+    profile: Option<Profile>,
+    api_symbol: Option<String>,
+    host_hash: Option<u64>,
+    modules: Vec<ModuleInfo>,
+}
+
+impl LibraryInfo {
+    /// Describe the library at `path`, without reading anything yet.
+    fn new(path: &Path) -> Self {
+        LibraryInfo {
+            path: path.to_path_buf(),
+            metadata: None,
+            abi_version: None,
+            state_size: None,
+            build_id: None,
+            capabilities: None,
+            state_layout: None,
+            profile: None,
+            api_symbol: None,
+            host_hash: None,
+            modules: Vec::new(),
+        }
+    }
+
+    /// The path the library will be loaded from.
+    ///
+    /// This is the path the `Reloadable` was given, made absolute, so it might
+    /// be a symlink.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The size of the library file in bytes, or `None` if it can't be read.
+    pub fn file_size(&self) -> Option<u64> {
+        self.metadata.as_ref().map(std::fs::Metadata::len)
+    }
+
+    /// When the library file was last modified, if that's known.
+    pub fn modified(&self) -> Option<std::time::SystemTime> {
+        self.metadata.as_ref().and_then(|metadata| metadata.modified().ok())
+    }
+
+    /// The [ABI version][] the loaded library was built with.
+    ///
+    /// [ABI version]: internals/constant.ABI_VERSION.html
+    pub fn abi_version(&self) -> Option<u32> {
+        self.abi_version
+    }
+
+    /// The size in bytes of the loaded library's `State`.
+    ///
+    /// The alignment isn't reported by the library; the `State` is always
+    /// kept 8-byte aligned.
+    pub fn state_size(&self) -> Option<usize> {
+        self.state_size
+    }
+
+    /// The build id of the loaded library, like `0.2.0+host-v2`.
+    ///
+    /// This is also `None` for a library that doesn't export one, which is
+    /// only loaded if the build id check is turned off (see
+    /// [`Builder::check_build_id`][]), and for a library run in-process.
+    ///
+    /// [`Builder::check_build_id`]: struct.Builder.html#method.check_build_id
+    pub fn build_id(&self) -> Option<&str> {
+        self.build_id.as_deref()
+    }
+
+    /// The optional functions the loaded library implements.
+    pub fn capabilities(&self) -> Option<Capabilities> {
+        self.capabilities
+    }
+
+    /// The layout hash of the loaded library's `State`, if it was declared
+    /// with [`reloadable_state!`][] and passed to `live_reload!`.
+    ///
+    /// Compare it before and after a reload to tell whether the reload
+    /// changed the layout of the `State`.
+    ///
+    /// [`reloadable_state!`]: macro.reloadable_state.html
+    pub fn state_layout(&self) -> Option<u64> {
+        self.state_layout
+    }
+
+    /// Whether the loaded library was built with `debug_assertions`, as in a
+    /// `dev` build, rather than as a `release` build.
+    ///
+    /// This is `None` for a library built before the profile was exported.
+    /// A library run in-process shares the host's profile.
+    pub fn is_debug_build(&self) -> Option<bool> {
+        self.profile.map(|profile| profile == Profile::Debug)
+    }
+
+    /// The name the loaded library's `ReloadApi` was found under, out of
+    /// those given to [`Builder::symbol_versions`][], like `RELOAD_API_V2`.
+    ///
+    /// This is `None` for a library run in-process, and when describing a
+    /// library before it's loaded.
+    ///
+    /// [`Builder::symbol_versions`]: struct.Builder.html#method.symbol_versions
+    pub fn api_symbol(&self) -> Option<&str> {
+        self.api_symbol.as_deref()
+    }
+
+    /// The [fingerprint][] of the `Host` the library was built for.
+    ///
+    /// This is only filled in by [`Reloadable::inspect`][], since a library
+    /// that's loaded has already been checked against the `Host`. It's also
+    /// `None` for a library that doesn't export one, like one built with
+    /// [`live_reload_multi!`][].
+    ///
+    /// [fingerprint]: internals/fn.host_hash.html
+    /// [`Reloadable::inspect`]: struct.Reloadable.html#method.inspect
+    /// [`live_reload_multi!`]: macro.live_reload_multi.html
+    pub fn host_hash(&self) -> Option<u64> {
+        self.host_hash
+    }
+
+    /// The modules of a library that exports several with
+    /// [`live_reload_multi!`][], in the order they're declared.
+    ///
+    /// This is only filled in by [`Reloadable::inspect`][], and is empty for
+    /// a library built with `live_reload!`.
+    ///
+    /// [`live_reload_multi!`]: macro.live_reload_multi.html
+    /// [`Reloadable::inspect`]: struct.Reloadable.html#method.inspect
+    pub fn modules(&self) -> &[ModuleInfo] {
+        &self.modules
+    }
+}
+
+/// What's known about one of the modules of a library built with
+/// [`live_reload_multi!`][].
+///
+/// [`live_reload_multi!`]: macro.live_reload_multi.html
+#[derive(Debug, Clone)]
+pub struct ModuleInfo {
+    name: String,
+    state_size: usize,
+    capabilities: Capabilities,
+}
+
+impl ModuleInfo {
+    /// The name of the module, which identifies it across reloads.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The size in bytes of the module's `State`.
+    pub fn state_size(&self) -> usize {
+        self.state_size
+    }
+
+    /// The optional functions the module implements.
+    pub fn capabilities(&self) -> Capabilities {
+        self.capabilities
+    }
+}
+
+/// What to do when a reload shrinks the `State`.
+///
+/// This is returned by the hook set with [`Builder::on_state_shrink`][].
+///
+/// [`Builder::on_state_shrink`]: struct.Builder.html#method.on_state_shrink
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShrinkAction {
+    /// Zero the bytes past the end of the new `State` and carry on reloading.
+    ZeroTail,
+    /// Abort the reload with [`Error::StateShrank`][], leaving no library
+    /// loaded and the state untouched.
+    ///
+    /// [`Error::StateShrank`]: enum.Error.html#variant.StateShrank
+    Abort,
+}
+
+/// How strictly to check that a library was built for the same `Host`.
+///
+/// This is set with [`Builder::host_check`][]. Every library built with this
+/// version of [`live_reload!`][] exports a fingerprint of its `Host`, made of
+/// its size and alignment, but libraries built with older versions don't.
+///
+/// Nothing else about the `Host` is known the same way on both sides, so
+/// this only checks its size. It catches a field added or removed, but not
+/// two fields swapped, or a field changed to another type of the same size
+/// and alignment.
+///
+/// [`Builder::host_check`]: struct.Builder.html#method.host_check
+/// [`live_reload!`]: macro.live_reload.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HostCheckMode {
+    /// Refuse a library whose fingerprint differs from the host's, or that
+    /// doesn't export one.
+    Strict,
+    /// Refuse a library whose fingerprint differs from the host's, but
+    /// assume one without a fingerprint is compatible. This is the default,
+    /// so that libraries built before fingerprints existed keep loading.
+    #[default]
+    Lenient,
+    /// Don't check the fingerprint at all.
+    Off,
+}
+
 /// The errors that can occur while working with a `Reloadable` object.
 #[derive(Debug)]
 pub enum Error {
     /// An I/O error occurred while trying to load or reload the library. This
-    /// can indicate that the file is missing, or that the library didn't have
-    /// the expected `RELOAD_API` symbol.
-    // @Diagnostics: Add an error type to distinguish this latter situation
+    /// can indicate that the file is missing, or isn't a library.
+    ///
+    /// A library that's empty or cut short, as it is while the linker is
+    /// still writing it, is refused with an `UnexpectedEof` error rather
+    /// than being handed to the system's loader, and is retried like any
+    /// other I/O error (see [`Builder::reload_retries`][]).
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # if cfg!(miri) { return; }
+    /// use std::fs;
+    /// use std::io::ErrorKind;
+    /// use live_reload::{Error, Reloadable};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("live-reload-empty-library-{}", std::process::id()));
+    /// fs::create_dir_all(&dir).unwrap();
+    /// let path = dir.join("libgame.so");
+    /// fs::write(&path, b"").unwrap();
+    /// match Reloadable::new(&path, ()) {
+    ///     Err(Error::Io(ref err)) => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+    ///     other => panic!("an empty library should be refused, got {:?}", other.err()),
+    /// }
+    /// # fs::remove_dir_all(&dir).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`Builder::reload_retries`]: struct.Builder.html#method.reload_retries
     Io(std::io::Error),
+    /// The library doesn't export its `ReloadApi` under any of the names the
+    /// host tried, `RELOAD_API` unless [`Builder::symbol_versions`][] says
+    /// otherwise, so it wasn't built with [`live_reload!`][], or is of a
+    /// generation the host doesn't know.
+    ///
+    /// [`Builder::symbol_versions`]: struct.Builder.html#method.symbol_versions
+    /// [`live_reload!`]: macro.live_reload.html
+    MissingSymbol {
+        /// The names that were tried, in order.
+        tried: Vec<String>,
+    },
     /// An error occurred while creating the filesystem watcher.
     Watch(notify::Error),
-    /// The `Host` type of the host and library don't match.
+    /// The filesystem watcher couldn't be created because the system is out
+    /// of file watches, or of the file descriptors they need.
+    ///
+    /// On Linux, this means inotify's limits were reached, usually because
+    /// other programs, like editors and build tools, are watching many files
+    /// too. They can be raised with the `fs.inotify.max_user_watches` and
+    /// `fs.inotify.max_user_instances` settings of `sysctl`.
+    WatchLimitReached(notify::Error),
+    /// The filesystem watcher reported an error, so changes to the library
+    /// may have been missed.
+    ///
+    /// The watcher has already been replaced with a fresh one by the time
+    /// this is returned, so reloading keeps working. Set a hook with
+    /// [`Builder::on_watch_error`][] to hear about these without failing the
+    /// reload.
+    ///
+    /// [`Builder::on_watch_error`]: struct.Builder.html#method.on_watch_error
+    WatchLost(notify::Error),
+    /// The `Host` type of the host and library don't match, going by the
+    /// fingerprint the library exports. With [`HostCheckMode::Strict`][],
+    /// this is also returned for a library that doesn't export one.
+    ///
+    /// [`HostCheckMode::Strict`]: enum.HostCheckMode.html#variant.Strict
     MismatchedHost,
+    /// The library's `ReloadApi` has a null function pointer, so the library
+    /// is corrupt or was built wrong.
+    InvalidApi {
+        /// The name of the first `ReloadApi` function found to be null.
+        function: &'static str,
+    },
+    /// The library was built against a version of `live-reload` with a
+    /// different `ReloadApi` layout than the host, so none of its function
+    /// pointers can be trusted.
+    AbiMismatch {
+        /// The ABI version the host understands.
+        expected: u32,
+        /// The ABI version the library reported.
+        found: u32,
+    },
+    /// A reload was aborted by the [`on_state_shrink`][] hook because the new
+    /// library's `State` is smaller than the old one's.
+    ///
+    /// [`on_state_shrink`]: struct.Builder.html#method.on_state_shrink
+    StateShrank {
+        /// The size of the old `State` in bytes.
+        old_size: usize,
+        /// The size of the new `State` in bytes.
+        new_size: usize,
+    },
+    /// The library panicked during a call made by the `Reloadable`.
+    ///
+    /// See [`Reloadable::try_update`][] for which calls are guarded.
+    ///
+    /// [`Reloadable::try_update`]: struct.Reloadable.html#method.try_update
+    LibraryPanicked {
+        /// The lifecycle function that panicked.
+        phase: LifecyclePhase,
+        /// The panic message, if the payload was a string.
+        message: String,
+        /// Where the library panicked, if it could be found out.
+        location: Option<PanicLocation>,
+    },
+    /// A snapshot or saved state holds a `State` with a different size than
+    /// the loaded library's, so it can't be restored. This is also returned
+    /// when copying between two `Reloadable`s whose `State`s differ in size.
+    SnapshotMismatch {
+        /// The size in bytes of the `State` being restored into.
+        expected: usize,
+        /// The size in bytes of the `State` being restored from.
+        found: usize,
+    },
+    /// A snapshot was taken on a machine with a different byte order, so the
+    /// integers in its `State` would all read back wrong.
+    ///
+    /// The `State` is saved as raw bytes, so this can't be fixed up when
+    /// restoring. To move state between machines like that, serialize it
+    /// yourself, field by field, in a fixed byte order.
+    EndianMismatch,
+    /// The library was built with a different version of `live-reload`, or
+    /// a different build tag, than the host expects. This is also returned
+    /// by [`Reloadable::load_state_checked`][] for a save taken while a
+    /// library with another build id was loaded.
+    ///
+    /// See [`Builder::build_tag`][] for how to set the expected tag, or turn
+    /// the check off.
+    ///
+    /// [`Reloadable::load_state_checked`]: struct.Reloadable.html#method.load_state_checked
+    /// [`Builder::build_tag`]: struct.Builder.html#method.build_tag
+    BuildIdMismatch {
+        /// The build id the host expects.
+        expected: String,
+        /// The build id the library reported, if it exported one.
+        found: Option<String>,
+    },
+    /// The library was built with a different panic strategy than the host,
+    /// so its panics can't be caught safely.
+    ///
+    /// See [`Builder::check_panic_strategy`][] for why, and how to turn the
+    /// check off.
+    ///
+    /// [`Builder::check_panic_strategy`]: struct.Builder.html#method.check_panic_strategy
+    PanicStrategyMismatch {
+        /// The host's panic strategy.
+        expected: PanicStrategy,
+        /// The library's panic strategy, if it exported one this version of
+        /// `live-reload` knows.
+        found: Option<PanicStrategy>,
+    },
+    /// The library was built with a different profile than the one required
+    /// with [`Builder::require_build_profile`][].
+    ///
+    /// [`Builder::require_build_profile`]: struct.Builder.html#method.require_build_profile
+    ProfileMismatch {
+        /// The profile the host requires.
+        expected: Profile,
+        /// The library's profile, if it exported one this version of
+        /// `live-reload` knows.
+        found: Option<Profile>,
+    },
+    /// The library didn't pass the signature check set with
+    /// [`Builder::verify_signature`][], so it wasn't opened.
+    ///
+    /// [`Builder::verify_signature`]: struct.Builder.html#method.verify_signature
+    SignatureInvalid,
+    /// The new version of the library found the state inconsistent after a
+    /// reload, with the `validate_state` function given to
+    /// [`live_reload!`][].
+    ///
+    /// The state is put back as it was before the new version's `reload`.
+    /// If the previous version of the library was kept, which takes
+    /// [`Builder::retain_old_images`][] (or a library run in-process), it's
+    /// loaded again and `rolled_back` is true. Otherwise, nothing is loaded
+    /// until the next reload, as with any other failed reload.
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`Builder::retain_old_images`]: struct.Builder.html#method.retain_old_images
+    StateValidationFailed {
+        /// Whether the previous version of the library is running again.
+        rolled_back: bool,
+    },
+    /// [`Reloadable::load_state_checked`][] was given a save taken with a
+    /// different `State` layout than the loaded library's.
+    ///
+    /// The layouts come from [`reloadable_state!`][], and are only compared
+    /// when both libraries reported one.
+    ///
+    /// [`Reloadable::load_state_checked`]: struct.Reloadable.html#method.load_state_checked
+    /// [`reloadable_state!`]: macro.reloadable_state.html
+    StateLayoutMismatch {
+        /// The layout hash of the loaded library's `State`.
+        expected: u64,
+        /// The layout hash of the `State` in the save.
+        found: u64,
+    },
+    /// [`Reloadable::transfer_state_to`][] was asked to move a `State` into a
+    /// library that's at a different [`state_version`][].
+    ///
+    /// [`Reloadable::transfer_state_to`]: struct.Reloadable.html#method.transfer_state_to
+    /// [`state_version`]: struct.Reloadable.html#method.state_version
+    StateVersionMismatch {
+        /// The version of the receiving library's `State`.
+        expected: u32,
+        /// The version of the `State` being moved.
+        found: u32,
+    },
+    /// The loaded library's `State` doesn't match the [`StateMirror`][] a
+    /// [`TypedReloadable`][] was given, so the state can't be viewed as one.
+    ///
+    /// [`StateMirror`]: trait.StateMirror.html
+    /// [`TypedReloadable`]: struct.TypedReloadable.html
+    StateLayoutChanged {
+        /// The layout hash of the mirror.
+        expected: u64,
+        /// The layout hash of the loaded library's `State`, if it reported
+        /// one.
+        found: Option<u64>,
+    },
+    /// Reloading failed too many times in a row, so it has stopped, as set
+    /// up with [`Builder::detect_reload_loops`][].
+    ///
+    /// [`Reloadable::reload`][] doesn't try again until the library's
+    /// contents change, or until [`Reloadable::reset_reload_loop`][].
+    ///
+    /// [`Builder::detect_reload_loops`]: struct.Builder.html#method.detect_reload_loops
+    /// [`Reloadable::reload`]: struct.Reloadable.html#method.reload
+    /// [`Reloadable::reset_reload_loop`]: struct.Reloadable.html#method.reset_reload_loop
+    ReloadLoop {
+        /// How many reloads failed.
+        failures: u32,
+        /// The time they all failed within.
+        window: Duration,
+        /// The error from the last failed reload.
+        last: Box<Error>,
+    },
+    /// The library file failed to load before, and hasn't changed since, so
+    /// it wasn't tried again, as set up with
+    /// [`Builder::quarantine_bad_libraries`][].
+    ///
+    /// [`Builder::quarantine_bad_libraries`]: struct.Builder.html#method.quarantine_bad_libraries
+    QuarantinedLibrary {
+        /// The hash of the library's contents.
+        hash: u64,
+    },
+    /// The library reported a `State` larger than the maximum set with
+    /// [`Builder::max_state_size`][], or than the space reserved with
+    /// [`Builder::reserve_state`][] or the [`capacity`][] of a custom store,
+    /// so no buffer was allocated for it.
+    ///
+    /// [`Builder::max_state_size`]: struct.Builder.html#method.max_state_size
+    /// [`Builder::reserve_state`]: struct.Builder.html#method.reserve_state
+    /// [`capacity`]: trait.StateStore.html#method.capacity
+    StateTooLarge {
+        /// The size in bytes the library reported.
+        reported: usize,
+        /// The largest size in bytes that's allowed.
+        max: usize,
+    },
 }
 
 impl From<std::io::Error> for Error {
@@ -54,221 +826,5850 @@ impl From<std::io::Error> for Error {
 
 impl From<notify::Error> for Error {
     fn from(err: notify::Error) -> Error {
-        Error::Watch(err)
+        match err {
+            notify::Error::Io(ref io) if is_watch_limit(io) => Error::WatchLimitReached(err),
+            err => Error::Watch(err),
+        }
     }
 }
 
 impl std::fmt::Display for Error {
     fn fmt(&self, fmt: &mut std::fmt::Formatter) -> Result<(), std::fmt::Error> {
-        write!(fmt, "{:?}", self)
+        match *self {
+            Error::Io(ref err) => write!(fmt, "I/O error: {}", err),
+            Error::Watch(notify::Error::PathNotFound) => write!(
+                fmt,
+                "failed to watch the library for changes: its directory doesn't exist",
+            ),
+            Error::Watch(notify::Error::Io(ref err)) if err.kind() == std::io::ErrorKind::PermissionDenied => write!(
+                fmt,
+                "failed to watch the library for changes: permission to read its directory was denied",
+            ),
+            Error::Watch(ref err) => write!(fmt, "failed to watch the library for changes: {}", err),
+            Error::WatchLimitReached(ref err) => write!(
+                fmt,
+                "failed to watch the library for changes, since the system is out of file watches ({}); \
+                 close other programs that watch files, or on Linux raise fs.inotify.max_user_watches \
+                 and fs.inotify.max_user_instances with sysctl",
+                err,
+            ),
+            Error::WatchLost(ref err) => write!(fmt, "the library's watcher failed and was restarted: {}", err),
+            Error::MismatchedHost => write!(fmt, "mismatch between host and library's Host types"),
+            Error::InvalidApi { function } => write!(fmt, "the library's ReloadApi has a null {} function", function),
+            Error::AbiMismatch { expected, found } => write!(
+                fmt,
+                "the library was built with live-reload ABI version {}, but the host expects {}",
+                found, expected,
+            ),
+            Error::StateShrank { old_size, new_size } => write!(
+                fmt,
+                "reload aborted because the library's State shrank from {} to {} bytes",
+                old_size, new_size,
+            ),
+            Error::LibraryPanicked { phase, ref message, location: Some(ref location) } => {
+                write!(fmt, "the library's {} panicked at {}: {}", phase, location, message)
+            }
+            Error::LibraryPanicked { phase, ref message, location: None } => {
+                write!(fmt, "the library's {} panicked: {}", phase, message)
+            }
+            Error::SnapshotMismatch { expected, found } => write!(
+                fmt,
+                "the snapshot holds a State of {} bytes, but the library's State is {} bytes",
+                found, expected,
+            ),
+            Error::EndianMismatch => write!(
+                fmt,
+                "the snapshot was taken on a machine with a different byte order",
+            ),
+            Error::BuildIdMismatch { ref expected, found: Some(ref found) } => write!(
+                fmt,
+                "the library has build id {}, but the host expects {}",
+                found, expected,
+            ),
+            Error::BuildIdMismatch { ref expected, found: None } => write!(
+                fmt,
+                "the library has no build id, but the host expects {}",
+                expected,
+            ),
+            Error::PanicStrategyMismatch { expected, found: Some(found) } => write!(
+                fmt,
+                "the library was built with panic = \"{}\", but the host with panic = \"{}\"",
+                found, expected,
+            ),
+            Error::PanicStrategyMismatch { expected, found: None } => write!(
+                fmt,
+                "the library has no known panic strategy, but the host was built with panic = \"{}\"",
+                expected,
+            ),
+            Error::ProfileMismatch { expected, found: Some(found) } => write!(
+                fmt,
+                "the library is a {} build, but the host requires a {} build",
+                found, expected,
+            ),
+            Error::ProfileMismatch { expected, found: None } => write!(
+                fmt,
+                "the library has no known build profile, but the host requires a {} build",
+                expected,
+            ),
+            Error::MissingSymbol { ref tried } => write!(
+                fmt,
+                "the library exports none of the symbols {}, so it wasn't built with live_reload! for this host",
+                tried.join(", "),
+            ),
+            Error::SignatureInvalid => write!(
+                fmt,
+                "the library doesn't match its signature, so it wasn't loaded",
+            ),
+            Error::StateValidationFailed { rolled_back: true } => write!(
+                fmt,
+                "the reloaded library found its State invalid, so the previous version was restored",
+            ),
+            Error::StateValidationFailed { rolled_back: false } => write!(
+                fmt,
+                "the reloaded library found its State invalid, and no previous version was kept to go back to",
+            ),
+            Error::StateLayoutMismatch { expected, found } => write!(
+                fmt,
+                "the snapshot holds a State with layout {:016x}, but the library's State has layout {:016x}",
+                found, expected,
+            ),
+            Error::StateVersionMismatch { expected, found } => write!(
+                fmt,
+                "the State being moved is at version {}, but the library's State is at version {}",
+                found, expected,
+            ),
+            Error::StateLayoutChanged { expected, found: Some(found) } => write!(
+                fmt,
+                "the library's State has layout {:016x}, but the host's mirror of it has layout {:016x}",
+                found, expected,
+            ),
+            Error::StateLayoutChanged { expected, found: None } => write!(
+                fmt,
+                "the library doesn't report its State's layout, so it can't be checked against the host's mirror, with layout {:016x}",
+                expected,
+            ),
+            Error::ReloadLoop { failures, window, ref last } => write!(
+                fmt,
+                "reloading failed {} times within {:?}, so it has stopped until the library changes: {}",
+                failures, window, last,
+            ),
+            Error::QuarantinedLibrary { hash } => write!(
+                fmt,
+                "the library failed to load before, and is quarantined until it changes (contents {:016x})",
+                hash,
+            ),
+            Error::StateTooLarge { reported, max } => write!(
+                fmt,
+                "the library's State is {} bytes, which is more than the maximum of {} bytes",
+                reported, max,
+            ),
+        }
     }
 }
 
 impl std::error::Error for Error {
-    fn description(&self) -> &str {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match *self {
-            Error::Io(ref err) => err.description(),
-            Error::Watch(ref err) => err.description(),
-            Error::MismatchedHost => "mismatch between host and library's Host types",
+            Error::Io(ref err) => Some(err),
+            Error::Watch(ref err) |
+            Error::WatchLimitReached(ref err) |
+            Error::WatchLost(ref err) => Some(err),
+            Error::ReloadLoop { ref last, .. } => Some(&**last),
+            Error::MismatchedHost |
+            Error::InvalidApi { .. } |
+            Error::AbiMismatch { .. } |
+            Error::StateShrank { .. } |
+            Error::LibraryPanicked { .. } |
+            Error::SnapshotMismatch { .. } |
+            Error::EndianMismatch |
+            Error::BuildIdMismatch { .. } |
+            Error::PanicStrategyMismatch { .. } |
+            Error::ProfileMismatch { .. } |
+            Error::MissingSymbol { .. } |
+            Error::SignatureInvalid |
+            Error::StateValidationFailed { .. } |
+            Error::StateLayoutMismatch { .. } |
+            Error::StateVersionMismatch { .. } |
+            Error::StateLayoutChanged { .. } |
+            Error::QuarantinedLibrary { .. } |
+            Error::StateTooLarge { .. } => None,
         }
     }
 }
 
-impl<Host> AppSym<Host> {
-    fn new<P: AsRef<Path>>(path: P) -> Result<Self, Error> {
-        let library = Library::new(path.as_ref())?;
-        let api = unsafe {
-            library
-                .get::<*mut internals::ReloadApi<Host>>(b"RELOAD_API")?
-                .into_raw()
-        };
-        Ok(AppSym {
-            _lib: library,
-            api: api,
-        })
-    }
+/// The storage backing the `State` of a `Reloadable`.
+enum StateBuffer {
+    Store(Box<dyn StateStore + Send>),
+    #[cfg(all(feature = "mmap", unix))]
+    Mapped(MappedState),
+    #[cfg(all(feature = "shm", unix))]
+    Shared(SharedState),
+    /// The stand-in for the `State` of a `MockLibrary`, which has none. Its
+    /// lifecycle functions are handed this pointer to the mock instead, so
+    /// nothing that writes the `State` can reach it.
+    #[cfg(feature = "testing")]
+    Mock(*mut ()),
 }
 
-impl<Host> Reloadable<Host> {
-    /// Create a new Reloadable library.
-    ///
-    /// This takes the path to a dynamic library containing a `RELOAD_API`
-    /// symbol that exports the functions needed for live reloading. In order to
-    /// define this symbol in your own reloadable libraries, see the
-    /// [`live_reload!`][] macro. This will load the library and initialize a
-    /// filesystem watcher pointing to the file in order to know when the
-    /// library has changed.
-    ///
-    /// [`live_reload!`]: macro.live_reload.html
-    pub fn new<P: AsRef<Path>>(path: P, host: Host) -> Result<Self, Error> {
-        let sym = AppSym::new(&path)?;
-        let size = (unsafe { &**sym.api }.size)();
-        let (tx, rx) = channel();
-        let mut watcher = notify::watcher(tx, Duration::from_secs(1))?;
-        let mut new_path = PathBuf::new();
-        new_path.push(path);
-        watcher.watch(
-            new_path.parent().unwrap(),
-            notify::RecursiveMode::NonRecursive,
-        )?;
-        let mut app = Reloadable {
-            path: new_path.canonicalize()?,
-            sym: Some(sym),
-            state: Vec::new(),
-            _watcher: watcher,
-            rx: rx,
-            host: host,
-        };
-        app.realloc_buffer(size);
-        if let Some(AppSym { ref mut api, .. }) = app.sym {
-            (unsafe { &***api }.init)(&mut app.host, Self::get_state_ptr(&mut app.state));
-        }
-        Ok(app)
-    }
+/// The alignment every `State` buffer provides.
+const STATE_ALIGN: usize = 8;
 
-    /// Reload the library if it has changed, otherwise do nothing.
-    ///
-    /// This will consult with the filesystem watcher, and if the library has
-    /// been recreated or updated, it will reload the library. See
-    /// [`reload_now`][] for details on what happens when a library is reloaded.
+impl StateBuffer {
+    /// Grow or shrink the buffer to hold at least `size` bytes.
     ///
-    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
-    pub fn reload(&mut self) -> Result<(), Error> {
-        let mut should_reload = false;
-        while let Ok(evt) = self.rx.try_recv() {
-            use notify::DebouncedEvent::*;
-            match evt {
-                NoticeWrite(ref path) |
-                Write(ref path) |
-                Create(ref path) => {
-                    if *path == self.path {
-                        should_reload = true;
+    /// A store with a fixed capacity refuses anything bigger with
+    /// `Error::StateTooLarge`, and leaves the buffer as it was.
+    fn resize(&mut self, size: usize) -> Result<(), Error> {
+        match *self {
+            StateBuffer::Store(ref mut store) => {
+                if let Some(max) = store.capacity() {
+                    if size > max {
+                        return Err(Error::StateTooLarge { reported: size, max });
                     }
                 }
-                _ => {}
+                store.ensure(size, STATE_ALIGN);
+            }
+            #[cfg(all(feature = "mmap", unix))]
+            StateBuffer::Mapped(ref mut map) => map.resize(size.div_ceil(8))?,
+            #[cfg(all(feature = "shm", unix))]
+            StateBuffer::Shared(ref mut shared) => shared.resize(size.div_ceil(8))?,
+            #[cfg(feature = "testing")]
+            StateBuffer::Mock(_) => {
+                if size > 0 {
+                    return Err(Error::StateTooLarge { reported: size, max: 0 });
+                }
             }
         }
+        Ok(())
+    }
 
-        if should_reload || self.sym.is_none() {
-            self.reload_now()
-        } else {
-            Ok(())
+    fn as_mut_ptr(&mut self) -> *mut () {
+        #[cfg(feature = "testing")]
+        {
+            if let StateBuffer::Mock(mock) = *self {
+                return mock;
+            }
+        }
+        // An empty buffer has nothing to point at, and a store is free to
+        // hand out anything for it, even null. A zero-sized `State` still
+        // needs a pointer that's non-null and aligned, so it gets a dangling
+        // one, whichever kind of buffer it's in.
+        if self.as_bytes().is_empty() {
+            return std::ptr::NonNull::<u64>::dangling().as_ptr() as *mut ();
+        }
+        match *self {
+            StateBuffer::Store(ref mut store) => store.ptr(),
+            #[cfg(all(feature = "mmap", unix))]
+            StateBuffer::Mapped(ref mut map) => map.as_mut_ptr() as *mut (),
+            #[cfg(all(feature = "shm", unix))]
+            StateBuffer::Shared(ref mut shared) => shared.as_mut_ptr() as *mut (),
+            #[cfg(feature = "testing")]
+            StateBuffer::Mock(mock) => mock,
         }
     }
 
-    /// Immediately reload the library without checking whether it has changed.
-    ///
-    /// This first calls `unload` on the currently loaded library, then unloads
-    /// the dynamic library. Next, it loads the new dynamic library, and calls
-    /// `reload` on that. If the new library fails to load, this method will
-    /// return an `Err` and the `Reloadable` will be left with no library
-    /// loaded.
-    ///
-    /// [`update`]: struct.Reloadable.html#method.update
-    pub fn reload_now(&mut self) -> Result<(), Error> {
-        if let Some(AppSym { ref mut api, .. }) = self.sym {
-            (unsafe { &***api }.unload)(&mut self.host, Self::get_state_ptr(&mut self.state));
+    /// View the whole buffer, including any padding at the end, as bytes.
+    fn as_bytes(&self) -> &[u8] {
+        match *self {
+            StateBuffer::Store(ref store) => store.bytes(),
+            #[cfg(all(feature = "mmap", unix))]
+            StateBuffer::Mapped(ref map) => units_as_bytes(map.as_slice()),
+            #[cfg(all(feature = "shm", unix))]
+            StateBuffer::Shared(ref shared) => units_as_bytes(shared.as_slice()),
+            #[cfg(feature = "testing")]
+            StateBuffer::Mock(_) => &[],
         }
-        self.sym = None;
-        let sym = AppSym::new(&self.path)?;
-        // @Avoid reallocating if unnecessary
-        self.realloc_buffer((unsafe { &**sym.api }.size)());
-        (unsafe { &**sym.api }.reload)(&mut self.host, Self::get_state_ptr(&mut self.state));
-        self.sym = Some(sym);
+    }
 
-        Ok(())
+    /// View the whole buffer, including any padding at the end, as mutable bytes.
+    fn as_mut_bytes(&mut self) -> &mut [u8] {
+        let len = self.as_bytes().len();
+        unsafe { std::slice::from_raw_parts_mut(self.as_mut_ptr() as *mut u8, len) }
     }
 
-    /// Call the update method on the library.
-    ///
-    /// If no library is currently loaded, this does nothing and returns
-    /// [`ShouldQuit::No`](enum.ShouldQuit.html#).
-    pub fn update(&mut self) -> ShouldQuit {
-        if let Some(AppSym { ref mut api, .. }) = self.sym {
-            (unsafe { &***api }.update)(&mut self.host, Self::get_state_ptr(&mut self.state))
-        } else {
-            ShouldQuit::No
-        }
+    /// Copy the contents of the buffer into whole `u64`s.
+    fn to_units(&self) -> Vec<u64> {
+        let mut units = Vec::new();
+        self.copy_units_into(&mut units);
+        units
     }
 
-    /// Reallocate the buffer used to store the `State`.
-    fn realloc_buffer(&mut self, size: usize) {
-        let alloc_size_u64s = (size + 7) / 8;
-        self.state.resize(alloc_size_u64s, 0);
+    /// Copy the contents of the buffer into whole `u64`s in `units`, reusing
+    /// its allocation.
+    fn copy_units_into(&self, units: &mut Vec<u64>) {
+        let bytes = self.as_bytes();
+        units.clear();
+        units.resize(bytes.len().div_ceil(8), 0);
+        unsafe {
+            std::ptr::copy_nonoverlapping(bytes.as_ptr(), units.as_mut_ptr() as *mut u8, bytes.len());
+        }
     }
 
-    /// Get a void pointer to the `State` buffer.
-    fn get_state_ptr(buffer: &mut Vec<u64>) -> *mut () {
-        buffer.as_mut_ptr() as *mut ()
+    /// Replace the contents of the buffer with `saved`.
+    ///
+    /// Growing the buffer is the only step that can fail, and it keeps the
+    /// existing contents, so on error the buffer is left as it was.
+    fn load(&mut self, saved: &[u64]) -> Result<(), Error> {
+        self.resize(saved.len() * 8)?;
+        let saved = unsafe { std::slice::from_raw_parts(saved.as_ptr() as *const u8, saved.len() * 8) };
+        // The buffer might be larger than the save, so zero whatever the save
+        // doesn't cover, the same as a buffer that was truncated and regrown.
+        let (head, tail) = self.as_mut_bytes().split_at_mut(saved.len());
+        head.copy_from_slice(saved);
+        for byte in tail {
+            *byte = 0;
+        }
+        Ok(())
     }
+}
 
-    /// Get a reference to the `Host` struct>
-    pub fn host(&self) -> &Host { &self.host }
+/// Get the modification time of the file at `path`, if it can be read.
+pub(crate) fn file_mtime(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
 
-    /// Get a mutable reference to the `Host` struct.
-    pub fn host_mut(&mut self) -> &mut Host { &mut self.host }
+/// The size of the file at `path`, or `None` if it's empty or missing.
+fn file_size(path: &Path) -> Option<u64> {
+    std::fs::metadata(path).map(|metadata| metadata.len()).ok().filter(|&len| len > 0)
+}
 
-    /// Save a copy of the state
-    pub fn save_state(&self) -> SaveState {
-        SaveState { state: self.state.clone() }
+/// Hash the contents of the files at `paths` together, if they can all be
+/// read.
+fn files_hash(paths: &[&Path]) -> Option<u64> {
+    use std::hash::{Hash, Hasher};
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for path in paths {
+        std::fs::read(path).ok()?.hash(&mut hasher);
     }
+    Some(hasher.finish())
+}
 
-    /// Load a copy of the state
-    pub fn load_state(&mut self, state: &SaveState) {
-        self.state.clear();
-        self.state.extend_from_slice(state.state.as_slice());
+/// The directory that's watched for changes to the library at `path`.
+///
+/// This is the library's parent, or the working directory for a path that
+/// has none, like a bare file name or the root.
+pub(crate) fn watched_dir(path: &Path) -> &Path {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
     }
 }
 
-/// A saved copy of the state
-pub struct SaveState {
-    state: Vec<u64>,
+/// Whether `err` says the system ran out of file watches, or of the file
+/// descriptors they need.
+fn is_watch_limit(err: &std::io::Error) -> bool {
+    #[cfg(unix)]
+    {
+        let limits = [::libc::ENOSPC, ::libc::EMFILE, ::libc::ENFILE];
+        err.raw_os_error().is_some_and(|code| limits.contains(&code))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = err;
+        false
+    }
 }
 
-impl<Host> Drop for Reloadable<Host> {
-    fn drop(&mut self) {
-        if let Some(AppSym { ref mut api, .. }) = self.sym {
-            unsafe {
-                ((***api).deinit)(&mut self.host, Self::get_state_ptr(&mut self.state));
-            }
+/// Read the build id `library` exported, if it did.
+///
+/// This is unsafe, since it trusts that a symbol by that name is a `BuildId`.
+unsafe fn read_build_id(library: &Library) -> Option<internals::BuildId> {
+    library
+        .get::<*const internals::BuildId>(b"RELOAD_BUILD_ID")
+        .ok()
+        .map(|id| **id)
+}
+
+/// Read the layout hash `library` exported, if it did.
+///
+/// This is unsafe, since it trusts that a symbol by that name is a `u64`.
+unsafe fn read_state_layout(library: &Library) -> Option<u64> {
+    library
+        .get::<*const u64>(b"RELOAD_STATE_LAYOUT")
+        .ok()
+        .map(|layout| **layout)
+        .filter(|&layout| layout != 0)
+}
+
+/// Does `err` mean that the library file itself is bad, rather than the one
+/// it was replacing, or the host?
+fn is_bad_library(err: &Error) -> bool {
+    matches!(
+        *err,
+        Error::Io(_)
+            | Error::InvalidApi { .. }
+            | Error::AbiMismatch { .. }
+            | Error::MismatchedHost
+            | Error::BuildIdMismatch { .. }
+            | Error::PanicStrategyMismatch { .. }
+            | Error::ProfileMismatch { .. }
+            | Error::MissingSymbol { .. }
+            | Error::SignatureInvalid
+            | Error::StateValidationFailed { .. }
+            | Error::StateTooLarge { .. }
+            | Error::LibraryPanicked { phase: LifecyclePhase::Reload, .. }
+    )
+}
+
+/// Check that a `State` with the layout hash `found` fits one with the hash
+/// `expected`, when both have one.
+fn check_layout(expected: Option<u64>, found: Option<u64>) -> Result<(), Error> {
+    match (expected, found) {
+        (Some(expected), Some(found)) if expected != found => {
+            Err(Error::StateLayoutMismatch { expected, found })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Load the bytes of `save` into `state`, for a library whose `State` has
+/// `size` and `layout`, panicking like `Reloadable::load_state` if it
+/// doesn't fit.
+fn load_fitting(state: &mut StateBuffer, size: usize, layout: Option<u64>, save: &SaveState) {
+    let fits = check_layout(layout, save.state_layout).and_then(|()| {
+        if save.size != size {
+            return Err(Error::SnapshotMismatch { expected: size, found: save.size });
         }
+        state.load(&save.state)
+    });
+    if let Err(error) = fits {
+        panic!("failed to load the state: {}", error);
     }
 }
 
-/// Exported for compilation reasons but not useful, only look if you're curious.
+/// Read the fingerprint of the `Host` that `library` exported, if it did.
 ///
-/// This module holds to the `ReloadApi` struct, which is what what is looked up
-/// by the `Reloadable` in order to communicate with the reloadable library. It
-/// needs to be exported in order to avoid forcing the type definition into the
-/// pub symbols of the wrapped library. An instance of `ReloadApi` called
-/// `RELOAD_API` is generated by the [`live_reload!`][] macro.
+/// This is unsafe, since it trusts that a symbol by that name is a `u64`.
+unsafe fn read_host_hash(library: &Library) -> Option<u64> {
+    library
+        .get::<*const u64>(b"RELOAD_HOST_HASH")
+        .ok()
+        .map(|hash| **hash)
+}
+
+/// Check that `library` exported the same panic strategy the host was built
+/// with.
 ///
-/// [`live_reload!`]: ../macro.live_reload.html
-pub mod internals {
-    /// Contains function pointers for all the parts of the reloadable object lifecycle.
-    #[repr(C)]
-    pub struct ReloadApi<Host> {
-        /// Returns the size of the State struct so that the host can allocate
-        /// space for it.
-        pub size: fn() -> usize,
-        /// Initializes the State struct when the program is first started.
-        pub init: fn(&mut Host, *mut ()),
-        /// Makes any necessary updates when the program is reloaded.
-        ///
-        /// This will probably be normally empty. If you changed the State
-        /// struct since the last compile, then it won't necessarily be
-        /// correctly initialized. For safety, you should make your State struct
-        /// `#[repr(C)]` and only add members at the end.
-        pub reload: fn(&mut Host, *mut ()),
-        /// Update the
-        pub update: fn(&mut Host, *mut ()) -> super::ShouldQuit,
-        /// Prepare for the library to be unloaded before a new version loads.
-        ///
-        /// This will probably normally be empty except for short periods in
-        /// development when you're making lots of live changes and need to do
-        /// some kind of migration.
-        pub unload: fn(&mut Host, *mut ()),
-        /// Do final shutdowns before the program completely quits.
-        pub deinit: fn(&mut Host, *mut ()),
+/// This is unsafe, since it trusts that a symbol by that name is a `u8`.
+pub(crate) unsafe fn check_panic_strategy(library: &Library) -> Result<(), Error> {
+    let expected = PanicStrategy::current();
+    let found = library
+        .get::<*const u8>(b"RELOAD_PANIC_STRATEGY")
+        .ok()
+        .and_then(|raw| PanicStrategy::from_raw(**raw));
+    if found != Some(expected) {
+        return Err(Error::PanicStrategyMismatch { expected, found });
+    }
+    Ok(())
+}
+
+/// Read the build profile `library` exported, if it did and it's one this
+/// version knows.
+///
+/// This is unsafe, since it trusts that a symbol by that name is a `u8`.
+unsafe fn read_profile(library: &Library) -> Option<Profile> {
+    library
+        .get::<*const u8>(b"RELOAD_BUILD_PROFILE")
+        .ok()
+        .and_then(|raw| Profile::from_raw(**raw))
+}
+
+impl<Host> AppSym<Host> {
+    pub(crate) fn new<P: AsRef<Path>>(path: P, config: &Config<Host>) -> Result<Self, Error> {
+        let mut companions = Vec::with_capacity(config.companions.len());
+        for companion in &config.companions {
+            let (lib, copy, _) = Self::open(companion, config)?;
+            companions.insert(0, Companion { _lib: lib, _copy: copy });
+        }
+        let (library, copy, file) = Self::open(path.as_ref(), config)?;
+        let mut sym = Self::adopt(library, copy, config)?;
+        sym.file = Some(file);
+        sym.companions = companions;
+        Ok(sym)
+    }
+
+    /// Open the library file at `path`, copying and verifying it first as
+    /// configured, and return it with its copy and the file it came from.
+    fn open(path: &Path, config: &Config<Host>) -> Result<(Library, Option<TempCopy>, PathBuf), Error> {
+        complete::check_complete(path)?;
+        let copy = match config.copy {
+            Some(ref copy) => Some(TempCopy::create(path, copy)?),
+            None => None,
+        };
+        #[cfg(feature = "verify")]
+        {
+            if let Some(ref verifier) = config.verifier {
+                let loaded = copy.as_ref().map_or(path, |copy| copy.path());
+                if !verifier.verify(loaded)? {
+                    return Err(Error::SignatureInvalid);
+                }
+            }
+        }
+        let file = copy.as_ref().map_or(path, |copy| copy.path()).to_path_buf();
+        let library = open_flags::open(&file, config.open_flags)?;
+        Ok((library, copy, file))
+    }
+
+    /// Read the `ReloadApi` from a library that's already open, and check it
+    /// like `new` does.
+    fn adopt(library: Library, copy: Option<TempCopy>, config: &Config<Host>) -> Result<Self, Error> {
+        let (api, api_symbol) = config.api_symbols.iter()
+            .find_map(|name| {
+                let api = unsafe { library.get::<*mut internals::ReloadApi<Host>>(name.as_bytes()).ok()? };
+                Some((unsafe { api.into_raw() }, name.clone()))
+            })
+            .ok_or_else(|| Error::MissingSymbol { tried: config.api_symbols.clone() })?;
+        // Only the leading `abi_version` is at a known offset until we've
+        // checked that it matches, so don't touch any other field before that.
+        let found = unsafe { (**api).abi_version };
+        if found != internals::ABI_VERSION {
+            return Err(Error::AbiMismatch {
+                expected: internals::ABI_VERSION,
+                found,
+            });
+        }
+        unsafe { internals::ReloadApi::validate(*api)? };
+        let found = unsafe { read_build_id(&library) };
+        let host_hash = unsafe { read_host_hash(&library) };
+        let host_matches = match host_hash {
+            Some(hash) => hash == internals::host_hash::<Host>(),
+            None => config.host_check != HostCheckMode::Strict,
+        };
+        if config.host_check != HostCheckMode::Off && !host_matches {
+            return Err(Error::MismatchedHost);
+        }
+        if config.check_panic_strategy {
+            unsafe { check_panic_strategy(&library)? };
+        }
+        let profile = unsafe { read_profile(&library) };
+        if let Some(expected) = config.required_profile {
+            if profile != Some(expected) {
+                return Err(Error::ProfileMismatch { expected, found: profile });
+            }
+        }
+        let state_layout = unsafe { read_state_layout(&library) };
+        if config.check_build_id {
+            let expected = internals::BuildId {
+                version: internals::VERSION,
+                tag: config.build_tag,
+            };
+            if found != Some(expected) {
+                return Err(Error::BuildIdMismatch {
+                    expected: expected.to_string(),
+                    found: found.map(|id| id.to_string()),
+                });
+            }
+        }
+        Ok(AppSym {
+            lib: Some(library),
+            companions: Vec::new(),
+            api: Api::Symbol(api),
+            build_id: found,
+            state_layout,
+            profile,
+            api_symbol: Some(api_symbol),
+            file: None,
+            _copy: copy,
+        })
+    }
+
+    #[cfg(feature = "in-process")]
+    fn in_process(api: internals::ReloadApi<Host>) -> Self {
+        AppSym {
+            lib: None,
+            companions: Vec::new(),
+            api: Api::InProcess(api),
+            build_id: None,
+            state_layout: None,
+            profile: Some(Profile::current()),
+            api_symbol: None,
+            file: None,
+            _copy: None,
+        }
+    }
+
+    fn shared(shared: Arc<AppSym<Host>>) -> Self {
+        AppSym {
+            lib: None,
+            companions: Vec::new(),
+            api: Api::Shared(shared),
+            build_id: None,
+            state_layout: None,
+            profile: None,
+            api_symbol: None,
+            file: None,
+            _copy: None,
+        }
+    }
+
+    /// Get the lifecycle functions of the library.
+    fn api(&self) -> &internals::ReloadApi<Host> {
+        match self.api {
+            // The symbol stays valid for as long as `lib` keeps the library
+            // open, and we checked its ABI version when loading it.
+            Api::Symbol(ref sym) => unsafe { &***sym },
+            #[cfg(feature = "in-process")]
+            Api::InProcess(ref api) => api,
+            Api::Shared(ref shared) => shared.api(),
+        }
+    }
+
+    /// The file the library was loaded from, if it came from one.
+    fn file(&self) -> Option<&Path> {
+        match self.api {
+            Api::Shared(ref shared) => shared.file(),
+            _ => self.file.as_deref(),
+        }
+    }
+
+    /// Get the dynamic library, if there is one.
+    fn library(&self) -> Option<&Library> {
+        match self.api {
+            Api::Shared(ref shared) => shared.library(),
+            _ => self.lib.as_ref(),
+        }
+    }
+
+    /// Get the build id the library exported, if it did.
+    fn build_id(&self) -> Option<internals::BuildId> {
+        match self.api {
+            Api::Shared(ref shared) => shared.build_id(),
+            _ => self.build_id,
+        }
+    }
+
+    /// Get the layout hash the library exported, if it did.
+    fn state_layout(&self) -> Option<u64> {
+        match self.api {
+            Api::Shared(ref shared) => shared.state_layout(),
+            _ => self.state_layout,
+        }
+    }
+
+    /// Get the profile the library was built with, if it's known.
+    fn profile(&self) -> Option<Profile> {
+        match self.api {
+            Api::Shared(ref shared) => shared.profile(),
+            _ => self.profile,
+        }
+    }
+
+    /// Get the name the `ReloadApi` was found under, for a library file.
+    fn api_symbol(&self) -> Option<&str> {
+        match self.api {
+            Api::Shared(ref shared) => shared.api_symbol(),
+            _ => self.api_symbol.as_deref(),
+        }
+    }
+}
+
+impl<Host> Reloadable<Host> {
+    /// Create a new Reloadable library.
+    ///
+    /// This takes the path to a dynamic library containing a `RELOAD_API`
+    /// symbol that exports the functions needed for live reloading. In order to
+    /// define this symbol in your own reloadable libraries, see the
+    /// [`live_reload!`][] macro. This will load the library and initialize a
+    /// filesystem watcher pointing to the file in order to know when the
+    /// library has changed.
+    ///
+    /// If the library doesn't exist yet, say because the host was started
+    /// before the first build finished, this still succeeds, with nothing
+    /// loaded (see [`is_loaded`][]). The directory it will appear in is
+    /// watched as usual, and the first [`reload`][] after it appears loads
+    /// it, calling `init` as if it had been there all along. The directory
+    /// itself has to exist.
+    ///
+    /// A relative path is taken from the working directory, so a bare file
+    /// name watches the working directory. If the watcher can't be created,
+    /// this fails with [`Error::Watch`][], or with
+    /// [`Error::WatchLimitReached`][] if the system is out of file watches.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # if cfg!(miri) { return; } // Miri can't watch files.
+    /// use live_reload::Reloadable;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("live-reload-bare-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// let cwd = std::env::current_dir().unwrap();
+    /// std::env::set_current_dir(&dir).unwrap();
+    ///
+    /// let app = Reloadable::new("libnot_built_yet.so", ()).unwrap();
+    /// assert!(!app.is_loaded());
+    /// # drop(app);
+    /// std::env::set_current_dir(&cwd).unwrap();
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// The path is kept as a `Path` all the way through, including in the
+    /// names of temporary copies, so it doesn't have to be valid UTF-8, as
+    /// long as the platform can open it. Only error messages show it with
+    /// the bytes that aren't UTF-8 replaced.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # #[cfg(not(unix))] fn main() {}
+    /// # #[cfg(unix)]
+    /// # fn main() {
+    /// # if cfg!(miri) { return; } // Miri can't watch files.
+    /// use std::ffi::OsStr;
+    /// use std::os::unix::ffi::OsStrExt;
+    /// use live_reload::Reloadable;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("live-reload-utf8-{}", std::process::id()));
+    /// let dir = dir.join(OsStr::from_bytes(b"caf\xe9"));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// let path = dir.join(OsStr::from_bytes(b"lib\xffgame.so"));
+    ///
+    /// let mut app = Reloadable::new(&path, ()).unwrap();
+    /// std::fs::write(&path, [0x7f; 4096]).unwrap();
+    /// let err = app.reload_now().unwrap_err();
+    /// assert!(err.to_string().contains("caf\u{fffd}/lib\u{fffd}game.so"));
+    /// # drop(app);
+    /// # std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// To change any of the default options, use a [`Builder`][] instead.
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`Builder`]: struct.Builder.html
+    /// [`is_loaded`]: struct.Reloadable.html#method.is_loaded
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`Error::Watch`]: enum.Error.html#variant.Watch
+    /// [`Error::WatchLimitReached`]: enum.Error.html#variant.WatchLimitReached
+    pub fn new<P: AsRef<Path>>(path: P, host: Host) -> Result<Self, Error> {
+        Builder::new(path).build(host)
+    }
+
+    /// Load the library at `path` without watching it for changes.
+    ///
+    /// This is [`new`][] without the filesystem watcher and the thread that
+    /// feeds it, for benchmarks and tests that load a library once and run
+    /// it, and shouldn't pay for or be disturbed by the watcher. [`reload`][]
+    /// does nothing, so [`reload_now`][] is the only way to reload. See
+    /// [`Builder::headless`][] to combine this with other options.
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use std::time::Instant;
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_headless("target/release/libgame.so", Host).unwrap();
+    /// let started = Instant::now();
+    /// for _ in 0..10_000 {
+    ///     app.update();
+    /// }
+    /// println!("{:?} per update", started.elapsed() / 10_000);
+    /// # }
+    /// ```
+    ///
+    /// [`new`]: struct.Reloadable.html#method.new
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    /// [`Builder::headless`]: struct.Builder.html#method.headless
+    pub fn new_headless<P: AsRef<Path>>(path: P, host: Host) -> Result<Self, Error> {
+        Builder::new(path).headless(true).build(host)
+    }
+
+    /// Load a library without starting it, to read and edit its state.
+    ///
+    /// This loads the library and sizes the `State` buffer like [`new`][],
+    /// but never calls `init`, so nothing the library does on start up
+    /// happens. The state stays zeroed until it's filled in with
+    /// [`load_state`][], [`restore_from_path`][] or the like, and can be read
+    /// back with [`save_state`][] or [`state_bytes`][], while
+    /// [`library_info`][] describes the library. That makes this the mode for
+    /// tools like save file editors, which need the library's `State` but
+    /// must not run it.
+    ///
+    /// [`update`][] does nothing and returns `ShouldQuit::No`, rather than
+    /// calling `init` like it does after [`Builder::lazy_init`][], and
+    /// reloads swap the library without calling `unload` or `reload`. Call
+    /// [`ensure_init`][] to leave this mode and start the library; from then
+    /// on, it runs like any other. Dropping an inert `Reloadable` doesn't
+    /// call `deinit`.
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::{Reloadable, ShouldQuit};
+    ///
+    /// let mut app = Reloadable::open_inert("target/debug/libgame.so", Host).unwrap();
+    /// app.restore_from_path("game.snapshot").unwrap();
+    /// println!("{:?}", app.state_bytes());
+    /// assert_eq!(app.update(), ShouldQuit::No);
+    /// # }
+    /// ```
+    ///
+    /// [`new`]: struct.Reloadable.html#method.new
+    /// [`load_state`]: struct.Reloadable.html#method.load_state
+    /// [`restore_from_path`]: struct.Reloadable.html#method.restore_from_path
+    /// [`save_state`]: struct.Reloadable.html#method.save_state
+    /// [`state_bytes`]: struct.Reloadable.html#method.state_bytes
+    /// [`library_info`]: struct.Reloadable.html#method.library_info
+    /// [`update`]: struct.Reloadable.html#method.update
+    /// [`Builder::lazy_init`]: struct.Builder.html#method.lazy_init
+    /// [`ensure_init`]: struct.Reloadable.html#method.ensure_init
+    pub fn open_inert<P: AsRef<Path>>(path: P, host: Host) -> Result<Self, Error> {
+        let mut app = Builder::new(path).lazy_init(true).build(host)?;
+        app.config.lazy_init = false;
+        app.inert = true;
+        Ok(app)
+    }
+
+    /// Start building a Reloadable library with non-default options.
+    ///
+    /// This is the same as [`Builder::new`](struct.Builder.html#method.new).
+    pub fn builder<P: AsRef<Path>>(path: P) -> Builder<Host> {
+        Builder::new(path)
+    }
+
+    /// Create a new Reloadable library whose `State` lives in a memory-mapped
+    /// file.
+    ///
+    /// This behaves like [`new`][], except that the `State` buffer is a
+    /// `MAP_SHARED` mapping of `state_file` (see [`MappedState`][]), which is
+    /// created if it doesn't exist and grown whenever the library reports a
+    /// larger `State`. Since every write the library makes goes straight to
+    /// the file, the state survives the host crashing, and a host restarted
+    /// with the same `state_file` picks up where it left off.
+    ///
+    /// If `state_file` already holds some state, the library's `reload` is
+    /// called on it instead of `init`, so that the recovered state isn't
+    /// clobbered. A new or empty file gets `init` as usual.
+    ///
+    /// There are a few caveats to keep in mind:
+    ///
+    /// - The mapping is page-aligned, so any `State` with an alignment of up
+    ///   to the page size is fine.
+    /// - The state reaches the disk when the OS gets around to it. It is
+    ///   flushed after `deinit` when the `Reloadable` is dropped, but a power
+    ///   loss can still lose recent writes.
+    /// - The file holds the raw bytes of the `State`, so it's only valid for a
+    ///   library with the same `State` layout. Pointers stored in the state
+    ///   will dangle after a restart.
+    ///
+    /// This is only available on Unix with the `mmap` feature enabled.
+    ///
+    /// [`new`]: struct.Reloadable.html#method.new
+    /// [`MappedState`]: struct.MappedState.html
+    #[cfg(all(feature = "mmap", unix))]
+    pub fn new_mmap<P, S>(path: P, host: Host, state_file: S) -> Result<Self, Error>
+    where
+        P: AsRef<Path>,
+        S: AsRef<Path>,
+    {
+        let mapped = MappedState::open(state_file)?;
+        Self::with_buffer(path, host, StateBuffer::Mapped(mapped), Config::default())
+    }
+
+    /// Create a new Reloadable library whose `State` lives in a named
+    /// shared-memory segment, which other host processes can share.
+    ///
+    /// This behaves like [`new`][], except that the `State` buffer is the
+    /// [`SharedState`][] called `shm_name`. The first process to open the
+    /// name creates the segment and calls the library's `init` on it. Every
+    /// process that opens the name later waits for that to finish, and then
+    /// calls `reload` instead, so that the state isn't initialized twice.
+    ///
+    /// All the processes should load the same library, and reload it
+    /// whenever it changes. The segment grows whenever any of them loads a
+    /// library with a larger `State`, and never shrinks. The processes all
+    /// read and write the same `State` with no locking, so the library needs
+    /// to be careful about what each of them touches.
+    ///
+    /// This is only available on Unix with the `shm` feature enabled.
+    ///
+    /// [`new`]: struct.Reloadable.html#method.new
+    /// [`SharedState`]: struct.SharedState.html
+    #[cfg(all(feature = "shm", unix))]
+    pub fn new_shared<P: AsRef<Path>>(path: P, host: Host, shm_name: &str) -> Result<Self, Error> {
+        let shared = SharedState::open(shm_name)?;
+        Self::with_buffer(path, host, StateBuffer::Shared(shared), Config::default())
+    }
+
+    /// Create a new Reloadable library that continues from a saved state.
+    ///
+    /// This behaves like [`new`][], except that the `State` starts out as
+    /// `save`, and the library's `reload` is called on it instead of `init`,
+    /// so that it can fix up anything that doesn't survive a restart. The
+    /// save is read with the library's `deserialize` when both have one, as
+    /// in [`load_state`][]. If `save` was taken with a library whose `State`
+    /// had a different size, or a different layout when both libraries
+    /// report one, this fails with [`Error::SnapshotMismatch`][] or
+    /// [`Error::StateLayoutMismatch`][] without calling anything.
+    ///
+    /// [`new`]: struct.Reloadable.html#method.new
+    /// [`load_state`]: struct.Reloadable.html#method.load_state
+    /// [`Error::SnapshotMismatch`]: enum.Error.html#variant.SnapshotMismatch
+    /// [`Error::StateLayoutMismatch`]: enum.Error.html#variant.StateLayoutMismatch
+    pub fn new_with_state<P: AsRef<Path>>(path: P, host: Host, save: &SaveState) -> Result<Self, Error> {
+        // Sized for the save, so that `start` picks it up rather than
+        // calling `init`.
+        let store = Box::new(vec![0; save.state.len()]);
+        let mut app = Self::open(path, host, StateBuffer::Store(store), Config::default())?;
+        let size = app.sym.as_ref().map_or(0, |sym| (sym.api().size)());
+        let fits = if size == save.size {
+            app.check_state_layout(save)
+        } else {
+            Err(Error::SnapshotMismatch {
+                expected: size,
+                found: save.size,
+            })
+        };
+        if let Err(err) = fits {
+            // Don't let `drop` call `deinit` on a state that never started.
+            app.sym = None;
+            return Err(err);
+        }
+        if !app.load_serialized(save) {
+            app.load_buffer(&save.state)?;
+        }
+        app.start()
+    }
+
+    /// Create a new Reloadable that runs a library loaded by a
+    /// [`SharedLibrary`][], rather than loading its own copy.
+    ///
+    /// This behaves like [`new`][], with its own `Host` and its own `State`,
+    /// but the library image is shared with every other `Reloadable` made
+    /// from `library`. The `Reloadable` has no watcher of its own: once
+    /// [`SharedLibrary::reload`][] has loaded a new version, the next
+    /// [`reload`][] of each `Reloadable` swaps to it, and the old version is
+    /// unloaded once none of them are running it anymore.
+    ///
+    /// [`SharedLibrary`]: struct.SharedLibrary.html
+    /// [`SharedLibrary::reload`]: struct.SharedLibrary.html#method.reload
+    /// [`new`]: struct.Reloadable.html#method.new
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    pub fn from_shared(library: &SharedLibrary<Host>, host: Host) -> Result<Self, Error> {
+        let mut shared = library.handle();
+        let app = Reloadable {
+            path: library.path().to_path_buf(),
+            request_path: library.request_path().to_path_buf(),
+            sym: Some(AppSym::shared(shared.latest())),
+            state: StateBuffer::Store(Box::new(Vec::<u64>::new())),
+            state_size: 0,
+            loaded_mtimes: Vec::new(),
+            reload_count: 0,
+            config: Config::default(),
+            history: None,
+            pending_reload: false,
+            initialized: false,
+            inert: false,
+            deferred: false,
+            paused: false,
+            reload_failures: VecDeque::new(),
+            reload_loop: None,
+            quarantine: Vec::new(),
+            dir_removed: false,
+            reload_gate: None,
+            metrics: ReloadMetrics::default(),
+            metrics_sink: None,
+            on_state_moved: None,
+            on_quit_requested: None,
+            quit_requested: false,
+            loader: None,
+            watchdog: None,
+            load_generation: 0,
+            state_version: 0,
+            reload_progress: None,
+            observer: None,
+            reload_log: ReloadLog::new(),
+            state_layout: None,
+            next_generation: AtomicU64::new(0),
+            watcher: None,
+            #[cfg(feature = "in-process")]
+            in_process: None,
+            events: Events::closed(),
+            shared: Some(shared),
+            old_images: VecDeque::new(),
+            #[cfg(feature = "testing")]
+            mock: None,
+            host,
+        };
+        app.start()
+    }
+
+    /// Create a new Reloadable that runs `library`, which the host has
+    /// already opened itself, and gets each new version from `loader`.
+    ///
+    /// This is for hosts that manage the `libloading::Library` themselves,
+    /// with their own search paths or flags. The `Reloadable` reads the
+    /// `RELOAD_API` from `library` and checks it as [`new`][] would, and then
+    /// owns it. It has no path, so there's no watcher and no temporary copy:
+    /// [`reload`][] never finds a change, and only [`reload_now`][] reloads,
+    /// calling `loader` to open the next version. An error
+    /// from `loader` fails the reload like any other, leaving nothing loaded
+    /// until the next one.
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # extern crate libloading;
+    /// # fn main() -> Result<(), live_reload::Error> {
+    /// # struct Host;
+    /// use libloading::Library;
+    /// use live_reload::Reloadable;
+    ///
+    /// let library = Library::new("plugins/libgame.so")?;
+    /// let mut app = Reloadable::from_library(library, Host, || {
+    ///     Ok(Library::new("plugins/libgame.so")?)
+    /// })?;
+    /// app.update();
+    /// app.reload_now()?;
+    /// # Ok(())
+    /// # }
+    /// ```
+    ///
+    /// [`new`]: struct.Reloadable.html#method.new
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    pub fn from_library<F>(library: Library, host: Host, loader: F) -> Result<Self, Error>
+    where
+        F: FnMut() -> Result<Library, Error> + Send + 'static,
+    {
+        let config = Config::default();
+        let sym = AppSym::adopt(library, None, &config)?;
+        let app = Reloadable {
+            path: PathBuf::new(),
+            request_path: PathBuf::new(),
+            sym: Some(sym),
+            state: StateBuffer::Store(Box::new(Vec::<u64>::new())),
+            state_size: 0,
+            loaded_mtimes: Vec::new(),
+            reload_count: 0,
+            config,
+            history: None,
+            pending_reload: false,
+            initialized: false,
+            inert: false,
+            deferred: false,
+            paused: false,
+            reload_failures: VecDeque::new(),
+            reload_loop: None,
+            quarantine: Vec::new(),
+            dir_removed: false,
+            reload_gate: None,
+            metrics: ReloadMetrics::default(),
+            metrics_sink: None,
+            on_state_moved: None,
+            on_quit_requested: None,
+            quit_requested: false,
+            loader: Some(Box::new(loader)),
+            watchdog: None,
+            load_generation: 0,
+            state_version: 0,
+            reload_progress: None,
+            observer: None,
+            reload_log: ReloadLog::new(),
+            state_layout: None,
+            next_generation: AtomicU64::new(0),
+            watcher: None,
+            #[cfg(feature = "in-process")]
+            in_process: None,
+            events: Events::closed(),
+            shared: None,
+            old_images: VecDeque::new(),
+            #[cfg(feature = "testing")]
+            mock: None,
+            host,
+        };
+        app.start()
+    }
+
+    pub(crate) fn with_config(path: PathBuf, host: Host, mut config: Config<Host>) -> Result<Self, Error> {
+        let store = config.store.take().unwrap_or_else(|| Box::new(Vec::<u64>::new()));
+        Self::with_buffer(path, host, StateBuffer::Store(store), config)
+    }
+
+    fn with_buffer<P: AsRef<Path>>(
+        path: P,
+        host: Host,
+        state: StateBuffer,
+        config: Config<Host>,
+    ) -> Result<Self, Error> {
+        Self::open(path, host, state, config)?.start()
+    }
+
+    /// Load the library and set up the watcher, without starting the library.
+    fn open<P: AsRef<Path>>(
+        path: P,
+        host: Host,
+        state: StateBuffer,
+        mut config: Config<Host>,
+    ) -> Result<Self, Error> {
+        if let Some(ref copy) = config.copy {
+            let _ = temp_copy::remove_stale(&copy.dir);
+        }
+        let new_path = config.resolve(path.as_ref())?;
+        config.resolve_companions()?;
+        if let Some(build) = config.build_command.take() {
+            config.trigger = Some(Box::new(BuildTrigger::new(build, &new_path)?));
+        }
+        // A missing library is waited for, unless there's a recovered state
+        // that it would have to pick up straight away.
+        let deferred = !new_path.exists() && state.as_bytes().is_empty();
+        let loaded_mtimes = config.group(&new_path).into_iter().map(file_mtime).collect();
+        let sym = if deferred { None } else { Some(AppSym::new(config.library_file(&new_path), &config)?) };
+        let (watcher, events) = if config.trigger.is_some() || config.headless {
+            (None, Events::closed())
+        } else {
+            let (watcher, events) = Self::watch(&new_path, &config)?;
+            (Some(watcher), events)
+        };
+        Ok(Reloadable {
+            path: if deferred { new_path.clone() } else { new_path.canonicalize()? },
+            request_path: new_path,
+            sym,
+            state,
+            state_size: 0,
+            loaded_mtimes,
+            reload_count: 0,
+            config,
+            history: None,
+            pending_reload: false,
+            initialized: false,
+            inert: false,
+            deferred,
+            paused: false,
+            reload_failures: VecDeque::new(),
+            reload_loop: None,
+            quarantine: Vec::new(),
+            dir_removed: false,
+            reload_gate: None,
+            metrics: ReloadMetrics::default(),
+            metrics_sink: None,
+            on_state_moved: None,
+            on_quit_requested: None,
+            quit_requested: false,
+            loader: None,
+            watchdog: None,
+            load_generation: 0,
+            state_version: 0,
+            reload_progress: None,
+            observer: None,
+            reload_log: ReloadLog::new(),
+            state_layout: None,
+            next_generation: AtomicU64::new(0),
+            watcher,
+            #[cfg(feature = "in-process")]
+            in_process: None,
+            events,
+            shared: None,
+            old_images: VecDeque::new(),
+            #[cfg(feature = "testing")]
+            mock: None,
+            host,
+        })
+    }
+
+    /// Start watching the directory of the library at `path` for changes.
+    pub(crate) fn watch(path: &Path, config: &Config<Host>) -> Result<(RecommendedWatcher, Events), Error> {
+        let (tx, rx) = channel();
+        let mut watcher = notify::watcher(tx, config.debounce)?;
+        for dir in config.watched_dirs(path) {
+            watcher.watch(dir, config.recursive_mode())?;
+        }
+        Ok((watcher, Events::new(rx)))
+    }
+
+    /// Create a Reloadable that drives a `ReloadApi` built into the host,
+    /// rather than one loaded from a dynamic library.
+    ///
+    /// There's no library file and no filesystem watcher involved, so
+    /// [`reload`][] never finds a change (and [`reload_blocking_until_changed`][]
+    /// returns straight away), while [`reload_now`][] runs `unload` and then
+    /// `reload` from the same `api`. Everything else behaves exactly as it
+    /// does for a real library, which makes this handy for testing code that
+    /// drives a `Reloadable` without building a `cdylib`. This is only
+    /// available with the `in-process` feature enabled.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// mod counter {
+    ///     use live_reload::ShouldQuit;
+    ///
+    ///     pub struct Host {
+    ///         pub log: Vec<String>,
+    ///     }
+    ///
+    ///     live_reload! {
+    ///         host: Host;
+    ///         state: State;
+    ///         init: init;
+    ///         reload: reload;
+    ///         update: update;
+    ///         unload: unload;
+    ///         deinit: deinit;
+    ///     }
+    ///
+    ///     struct State {
+    ///         counter: u64,
+    ///     }
+    ///
+    ///     fn init(host: &mut Host, state: &mut State) {
+    ///         state.counter = 0;
+    ///         host.log.push("init".into());
+    ///     }
+    ///
+    ///     fn reload(host: &mut Host, state: &mut State) {
+    ///         host.log.push(format!("reload {}", state.counter));
+    ///     }
+    ///
+    ///     fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///         state.counter += 1;
+    ///         if state.counter == 3 { ShouldQuit::Yes } else { ShouldQuit::No }
+    ///     }
+    ///
+    ///     fn unload(host: &mut Host, state: &mut State) {
+    ///         host.log.push(format!("unload {}", state.counter));
+    ///     }
+    ///
+    ///     fn deinit(host: &mut Host, _: &mut State) {
+    ///         host.log.push("deinit".into());
+    ///     }
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::{Reloadable, ShouldQuit};
+    ///
+    /// let host = counter::Host { log: Vec::new() };
+    /// let mut app = Reloadable::new_in_process(counter::RELOAD_API, host).unwrap();
+    /// assert_eq!(app.update(), ShouldQuit::No);
+    /// app.reload_now().unwrap();
+    /// assert_eq!(app.update(), ShouldQuit::No);
+    /// assert_eq!(app.update(), ShouldQuit::Yes);
+    /// assert_eq!(app.host().log, ["init", "unload 1", "reload 1"]);
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_blocking_until_changed`]: struct.Reloadable.html#method.reload_blocking_until_changed
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    #[cfg(feature = "in-process")]
+    pub fn new_in_process(api: internals::ReloadApi<Host>, host: Host) -> Result<Self, Error> {
+        Self::in_process_with_config(api, host, Config::default())
+    }
+
+    #[cfg(feature = "in-process")]
+    pub(crate) fn in_process_with_config(
+        api: internals::ReloadApi<Host>,
+        host: Host,
+        mut config: Config<Host>,
+    ) -> Result<Self, Error> {
+        let store = config.store.take().unwrap_or_else(|| Box::new(Vec::<u64>::new()));
+        let app = Reloadable {
+            path: PathBuf::new(),
+            request_path: PathBuf::new(),
+            sym: Some(AppSym::in_process(api)),
+            state: StateBuffer::Store(store),
+            state_size: 0,
+            loaded_mtimes: Vec::new(),
+            reload_count: 0,
+            config,
+            history: None,
+            pending_reload: false,
+            initialized: false,
+            inert: false,
+            deferred: false,
+            paused: false,
+            reload_failures: VecDeque::new(),
+            reload_loop: None,
+            quarantine: Vec::new(),
+            dir_removed: false,
+            reload_gate: None,
+            metrics: ReloadMetrics::default(),
+            metrics_sink: None,
+            on_state_moved: None,
+            on_quit_requested: None,
+            quit_requested: false,
+            loader: None,
+            watchdog: None,
+            load_generation: 0,
+            state_version: 0,
+            reload_progress: None,
+            observer: None,
+            reload_log: ReloadLog::new(),
+            state_layout: None,
+            next_generation: AtomicU64::new(0),
+            watcher: None,
+            in_process: Some(api),
+            events: Events::closed(),
+            shared: None,
+            old_images: VecDeque::new(),
+            #[cfg(feature = "testing")]
+            mock: None,
+            host,
+        };
+        app.start()
+    }
+
+    /// Create a Reloadable that runs a [`MockLibrary`][], rather than a real
+    /// library.
+    ///
+    /// This works like [`new_in_process`][], with the mock's closures standing
+    /// in for the library's lifecycle functions: `init` runs straight away,
+    /// [`reload_now`][] runs `unload` and then `reload`, and so on. The mock
+    /// has no `State`, so its saves are empty, and loading a save of any
+    /// other `State` fails like it would for a library whose `State` is a
+    /// different size. This is only available with the `testing` feature
+    /// enabled.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// use live_reload::{Error, MockLibrary, Reloadable, SaveState, ShouldQuit};
+    ///
+    /// let mock = MockLibrary::new().on_update(|frames: &mut u32| {
+    ///     *frames += 1;
+    ///     ShouldQuit::No
+    /// });
+    /// let mut app = Reloadable::with_mock(0, mock).unwrap();
+    /// assert_eq!(app.save_state().bytes(), &[]);
+    ///
+    /// // Bytes saved from some other library can't reach the mock.
+    /// let other = SaveState::from_bytes(&[0xff; 8]);
+    /// assert!(matches!(app.try_load_state(&other), Err(Error::SnapshotMismatch { .. })));
+    /// app.update();
+    /// assert_eq!(*app.host(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`MockLibrary`]: struct.MockLibrary.html
+    /// [`new_in_process`]: struct.Reloadable.html#method.new_in_process
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    #[cfg(feature = "testing")]
+    pub fn with_mock(host: Host, mock: MockLibrary<Host>) -> Result<Self, Error> {
+        // `init` has to wait until the pointer to the mock is in place.
+        let config = Config {
+            lazy_init: true,
+            ..Config::default()
+        };
+        let mut app = Self::in_process_with_config(MockLibrary::api(), host, config)?;
+        let mock = OwnedMock::new(mock);
+        app.state = StateBuffer::Mock(mock.as_ptr() as *mut ());
+        app.mock = Some(mock);
+        app.config.lazy_init = false;
+        app.ensure_init()?;
+        Ok(app)
+    }
+
+    /// Size the buffer for the freshly loaded library, and start it up.
+    fn start(mut self) -> Result<Self, Error> {
+        if let Some((timeout, on_hang)) = self.config.watchdog.take() {
+            self.watchdog = Some(Watchdog::spawn(timeout, on_hang)?);
+        }
+        let size = match self.sym {
+            Some(ref sym) => (sym.api().size)(),
+            None => 0,
+        };
+        if let Err(err) = self.check_state_size(size) {
+            self.sym = None;
+            return Err(err);
+        }
+        let recovered = !self.state.as_bytes().is_empty();
+        self.realloc_buffer(size)?;
+        self.state_size = size;
+        self.state_layout = self.sym.as_ref().and_then(AppSym::state_layout);
+        if self.deferred || (!recovered && self.config.lazy_init) {
+            return Ok(self);
+        }
+        let started = match self.sym {
+            Some(ref sym) => {
+                let api = sym.api();
+                let host = &mut self.host;
+                let state = Self::get_state_ptr(&mut self.state);
+                let progress = self.reload_progress;
+                let watchdog = self.watchdog.as_ref();
+                if recovered {
+                    watchdog::call(watchdog, LifecyclePhase::Reload, |sink| {
+                        api.call_reload(host, state, progress, sink)
+                    })
+                } else {
+                    let reload = self.config.reload_on_first_load;
+                    let init = watchdog::call(watchdog, LifecyclePhase::Init, |sink| (api.init)(host, state, sink));
+                    init.and_then(|()| {
+                        if !reload {
+                            return Ok(());
+                        }
+                        watchdog::call(watchdog, LifecyclePhase::Reload, |sink| {
+                            api.call_reload(host, state, progress, sink)
+                        })
+                    })
+                }
+            }
+            None => Ok(()),
+        };
+        if let Err(err) = started {
+            // Don't let `drop` call `deinit` on a state that never started.
+            self.sym = None;
+            return Err(err);
+        }
+        self.initialized = true;
+        self.state_version = self.sym.as_ref().map_or(0, |sym| sym.api().state_migrations().0);
+        #[cfg(all(feature = "shm", unix))]
+        {
+            if let StateBuffer::Shared(ref shared) = self.state {
+                shared.mark_ready();
+            }
+        }
+        Ok(self)
+    }
+
+    /// Call the library's `init`, if it was put off by
+    /// [`Builder::lazy_init`][] and hasn't been called yet.
+    ///
+    /// [`update`][] does this by itself, so this is only needed to control
+    /// exactly when `init` runs. If `init` panics, this returns
+    /// [`Error::LibraryPanicked`][], and the next call tries again. This is
+    /// also how a `Reloadable` opened with [`open_inert`][] is started.
+    ///
+    /// [`Builder::lazy_init`]: struct.Builder.html#method.lazy_init
+    /// [`open_inert`]: struct.Reloadable.html#method.open_inert
+    /// [`update`]: struct.Reloadable.html#method.update
+    /// [`Error::LibraryPanicked`]: enum.Error.html#variant.LibraryPanicked
+    pub fn ensure_init(&mut self) -> Result<(), Error> {
+        self.inert = false;
+        if self.initialized {
+            return Ok(());
+        }
+        if let Some(ref sym) = self.sym {
+            let init = sym.api().init;
+            let host = &mut self.host;
+            let state = Self::get_state_ptr(&mut self.state);
+            watchdog::call(self.watchdog.as_ref(), LifecyclePhase::Init, |sink| init(host, state, sink))?;
+            self.initialized = true;
+            self.state_version = sym.api().state_migrations().0;
+            self.observe(|observer, app| observer.on_init(app));
+            self.log(ReloadLogKind::Init, |app| app.path.display().to_string());
+            if let (true, Some(sym)) = (self.config.reload_on_first_load, self.sym.as_ref()) {
+                let api = sym.api();
+                let host = &mut self.host;
+                let state = Self::get_state_ptr(&mut self.state);
+                let progress = self.reload_progress;
+                watchdog::call(self.watchdog.as_ref(), LifecyclePhase::Reload, |sink| {
+                    api.call_reload(host, state, progress, sink)
+                })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Tear the library down with `deinit`, and start it again from a zeroed
+    /// `State` with `init`, without reloading it.
+    ///
+    /// This is for something like restarting a level, where the library
+    /// should start over as if the host had just started, but gets to
+    /// release whatever it holds first. The state buffer is sized for the
+    /// loaded library and zeroed in between, and the host is kept as it is.
+    ///
+    /// If `deinit` panics, this returns [`Error::LibraryPanicked`][] and
+    /// leaves the state as `deinit` left it, while `init` is put off until
+    /// the next [`update`][] or [`ensure_init`][]. If `init` panics, it's
+    /// retried the same way. This does nothing if no library is loaded yet.
+    ///
+    /// A library with a `persistent_range` (see [`live_reload!`][]) keeps
+    /// those bytes of the state through the restart: they aren't zeroed, so
+    /// `init` can read them, and they're put back as they were once `init`
+    /// has run, whatever it wrote there. It's asked before `deinit`, so if
+    /// it panics, this returns [`Error::LibraryPanicked`][] without having
+    /// called anything else, and the state is left as it was.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{Reloadable, ShouldQuit};
+    ///
+    /// pub struct Host;
+    /// #[repr(C)]
+    /// pub struct State {
+    ///     level_time: u32,
+    ///     volume: u32,
+    /// }
+    ///
+    /// fn init(_: &mut Host, state: &mut State) {
+    ///     state.volume = 5;
+    /// }
+    /// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     state.level_time += 1;
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    /// fn settings() -> (usize, usize) {
+    ///     (4, 4)
+    /// }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    ///     persistent_range: settings;
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.update();
+    /// // Turned down by the player.
+    /// unsafe { app.state_bytes_mut()[4..8].copy_from_slice(&2u32.to_ne_bytes()) };
+    /// app.restart().unwrap();
+    /// assert_eq!(app.state_bytes()[..4], 0u32.to_ne_bytes());
+    /// assert_eq!(app.state_bytes()[4..8], 2u32.to_ne_bytes());
+    /// # }
+    /// ```
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{Error, LifecyclePhase, Reloadable, ShouldQuit};
+    ///
+    /// pub struct Host;
+    /// #[repr(C)] pub struct State { volume: u32 }
+    ///
+    /// fn init(_: &mut Host, state: &mut State) {
+    ///     state.volume = 5;
+    /// }
+    /// fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    /// fn settings() -> (usize, usize) {
+    ///     panic!("the settings moved");
+    /// }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    ///     persistent_range: settings;
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// unsafe { app.state_bytes_mut().copy_from_slice(&2u32.to_ne_bytes()) };
+    /// match app.restart() {
+    ///     Err(Error::LibraryPanicked { phase: LifecyclePhase::PersistentRange, .. }) => {}
+    ///     other => panic!("persistent_range should have panicked, got {:?}", other.err()),
+    /// }
+    /// assert_eq!(app.state_bytes(), 2u32.to_ne_bytes());
+    /// # }
+    /// ```
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host { log: Vec<String> }
+    /// #[repr(C)] pub struct State { score: u64 }
+    ///
+    /// fn init(host: &mut Host, state: &mut State) {
+    ///     host.log.push(format!("init {}", state.score));
+    /// }
+    /// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     state.score += 10;
+    ///     ShouldQuit::No
+    /// }
+    /// fn deinit(host: &mut Host, state: &mut State) {
+    ///     host.log.push(format!("deinit {}", state.score));
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: deinit;
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host { log: Vec::new() }).unwrap();
+    /// app.update();
+    /// app.update();
+    /// app.restart().unwrap();
+    /// assert_eq!(app.host().log, ["init 0", "deinit 20", "init 0"]);
+    /// assert_eq!(app.reload_count(), 0);
+    /// # }
+    /// ```
+    ///
+    /// [`Error::LibraryPanicked`]: enum.Error.html#variant.LibraryPanicked
+    /// [`update`]: struct.Reloadable.html#method.update
+    /// [`ensure_init`]: struct.Reloadable.html#method.ensure_init
+    /// [`live_reload!`]: macro.live_reload.html
+    pub fn restart(&mut self) -> Result<(), Error> {
+        // Ask what to keep first, so that a panic there changes nothing.
+        let (deinit, persistent) = match self.sym {
+            Some(ref sym) => (sym.api().deinit, sym.api().persistent_bytes()?),
+            None => return Ok(()),
+        };
+        if self.initialized {
+            self.initialized = false;
+            let host = &mut self.host;
+            let state = Self::get_state_ptr(&mut self.state);
+            let result = watchdog::call(self.watchdog.as_ref(), LifecyclePhase::Deinit, |sink| {
+                deinit(host, state, sink)
+            });
+            match result {
+                Ok(()) => {
+                    self.observe(|observer, app| observer.on_deinit(app));
+                    self.log(ReloadLogKind::Deinit, |_| String::new());
+                }
+                Err(ref err) => {
+                    self.observe(|observer, app| observer.on_error(app, err));
+                    self.log(ReloadLogKind::Error, |_| err.to_string());
+                }
+            }
+            result?;
+        }
+        let size = self.state_size;
+        let kept = persistent.map(|range| {
+            let range = range.start.min(size)..range.end.min(size);
+            (range.start, self.state.as_bytes()[range].to_vec())
+        });
+        self.realloc_buffer(size)?;
+        for byte in self.state.as_mut_bytes() {
+            *byte = 0;
+        }
+        if let Some((start, ref bytes)) = kept {
+            self.state.as_mut_bytes()[start..start + bytes.len()].copy_from_slice(bytes);
+        }
+        self.ensure_init()?;
+        if let Some((start, ref bytes)) = kept {
+            self.state.as_mut_bytes()[start..start + bytes.len()].copy_from_slice(bytes);
+        }
+        Ok(())
+    }
+
+    /// Tell the observer, if there is one, about a step in the lifecycle.
+    fn observe<F: FnOnce(&mut dyn ReloadObserver<Host>, &Self)>(&mut self, tell: F) {
+        if let Some(mut observer) = self.observer.take() {
+            tell(&mut *observer, self);
+            // The observer can't replace itself, since it only sees `&Self`.
+            self.observer = Some(observer);
+        }
+    }
+
+    /// Check that the library's `init` has run on the `State` before it's
+    /// handed to `phase`.
+    ///
+    /// Nothing should get here otherwise, but a library that reads a `State`
+    /// that was never initialized goes wrong far from the cause, so debug
+    /// builds stop here instead. Release builds leave the check out.
+    fn debug_assert_initialized(&self, phase: LifecyclePhase) {
+        debug_assert!(
+            self.initialized,
+            "live_reload: the library's `{}` was about to run on a `State` that `init` never ran on",
+            phase,
+        );
+    }
+
+    /// Add an entry to the reload log, if one is kept.
+    fn log<F: FnOnce(&Self) -> String>(&mut self, kind: ReloadLogKind, detail: F) {
+        let capacity = self.config.reload_log_capacity;
+        if capacity > 0 {
+            let detail = detail(self);
+            self.reload_log.push(capacity, kind, detail);
+        }
+    }
+
+    /// The layout hash of the `State` of the last library that loaded, if it
+    /// reported one.
+    pub(crate) fn loaded_state_layout(&self) -> Option<u64> {
+        self.state_layout
+    }
+
+    /// Whether the library comes from a file at a known path, rather than
+    /// being built into the host or opened by a loader, so that there's a
+    /// file to look at.
+    fn has_file(&self) -> bool {
+        self.loader.is_none() && !self.is_in_process()
+    }
+
+    /// Whether the library is built into the host.
+    pub(crate) fn is_in_process(&self) -> bool {
+        #[cfg(feature = "in-process")]
+        {
+            if self.in_process.is_some() {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Hash the contents of the library file and its companions together,
+    /// if there's one to read.
+    fn library_hash(&self) -> Option<u64> {
+        if self.has_file() { files_hash(&self.config.group(&self.request_path)) } else { None }
+    }
+
+    /// Get the modification times of the library file and its companions,
+    /// to compare with `loaded_mtimes`. There are none without a file.
+    fn group_mtimes(&self) -> Vec<Option<SystemTime>> {
+        if !self.has_file() {
+            return Vec::new();
+        }
+        self.config.group(&self.request_path).into_iter().map(file_mtime).collect()
+    }
+
+    /// Describe the library file, without anything the library reports.
+    fn describe_file(&self) -> LibraryInfo {
+        let mut info = LibraryInfo::new(&self.request_path);
+        if self.has_file() {
+            info.metadata = std::fs::metadata(&self.request_path).ok();
+        }
+        info
+    }
+
+    /// Load the library again.
+    fn load(&mut self) -> Result<AppSym<Host>, Error> {
+        #[cfg(feature = "in-process")]
+        {
+            if let Some(api) = self.in_process {
+                return Ok(AppSym::in_process(api));
+            }
+        }
+        if let Some(ref mut shared) = self.shared {
+            return Ok(AppSym::shared(shared.latest()));
+        }
+        if let Some(ref mut loader) = self.loader {
+            return AppSym::adopt(loader()?, None, &self.config);
+        }
+        AppSym::new(self.config.library_file(&self.request_path), &self.config)
+    }
+
+    /// Reload the library if it has changed, otherwise do nothing.
+    ///
+    /// This will consult with the filesystem watcher, and if the library has
+    /// been recreated or updated, it will reload the library. With a trigger
+    /// set by [`Builder::trigger`][], it asks the trigger instead. See
+    /// [`reload_now`][] for details on what happens when a library is reloaded.
+    ///
+    /// If the library was declared with an `is_safe_to_reload` function (see
+    /// [`live_reload!`][]), or a reload gate is set with [`set_reload_gate`][],
+    /// the reload only happens once both allow it. Until then, the change is
+    /// remembered, so a later call to `reload` will still pick it up.
+    ///
+    /// With [`Builder::require_mtime_change`][], a change is first checked
+    /// against the modification time of the library when it was loaded, and
+    /// forgotten if that hasn't changed.
+    ///
+    /// With [`Builder::reload_cooldown`][], a change that comes too soon after
+    /// the last reload is held until the cooldown is over.
+    ///
+    /// Once it's allowed, the hook set with [`Builder::before_reload`][] gets
+    /// the final say. If it returns false, the change is forgotten and the
+    /// current library keeps running.
+    ///
+    /// With [`Builder::detect_reload_loops`][], a reload that keeps failing
+    /// eventually returns [`Error::ReloadLoop`][], and isn't tried again
+    /// until the library changes.
+    ///
+    /// With [`Builder::defer_reloads`][], this only notes the change, and
+    /// everything above happens in [`apply_deferred_reload`][] instead.
+    ///
+    /// [`Builder::defer_reloads`]: struct.Builder.html#method.defer_reloads
+    /// [`apply_deferred_reload`]: struct.Reloadable.html#method.apply_deferred_reload
+    /// [`Builder::trigger`]: struct.Builder.html#method.trigger
+    /// [`Builder::before_reload`]: struct.Builder.html#method.before_reload
+    /// [`Builder::detect_reload_loops`]: struct.Builder.html#method.detect_reload_loops
+    /// [`Error::ReloadLoop`]: enum.Error.html#variant.ReloadLoop
+    /// [`Builder::require_mtime_change`]: struct.Builder.html#method.require_mtime_change
+    /// [`Builder::reload_cooldown`]: struct.Builder.html#method.reload_cooldown
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    /// [`set_reload_gate`]: struct.Reloadable.html#method.set_reload_gate
+    pub fn reload(&mut self) -> Result<(), Error> {
+        if self.paused && self.sym.is_some() {
+            return Ok(());
+        }
+        self.drain_and_coalesce()?;
+        if self.config.defer_reloads {
+            return Ok(());
+        }
+        self.apply_pending_reload()
+    }
+
+    /// Reload now if [`reload`][] noted a change, with
+    /// [`Builder::defer_reloads`][] on.
+    ///
+    /// This is the second half of `reload`: the change has already been
+    /// seen, and this swaps the library, still waiting for the library's
+    /// `is_safe_to_reload`, the reload gate and so on. So call `reload` at
+    /// the top of a frame, where it's cheap and changes nothing, and this at
+    /// the end, once nothing from the frame still points into the library or
+    /// the `State`. Without `defer_reloads`, `reload` has already done this,
+    /// unless something held the reload back, in which case this tries
+    /// again.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::{Builder, ReloadOutcome};
+    ///
+    /// let mut app = Builder::new("")
+    ///     .defer_reloads(true)
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// app.inject_change_event();
+    ///
+    /// // Top of the frame: the change is only noted.
+    /// app.reload().unwrap();
+    /// assert!(app.has_pending_reload());
+    /// app.update();
+    /// // End of the frame: now it's applied.
+    /// assert_eq!(app.apply_deferred_reload().unwrap(), ReloadOutcome::Reloaded);
+    /// assert_eq!(app.apply_deferred_reload().unwrap(), ReloadOutcome::Unchanged);
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`Builder::defer_reloads`]: struct.Builder.html#method.defer_reloads
+    pub fn apply_deferred_reload(&mut self) -> Result<ReloadOutcome, Error> {
+        if self.paused && self.sym.is_some() {
+            return Ok(ReloadOutcome::Unchanged);
+        }
+        let before = self.reload_count;
+        self.apply_pending_reload()?;
+        Ok(if self.reload_count != before { ReloadOutcome::Reloaded } else { ReloadOutcome::Unchanged })
+    }
+
+    /// Reload if a change was noted and everything allows it.
+    fn apply_pending_reload(&mut self) -> Result<(), Error> {
+        if self.deferred && !self.request_path.exists() {
+            return Ok(());
+        }
+
+        if let Some(stopped_at) = self.reload_loop {
+            // Only look at the contents again once they might have changed.
+            if !self.pending_reload || self.library_hash() == stopped_at {
+                self.pending_reload = false;
+                return Ok(());
+            }
+            self.reset_reload_loop();
+        }
+        if !(self.pending_reload || self.sym.is_none()) {
+            return Ok(());
+        }
+        if self.config.require_mtime_change && self.sym.is_some()
+            && self.group_mtimes() == self.loaded_mtimes
+        {
+            self.pending_reload = false;
+            return Ok(());
+        }
+        let cooldown = self.config.reload_cooldown;
+        if self.sym.is_some() && self.metrics.time_since_last_reload().is_some_and(|since| since < cooldown) {
+            return Ok(());
+        }
+        let allowed = self.library_allows_reload() && match self.reload_gate {
+            Some(ref mut gate) => gate(&self.host),
+            None => true,
+        };
+        if !allowed {
+            return Ok(());
+        }
+        if let Some(before_reload) = self.config.before_reload {
+            if self.sym.is_some() && !before_reload(&self.describe_file()) {
+                self.pending_reload = false;
+                return Ok(());
+            }
+        }
+        match self.reload_now() {
+            Err(Error::QuarantinedLibrary { .. }) => {
+                self.pending_reload = false;
+                Ok(())
+            }
+            Err(err) => self.reload_failed(err),
+            Ok(_) => {
+                self.reload_failures.clear();
+                Ok(())
+            }
+        }
+    }
+
+    /// Handle the events the watcher has queued, and sort out which files
+    /// they say changed.
+    ///
+    /// This does the same as the first half of [`reload`][]: a change to the
+    /// library is noted, so that the next `reload` picks it up, errors
+    /// replace the watcher, and so on. Every other file that was written,
+    /// created or renamed into place, except those with an ignored extension
+    /// (see [`Builder::ignore_extension`][]), is listed in the summary as an
+    /// asset. So a host that keeps its assets next to the
+    /// library, or below it with [`Builder::recursive_watch`][], can handle
+    /// the events once for both, instead of taking some with
+    /// [`try_recv_event`][] and leaving `reload` to miss them. `reload` then
+    /// finds the queue empty and applies the change noted here; any assets
+    /// it comes across itself are dropped.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # if cfg!(miri) { return; } // Miri can't watch files.
+    /// use std::time::{Duration, Instant};
+    /// use live_reload::Builder;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("live-reload-coalesce-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    ///
+    /// let mut app = Builder::new(dir.join("libnot_built_yet.so"))
+    ///     .debounce(Duration::from_millis(10))
+    ///     .build(())
+    ///     .unwrap();
+    /// std::fs::write(dir.join("level.json"), b"{}").unwrap();
+    ///
+    /// let deadline = Instant::now() + Duration::from_secs(10);
+    /// let mut assets = Vec::new();
+    /// while assets.is_empty() && Instant::now() < deadline {
+    ///     std::thread::sleep(Duration::from_millis(10));
+    ///     let changes = app.drain_and_coalesce().unwrap();
+    ///     assert!(!changes.code_changed());
+    ///     assets.extend_from_slice(changes.assets_changed());
+    /// }
+    /// assert_eq!(assets[0].file_name().unwrap(), "level.json");
+    /// # drop(app);
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`try_recv_event`]: struct.Reloadable.html#method.try_recv_event
+    /// [`Builder::ignore_extension`]: struct.Builder.html#method.ignore_extension
+    /// [`Builder::recursive_watch`]: struct.Builder.html#method.recursive_watch
+    pub fn drain_and_coalesce(&mut self) -> Result<ChangeSummary, Error> {
+        let mut assets_changed: Vec<PathBuf> = Vec::new();
+        for _ in 0..self.config.max_events_per_reload {
+            let evt = match self.events.try_recv() {
+                Some(evt) => evt,
+                None => break,
+            };
+            if let notify::DebouncedEvent::Error(err, _) = evt {
+                self.watch_lost(err)?;
+            } else if events::is_removal(&evt) {
+                // The watch doesn't always report its own directory going
+                // away, so check whenever anything in it is removed.
+                if !watched_dir(&self.request_path).is_dir() {
+                    self.dir_removed = true;
+                }
+            } else if self.is_change(&evt) {
+                self.pending_reload = true;
+            } else if let Some(changed) = events::written_path(&evt) {
+                let ignored = events::is_ignored(changed, &self.request_path, &self.config.ignored_extensions);
+                if !ignored && !assets_changed.iter().any(|asset| asset == changed) {
+                    assets_changed.push(changed.to_path_buf());
+                }
+            }
+        }
+        if self.dir_removed && watched_dir(&self.request_path).is_dir() {
+            self.rearm_watcher()?;
+        }
+        if self.shared.as_ref().is_some_and(SharedHandle::is_stale) {
+            self.pending_reload = true;
+        }
+        if let Some(ref mut trigger) = self.config.trigger {
+            if trigger.should_reload() {
+                self.pending_reload = true;
+            }
+        }
+        Ok(ChangeSummary { code_changed: self.pending_reload, assets_changed })
+    }
+
+    /// Reload straight away if a change is waiting, whatever would hold it
+    /// back in [`reload`][].
+    ///
+    /// This handles the watcher's queued events like `reload` does, and if
+    /// any change has been seen, by them or by an earlier `reload` that put
+    /// it off, it runs [`reload_now`][] and forgets the change. The library's
+    /// `is_safe_to_reload`, the reload gate, the `before_reload` hook and a
+    /// stopped reload loop are all skipped (and a successful reload ends the
+    /// loop), so this is the way to apply whatever is queued before saving
+    /// or quitting.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::{ReloadOutcome, Reloadable};
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.set_reload_gate(|_| false);
+    /// app.inject_change_event();
+    /// app.reload().unwrap();
+    /// assert_eq!(app.reload_count(), 0);
+    ///
+    /// assert_eq!(app.flush_pending_reload().unwrap(), ReloadOutcome::Reloaded);
+    /// assert_eq!(app.flush_pending_reload().unwrap(), ReloadOutcome::Unchanged);
+    /// assert_eq!(app.reload_count(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    pub fn flush_pending_reload(&mut self) -> Result<ReloadOutcome, Error> {
+        self.drain_and_coalesce()?;
+        if !self.pending_reload {
+            return Ok(ReloadOutcome::Unchanged);
+        }
+        self.reload_now()?;
+        self.reset_reload_loop();
+        Ok(ReloadOutcome::Reloaded)
+    }
+
+    /// Whether a change has been seen that hasn't been applied yet, because
+    /// something held it back, such as the reload gate or the library's
+    /// `is_safe_to_reload`.
+    ///
+    /// This only reads what earlier calls to [`reload`][] found. It doesn't
+    /// look at the watcher's queued events (see [`pending_event_count`][]),
+    /// so it's cheap and changes nothing, and can be asked every frame, say
+    /// to show that a reload is waiting.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.set_reload_gate(|_| false);
+    /// app.inject_change_event();
+    /// assert!(!app.has_pending_reload());
+    ///
+    /// app.reload().unwrap();
+    /// assert!(app.has_pending_reload());
+    ///
+    /// app.flush_pending_reload().unwrap();
+    /// assert!(!app.has_pending_reload());
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`pending_event_count`]: struct.Reloadable.html#method.pending_event_count
+    pub fn has_pending_reload(&self) -> bool {
+        self.pending_reload
+    }
+
+    /// Count a failed reload towards a reload loop, and stop reloading if
+    /// it makes one.
+    fn reload_failed(&mut self, err: Error) -> Result<(), Error> {
+        let (failures, window) = match self.config.reload_loop {
+            Some(limit) => limit,
+            None => return Err(err),
+        };
+        let now = Instant::now();
+        while self.reload_failures.front().is_some_and(|&failed| now.duration_since(failed) > window) {
+            self.reload_failures.pop_front();
+        }
+        self.reload_failures.push_back(now);
+        if (self.reload_failures.len() as u64) < u64::from(failures) {
+            return Err(err);
+        }
+        self.reload_failures.clear();
+        self.pending_reload = false;
+        self.reload_loop = Some(self.library_hash());
+        Err(Error::ReloadLoop {
+            failures,
+            window,
+            last: Box::new(err),
+        })
+    }
+
+    /// Start reloading again after [`Error::ReloadLoop`][] stopped it.
+    ///
+    /// The next [`reload`][] tries again if there's no library loaded, or if
+    /// a change is waiting, and the count of failures starts over.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host { broken: bool }
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// fn reload(host: &mut Host, _: &mut State) {
+    ///     assert!(!host.broken, "this build is broken");
+    /// }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: reload;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use std::time::Duration;
+    /// use live_reload::{Builder, Error};
+    ///
+    /// let mut app = Builder::new("")
+    ///     .detect_reload_loops(3, Duration::from_secs(60))
+    ///     .build_in_process(RELOAD_API, Host { broken: true })
+    ///     .unwrap();
+    /// assert!(app.reload_now().is_err());
+    ///
+    /// // With nothing loaded, every `reload` tries again, until it gives up.
+    /// assert!(matches!(app.reload(), Err(Error::LibraryPanicked { .. })));
+    /// assert!(matches!(app.reload(), Err(Error::LibraryPanicked { .. })));
+    /// assert!(matches!(app.reload(), Err(Error::ReloadLoop { failures: 3, .. })));
+    /// assert!(app.reload().is_ok());
+    /// assert!(!app.is_loaded());
+    ///
+    /// app.host_mut().broken = false;
+    /// app.reset_reload_loop();
+    /// app.reload().unwrap();
+    /// assert!(app.is_loaded());
+    /// # }
+    /// ```
+    ///
+    /// [`Error::ReloadLoop`]: enum.Error.html#variant.ReloadLoop
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    pub fn reset_reload_loop(&mut self) {
+        self.reload_loop = None;
+        self.reload_failures.clear();
+    }
+
+    /// Forget which libraries failed to load, so that they're tried again,
+    /// with [`Builder::quarantine_bad_libraries`][].
+    ///
+    /// [`Builder::quarantine_bad_libraries`]: struct.Builder.html#method.quarantine_bad_libraries
+    pub fn clear_quarantine(&mut self) {
+        self.quarantine.clear();
+    }
+
+    /// Whether a library is loaded right now.
+    ///
+    /// This is `false` until a library that didn't exist when the
+    /// `Reloadable` was made has been loaded, and after a failed reload,
+    /// until a later one succeeds. While it's `false`, [`update`][] does
+    /// nothing.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # if cfg!(miri) { return; } // Miri can't watch files.
+    /// use live_reload::{Reloadable, ShouldQuit};
+    ///
+    /// let dir = std::env::temp_dir().join(format!("live-reload-unbuilt-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    ///
+    /// let mut app = Reloadable::new(dir.join("libnot_built_yet.so"), ()).unwrap();
+    /// assert!(!app.is_loaded());
+    /// app.reload().unwrap();
+    /// assert!(!app.is_loaded());
+    /// assert_eq!(app.update(), ShouldQuit::No);
+    /// # drop(app);
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`update`]: struct.Reloadable.html#method.update
+    pub fn is_loaded(&self) -> bool {
+        self.sym.is_some()
+    }
+
+    /// Ask the loaded library whether its state can be reloaded right now.
+    fn library_allows_reload(&self) -> bool {
+        match self.sym {
+            Some(ref sym) => sym.api().allows_reload(self.state.as_bytes().as_ptr() as *const ()),
+            None => true,
+        }
+    }
+
+    /// Queue a synthetic event saying the library was written, as if it came
+    /// from the filesystem watcher.
+    ///
+    /// The next [`reload`][] then handles it like any other event, so it goes
+    /// through the same filtering and checks as a real change, and reloads if
+    /// nothing holds it back. This is for testing code that reacts to
+    /// reloads, without touching the library file and waiting on the
+    /// watcher. It's only available with the `testing` feature enabled.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.reload().unwrap();
+    /// assert_eq!(app.reload_count(), 0);
+    ///
+    /// app.inject_change_event();
+    /// assert_eq!(app.pending_event_count(), 1);
+    /// app.reload().unwrap();
+    /// assert_eq!(app.reload_count(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    #[cfg(feature = "testing")]
+    pub fn inject_change_event(&mut self) {
+        let path = self.path.clone();
+        self.events.inject(notify::DebouncedEvent::Write(path));
+    }
+
+    /// Count the watcher events that are waiting to be handled by [`reload`][].
+    ///
+    /// The watcher queues an event for everything that happens in the
+    /// library's directory, not just to the library itself. If this keeps
+    /// growing between calls to [`reload`][], you're probably watching too
+    /// broad a directory. Counting the events doesn't handle them, so they
+    /// are all still there for the next [`reload`][].
+    ///
+    /// Events are coalesced as they arrive, so that the queue can't grow
+    /// without bound while `reload` isn't being called: an event that repeats
+    /// one already waiting, like a second write to the same file, isn't
+    /// queued again, and once 256 are waiting they're all replaced by a
+    /// single one that counts as a change to the library. So this is at most
+    /// 256, and the count no longer says how many times each file changed.
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    pub fn pending_event_count(&self) -> usize {
+        self.events.len()
+    }
+
+    /// Take the next event waiting from the watcher, as it came, without
+    /// acting on it.
+    ///
+    /// This is an escape hatch for hosts that want to decide for themselves
+    /// which events call for a reload, and then call [`reload_now`][]. An
+    /// event taken here is gone: [`reload`][] takes events from the same
+    /// queue, so mixing the two means each misses the events the other took,
+    /// and a change can go unnoticed. Errors are handed over too, so a
+    /// watcher that stops working is no longer replaced; see
+    /// [`rearm_watcher`][] to do that by hand. The queue is coalesced as
+    /// described for [`pending_event_count`][], so a repeated event can come
+    /// out only once.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # extern crate notify;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use notify::DebouncedEvent;
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.inject_change_event();
+    /// while let Some(evt) = app.try_recv_event() {
+    ///     if let DebouncedEvent::Write(_) = evt {
+    ///         app.reload_now().unwrap();
+    ///     }
+    /// }
+    /// assert_eq!(app.reload_count(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    /// [`rearm_watcher`]: struct.Reloadable.html#method.rearm_watcher
+    /// [`pending_event_count`]: struct.Reloadable.html#method.pending_event_count
+    pub fn try_recv_event(&mut self) -> Option<notify::DebouncedEvent> {
+        self.events.try_recv()
+    }
+
+    /// Only let [`reload`][] swap in a changed library when `gate` allows it.
+    ///
+    /// `gate` is called whenever [`reload`][] has a change to apply, and the
+    /// reload is put off until it returns `true`. This separates detecting a
+    /// change from deciding that it's okay to reload right now, say only at a
+    /// safe point in a frame, or only while a debug key is held. Reloads you
+    /// ask for explicitly with [`reload_now`][] aren't gated.
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    pub fn set_reload_gate<F>(&mut self, gate: F)
+    where
+        F: FnMut(&Host) -> bool + Send + 'static,
+    {
+        self.reload_gate = Some(Box::new(gate));
+    }
+
+    /// Remove the gate set with [`set_reload_gate`][], so that changes are
+    /// reloaded straight away again.
+    ///
+    /// [`set_reload_gate`]: struct.Reloadable.html#method.set_reload_gate
+    pub fn clear_reload_gate(&mut self) {
+        self.reload_gate = None;
+    }
+
+    /// Totals over the reloads so far: how many succeeded and failed, how
+    /// long they took, and when the last one was.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// assert_eq!(app.metrics().average_reload_time(), None);
+    ///
+    /// app.reload_now().unwrap();
+    /// app.reload_now().unwrap();
+    /// let metrics = app.metrics();
+    /// assert_eq!((metrics.reloads(), metrics.failures()), (2, 0));
+    /// assert!(metrics.average_reload_time().unwrap() <= metrics.total_reload_time());
+    /// assert!(metrics.time_since_last_reload().is_some());
+    /// # }
+    /// ```
+    pub fn metrics(&self) -> ReloadMetrics {
+        self.metrics.clone()
+    }
+
+    /// How long the current code has been running, as the time since the
+    /// last successful reload finished, or `None` before the first one.
+    ///
+    /// This is the same as [`ReloadMetrics::time_since_last_reload`][],
+    /// without cloning the metrics, for a HUD or for holding something back
+    /// for a moment after each reload:
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    /// use std::time::Duration;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// assert_eq!(app.since_last_reload(), None);
+    ///
+    /// app.reload_now().unwrap();
+    /// let ignore_input = app.since_last_reload().is_some_and(|since| since < Duration::from_secs(60));
+    /// assert!(ignore_input);
+    /// # }
+    /// ```
+    ///
+    /// [`ReloadMetrics::time_since_last_reload`]: struct.ReloadMetrics.html#method.time_since_last_reload
+    pub fn since_last_reload(&self) -> Option<Duration> {
+        self.metrics.time_since_last_reload()
+    }
+
+    /// Call `sink` with the [`metrics`][] after each reload, successful or
+    /// not, say to pass them on to a metrics system.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use std::sync::mpsc::channel;
+    /// use live_reload::Reloadable;
+    ///
+    /// let (sender, receiver) = channel();
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.set_metrics_sink(move |metrics| sender.send(metrics.reloads()).unwrap());
+    /// app.reload_now().unwrap();
+    /// app.reload_now().unwrap();
+    /// assert_eq!(receiver.try_iter().collect::<Vec<_>>(), [1, 2]);
+    /// # }
+    /// ```
+    ///
+    /// [`metrics`]: struct.Reloadable.html#method.metrics
+    pub fn set_metrics_sink<F>(&mut self, sink: F)
+    where
+        F: FnMut(&ReloadMetrics) + Send + 'static,
+    {
+        self.metrics_sink = Some(Box::new(sink));
+    }
+
+    /// Stop calling the sink set with [`set_metrics_sink`][].
+    ///
+    /// [`set_metrics_sink`]: struct.Reloadable.html#method.set_metrics_sink
+    pub fn clear_metrics_sink(&mut self) {
+        self.metrics_sink = None;
+    }
+
+    /// Call `hook` with the old and new address of the `State` whenever the
+    /// buffer holding it moves.
+    ///
+    /// The buffer is resized to fit each library's `State` when it's loaded,
+    /// which can move it, as can restoring a saved state of another size. A
+    /// host that keeps a pointer into the `State` between calls can use this
+    /// to drop or rebase it. The hook is called before the library's `reload`
+    /// sees the new address, and not for the first allocation, when there was
+    /// no `State` to move yet. To keep the `State` from ever moving instead,
+    /// see [`Builder::reserve_state`][].
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { frames: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use std::sync::mpsc::channel;
+    /// use live_reload::Reloadable;
+    ///
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use live_reload::internals::ReloadApi;
+    ///
+    /// // A library whose `State` grows on the next reload, as if a field had
+    /// // been added to it.
+    /// static SIZE: AtomicUsize = AtomicUsize::new(8);
+    /// let api = ReloadApi { size: || SIZE.load(Ordering::SeqCst), ..RELOAD_API };
+    ///
+    /// let (sender, moves) = channel();
+    /// let mut app = Reloadable::new_in_process(api, Host).unwrap();
+    /// app.set_on_state_moved(move |old, new| sender.send((old as usize, new as usize)).unwrap());
+    /// // The `State` stays the same size, so it stays put.
+    /// app.reload_now().unwrap();
+    /// assert!(moves.try_recv().is_err());
+    ///
+    /// // Growing it to a megabyte needs a new buffer.
+    /// let old = app.state_bytes().as_ptr() as usize;
+    /// SIZE.store(1 << 20, Ordering::SeqCst);
+    /// app.reload_now().unwrap();
+    /// let new = app.state_bytes().as_ptr() as usize;
+    /// assert_ne!(old, new);
+    /// assert_eq!(moves.try_recv(), Ok((old, new)));
+    /// # }
+    /// ```
+    ///
+    /// [`Builder::reserve_state`]: struct.Builder.html#method.reserve_state
+    pub fn set_on_state_moved<F>(&mut self, hook: F)
+    where
+        F: FnMut(*mut (), *mut ()) + Send + 'static,
+    {
+        self.on_state_moved = Some(Box::new(hook));
+    }
+
+    /// Stop calling the hook set with [`set_on_state_moved`][].
+    ///
+    /// [`set_on_state_moved`]: struct.Reloadable.html#method.set_on_state_moved
+    pub fn clear_on_state_moved(&mut self) {
+        self.on_state_moved = None;
+    }
+
+    /// Call `hook` when the library starts asking to quit.
+    ///
+    /// `hook` is called once, from the first [`update`][] that returns
+    /// `ShouldQuit::Yes`, and not again for the updates after it that keep
+    /// returning `Yes`, so it can start a shutdown screen or save, without
+    /// the host keeping track of it. If an update returns `ShouldQuit::No`
+    /// in between, the next `Yes` calls it again. An observer hears about the
+    /// same moment in [`ReloadObserver::on_quit_requested`][].
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// pub struct Host { notices: u32 }
+    /// # #[repr(C)] pub struct State { frames: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     state.frames += 1;
+    ///     if state.frames >= 3 { ShouldQuit::Yes } else { ShouldQuit::No }
+    /// }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host { notices: 0 }).unwrap();
+    /// app.set_on_quit_requested(|host| host.notices += 1);
+    /// for _ in 0..5 {
+    ///     app.update();
+    /// }
+    /// assert_eq!(app.host().notices, 1);
+    /// # }
+    /// ```
+    ///
+    /// [`update`]: struct.Reloadable.html#method.update
+    /// [`ReloadObserver::on_quit_requested`]: trait.ReloadObserver.html#method.on_quit_requested
+    pub fn set_on_quit_requested<F>(&mut self, hook: F)
+    where
+        F: FnMut(&mut Host) + Send + 'static,
+    {
+        self.on_quit_requested = Some(Box::new(hook));
+    }
+
+    /// Stop calling the hook set with [`set_on_quit_requested`][].
+    ///
+    /// [`set_on_quit_requested`]: struct.Reloadable.html#method.set_on_quit_requested
+    pub fn clear_on_quit_requested(&mut self) {
+        self.on_quit_requested = None;
+    }
+
+    /// Have the library report how far along its `reload` is to `progress`.
+    ///
+    /// This is for migrations that take long enough for the host to look
+    /// frozen, so it can draw a loading bar instead. It only has an effect
+    /// on libraries declared with a `reload_progress` function (see
+    /// [`live_reload!`][]), which then gets called instead of `reload`, with
+    /// `progress` to call with values from `0.0` to `1.0`. `progress` is
+    /// called on the thread doing the reload, in the middle of it, so it
+    /// shouldn't touch the `Reloadable`.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host;
+    /// #[repr(C)]
+    /// pub struct State {
+    ///     rows: Vec<u32>,
+    /// }
+    ///
+    /// fn migrate(_: &mut Host, state: &mut State, progress: fn(f32)) {
+    ///     for (i, row) in state.rows.iter_mut().enumerate() {
+    ///         *row += 1;
+    ///         progress((i + 1) as f32 / 4.0);
+    ///     }
+    /// }
+    /// fn init(_: &mut Host, state: &mut State) {
+    ///     state.rows = vec![0; 4];
+    /// }
+    /// fn reload(host: &mut Host, state: &mut State) {
+    ///     migrate(host, state, |_| {});
+    /// }
+    /// fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: reload;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    ///     reload_progress: migrate;
+    /// }
+    ///
+    /// static REPORTS: AtomicUsize = AtomicUsize::new(0);
+    ///
+    /// fn draw_loading_bar(done: f32) {
+    ///     assert!(0.0 <= done && done <= 1.0);
+    ///     REPORTS.fetch_add(1, Ordering::SeqCst);
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.set_reload_progress(draw_loading_bar);
+    /// app.reload_now().unwrap();
+    /// assert_eq!(REPORTS.load(Ordering::SeqCst), 4);
+    /// # }
+    /// ```
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    pub fn set_reload_progress(&mut self, progress: fn(f32)) {
+        self.reload_progress = Some(progress);
+    }
+
+    /// Stop reporting reload progress, so the library's plain `reload` is
+    /// called again.
+    pub fn clear_reload_progress(&mut self) {
+        self.reload_progress = None;
+    }
+
+    /// Have `observer` hear about each step in the library's lifecycle from
+    /// now on, replacing any observer set before.
+    ///
+    /// See [`ReloadObserver`][] for which steps it hears about, and when.
+    ///
+    /// [`ReloadObserver`]: trait.ReloadObserver.html
+    pub fn set_observer(&mut self, observer: Box<dyn ReloadObserver<Host>>) {
+        self.observer = Some(observer);
+    }
+
+    /// Stop telling the observer about the library's lifecycle, and hand it
+    /// back.
+    pub fn take_observer(&mut self) -> Option<Box<dyn ReloadObserver<Host>>> {
+        self.observer.take()
+    }
+
+    /// The last few lifecycle steps and errors, oldest first.
+    ///
+    /// This is empty unless the log is kept with
+    /// [`Builder::reload_log_capacity`][].
+    ///
+    /// [`Builder::reload_log_capacity`]: struct.Builder.html#method.reload_log_capacity
+    pub fn reload_log(&self) -> &[ReloadLogEntry] {
+        self.reload_log.entries()
+    }
+
+    /// Stop watching the library for changes until [`resume_watching`][].
+    ///
+    /// This is for stretches where a reload would get in the way, like a
+    /// long asset import. No watcher events pile up in the meantime, and
+    /// [`reload`][] does nothing unless there's no library loaded at all.
+    /// [`reload_now`][] still reloads when asked to.
+    ///
+    /// [`resume_watching`]: struct.Reloadable.html#method.resume_watching
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    pub fn pause_watching(&mut self) -> Result<(), Error> {
+        if self.paused {
+            return Ok(());
+        }
+        if let Some(ref mut watcher) = self.watcher {
+            for dir in self.config.watched_dirs(&self.request_path) {
+                watcher.unwatch(dir)?;
+            }
+        }
+        self.paused = true;
+        Ok(())
+    }
+
+    /// Whether the library is being watched for changes, so that [`reload`][]
+    /// can notice them.
+    ///
+    /// This is false while watching is [paused][pause_watching], and for a
+    /// `Reloadable` with no watcher at all, like one made with
+    /// [`new_in_process`][] or with a [`Builder::trigger`][] in place of the
+    /// watcher. It says nothing about whether the watcher still works; see
+    /// [`watcher_healthy`][] for that.
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [pause_watching]: struct.Reloadable.html#method.pause_watching
+    /// [`new_in_process`]: struct.Reloadable.html#method.new_in_process
+    /// [`Builder::trigger`]: struct.Builder.html#method.trigger
+    /// [`watcher_healthy`]: struct.Reloadable.html#method.watcher_healthy
+    pub fn is_watching(&self) -> bool {
+        self.watcher.is_some() && !self.paused
+    }
+
+    /// Whether the library is being watched, and the watch looks like it
+    /// still works.
+    ///
+    /// This is [`is_watching`][], and a quick check that the library's
+    /// directory still exists, that the watcher hasn't stopped, and that it
+    /// hasn't reported an error that's waiting for [`reload`][]. It's cheap
+    /// enough to call every frame, for something like an
+    /// "auto-reload: on/off/broken" indicator. A watch that's broken can
+    /// usually be fixed with [`rearm_watcher`][], once the directory is back.
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # if cfg!(miri) { return; } // Miri can't watch files.
+    /// use std::fs;
+    /// use live_reload::{Builder, MissedChanges};
+    ///
+    /// let dir = std::env::temp_dir().join("live-reload-watcher-health-doctest");
+    /// # let _ = fs::remove_dir_all(&dir);
+    /// fs::create_dir_all(&dir).unwrap();
+    /// // The library isn't there yet, so this waits for it, watching `dir`.
+    /// let mut app = Builder::new(dir.join("libgame.so")).build(()).unwrap();
+    /// assert!(app.is_watching() && app.watcher_healthy());
+    ///
+    /// app.pause_watching().unwrap();
+    /// assert!(!app.is_watching() && !app.watcher_healthy());
+    /// app.resume_watching(MissedChanges::Reload).unwrap();
+    ///
+    /// fs::remove_dir_all(&dir).unwrap();
+    /// assert!(app.is_watching());
+    /// assert!(!app.watcher_healthy());
+    /// # }
+    /// ```
+    ///
+    /// [`is_watching`]: struct.Reloadable.html#method.is_watching
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`rearm_watcher`]: struct.Reloadable.html#method.rearm_watcher
+    pub fn watcher_healthy(&self) -> bool {
+        self.is_watching()
+            && !self.dir_removed
+            && watched_dir(&self.request_path).is_dir()
+            && !self.events.is_broken()
+    }
+
+    /// Start watching the library for changes again, after
+    /// [`pause_watching`][].
+    ///
+    /// Changes made while paused weren't seen by the watcher, so `missed`
+    /// says what to do about them: [`MissedChanges::Reload`][] compares the
+    /// library's modification time with the one it had when it was loaded,
+    /// and has the next [`reload`][] swap it in if they differ, while
+    /// [`MissedChanges::Ignore`][] also forgets any change that was seen
+    /// before pausing.
+    ///
+    /// [`pause_watching`]: struct.Reloadable.html#method.pause_watching
+    /// [`MissedChanges::Reload`]: enum.MissedChanges.html#variant.Reload
+    /// [`MissedChanges::Ignore`]: enum.MissedChanges.html#variant.Ignore
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    pub fn resume_watching(&mut self, missed: MissedChanges) -> Result<(), Error> {
+        if !self.paused {
+            return Ok(());
+        }
+        if let Some(ref mut watcher) = self.watcher {
+            for dir in self.config.watched_dirs(&self.request_path) {
+                watcher.watch(dir, self.config.recursive_mode())?;
+            }
+        }
+        self.paused = false;
+        match missed {
+            MissedChanges::Reload => {
+                if self.group_mtimes() != self.loaded_mtimes {
+                    self.pending_reload = true;
+                }
+            }
+            MissedChanges::Ignore => {
+                while self.events.try_recv().is_some() {}
+                self.pending_reload = false;
+            }
+        }
+        Ok(())
+    }
+
+    /// Block until the library changes, then reload it.
+    ///
+    /// This waits on the filesystem watcher for up to `timeout`. If the
+    /// library is recreated or updated in that time, it is reloaded with
+    /// [`reload_now`][] and `ReloadOutcome::Reloaded` is returned, otherwise
+    /// this gives up and returns `ReloadOutcome::Unchanged`. Any further events
+    /// already queued for the same change are drained so that a following
+    /// [`reload`][] doesn't reload a second time.
+    ///
+    /// This is mostly useful in tests, where you trigger a rebuild and then
+    /// want to wait for it to be picked up rather than polling [`reload`][].
+    /// Keep in mind that the watcher debounces events, so the timeout should
+    /// be comfortably longer than a second.
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    pub fn reload_blocking_until_changed(&mut self, timeout: Duration) -> Result<ReloadOutcome, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.events.recv_timeout(remaining) {
+                Ok(notify::DebouncedEvent::Error(err, _)) => self.watch_lost(err)?,
+                Ok(ref evt) if self.is_change(evt) => break,
+                Ok(_) => {}
+                Err(_) => return Ok(ReloadOutcome::Unchanged),
+            }
+        }
+        while self.events.try_recv().is_some() {}
+        self.reload_now()?;
+        Ok(ReloadOutcome::Reloaded)
+    }
+
+    /// A handle that waits for the watcher's events from another thread.
+    #[cfg(feature = "autoreload")]
+    pub(crate) fn event_waiter(&self) -> events::Waiter {
+        self.events.waiter()
+    }
+
+    /// Replace a watcher that reported an error with a fresh one, and then
+    /// report the error.
+    ///
+    /// Changes made while the old watcher was failing may have been missed,
+    /// so a reload is queued if the library's mtime has moved on since it
+    /// was loaded.
+    fn watch_lost(&mut self, err: notify::Error) -> Result<(), Error> {
+        self.rearm_watcher()?;
+        match self.config.on_watch_error {
+            Some(hook) => {
+                hook(&err);
+                Ok(())
+            }
+            None => Err(Error::WatchLost(err)),
+        }
+    }
+
+    /// Replace the filesystem watcher with a fresh one.
+    ///
+    /// A watch is tied to the directory it was made on, so if the library's
+    /// directory is removed and made again (say by `cargo clean` followed by
+    /// a build), the old watch never sees another event. [`reload`][] notices
+    /// the directory being removed, and does this by itself once it's back,
+    /// but you can also call it whenever you know the watch has gone stale.
+    /// If the library changed since it was loaded, the next [`reload`][]
+    /// picks that up too, since the old watcher may have missed it.
+    ///
+    /// This does nothing for a `Reloadable` that has no watcher (see
+    /// [`new_in_process`][]), and keeps the new watcher paused if watching
+    /// was paused with [`pause_watching`][].
+    ///
+    /// # Example
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use std::fs;
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new("target/debug/libgame.so", Host).unwrap();
+    /// let built = fs::read("target/debug/libgame.so").unwrap();
+    /// fs::remove_dir_all("target/debug").unwrap();
+    /// fs::create_dir_all("target/debug").unwrap();
+    /// fs::write("target/debug/libgame.so", built).unwrap();
+    ///
+    /// app.rearm_watcher().unwrap();
+    /// app.reload().unwrap();
+    /// assert_eq!(app.reload_count(), 1);
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`new_in_process`]: struct.Reloadable.html#method.new_in_process
+    /// [`pause_watching`]: struct.Reloadable.html#method.pause_watching
+    pub fn rearm_watcher(&mut self) -> Result<(), Error> {
+        if self.watcher.is_none() {
+            return Ok(());
+        }
+        let (mut watcher, events) = Self::watch(&self.request_path, &self.config)?;
+        if self.paused {
+            for dir in self.config.watched_dirs(&self.request_path) {
+                watcher.unwatch(dir)?;
+            }
+        }
+        self.watcher = Some(watcher);
+        self.events = events;
+        self.dir_removed = false;
+        if self.group_mtimes() != self.loaded_mtimes {
+            self.pending_reload = true;
+        }
+        Ok(())
+    }
+
+    /// Have the watcher wait `debounce` for writes to settle before reporting
+    /// a change, from now on.
+    ///
+    /// The watcher's debounce can't be changed once it's running, so this
+    /// replaces it with a new one, like [`rearm_watcher`][]. The loaded
+    /// library and its state are kept, and so are changes the old watcher
+    /// already reported. One it was still waiting on is picked up by the next
+    /// [`reload`][], since the library's modification time has moved on.
+    ///
+    /// This does nothing for a `Reloadable` that has no watcher (see
+    /// [`new_in_process`][]). To start out with a different debounce, use
+    /// [`Builder::debounce`][].
+    ///
+    /// ```rust
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # if cfg!(miri) { return; } // Miri can't watch files.
+    /// use std::time::{Duration, Instant};
+    /// use live_reload::Reloadable;
+    ///
+    /// let dir = std::env::temp_dir().join(format!("live-reload-debounce-{}", std::process::id()));
+    /// std::fs::create_dir_all(&dir).unwrap();
+    /// let path = dir.join("libnot_built_yet.so");
+    ///
+    /// // Starting out with an hour-long debounce, the change below would
+    /// // only be reported in an hour.
+    /// let mut app = Reloadable::builder(&path)
+    ///     .debounce(Duration::from_secs(60 * 60))
+    ///     .build(())
+    ///     .unwrap();
+    /// app.set_debounce(Duration::from_millis(10)).unwrap();
+    /// std::fs::write(&path, b"not really a library").unwrap();
+    ///
+    /// let deadline = Instant::now() + Duration::from_secs(10);
+    /// while app.pending_event_count() == 0 && Instant::now() < deadline {
+    ///     std::thread::sleep(Duration::from_millis(10));
+    /// }
+    /// assert!(app.pending_event_count() > 0);
+    /// # drop(app);
+    /// # std::fs::remove_dir_all(&dir).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`rearm_watcher`]: struct.Reloadable.html#method.rearm_watcher
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`new_in_process`]: struct.Reloadable.html#method.new_in_process
+    /// [`Builder::debounce`]: struct.Builder.html#method.debounce
+    pub fn set_debounce(&mut self, debounce: Duration) -> Result<(), Error> {
+        self.drain_and_coalesce()?;
+        self.config.debounce = debounce;
+        self.rearm_watcher()
+    }
+
+    /// Does this watcher event mean that the library has changed?
+    fn is_change(&self, evt: &notify::DebouncedEvent) -> bool {
+        let ignored = &self.config.ignored_extensions;
+        let companion_changed = self.config.companions.iter().any(|companion| {
+            events::is_change(evt, companion, companion, ignored)
+        });
+        companion_changed || match self.config.event_filter {
+            Some(ref filter) => events::is_change_by(evt, &self.request_path, ignored, |changed| filter(changed)),
+            None => events::is_change(evt, &self.path, &self.request_path, ignored),
+        }
+    }
+
+    /// Immediately reload the library without checking whether it has changed.
+    ///
+    /// This first calls `unload` on the currently loaded library, then unloads
+    /// the dynamic library. Next, it loads the new dynamic library, and calls
+    /// `reload` on that. If the new library fails to load, this method will
+    /// return an `Err` and the `Reloadable` will be left with no library
+    /// loaded. It can be retried first; see [`Builder::reload_retries`][].
+    ///
+    /// On success, this returns what's known about the library before and
+    /// after the swap, which is what a log line of the reload needs:
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { frames: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let transition = app.reload_now().unwrap();
+    /// let old = transition.before().unwrap();
+    /// let new = transition.after();
+    /// println!(
+    ///     "reloaded from build {:?} ({:?} bytes) to {:?} ({:?} bytes)",
+    ///     old.build_id(), old.state_size(), new.build_id(), new.state_size(),
+    /// );
+    /// assert_eq!(old.state_size(), Some(8));
+    /// assert_eq!(new.state_size(), Some(8));
+    /// # }
+    /// ```
+    ///
+    /// If the new library's `State` is smaller than the old one's, the bytes
+    /// past its end are zeroed before `reload` is called, unless the hook set
+    /// with [`Builder::on_state_shrink`][] says otherwise. A change in size
+    /// that comes without a new state version can be caught with
+    /// [`Builder::on_unexpected_size_change`][].
+    ///
+    /// If `unload` panics, this returns [`Error::LibraryPanicked`][] and the
+    /// old library stays loaded. If `reload` panics, it returns the same error
+    /// with no library loaded.
+    ///
+    /// If the new library has a `validate_state` function, it's called after
+    /// `reload`, and a `false` undoes the reload; see below.
+    ///
+    /// # Order of calls
+    ///
+    /// The order of the lifecycle calls is part of the contract, so that a
+    /// library can rely on it to hand resources over between versions:
+    ///
+    /// 1. [`new`][] opens the library and calls `init`.
+    /// 2. A reload calls the old library's `unload`, then closes it, then
+    ///    opens the new library and calls its `reload`. The two versions are
+    ///    never loaded at the same time, unless the old one is kept by
+    ///    [`Builder::retain_old_images`][], in which case it's only closed
+    ///    later, without any more calls into it.
+    /// 3. If `unload` panics, nothing else happens, and the old library keeps
+    ///    running. If the new library fails to load, or its `reload` panics,
+    ///    no library is loaded until the next successful reload, which calls
+    ///    `reload` (not `init`) on the same state.
+    /// 4. Dropping the `Reloadable` calls `deinit` on the loaded library, if
+    ///    there is one, and then closes it.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use std::sync::Mutex;
+    /// use live_reload::{Error, Reloadable, ShouldQuit};
+    ///
+    /// static CALLS: Mutex<Vec<&'static str>> = Mutex::new(Vec::new());
+    ///
+    /// pub struct Host {
+    ///     fail_unload: bool,
+    ///     fail_reload: bool,
+    /// }
+    /// #[repr(C)]
+    /// pub struct State;
+    ///
+    /// fn call(name: &'static str) {
+    ///     CALLS.lock().unwrap().push(name);
+    /// }
+    /// fn init(_: &mut Host, _: &mut State) { call("init") }
+    /// fn reload(host: &mut Host, _: &mut State) {
+    ///     call("reload");
+    ///     if host.fail_reload { panic!("reload failed") }
+    /// }
+    /// fn update(_: &mut Host, _: &mut State) -> ShouldQuit {
+    ///     call("update");
+    ///     ShouldQuit::No
+    /// }
+    /// fn unload(host: &mut Host, _: &mut State) {
+    ///     call("unload");
+    ///     if host.fail_unload { panic!("unload failed") }
+    /// }
+    /// fn deinit(_: &mut Host, _: &mut State) { call("deinit") }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: reload;
+    ///     update: update;
+    ///     unload: unload;
+    ///     deinit: deinit;
+    /// }
+    ///
+    /// fn take_calls() -> Vec<&'static str> {
+    ///     std::mem::take(&mut *CALLS.lock().unwrap())
+    /// }
+    ///
+    /// # fn main() {
+    /// let host = Host { fail_unload: false, fail_reload: false };
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, host).unwrap();
+    /// app.update();
+    /// app.reload_now().unwrap();
+    /// assert_eq!(take_calls(), ["init", "update", "unload", "reload"]);
+    ///
+    /// // A failed `unload` leaves the old library running.
+    /// app.host_mut().fail_unload = true;
+    /// assert!(matches!(app.reload_now(), Err(Error::LibraryPanicked { .. })));
+    /// app.host_mut().fail_unload = false;
+    /// app.update();
+    /// assert_eq!(take_calls(), ["unload", "update"]);
+    ///
+    /// // A failed `reload` leaves nothing loaded, until the next reload.
+    /// app.host_mut().fail_reload = true;
+    /// assert!(matches!(app.reload_now(), Err(Error::LibraryPanicked { .. })));
+    /// app.host_mut().fail_reload = false;
+    /// app.update();
+    /// app.reload().unwrap();
+    /// app.update();
+    /// assert_eq!(take_calls(), ["unload", "reload", "reload", "update"]);
+    ///
+    /// drop(app);
+    /// assert_eq!(take_calls(), ["deinit"]);
+    /// # }
+    /// ```
+    ///
+    /// # Validating the state
+    ///
+    /// A library can check that its `State` still holds together after its
+    /// `reload`, with the `validate_state` function given to
+    /// [`live_reload!`][]. When that returns `false` (or panics), the new
+    /// version's `unload` is called, the state is put back byte for byte as
+    /// it was handed over, and the previous version of the library is loaded
+    /// again with a call to its `reload`, if it was kept by
+    /// [`Builder::retain_old_images`][]. The reload then returns
+    /// [`Error::StateValidationFailed`][].
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{Error, Reloadable, ShouldQuit};
+    ///
+    /// pub struct Host {
+    ///     break_next_reload: bool,
+    /// }
+    /// #[repr(C)]
+    /// pub struct State {
+    ///     selected: usize,
+    ///     items: [u32; 4],
+    /// }
+    ///
+    /// fn init(_: &mut Host, state: &mut State) { state.items = [1, 2, 3, 4] }
+    /// fn reload(host: &mut Host, state: &mut State) {
+    ///     if host.break_next_reload {
+    ///         host.break_next_reload = false;
+    ///         state.selected = 99;
+    ///     }
+    /// }
+    /// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     state.selected = (state.selected + 1) % state.items.len();
+    ///     ShouldQuit::No
+    /// }
+    /// fn unload(_: &mut Host, _: &mut State) {}
+    /// fn deinit(_: &mut Host, _: &mut State) {}
+    /// fn validate_state(state: &State) -> bool {
+    ///     state.selected < state.items.len()
+    /// }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: reload;
+    ///     update: update;
+    ///     unload: unload;
+    ///     deinit: deinit;
+    ///     validate_state: validate_state;
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host { break_next_reload: false }).unwrap();
+    /// app.update();
+    /// let before = app.save_state();
+    ///
+    /// app.host_mut().break_next_reload = true;
+    /// match app.reload_now() {
+    ///     Err(Error::StateValidationFailed { rolled_back }) => assert!(rolled_back),
+    ///     other => panic!("expected a failed validation, got {:?}", other),
+    /// }
+    /// assert_eq!(app.save_state().bytes(), before.bytes());
+    /// # }
+    /// ```
+    ///
+    /// [`new`]: struct.Reloadable.html#method.new
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`Error::StateValidationFailed`]: enum.Error.html#variant.StateValidationFailed
+    /// [`Builder::on_state_shrink`]: struct.Builder.html#method.on_state_shrink
+    /// [`Builder::on_unexpected_size_change`]: struct.Builder.html#method.on_unexpected_size_change
+    /// [`Builder::reload_retries`]: struct.Builder.html#method.reload_retries
+    /// [`Builder::retain_old_images`]: struct.Builder.html#method.retain_old_images
+    /// [`Error::LibraryPanicked`]: enum.Error.html#variant.LibraryPanicked
+    /// [`update`]: struct.Reloadable.html#method.update
+    pub fn reload_now(&mut self) -> Result<ReloadTransition, Error> {
+        let hash = if self.config.quarantine_bad_libraries { self.library_hash() } else { None };
+        if let Some(hash) = hash {
+            if self.quarantine.contains(&hash) {
+                return Err(Error::QuarantinedLibrary { hash });
+            }
+        }
+        let before = self.library_info();
+        if let Err(err) = self.instrumented(Phase::Reload, Self::reload_inner) {
+            if let Some(hash) = hash.filter(|_| is_bad_library(&err)) {
+                self.quarantine.push(hash);
+            }
+            return Err(err);
+        }
+        self.quarantine.clear();
+        let after = self.library_info().unwrap_or_else(|| self.describe_file());
+        Ok(ReloadTransition { before, after })
+    }
+
+    /// Reload the library from `path`, rather than from the watched one,
+    /// without changing which library is watched.
+    ///
+    /// This does the same unload, load, and reload as [`reload_now`][], with
+    /// the same state, but only for this one reload. It's for trying another
+    /// build of the library against the live state, say to compare two
+    /// versions of some logic. The watched library is still what [`reload`][]
+    /// checks for changes, and the next reload of it swaps back, so use
+    /// [`reload_now`][] to swap back straight away.
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # struct Host;
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new("target/debug/libgame.so", Host).unwrap();
+    /// app.update();
+    /// app.reload_from("builds/experiment/libgame.so").unwrap();
+    /// app.update();
+    /// app.reload_now().unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    pub fn reload_from<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let path = self.config.resolve(path.as_ref())?;
+        self.instrumented(Phase::Reload, |app| app.swap_library(Some(&path)))
+    }
+
+    fn reload_inner(&mut self) -> Result<(), Error> {
+        self.pending_reload = false;
+        self.swap_library(None)
+    }
+
+    /// Unload the current library, and load the watched one again, or the one
+    /// at `from` instead.
+    fn swap_library(&mut self, from: Option<&Path>) -> Result<(), Error> {
+        let traced = self.config.trace_state_checksums && self.initialized && self.sym.is_some();
+        let checksum_before = if traced { Some(self.state_checksum()) } else { None };
+        if self.config.debugger_friendly {
+            if let Some(file) = self.sym.as_ref().and_then(AppSym::file) {
+                debugger::reload_starting(file);
+            }
+        }
+        if let (true, Some(sym)) = (self.initialized, self.sym.as_ref()) {
+            let unload = sym.api().unload;
+            let host = &mut self.host;
+            let state = Self::get_state_ptr(&mut self.state);
+            watchdog::call(self.watchdog.as_ref(), LifecyclePhase::Unload, |sink| unload(host, state, sink))?;
+            self.observe(|observer, app| observer.on_unload(app));
+            self.log(ReloadLogKind::Unload, |app| app.path.display().to_string());
+        }
+        let kept_old = match self.sym.take() {
+            Some(old) => {
+                self.load_generation += 1;
+                self.retire(old);
+                self.config.retain_old_images > 0
+            }
+            None => false,
+        };
+        let mut retries = self.config.reload_retries;
+        let has_file = self.has_file();
+        let sized = match from {
+            Some(path) => Some(path.to_path_buf()),
+            None if has_file => Some(self.config.library_file(&self.request_path)),
+            None => None,
+        };
+        let (sym, loaded_mtimes) = loop {
+            // A library linked into place whole can still be filling up, so
+            // an empty one is waited for, and one whose size changed while
+            // it was being loaded is loaded again once it's had time to
+            // settle. One that's already complete is loaded straight away.
+            let size = sized.as_ref().map(|path| file_size(path));
+            if retries > 0 && size == Some(None) {
+                retries -= 1;
+                std::thread::sleep(self.config.retry_delay);
+                continue;
+            }
+            let loaded_mtimes = self.group_mtimes();
+            let loaded = match from {
+                Some(path) => AppSym::new(path, &self.config),
+                None => self.load(),
+            };
+            match loaded {
+                Ok(sym) => {
+                    let settled = sized.as_ref().map(|path| file_size(path)) == size;
+                    if settled || retries == 0 {
+                        break (sym, loaded_mtimes);
+                    }
+                    drop(sym);
+                    retries -= 1;
+                    std::thread::sleep(self.config.retry_delay);
+                }
+                Err(Error::Io(_)) if retries > 0 => {
+                    retries -= 1;
+                    std::thread::sleep(self.config.retry_delay);
+                }
+                Err(err) => return Err(err),
+            }
+        };
+        if self.config.debugger_friendly {
+            if let Some(file) = sym.file() {
+                debugger::library_loaded(file);
+            }
+        }
+        // A one-off library doesn't count as having seen the watched one.
+        if from.is_none() && has_file {
+            self.loaded_mtimes = loaded_mtimes;
+            if let Ok(path) = self.request_path.canonicalize() {
+                self.path = path;
+            }
+        }
+        let old_size = self.state_size;
+        let (new_version, migrate) = sym.api().state_migrations();
+        let migrate = migrate.filter(|_| self.initialized && self.state_version < new_version);
+        // The state as it's handed over, for the migrations to read, and to
+        // roll back to if one fails or the new version finds it invalid.
+        let validates = sym.api().capabilities().contains(Capabilities::VALIDATE_STATE);
+        let handed_over = if self.initialized && (validates || migrate.is_some()) {
+            Some(self.state.to_units())
+        } else {
+            None
+        };
+        let new_size = (sym.api().size)();
+        self.check_state_size(new_size)?;
+        if let Some(hook) = self.config.on_unexpected_size_change {
+            let same_version = sym.api().state_migrations().0 == self.state_version;
+            if self.initialized && new_size != old_size && same_version {
+                hook(&mut self.host, old_size, new_size);
+            }
+        }
+        if new_size < old_size {
+            let action = match self.config.on_state_shrink {
+                Some(hook) => hook(&mut self.host, old_size, new_size),
+                None => ShrinkAction::ZeroTail,
+            };
+            if action == ShrinkAction::Abort {
+                return Err(Error::StateShrank { old_size, new_size });
+            }
+            let bytes = self.state.as_mut_bytes();
+            let end = old_size.min(bytes.len());
+            if new_size < end {
+                for byte in &mut bytes[new_size..end] {
+                    *byte = 0;
+                }
+            }
+        }
+        // @Avoid reallocating if unnecessary
+        self.realloc_buffer(new_size)?;
+        self.state_size = new_size;
+        if self.initialized {
+            // Bring the state up to the new version a step at a time, each
+            // step seeing the state as the old version left it. Going back to
+            // an older version has nothing to run.
+            if let (Some(migrate), Some(units)) = (migrate, handed_over.as_ref()) {
+                let old = &units_as_bytes(units)[..old_size];
+                for from in self.state_version..new_version {
+                    let host = &mut self.host;
+                    let state = Self::get_state_ptr(&mut self.state);
+                    let migrated = watchdog::call(self.watchdog.as_ref(), LifecyclePhase::Reload, |sink| {
+                        migrate(host, state, from, old, sink)
+                    });
+                    if let Err(err) = migrated {
+                        // Undo the steps before it, so that the state is
+                        // still at `state_version`, and the next reload runs
+                        // them all again.
+                        self.load_buffer(units)?;
+                        self.state_size = old_size;
+                        return Err(err);
+                    }
+                }
+            }
+            let api = sym.api();
+            let host = &mut self.host;
+            let state = Self::get_state_ptr(&mut self.state);
+            let progress = self.reload_progress;
+            watchdog::call(self.watchdog.as_ref(), LifecyclePhase::Reload, |sink| {
+                api.call_reload(host, state, progress, sink)
+            })?;
+        }
+        if let Some(units) = handed_over {
+            let state = Self::get_state_ptr(&mut self.state);
+            if !sym.api().state_is_valid(state) {
+                return self.roll_back(sym, old_size, &units, kept_old);
+            }
+        }
+        let old_layout = self.state_layout;
+        self.state_layout = sym.state_layout();
+        if self.initialized {
+            self.state_version = new_version;
+        }
+        self.sym = Some(sym);
+        self.deferred = false;
+        self.reload_count += 1;
+        if let (Some(old), Some(new)) = (old_layout, self.state_layout) {
+            if old != new {
+                self.observe(|observer, app| observer.on_migrate(app, old, new));
+                self.log(ReloadLogKind::Migrate, |_| format!("{:016x} -> {:016x}", old, new));
+            }
+        }
+        if let Some(before) = checksum_before {
+            let after = self.state_checksum();
+            self.observe(|observer, app| observer.on_state_checksums(app, before, after));
+        }
+        self.observe(|observer, app| observer.on_reload(app));
+        self.log(ReloadLogKind::Reload, |app| app.path.display().to_string());
+
+        Ok(())
+    }
+
+    /// Undo a reload whose new version `new` found the state invalid, by
+    /// restoring the state it was handed, `units` of `size` bytes, and going
+    /// back to the previous version if it was kept.
+    fn roll_back(&mut self, new: AppSym<Host>, size: usize, units: &[u64], kept_old: bool) -> Result<(), Error> {
+        // Let the new version let go of whatever its `reload` took. It's
+        // being thrown away either way, so a panic here changes nothing.
+        {
+            let unload = new.api().unload;
+            let host = &mut self.host;
+            let state = Self::get_state_ptr(&mut self.state);
+            let _ = watchdog::call(self.watchdog.as_ref(), LifecyclePhase::Unload, |sink| {
+                unload(host, state, sink)
+            });
+        }
+        let previous = if kept_old { self.old_images.pop_back() } else { None };
+        #[cfg(feature = "in-process")]
+        let previous = previous.or_else(|| self.in_process.map(AppSym::in_process));
+        self.retire(new);
+        self.load_buffer(units)?;
+        self.state_size = size;
+        let previous = match previous {
+            Some(previous) => previous,
+            None => return Err(Error::StateValidationFailed { rolled_back: false }),
+        };
+        self.debug_assert_initialized(LifecyclePhase::Reload);
+        {
+            let api = previous.api();
+            let host = &mut self.host;
+            let state = Self::get_state_ptr(&mut self.state);
+            let progress = self.reload_progress;
+            watchdog::call(self.watchdog.as_ref(), LifecyclePhase::Reload, |sink| {
+                api.call_reload(host, state, progress, sink)
+            })?;
+        }
+        self.sym = Some(previous);
+        Err(Error::StateValidationFailed { rolled_back: true })
+    }
+
+    /// Call the update method on the library.
+    ///
+    /// If no library is currently loaded, this does nothing and returns
+    /// [`ShouldQuit::No`](enum.ShouldQuit.html#). If history is enabled, the
+    /// state after the update is recorded; see [`enable_history`][].
+    ///
+    /// # Panics
+    ///
+    /// Panics if the library panics, with the library's message and location.
+    /// Use [`try_update`][] to get an error instead.
+    ///
+    /// [`enable_history`]: struct.Reloadable.html#method.enable_history
+    /// [`try_update`]: struct.Reloadable.html#method.try_update
+    pub fn update(&mut self) -> ShouldQuit {
+        match self.try_update() {
+            Ok(quit) => quit,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Call the update method on the library, catching any panic.
+    ///
+    /// This is like [`update`][], except that a panic in the library is caught
+    /// and returned as [`Error::LibraryPanicked`][], with the panic message
+    /// and the location in the library's source where it happened. The
+    /// library stays loaded, so you can fix the bug and let it reload.
+    /// `init`, `reload` and `unload` are always guarded like this, by the
+    /// methods that call them.
+    ///
+    /// # Panic hook
+    ///
+    /// Panics are caught on the library's side of the boundary, by the
+    /// functions that [`live_reload!`][] generates, since unwinding out of a
+    /// `cdylib` into the host would abort the process. The location is
+    /// captured by a panic hook that is installed just for the duration of
+    /// the call, and the previous hook is put back afterwards. Since the hook
+    /// is global to the process (or, for a `cdylib`, to the library's own copy
+    /// of the standard library), keep in mind that:
+    ///
+    /// - Panics on other threads during the call still go to the previous
+    ///   hook, but a hook that one of them installs in the meantime is lost.
+    /// - Guarded calls are serialized, so concurrent calls into the same
+    ///   library won't swap each other's hooks.
+    /// - The message of a panic on the calling thread isn't printed, since
+    ///   it's returned in the error instead.
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`update`]: struct.Reloadable.html#method.update
+    /// [`Error::LibraryPanicked`]: enum.Error.html#variant.LibraryPanicked
+    pub fn try_update(&mut self) -> Result<ShouldQuit, Error> {
+        self.instrumented(Phase::Update, |app| {
+            app.update_inner(|api, host, state, sink| (api.update)(host, state, sink))
+        })
+    }
+
+    /// Call the update method on the library, passing it `events`.
+    ///
+    /// This is for a library declared with `events: InputEvent;` in
+    /// [`live_reload!`][], whose `update` takes a `&[InputEvent]` after the
+    /// `&mut State`. The slice is handed over as is, without copying, so `E`
+    /// has to be the same `#[repr(C)]` type as the library's `InputEvent`,
+    /// which is best declared next to the `Host`. If the sizes of the two
+    /// don't match, the library panics instead of reading the events.
+    ///
+    /// A library that doesn't take events gets a plain `update`, and
+    /// `events` are ignored. Otherwise, this behaves like [`update`][].
+    ///
+    /// # Safety
+    ///
+    /// `E` must have the same layout as the library's event type. Only the
+    /// sizes are compared, so the library reads any other type of the same
+    /// size as its events.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host;
+    ///
+    /// #[repr(C)]
+    /// pub enum InputEvent {
+    ///     KeyDown(u32),
+    ///     Quit,
+    /// }
+    ///
+    /// struct State { keys: u32 }
+    ///
+    /// fn update(_: &mut Host, state: &mut State, events: &[InputEvent]) -> ShouldQuit {
+    ///     for event in events {
+    ///         match *event {
+    ///             InputEvent::KeyDown(_) => state.keys += 1,
+    ///             InputEvent::Quit => return ShouldQuit::Yes,
+    ///         }
+    ///     }
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     events: InputEvent;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let frame = [InputEvent::KeyDown(32), InputEvent::KeyDown(13)];
+    /// // `InputEvent` is the library's own event type.
+    /// assert_eq!(unsafe { app.update_with_events(&frame) }, ShouldQuit::No);
+    /// assert_eq!(app.update(), ShouldQuit::No);
+    /// assert_eq!(app.state_bytes(), &2u32.to_ne_bytes());
+    /// assert_eq!(unsafe { app.update_with_events(&[InputEvent::Quit]) }, ShouldQuit::Yes);
+    /// # }
+    /// ```
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`update`]: struct.Reloadable.html#method.update
+    pub unsafe fn update_with_events<E>(&mut self, events: &[E]) -> ShouldQuit {
+        match self.try_update_with_events(events) {
+            Ok(quit) => quit,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Call the update method on the library, passing it `events`, and
+    /// catching any panic.
+    ///
+    /// This is to [`update_with_events`][] what [`try_update`][] is to
+    /// [`update`][].
+    ///
+    /// # Safety
+    ///
+    /// As for `update_with_events`, `E` must have the same layout as the
+    /// library's event type.
+    ///
+    /// [`update_with_events`]: struct.Reloadable.html#method.update_with_events
+    /// [`try_update`]: struct.Reloadable.html#method.try_update
+    /// [`update`]: struct.Reloadable.html#method.update
+    pub unsafe fn try_update_with_events<E>(&mut self, events: &[E]) -> Result<ShouldQuit, Error> {
+        self.instrumented(Phase::Update, |app| {
+            app.update_inner(|api, host, state, sink| api.call_update(host, state, events, sink))
+        })
+    }
+
+    /// Call the update method on the library, lending it `extra` for the
+    /// call.
+    ///
+    /// This is for a library declared with `extra: Frame;` in
+    /// [`live_reload!`][], whose `update` takes a `&mut Frame` after the
+    /// `&mut State`, for data that only lives for a frame and so doesn't
+    /// belong in the `Host`. `extra` is handed over by pointer, so `T` has to
+    /// be the same `#[repr(C)]` type as the library's `Frame`, which is best
+    /// declared next to the `Host`. If the sizes of the two don't match, the
+    /// library panics instead of touching `extra`.
+    ///
+    /// A library that doesn't take extra data gets a plain `update`, and
+    /// `extra` is left alone. Otherwise, this behaves like [`update`][].
+    ///
+    /// # Safety
+    ///
+    /// `T` must have the same layout as the library's extra type. Only the
+    /// sizes are compared, so the library uses any other type of the same
+    /// size as its own.
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host;
+    ///
+    /// #[repr(C)]
+    /// #[derive(Default)]
+    /// pub struct Frame {
+    ///     commands: Vec<&'static str>,
+    /// }
+    ///
+    /// #[repr(C)] pub struct State;
+    ///
+    /// fn update(_: &mut Host, _: &mut State, frame: &mut Frame) -> ShouldQuit {
+    ///     frame.commands.push("draw");
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     extra: Frame;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let mut frame = Frame::default();
+    /// // `Frame` is the library's own extra type.
+    /// unsafe {
+    ///     app.update_with(&mut frame);
+    ///     app.update_with(&mut frame);
+    /// }
+    /// assert_eq!(frame.commands, ["draw", "draw"]);
+    /// # }
+    /// ```
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`update`]: struct.Reloadable.html#method.update
+    pub unsafe fn update_with<T>(&mut self, extra: &mut T) -> ShouldQuit {
+        match self.try_update_with(extra) {
+            Ok(quit) => quit,
+            Err(err) => panic!("{}", err),
+        }
+    }
+
+    /// Call the update method on the library, lending it `extra` for the
+    /// call, and catching any panic.
+    ///
+    /// This is to [`update_with`][] what [`try_update`][] is to
+    /// [`update`][].
+    ///
+    /// # Safety
+    ///
+    /// As for `update_with`, `T` must have the same layout as the library's
+    /// extra type.
+    ///
+    /// [`update_with`]: struct.Reloadable.html#method.update_with
+    /// [`try_update`]: struct.Reloadable.html#method.try_update
+    /// [`update`]: struct.Reloadable.html#method.update
+    pub unsafe fn try_update_with<T>(&mut self, extra: &mut T) -> Result<ShouldQuit, Error> {
+        self.instrumented(Phase::Update, |app| {
+            app.update_inner(|api, host, state, sink| api.call_update_with(host, state, &mut *extra, sink))
+        })
+    }
+
+    /// Run the whole frame loop: reload if needed, update, and wait for the
+    /// next tick, until the library asks to quit.
+    ///
+    /// Each frame calls [`reload`][] and then [`try_update`][], and then
+    /// sleeps until `tick` has passed since the frame started. The ticks are
+    /// scheduled on a fixed timestep, so the time spent in each frame doesn't
+    /// make the loop drift, and a frame that overruns just starts the next one
+    /// straight away instead of trying to catch up. This returns `Ok` once the
+    /// library's `update` returns `ShouldQuit::Yes`, or the first error from
+    /// either call.
+    ///
+    /// A call to [`interrupt`][] stops the loop too, returning `Ok`, so that
+    /// `deinit` still runs when the `Reloadable` is [shut down][shutdown] or
+    /// dropped. With [`Builder::handle_interrupts`][] turned on, so does the
+    /// first Ctrl-C (or `SIGTERM`) on Unix while this runs, and a second one
+    /// kills the process as usual.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host;
+    ///
+    /// struct State { frames: u64 }
+    ///
+    /// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     state.frames += 1;
+    ///     if state.frames == 3 { ShouldQuit::Yes } else { ShouldQuit::No }
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// use std::time::Duration;
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.update_until_quit(Duration::from_millis(1)).unwrap();
+    /// assert_eq!(app.state_bytes(), &3u64.to_ne_bytes());
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`try_update`]: struct.Reloadable.html#method.try_update
+    /// [shutdown]: struct.Reloadable.html#method.shutdown
+    /// [`Builder::handle_interrupts`]: struct.Builder.html#method.handle_interrupts
+    /// [`interrupt`]: fn.interrupt.html
+    pub fn update_until_quit(&mut self, tick: Duration) -> Result<(), Error> {
+        let _interrupt = if self.config.handle_interrupts {
+            Some(runner::Interrupt::install())
+        } else {
+            None
+        };
+        let mut next_tick = Instant::now();
+        while !runner::take_interrupt() {
+            self.reload()?;
+            if self.try_update()? == ShouldQuit::Yes {
+                break;
+            }
+            runner::wait_for_tick(&mut next_tick, tick);
+        }
+        Ok(())
+    }
+
+    /// Run one update, with `call` picking which of the library's update
+    /// functions it goes through.
+    fn update_inner<F>(&mut self, mut call: F) -> Result<ShouldQuit, Error>
+    where
+        F: FnMut(&internals::ReloadApi<Host>, &mut Host, *mut (), &mut PanicSink) -> ShouldQuit,
+    {
+        if self.inert {
+            return Ok(ShouldQuit::No);
+        }
+        self.ensure_init()?;
+        let quit = if let Some(ref sym) = self.sym {
+            self.debug_assert_initialized(LifecyclePhase::Update);
+            let api = sym.api();
+            let host = &mut self.host;
+            let state = Self::get_state_ptr(&mut self.state);
+            watchdog::call(self.watchdog.as_ref(), LifecyclePhase::Update, |sink| {
+                call(api, host, state, sink)
+            })?
+        } else {
+            return Ok(ShouldQuit::No);
+        };
+        self.push_history();
+        let requested = quit == ShouldQuit::Yes;
+        if requested && !self.quit_requested {
+            if let Some(ref mut hook) = self.on_quit_requested {
+                hook(&mut self.host);
+            }
+            self.observe(|observer, app| observer.on_quit_requested(app));
+            self.log(ReloadLogKind::QuitRequested, |_| String::new());
+        }
+        self.quit_requested = requested;
+        Ok(quit)
+    }
+
+    /// Run one phase of work, through the hook set with
+    /// [`Builder::instrument`](struct.Builder.html#method.instrument) if there
+    /// is one.
+    fn instrumented<R, F>(&mut self, phase: Phase, mut run: F) -> Result<R, Error>
+    where
+        F: FnMut(&mut Self) -> Result<R, Error>,
+    {
+        let started = Instant::now();
+        let result = self.instrumented_inner(phase, &mut run);
+        if phase == Phase::Reload {
+            self.metrics.record(started.elapsed(), result.is_ok());
+            if let Some(ref mut sink) = self.metrics_sink {
+                sink(&self.metrics);
+            }
+        }
+        if let Err(ref err) = result {
+            self.observe(|observer, app| observer.on_error(app, err));
+            self.log(ReloadLogKind::Error, |_| err.to_string());
+        }
+        result
+    }
+
+    fn instrumented_inner<R, F>(&mut self, phase: Phase, run: &mut F) -> Result<R, Error>
+    where
+        F: FnMut(&mut Self) -> Result<R, Error>,
+    {
+        let hook = match self.config.instrument {
+            Some(hook) => hook,
+            None => return run(self),
+        };
+        let info = PhaseInfo {
+            phase,
+            reload_count: self.reload_count,
+            state_size: self.state_size,
+        };
+        let mut result = None;
+        hook(&info, &mut || {
+            if result.is_some() {
+                return false;
+            }
+            let ran = run(self);
+            let ok = ran.is_ok();
+            result = Some(ran);
+            ok
+        });
+        match result {
+            Some(result) => result,
+            // The hook forgot to run the phase, but it still has to happen.
+            None => run(self),
+        }
+    }
+
+    /// Keep a replaced version of the library loaded, if
+    /// `retain_old_images` allows, unloading the oldest ones past the limit.
+    fn retire(&mut self, old: AppSym<Host>) {
+        let count = self.config.retain_old_images;
+        if count == 0 {
+            return;
+        }
+        self.old_images.push_back(old);
+        while self.old_images.len() > count {
+            self.old_images.pop_front();
+        }
+    }
+
+    /// Unload every replaced version of the library kept by
+    /// [`Builder::retain_old_images`][].
+    ///
+    /// Call this once nothing holds on to function pointers or references
+    /// from the old versions anymore. The library that's running stays
+    /// loaded.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Builder;
+    ///
+    /// let mut app = Builder::new("")
+    ///     .retain_old_images(2)
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// for _ in 0..3 {
+    ///     app.reload_now().unwrap();
+    /// }
+    /// assert_eq!(app.old_image_count(), 2);
+    /// app.release_old_images();
+    /// assert_eq!(app.old_image_count(), 0);
+    /// # }
+    /// ```
+    ///
+    /// [`Builder::retain_old_images`]: struct.Builder.html#method.retain_old_images
+    pub fn release_old_images(&mut self) {
+        self.old_images.clear();
+    }
+
+    /// The number of replaced versions of the library that are still kept
+    /// loaded by [`Builder::retain_old_images`][].
+    ///
+    /// [`Builder::retain_old_images`]: struct.Builder.html#method.retain_old_images
+    pub fn old_image_count(&self) -> usize {
+        self.old_images.len()
+    }
+
+    /// The number of times the library has been reloaded successfully.
+    ///
+    /// This doesn't count the first load.
+    pub fn reload_count(&self) -> u64 {
+        self.reload_count
+    }
+
+    /// A number that changes whenever anything taken from the library or
+    /// the `State` may have stopped being valid.
+    ///
+    /// It goes up each time the loaded version of the library is swapped
+    /// out, and each time the `State` moves (see [`set_on_state_moved`][]).
+    /// Unlike [`reload_count`][], that includes reloads that fail, which
+    /// still unload the old version. It goes up even when replaced versions
+    /// are kept loaded by [`Builder::retain_old_images`][], erring on the
+    /// side of calling a value stale. See [`tag`][] for the way to use it.
+    ///
+    /// [`set_on_state_moved`]: struct.Reloadable.html#method.set_on_state_moved
+    /// [`reload_count`]: struct.Reloadable.html#method.reload_count
+    /// [`Builder::retain_old_images`]: struct.Builder.html#method.retain_old_images
+    /// [`tag`]: struct.Reloadable.html#method.tag
+    pub fn load_generation(&self) -> u64 {
+        self.load_generation
+    }
+
+    /// Tag `value` with the current [`load_generation`][], so that it can be
+    /// checked before it's used after a reload.
+    ///
+    /// This is for values derived from the library or the `State` that the
+    /// host keeps around, like a function pointer copied out of
+    /// [`get_symbol`][], or a pointer into [`state_bytes`][]. The returned
+    /// [`Generational`][] only hands the value back while this `Reloadable`
+    /// is still in the same generation.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { frames: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let frames = app.tag(app.state_bytes().as_ptr());
+    /// assert!(frames.get(&app).is_some());
+    ///
+    /// app.reload_now().unwrap();
+    /// assert!(!frames.is_current(&app));
+    /// assert_eq!(frames.get(&app), None);
+    /// # }
+    /// ```
+    ///
+    /// [`load_generation`]: struct.Reloadable.html#method.load_generation
+    /// [`get_symbol`]: struct.Reloadable.html#method.get_symbol
+    /// [`state_bytes`]: struct.Reloadable.html#method.state_bytes
+    /// [`Generational`]: struct.Generational.html
+    pub fn tag<T>(&self, value: T) -> Generational<T> {
+        Generational::new(value, self.load_generation)
+    }
+
+    /// Refuse a `State` size over the configured maximum, which is most
+    /// likely a sign of a corrupt library, before trying to allocate it.
+    fn check_state_size(&self, size: usize) -> Result<(), Error> {
+        let max = match self.config.reserved_state {
+            Some(reserved) => reserved.min(self.config.max_state_size),
+            None => self.config.max_state_size,
+        };
+        if size > max {
+            return Err(Error::StateTooLarge { reported: size, max });
+        }
+        Ok(())
+    }
+
+    /// Reallocate the buffer used to store the `State`.
+    fn realloc_buffer(&mut self, size: usize) -> Result<(), Error> {
+        let old = self.state_address();
+        self.state.resize(size)?;
+        self.report_state_move(old);
+        Ok(())
+    }
+
+    /// Replace the contents of the `State` buffer with `units`.
+    fn load_buffer(&mut self, units: &[u64]) -> Result<(), Error> {
+        let old = self.state_address();
+        self.state.load(units)?;
+        self.report_state_move(old);
+        Ok(())
+    }
+
+    /// The address of the `State` buffer, or `None` while it's empty.
+    fn state_address(&mut self) -> Option<*mut ()> {
+        if self.state.as_bytes().is_empty() {
+            None
+        } else {
+            Some(self.state.as_mut_ptr())
+        }
+    }
+
+    /// Tell the hook, if any, that the buffer moved away from `old`.
+    fn report_state_move(&mut self, old: Option<*mut ()>) {
+        if let (Some(old), Some(new)) = (old, self.state_address()) {
+            if old != new {
+                self.load_generation += 1;
+                if let Some(ref mut hook) = self.on_state_moved {
+                    hook(old, new);
+                }
+            }
+        }
+    }
+
+    /// Get a void pointer to the `State` buffer.
+    fn get_state_ptr(buffer: &mut StateBuffer) -> *mut () {
+        buffer.as_mut_ptr()
+    }
+
+    /// The modification time of the library file when it was last loaded.
+    ///
+    /// This is read just before the library is loaded, through any symlinks.
+    /// It's `None` if the platform doesn't report modification times, or
+    /// there's no library file (see [`new_in_process`][]).
+    ///
+    /// [`new_in_process`]: struct.Reloadable.html#method.new_in_process
+    pub fn last_loaded_mtime(&self) -> Option<SystemTime> {
+        self.loaded_mtimes.first().cloned().and_then(|mtime| mtime)
+    }
+
+    /// Describe the loaded library, or `None` if no library is loaded.
+    ///
+    /// This is what was read from the library when it was loaded, so it
+    /// doesn't touch the file again, apart from its size and modification
+    /// time, which are read afresh. It's meant for things like a debug overlay
+    /// showing which build is running.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { counter: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    /// use live_reload::Capabilities;
+    /// use live_reload::internals::ABI_VERSION;
+    ///
+    /// let app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let info = app.library_info().unwrap();
+    /// assert_eq!(info.abi_version(), Some(ABI_VERSION));
+    /// assert_eq!(info.state_size(), Some(8));
+    /// assert_eq!(info.capabilities(), Some(Capabilities::empty()));
+    /// # }
+    /// ```
+    pub fn library_info(&self) -> Option<LibraryInfo> {
+        let sym = self.sym.as_ref()?;
+        let mut info = self.describe_file();
+        info.abi_version = Some(sym.api().abi_version);
+        info.state_size = Some(self.state_size);
+        info.build_id = sym.build_id().map(|id| id.to_string());
+        info.capabilities = Some(sym.api().capabilities());
+        info.state_layout = sym.state_layout();
+        info.profile = sym.profile();
+        info.api_symbol = sym.api_symbol().map(str::to_owned);
+        Some(info)
+    }
+
+    /// The size in bytes of the `State` reported by the loaded library.
+    ///
+    /// If the last reload failed, this is the size reported by the last
+    /// library that loaded successfully.
+    ///
+    /// A library with nothing to keep across reloads can have an empty
+    /// `State`, which has a size of zero. No buffer is allocated for it, and
+    /// the library gets a dangling pointer that's non-null and aligned, which
+    /// is all a zero-sized type needs.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{Reloadable, ShouldQuit};
+    ///
+    /// pub struct Host { calls: u32 }
+    /// #[repr(C)] pub struct State;
+    ///
+    /// fn call(host: &mut Host, state: &mut State) {
+    ///     assert_eq!(state as *mut State as usize % 8, 0);
+    ///     host.calls += 1;
+    /// }
+    /// fn update(host: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     call(host, state);
+    ///     ShouldQuit::No
+    /// }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: call;
+    ///     reload: call;
+    ///     update: update;
+    ///     unload: call;
+    ///     deinit: call;
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host { calls: 0 }).unwrap();
+    /// assert_eq!(app.state_size(), 0);
+    /// assert!(app.state_bytes().is_empty());
+    /// app.update();
+    /// app.reload_now().unwrap();
+    /// let save = app.save_state();
+    /// app.load_state(&save);
+    /// app.update();
+    /// assert_eq!(app.host().calls, 5);
+    /// app.shutdown().unwrap();
+    /// # }
+    /// ```
+    pub fn state_size(&self) -> usize {
+        self.state_size
+    }
+
+    /// The size and alignment of the `State`, as a `Layout`.
+    ///
+    /// The size is [`state_size`][]. Libraries don't report the alignment of
+    /// their `State`, so this is the alignment the buffer always provides, 8
+    /// bytes, which is what any memory meant to hold a copy of the `State`
+    /// needs. Allocating with it straight from `std::alloc` is fine as long
+    /// as the size isn't zero. (This isn't the layout hash that
+    /// [`LibraryInfo::state_layout`][] gives, which is what "state layout"
+    /// means everywhere else.)
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { position: [f32; 3], frames: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use std::alloc::{alloc_zeroed, dealloc};
+    /// use live_reload::Reloadable;
+    ///
+    /// let app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let layout = app.state_alloc_layout();
+    /// assert_eq!((layout.size(), layout.align()), (24, 8));
+    ///
+    /// unsafe {
+    ///     let copy = alloc_zeroed(layout);
+    ///     assert!(!copy.is_null());
+    ///     std::ptr::copy_nonoverlapping(app.state_bytes().as_ptr(), copy, layout.size());
+    ///     dealloc(copy, layout);
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// [`state_size`]: struct.Reloadable.html#method.state_size
+    /// [`LibraryInfo::state_layout`]: struct.LibraryInfo.html#method.state_layout
+    pub fn state_alloc_layout(&self) -> Layout {
+        // The size is capped well below `isize::MAX` by `max_state_size`.
+        Layout::from_size_align(self.state_size, STATE_ALIGN).expect("the State's size fits a Layout")
+    }
+
+    /// Ask the loaded library how big the resources the host provides it
+    /// should be, if it has a `requirements` function (see
+    /// [`live_reload!`][]).
+    ///
+    /// This is `None` if no library is loaded, the library has no
+    /// `requirements`, or it panicked; see [`try_requirements`][] to tell
+    /// those apart. It can be asked at any time, but is
+    /// meant for before `init`, by building with [`Builder::lazy_init`][]:
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{Builder, HostRequirements, ShouldQuit};
+    ///
+    /// pub struct Host {
+    ///     scratch: Vec<u8>,
+    /// }
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// fn init(host: &mut Host, _: &mut State) {
+    ///     assert_eq!(host.scratch.len(), 64 * 1024);
+    /// }
+    /// fn requirements() -> HostRequirements {
+    ///     HostRequirements { scratch_bytes: 64 * 1024, ..Default::default() }
+    /// }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    ///     requirements: requirements;
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut app = Builder::new("unused")
+    ///     .lazy_init(true)
+    ///     .build_in_process(RELOAD_API, Host { scratch: Vec::new() })
+    ///     .unwrap();
+    /// let requirements = app.requirements().unwrap();
+    /// app.host_mut().scratch = vec![0; requirements.scratch_bytes as usize];
+    /// app.update();
+    /// # }
+    /// ```
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`Builder::lazy_init`]: struct.Builder.html#method.lazy_init
+    /// [`try_requirements`]: struct.Reloadable.html#method.try_requirements
+    pub fn requirements(&self) -> Option<HostRequirements> {
+        self.try_requirements().ok()?
+    }
+
+    /// Ask the loaded library for its requirements like [`requirements`][],
+    /// but fail with [`Error::LibraryPanicked`][] if it panics, rather than
+    /// giving `None`.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{Builder, Error, HostRequirements, LifecyclePhase, ShouldQuit};
+    ///
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State;
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// fn requirements() -> HostRequirements {
+    ///     panic!("the config file is missing");
+    /// }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    ///     requirements: requirements;
+    /// }
+    ///
+    /// # fn main() {
+    /// let app = Builder::new("unused")
+    ///     .lazy_init(true)
+    ///     .build_in_process(RELOAD_API, Host)
+    ///     .unwrap();
+    /// match app.try_requirements() {
+    ///     Err(Error::LibraryPanicked { phase: LifecyclePhase::Requirements, .. }) => {}
+    ///     _ => panic!("the panic should have been reported"),
+    /// }
+    /// assert!(app.requirements().is_none());
+    /// # }
+    /// ```
+    ///
+    /// [`requirements`]: struct.Reloadable.html#method.requirements
+    /// [`Error::LibraryPanicked`]: enum.Error.html#variant.LibraryPanicked
+    pub fn try_requirements(&self) -> Result<Option<HostRequirements>, Error> {
+        match self.sym.as_ref().and_then(|sym| sym.api().host_requirements()) {
+            Some(found) => found.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// The version of the `State`, which is the number of `migrations` it's
+    /// been through (see [`live_reload!`][]).
+    ///
+    /// This is the version of the library that last started or reloaded
+    /// the state, or 0 for one without migrations. A reload to a library
+    /// with more migrations runs the ones from this version on, and a reload
+    /// to one with fewer sets this back, so the missing ones run again if
+    /// a later library brings them back.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{Reloadable, ShouldQuit};
+    ///
+    /// pub struct Host;
+    /// #[repr(C)]
+    /// pub struct State {
+    ///     hp: u32,
+    ///     max_hp: u32,
+    /// }
+    ///
+    /// fn init(_: &mut Host, state: &mut State) {
+    ///     state.hp = 10;
+    ///     state.max_hp = 10;
+    /// }
+    /// fn add_max_hp(_: &mut Host, state: &mut State, _old: &[u8]) {
+    ///     state.max_hp = state.hp;
+    /// }
+    /// fn scale_hp(_: &mut Host, state: &mut State, _old: &[u8]) {
+    ///     state.hp *= 10;
+    ///     state.max_hp *= 10;
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    /// fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    ///     migrations: [add_max_hp, scale_hp];
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.update();
+    /// assert_eq!(app.state_version(), 2);
+    /// // A reload to the same version has nothing to migrate.
+    /// app.reload_now().unwrap();
+    /// assert_eq!(app.state_bytes()[..4], 10u32.to_ne_bytes());
+    /// # }
+    /// ```
+    ///
+    /// [`live_reload!`]: macro.live_reload.html
+    pub fn state_version(&self) -> u32 {
+        self.state_version
+    }
+
+    /// View the live bytes of the `State`, without copying them.
+    ///
+    /// This is exactly [`state_size`][] bytes long, rather than the rounded-up
+    /// size of the underlying buffer, so it's suitable for hashing or comparing
+    /// the state between frames. For a copy that can outlive the next call to
+    /// [`update`][], use [`save_state`][] instead.
+    ///
+    /// [`state_size`]: struct.Reloadable.html#method.state_size
+    /// [`update`]: struct.Reloadable.html#method.update
+    /// [`save_state`]: struct.Reloadable.html#method.save_state
+    pub fn state_bytes(&self) -> &[u8] {
+        let bytes = self.state.as_bytes();
+        &bytes[..self.state_size.min(bytes.len())]
+    }
+
+    /// Hash the live bytes of the `State`.
+    ///
+    /// This hashes exactly [`state_bytes`][], with FNV-1a, which gives the
+    /// same result on every run and platform (for the same bytes), and
+    /// doesn't depend on how big the underlying buffer is. So it's a cheap
+    /// way to tell whether the state changed between two frames, or whether
+    /// two runs fed the same inputs stayed in step, without copying it like
+    /// [`save_state`][] does. Padding bytes are hashed too, so a `State`
+    /// with padding should be written the same way each time for the
+    /// checksums to match.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { frames: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, state: &mut State) -> ShouldQuit { state.frames += 1; ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut first = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let mut second = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// first.update();
+    /// assert_ne!(first.state_checksum(), second.state_checksum());
+    /// second.update();
+    /// assert_eq!(first.state_checksum(), second.state_checksum());
+    /// # }
+    /// ```
+    ///
+    /// [`state_bytes`]: struct.Reloadable.html#method.state_bytes
+    /// [`save_state`]: struct.Reloadable.html#method.save_state
+    pub fn state_checksum(&self) -> u64 {
+        internals::fnv1a(self.state_bytes())
+    }
+
+    /// Mutably view the live bytes of the `State`, without copying them.
+    ///
+    /// This is the same view as [`state_bytes`][]. Whatever you write here is
+    /// what the library will see on its next call.
+    ///
+    /// # Safety
+    ///
+    /// The bytes must still make up a valid `State` by the time the library
+    /// is next called. The library reads them as its `State` without any
+    /// checks, so writing anything else is as bad as a bad transmute.
+    ///
+    /// [`state_bytes`]: struct.Reloadable.html#method.state_bytes
+    pub unsafe fn state_bytes_mut(&mut self) -> &mut [u8] {
+        let size = self.state_size;
+        let bytes = self.state.as_mut_bytes();
+        let len = size.min(bytes.len());
+        &mut bytes[..len]
+    }
+
+    /// Look up some other symbol that the loaded library exports.
+    ///
+    /// This is the same as [`Library::get`][] on the library that is currently
+    /// loaded, for symbols besides the lifecycle functions, like a table of
+    /// debug commands. It returns an [`Error::Io`][] if no library is loaded,
+    /// or the library doesn't export `name`.
+    ///
+    /// The returned `Symbol` borrows the `Reloadable`, so it can't be held
+    /// across a reload. Don't copy the value out of it either: the library
+    /// that it points into is unloaded by the next reload, after which any
+    /// function pointer or reference taken from it dangles. Look the symbol up
+    /// again after every reload instead, or keep the value with [`tag`][] to
+    /// be able to tell when it's stale.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the actual type of the symbol, just like with
+    /// [`Library::get`][].
+    ///
+    /// [`Library::get`]: https://docs.rs/libloading/0.4/libloading/struct.Library.html#method.get
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    /// [`tag`]: struct.Reloadable.html#method.tag
+    pub unsafe fn get_symbol<T>(&self, name: &[u8]) -> Result<libloading::Symbol<'_, T>, Error> {
+        let lib = self.sym.as_ref().and_then(|sym| sym.library());
+        match lib {
+            Some(lib) => Ok(lib.get(name)?),
+            None => Err(Error::Io(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "no dynamic library is loaded",
+            ))),
+        }
+    }
+
+    /// Copy out a data symbol that the loaded library exports.
+    ///
+    /// This looks up `name` like [`get_symbol`][], but as a static of type
+    /// `T` rather than a function, and returns a copy of its value instead of
+    /// a handle into the library. That's the way to read something the
+    /// library bakes in, like a `#[repr(C)]` config table, in a form that
+    /// outlives the next reload. Look it up again after each reload to pick
+    /// up the new version's value.
+    ///
+    /// The library exports it with `#[no_mangle]`:
+    ///
+    /// ```rust,ignore
+    /// #[repr(C)]
+    /// #[derive(Clone, Copy)]
+    /// pub struct Tuning { gravity: f32, jump: f32 }
+    ///
+    /// #[no_mangle]
+    /// pub static TUNING: Tuning = Tuning { gravity: 9.8, jump: 4.0 };
+    /// ```
+    ///
+    /// And the host reads it after loading and after each reload:
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// # #[repr(C)] #[derive(Clone, Copy)] struct Tuning { gravity: f32, jump: f32 }
+    /// # struct Host;
+    /// use live_reload::Reloadable;
+    ///
+    /// let app = Reloadable::new("target/debug/libgame.so", Host).unwrap();
+    /// let tuning: Tuning = unsafe { app.get_data(b"TUNING").unwrap() };
+    /// # }
+    /// ```
+    ///
+    /// `T` is only ever copied, so anything in it that points into the
+    /// library, like a `&'static str`, still dangles after a reload. Keep to
+    /// plain values.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be the actual type of the static, as with [`get_symbol`][].
+    ///
+    /// [`get_symbol`]: struct.Reloadable.html#method.get_symbol
+    pub unsafe fn get_data<T: Copy>(&self, name: &[u8]) -> Result<T, Error> {
+        let symbol = self.get_symbol::<*const T>(name)?;
+        Ok(**symbol)
+    }
+
+    /// Call `deinit`, unload the library, and report anything that failed.
+    ///
+    /// This is what happens when a `Reloadable` is dropped, except that any
+    /// error is returned rather than ignored: a panic in `deinit` comes back
+    /// as [`Error::LibraryPanicked`][] instead of panicking in a destructor,
+    /// and failing to flush a [memory-mapped][] state comes back as an
+    /// [`Error::Io`][]. The library is unloaded either way.
+    ///
+    /// [`Error::LibraryPanicked`]: enum.Error.html#variant.LibraryPanicked
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    /// [memory-mapped]: struct.Reloadable.html#method.new_mmap
+    pub fn shutdown(mut self) -> Result<(), Error> {
+        self.finalize()
+    }
+
+    /// Run `deinit` and unload the library, if there is one loaded, so that
+    /// this happens only once.
+    fn finalize(&mut self) -> Result<(), Error> {
+        let mut result = Ok(());
+        if let (true, Some(sym)) = (self.initialized, self.sym.take()) {
+            let deinit = sym.api().deinit;
+            let host = &mut self.host;
+            let state = Self::get_state_ptr(&mut self.state);
+            result = watchdog::call(self.watchdog.as_ref(), LifecyclePhase::Deinit, |sink| {
+                deinit(host, state, sink)
+            });
+            match result {
+                Ok(()) => {
+                    self.observe(|observer, app| observer.on_deinit(app));
+                    self.log(ReloadLogKind::Deinit, |_| String::new());
+                }
+                Err(ref err) => {
+                    self.observe(|observer, app| observer.on_error(app, err));
+                    self.log(ReloadLogKind::Error, |_| err.to_string());
+                }
+            }
+        }
+        #[cfg(all(feature = "mmap", unix))]
+        {
+            if let StateBuffer::Mapped(ref map) = self.state {
+                let flushed = map.flush();
+                if result.is_ok() {
+                    result = flushed.map_err(Error::from);
+                }
+            }
+        }
+        result
+    }
+
+    /// Get a reference to the `Host` struct>
+    pub fn host(&self) -> &Host { &self.host }
+
+    /// Get a mutable reference to the `Host` struct.
+    pub fn host_mut(&mut self) -> &mut Host { &mut self.host }
+
+    /// Change the live `Host` with `f`, between calls into the library.
+    ///
+    /// The `Host`'s type is fixed for as long as the library is loaded, but
+    /// its values aren't, so this is the way to swap out a service the host
+    /// hands the library, like the function it prints with, in the middle of
+    /// a session. The library and its `State` are left alone: the library
+    /// just sees the new values the next time it's called.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use std::sync::Mutex;
+    /// use live_reload::{Reloadable, ShouldQuit};
+    ///
+    /// static LOG: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    ///
+    /// pub struct Host {
+    ///     print: fn(&str),
+    /// }
+    /// #[repr(C)]
+    /// pub struct State;
+    ///
+    /// fn to_log(message: &str) {
+    ///     LOG.lock().unwrap().push(message.to_owned());
+    /// }
+    ///
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    /// fn update(host: &mut Host, _: &mut State) -> ShouldQuit {
+    ///     (host.print)("frame");
+    ///     ShouldQuit::No
+    /// }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host { print: |_| {} }).unwrap();
+    /// app.update();
+    /// app.update_host(|host| host.print = to_log);
+    /// app.update();
+    /// assert_eq!(*LOG.lock().unwrap(), ["frame"]);
+    /// # }
+    /// ```
+    pub fn update_host<F: FnOnce(&mut Host)>(&mut self, f: F) {
+        f(&mut self.host)
+    }
+
+    /// Replace the `Host` with `new_host`, and [`reload_now`][] so the
+    /// library's `reload` sees it.
+    ///
+    /// Where [`update_host`][] changes the `Host` in place, this is for when
+    /// it has to be built again from scratch, say after the graphics context
+    /// it holds was lost, and the library has to rebind whatever it took
+    /// from the old one. The old `Host` is dropped before the reload. If the
+    /// reload fails, the new `Host` stays in place, just as if it had been
+    /// swapped in with [`host_mut`][] before a failed [`reload_now`][].
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{Reloadable, ShouldQuit};
+    ///
+    /// pub struct Host {
+    ///     context: u32,
+    /// }
+    /// #[repr(C)]
+    /// pub struct State {
+    ///     bound_to: u32,
+    /// }
+    ///
+    /// fn bind(host: &mut Host, state: &mut State) {
+    ///     state.bound_to = host.context;
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    /// fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: bind;
+    ///     reload: bind;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host { context: 1 }).unwrap();
+    /// app.reload_with_host(Host { context: 2 }).unwrap();
+    /// assert_eq!(app.host().context, 2);
+    /// assert_eq!(app.state_bytes()[..4], 2u32.to_ne_bytes());
+    /// # }
+    /// ```
+    ///
+    /// [`reload_now`]: struct.Reloadable.html#method.reload_now
+    /// [`update_host`]: struct.Reloadable.html#method.update_host
+    /// [`host_mut`]: struct.Reloadable.html#method.host_mut
+    pub fn reload_with_host(&mut self, new_host: Host) -> Result<ReloadTransition, Error> {
+        self.host = new_host;
+        self.reload_now()
+    }
+
+    /// Save a copy of the state
+    ///
+    /// Each save is tagged with the next [`Generation`][] of this
+    /// `Reloadable`, and with the build id of the loaded library, for
+    /// [`load_state_checked`][].
+    ///
+    /// If the library has a `serialize` function (see [`live_reload!`][]),
+    /// the save also keeps what it wrote, which [`load_state`][] and the
+    /// other loads hand to the library's `deserialize` in preference to the
+    /// bytes. If `serialize` fails, the save just has the bytes.
+    ///
+    /// A save is never torn by the library being partway through a call.
+    /// Every call into the library borrows the `Reloadable` mutably, so
+    /// nothing can save or load the state while one runs, including host
+    /// functions the library calls back into, which only get the `Host`.
+    /// Another thread can only get at the `Reloadable` through whatever it's
+    /// shared with, like the lock of an [`AutoReload`][], which serializes
+    /// saves with calls into the library the same way. The exception is a
+    /// state [shared between processes][shared], which another process can
+    /// change at any time.
+    ///
+    /// [`Generation`]: struct.Generation.html
+    /// [`load_state_checked`]: struct.Reloadable.html#method.load_state_checked
+    /// [`live_reload!`]: macro.live_reload.html
+    /// [`load_state`]: struct.Reloadable.html#method.load_state
+    /// [`AutoReload`]: struct.AutoReload.html
+    /// [shared]: struct.Reloadable.html#method.new_shared
+    pub fn save_state(&self) -> SaveState {
+        let mut save = SaveState::with_capacity(0);
+        self.save_state_into(&mut save);
+        save
+    }
+
+    /// Save a copy of the state into `out`, reusing its allocations.
+    ///
+    /// This is [`save_state`][] for a loop that saves every frame, say for
+    /// rollback, without allocating each time: keep a pool of saves, made
+    /// with [`SaveState::with_capacity`][] or by earlier saves, and save into
+    /// them in turn. Everything in `out` is replaced, so afterwards it's just
+    /// as if `save_state` had made it, with the next generation. A save only
+    /// avoids allocating if it already has room for the `State`, so a pool
+    /// is best kept for the one library, or ones with the same `State`
+    /// layout. Whatever a library's `serialize` writes is still collected
+    /// in a buffer of its own.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { frames: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     state.frames += 1;
+    ///     ShouldQuit::No
+    /// }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::{Reloadable, SaveState};
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let mut ring: Vec<SaveState> = (0..8).map(|_| SaveState::with_capacity(app.state_size())).collect();
+    /// for frame in 0..20 {
+    ///     app.update();
+    ///     app.save_state_into(&mut ring[frame % 8]);
+    /// }
+    /// // Roll back 3 frames.
+    /// app.load_state(&ring[16 % 8]);
+    /// assert_eq!(app.state_bytes(), 17u64.to_ne_bytes());
+    /// # }
+    /// ```
+    ///
+    /// [`save_state`]: struct.Reloadable.html#method.save_state
+    /// [`SaveState::with_capacity`]: struct.SaveState.html#method.with_capacity
+    pub fn save_state_into(&self, out: &mut SaveState) {
+        use std::fmt::Write;
+
+        let state = self.state.as_bytes().as_ptr() as *const ();
+        self.state.copy_units_into(&mut out.state);
+        out.size = self.state_size;
+        out.generation = Generation(self.next_generation.fetch_add(1, Ordering::Relaxed));
+        match self.sym.as_ref().and_then(AppSym::build_id) {
+            Some(id) => {
+                let build_id = out.build_id.get_or_insert_with(String::new);
+                build_id.clear();
+                let _ = write!(build_id, "{}", id);
+            }
+            None => out.build_id = None,
+        }
+        out.state_layout = self.sym.as_ref().and_then(AppSym::state_layout);
+        // Before `init`, there's no `State` for `serialize` to write yet.
+        out.serialized = match self.sym {
+            Some(ref sym) if self.initialized => sym.api().serialize_state(state),
+            _ => None,
+        };
+    }
+
+    /// Save a copy of the state, along with its [`Generation`][].
+    ///
+    /// This is [`save_state`][], with the generation pulled out to key the
+    /// save by, say in a `BTreeMap` of undo states.
+    ///
+    /// [`Generation`]: struct.Generation.html
+    /// [`save_state`]: struct.Reloadable.html#method.save_state
+    pub fn save_state_tagged(&self) -> (Generation, SaveState) {
+        let save = self.save_state();
+        (save.generation, save)
+    }
+
+    /// Load a copy of the state
+    ///
+    /// This panics if `state` doesn't fit the loaded library, as checked by
+    /// [`try_load_state`][], which returns the error instead. Like a save, a
+    /// load can't happen while the library is running; see [`save_state`][].
+    ///
+    /// If the save has what the library's `serialize` wrote, and the loaded
+    /// library has a `deserialize`, that's read back over the state instead
+    /// of loading the bytes, whatever its size. If reading it fails, the
+    /// state is put back and the bytes are loaded after all. The same goes
+    /// for the other ways of loading a save. A save made before `init` has
+    /// run only has the bytes, since there's no `State` to serialize yet.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use std::io::{self, Read, Write};
+    /// use live_reload::{Builder, Reloadable, ShouldQuit};
+    ///
+    /// pub struct Host;
+    /// #[repr(C)]
+    /// pub struct State {
+    ///     score: u32,
+    ///     // Only good for as long as this build is loaded.
+    ///     name: Option<&'static str>,
+    /// }
+    ///
+    /// fn save(state: &State, out: &mut dyn Write) -> io::Result<()> {
+    ///     out.write_all(&state.score.to_le_bytes())
+    /// }
+    /// fn load(state: &mut State, input: &mut dyn Read) -> io::Result<()> {
+    ///     let mut score = [0; 4];
+    ///     input.read_exact(&mut score)?;
+    ///     state.score = u32::from_le_bytes(score);
+    ///     state.name = Some("player");
+    ///     Ok(())
+    /// }
+    ///
+    /// fn init(_: &mut Host, state: &mut State) {
+    ///     state.name = Some("player");
+    /// }
+    /// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     state.score += 1;
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    ///     serialize: save;
+    ///     deserialize: load;
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.update();
+    /// let save = app.save_state();
+    /// assert_eq!(save.serialized(), Some(&1u32.to_le_bytes()[..]));
+    /// app.update();
+    /// app.load_state(&save);
+    /// assert_eq!(app.state_bytes()[..4], 1u32.to_ne_bytes());
+    ///
+    /// let unstarted = Builder::new("").lazy_init(true).build_in_process(RELOAD_API, Host).unwrap();
+    /// assert_eq!(unstarted.save_state().serialized(), None);
+    /// # }
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `state` was saved from a library with a different `State`
+    /// size or layout, or if the state is memory-mapped and the state file
+    /// can't be grown to fit `state`.
+    ///
+    /// [`try_load_state`]: struct.Reloadable.html#method.try_load_state
+    /// [`save_state`]: struct.Reloadable.html#method.save_state
+    pub fn load_state(&mut self, state: &SaveState) {
+        if let Err(error) = self.try_load_state(state) {
+            panic!("failed to load the state: {}", error);
+        }
+    }
+
+    /// Read `save` back with the library's `deserialize`, if both it and the
+    /// save can, and say whether that worked. On failure, the state is put
+    /// back as it was.
+    fn load_serialized(&mut self, save: &SaveState) -> bool {
+        let (bytes, sym) = match (save.serialized.as_ref(), self.sym.as_ref()) {
+            (Some(bytes), Some(sym)) => (bytes, sym),
+            _ => return false,
+        };
+        let before = self.state.to_units();
+        let state = Self::get_state_ptr(&mut self.state);
+        match sym.api().deserialize_state(state, bytes) {
+            Some(true) => true,
+            Some(false) => {
+                // It's the same size, so there's nothing to grow.
+                let _ = self.state.load(&before);
+                false
+            }
+            None => false,
+        }
+    }
+
+    /// Load a copy of the state, if it fits the loaded library.
+    ///
+    /// This returns [`Error::SnapshotMismatch`][] if `state` was saved from a
+    /// library whose `State` had a different size,
+    /// [`Error::StateLayoutMismatch`][] if it had a different layout, when
+    /// both libraries report one (see [`reloadable_state!`][]), and an
+    /// [`Error::Io`][] if the state is memory-mapped and the state file can't
+    /// be grown. In each case, the current state is left untouched.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host;
+    ///
+    /// struct Small { counter: u64 }
+    /// struct Large { counters: [u64; 4] }
+    ///
+    /// fn count_small(_: &mut Host, small: &mut Small) -> ShouldQuit {
+    ///     small.counter += 1;
+    ///     ShouldQuit::No
+    /// }
+    /// fn count_large(_: &mut Host, large: &mut Large) -> ShouldQuit {
+    ///     large.counters[0] += 10;
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing<T>(_: &mut Host, _: &mut T) {}
+    ///
+    /// live_reload! {
+    ///     module: small;
+    ///     host: Host;
+    ///     state: Small;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: count_small;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// live_reload! {
+    ///     module: large;
+    ///     host: Host;
+    ///     state: Large;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: count_large;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::{Error, Reloadable};
+    ///
+    /// let mut app = Reloadable::new_in_process(small::RELOAD_API, Host).unwrap();
+    /// let mut other = Reloadable::new_in_process(large::RELOAD_API, Host).unwrap();
+    /// app.update();
+    /// other.update();
+    ///
+    /// let before = app.state_bytes().to_vec();
+    /// match app.try_load_state(&other.save_state()) {
+    ///     Err(Error::SnapshotMismatch { expected: 8, found: 32 }) => {}
+    ///     _ => panic!("a save of a different size should be refused"),
+    /// }
+    /// assert_eq!(app.state_bytes(), &before[..]);
+    /// # }
+    /// ```
+    ///
+    /// [`Error::SnapshotMismatch`]: enum.Error.html#variant.SnapshotMismatch
+    /// [`Error::StateLayoutMismatch`]: enum.Error.html#variant.StateLayoutMismatch
+    /// [`reloadable_state!`]: macro.reloadable_state.html
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    pub fn try_load_state(&mut self, state: &SaveState) -> Result<(), Error> {
+        self.check_state_layout(state)?;
+        if self.load_serialized(state) {
+            return Ok(());
+        }
+        self.load_units(state.size, &state.state)
+    }
+
+    /// Load a copy of the state, if it was saved from a library of the same
+    /// shape as the loaded one.
+    ///
+    /// On top of the size check of [`try_load_state`][], this returns
+    /// [`Error::BuildIdMismatch`][] if `state` was saved while a library with
+    /// a different build id was loaded, and [`Error::StateLayoutMismatch`][]
+    /// if it was saved with a different `State` layout, when both libraries
+    /// report one. (Libraries run in-process have neither, so only their
+    /// sizes are compared.) The current state is left
+    /// untouched on error, so keeping many saves around, and going back to
+    /// any of them across reloads, can't feed the library a `State` it
+    /// doesn't understand.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { frame: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    /// #     state.frame += 1;
+    /// #     ShouldQuit::No
+    /// # }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use std::collections::BTreeMap;
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let mut saves = BTreeMap::new();
+    /// for _ in 0..3 {
+    ///     app.update();
+    ///     let (generation, save) = app.save_state_tagged();
+    ///     saves.insert(generation, save);
+    /// }
+    /// let second = *saves.keys().nth(1).unwrap();
+    /// assert!(second > *saves.keys().next().unwrap());
+    /// app.load_state_checked(&saves[&second]).unwrap();
+    /// assert_eq!(app.state_bytes(), &2u64.to_ne_bytes());
+    /// # }
+    /// ```
+    ///
+    /// [`try_load_state`]: struct.Reloadable.html#method.try_load_state
+    /// [`Error::BuildIdMismatch`]: enum.Error.html#variant.BuildIdMismatch
+    /// [`Error::StateLayoutMismatch`]: enum.Error.html#variant.StateLayoutMismatch
+    pub fn load_state_checked(&mut self, state: &SaveState) -> Result<(), Error> {
+        let loaded = self.sym.as_ref().and_then(AppSym::build_id).map(|id| id.to_string());
+        if let (Some(saved), Some(loaded)) = (state.build_id.as_ref(), loaded) {
+            if *saved != loaded {
+                return Err(Error::BuildIdMismatch {
+                    expected: loaded,
+                    found: Some(saved.clone()),
+                });
+            }
+        }
+        self.check_state_layout(state)?;
+        if self.load_serialized(state) {
+            return Ok(());
+        }
+        self.load_units(state.size, &state.state)
+    }
+
+    /// Reload the library if it changed, like [`reload`][], and then load
+    /// `save` as the state, whether or not it reloaded.
+    ///
+    /// This keeps the host on the same scenario across a code change: save
+    /// the state once, and each rebuild picks up from that same save, so the
+    /// effect of the change is all that differs. Since the build is expected
+    /// to change, the save is only checked like [`try_load_state`][] does,
+    /// plus the `State` layout when both libraries report one, as in
+    /// [`load_state_checked`][].
+    ///
+    /// If the reload fails, `save` isn't loaded. If the reload succeeds but
+    /// `save` doesn't fit the new library, the error is returned and the
+    /// state is left as the new library's `reload` made it.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { frame: u64 }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    /// #     state.frame += 1;
+    /// #     ShouldQuit::No
+    /// # }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: nothing;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// use live_reload::{ReloadOutcome, Reloadable};
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// app.update();
+    /// let scenario = app.save_state();
+    /// app.update();
+    /// app.update();
+    ///
+    /// app.inject_change_event();
+    /// assert_eq!(app.reload_preserving(&scenario).unwrap(), ReloadOutcome::Reloaded);
+    /// assert_eq!(app.state_bytes(), &1u64.to_ne_bytes());
+    /// # }
+    /// ```
+    ///
+    /// [`reload`]: struct.Reloadable.html#method.reload
+    /// [`try_load_state`]: struct.Reloadable.html#method.try_load_state
+    /// [`load_state_checked`]: struct.Reloadable.html#method.load_state_checked
+    pub fn reload_preserving(&mut self, save: &SaveState) -> Result<ReloadOutcome, Error> {
+        let before = self.reload_count;
+        self.reload()?;
+        self.try_load_state(save)?;
+        Ok(if self.reload_count != before { ReloadOutcome::Reloaded } else { ReloadOutcome::Unchanged })
+    }
+
+    /// Check that `state` was saved with the loaded library's `State`
+    /// layout, when both report one.
+    fn check_state_layout(&self, state: &SaveState) -> Result<(), Error> {
+        self.check_layout(state.state_layout)
+    }
+
+    /// Check that a `State` with the layout hash `found` fits the loaded
+    /// library, when both have one.
+    fn check_layout(&self, found: Option<u64>) -> Result<(), Error> {
+        check_layout(self.sym.as_ref().and_then(AppSym::state_layout), found)
+    }
+
+    /// Load `units` as the state, if `size` matches the loaded library's.
+    fn load_units(&mut self, size: usize, units: &[u64]) -> Result<(), Error> {
+        if size != self.state_size {
+            return Err(Error::SnapshotMismatch {
+                expected: self.state_size,
+                found: size,
+            });
+        }
+        self.load_buffer(units)
+    }
+
+    /// Copy the state straight into another `Reloadable`, without making a
+    /// [`SaveState`][] in between.
+    ///
+    /// Both have to have loaded libraries with the same `State` size and, when
+    /// both report one, the same layout, or this returns
+    /// [`Error::SnapshotMismatch`][] or [`Error::StateLayoutMismatch`][] and
+    /// leaves `other` untouched. Only
+    /// the bytes of the `State` are copied, so this is cheap enough to do
+    /// every frame, say to keep a shadow copy of a simulation for rollback.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host;
+    ///
+    /// struct State { frames: u64 }
+    ///
+    /// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     state.frames += 1;
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut primary = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let mut shadow = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// primary.update();
+    /// primary.update();
+    /// primary.clone_state_into(&mut shadow).unwrap();
+    /// assert_eq!(shadow.state_bytes(), primary.state_bytes());
+    /// # }
+    /// ```
+    ///
+    /// [`SaveState`]: struct.SaveState.html
+    /// [`Error::SnapshotMismatch`]: enum.Error.html#variant.SnapshotMismatch
+    /// [`Error::StateLayoutMismatch`]: enum.Error.html#variant.StateLayoutMismatch
+    pub fn clone_state_into(&self, other: &mut Reloadable<Host>) -> Result<(), Error> {
+        self.check_fits(other)?;
+        let size = self.state_size;
+        other.state.as_mut_bytes()[..size].copy_from_slice(&self.state.as_bytes()[..size]);
+        Ok(())
+    }
+
+    /// Move a copy of the state into `other`, which has a different library
+    /// loaded, and run `other`'s `reload` so that it can fix the state up.
+    ///
+    /// This is [`clone_state_into`][] for comparing two implementations of
+    /// the same `State` side by side, say an experimental build in one
+    /// `Reloadable` and the current one in another. Along with the size, the
+    /// two `State`s have to agree on their layout, when both libraries report
+    /// one (see [`reloadable_state!`][]), and on their [`state_version`][],
+    /// or this returns [`Error::StateLayoutMismatch`][] or
+    /// [`Error::StateVersionMismatch`][] and leaves `other` untouched. If
+    /// `other` hasn't started yet, its `init` runs once those checks have
+    /// passed, before the state is copied, so that it doesn't overwrite the
+    /// state later.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{Reloadable, ShouldQuit};
+    ///
+    /// pub struct Host;
+    /// #[repr(C)]
+    /// pub struct State {
+    ///     position: u32,
+    ///     speed: u32,
+    /// }
+    ///
+    /// fn init(_: &mut Host, state: &mut State) {
+    ///     state.position = 0;
+    ///     state.speed = 1;
+    /// }
+    /// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     state.position += state.speed;
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    /// fn double_speed(_: &mut Host, state: &mut State) {
+    ///     state.speed = 2;
+    /// }
+    ///
+    /// live_reload! {
+    ///     module: current;
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// live_reload! {
+    ///     module: experiment;
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: double_speed;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// let mut a = Reloadable::new_in_process(current::RELOAD_API, Host).unwrap();
+    /// let mut b = Reloadable::new_in_process(experiment::RELOAD_API, Host).unwrap();
+    /// a.update();
+    /// a.update();
+    /// a.transfer_state_to(&mut b).unwrap();
+    /// a.update();
+    /// b.update();
+    /// assert_eq!(a.state_bytes()[..4], 3u32.to_ne_bytes());
+    /// assert_eq!(b.state_bytes()[..4], 4u32.to_ne_bytes());
+    /// # }
+    /// ```
+    ///
+    /// An `other` that hasn't started is left that way when the state is
+    /// refused:
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// use live_reload::{Builder, Error, Reloadable, ShouldQuit};
+    ///
+    /// pub struct Host {
+    ///     inits: u32,
+    /// }
+    /// # #[repr(C)] pub struct State { frames: u64 }
+    /// fn init(host: &mut Host, _: &mut State) {
+    ///     host.inits += 1;
+    /// }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn step(_: &mut Host, _: &mut State, _: &[u8]) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    ///
+    /// live_reload! {
+    ///     module: v0;
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// live_reload! {
+    ///     module: v1;
+    ///     host: Host;
+    ///     state: State;
+    ///     init: init;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    ///     migrations: [step];
+    /// }
+    ///
+    /// # fn main() {
+    /// let a = Reloadable::new_in_process(v0::RELOAD_API, Host { inits: 0 }).unwrap();
+    /// let mut b = Builder::new("")
+    ///     .lazy_init(true)
+    ///     .build_in_process(v1::RELOAD_API, Host { inits: 0 })
+    ///     .unwrap();
+    /// match a.transfer_state_to(&mut b) {
+    ///     Err(Error::StateVersionMismatch { expected: 1, found: 0 }) => {}
+    ///     other => panic!("the versions should differ, got {:?}", other.err()),
+    /// }
+    /// assert_eq!(b.host().inits, 0);
+    /// # }
+    /// ```
+    ///
+    /// [`clone_state_into`]: struct.Reloadable.html#method.clone_state_into
+    /// [`reloadable_state!`]: macro.reloadable_state.html
+    /// [`state_version`]: struct.Reloadable.html#method.state_version
+    /// [`Error::StateLayoutMismatch`]: enum.Error.html#variant.StateLayoutMismatch
+    /// [`Error::StateVersionMismatch`]: enum.Error.html#variant.StateVersionMismatch
+    pub fn transfer_state_to(&self, other: &mut Reloadable<Host>) -> Result<(), Error> {
+        self.check_fits(other)?;
+        // Until `other` starts, its version is the one its library will
+        // start it at.
+        let expected = match other.sym {
+            Some(ref sym) if !other.initialized => sym.api().state_migrations().0,
+            _ => other.state_version,
+        };
+        if self.state_version != expected {
+            return Err(Error::StateVersionMismatch {
+                expected,
+                found: self.state_version,
+            });
+        }
+        other.ensure_init()?;
+        self.clone_state_into(other)?;
+        if let Some(ref sym) = other.sym {
+            other.debug_assert_initialized(LifecyclePhase::Reload);
+            let api = sym.api();
+            let host = &mut other.host;
+            let state = Self::get_state_ptr(&mut other.state);
+            let progress = other.reload_progress;
+            watchdog::call(other.watchdog.as_ref(), LifecyclePhase::Reload, |sink| {
+                api.call_reload(host, state, progress, sink)
+            })?;
+        }
+        Ok(())
+    }
+
+    /// Check that this state would fit `other`'s library, going by its size
+    /// and layout.
+    fn check_fits(&self, other: &Reloadable<Host>) -> Result<(), Error> {
+        other.check_layout(self.sym.as_ref().and_then(AppSym::state_layout))?;
+        if other.state_size != self.state_size {
+            return Err(Error::SnapshotMismatch {
+                expected: other.state_size,
+                found: self.state_size,
+            });
+        }
+        Ok(())
+    }
+
+    /// Load the state that `diff` leads to from `base`, the save it was made
+    /// from.
+    ///
+    /// This returns [`Error::SnapshotMismatch`][] if `base` has a different
+    /// `State` size than the one the diff was made from, or if the state it
+    /// leads to doesn't fit the loaded library, and leaves the current state
+    /// untouched. Nothing else is checked: a diff applied to any other base
+    /// of the same size loads a state that was never saved.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #[macro_use] extern crate live_reload;
+    ///
+    /// use live_reload::ShouldQuit;
+    ///
+    /// pub struct Host;
+    ///
+    /// #[repr(C)]
+    /// pub struct State {
+    ///     frame: u64,
+    ///     map: [u8; 4096],
+    /// }
+    ///
+    /// fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    ///     state.map[state.frame as usize] = 1;
+    ///     state.frame += 1;
+    ///     ShouldQuit::No
+    /// }
+    /// fn nothing(_: &mut Host, _: &mut State) {}
+    ///
+    /// live_reload! {
+    ///     host: Host;
+    ///     state: State;
+    ///     init: nothing;
+    ///     reload: nothing;
+    ///     update: update;
+    ///     unload: nothing;
+    ///     deinit: nothing;
+    /// }
+    ///
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    ///
+    /// let mut app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let base = app.save_state();
+    /// let mut diffs = Vec::new();
+    /// for _ in 0..10 {
+    ///     app.update();
+    ///     diffs.push(app.save_state().diff(&base));
+    /// }
+    /// assert!(diffs[9].changed_bytes() < 32);
+    ///
+    /// let latest = app.state_bytes().to_vec();
+    /// app.apply_diff(&base, &diffs[4]).unwrap();
+    /// assert_eq!(app.state_bytes()[..8], 5u64.to_ne_bytes());
+    /// app.apply_diff(&base, &diffs[9]).unwrap();
+    /// assert_eq!(app.state_bytes(), &latest[..]);
+    /// # }
+    /// ```
+    ///
+    /// [`Error::SnapshotMismatch`]: enum.Error.html#variant.SnapshotMismatch
+    pub fn apply_diff(&mut self, base: &SaveState, diff: &StateDiff) -> Result<(), Error> {
+        self.check_state_layout(base)?;
+        if base.size != diff.base_size() {
+            return Err(Error::SnapshotMismatch {
+                expected: diff.base_size(),
+                found: base.size,
+            });
+        }
+        self.load_units(diff.state_size(), &diff.apply(&base.state))
+    }
+
+    /// Save a copy of the state to the file at `path`.
+    ///
+    /// The file starts with a small header that records the format version,
+    /// the byte order of this machine, and the size and layout hash of the
+    /// `State`, so that [`restore_from_path`][] can check that it fits before
+    /// loading it. The
+    /// `State` itself is stored as raw bytes, so like with [`new_mmap`][], a
+    /// snapshot is only meaningful to a library with the same `State` layout,
+    /// on the same kind of machine. Restoring one taken on a machine with the
+    /// other byte order fails with [`Error::EndianMismatch`][]; to carry state
+    /// between those, serialize it yourself.
+    ///
+    /// [`restore_from_path`]: struct.Reloadable.html#method.restore_from_path
+    /// [`Error::EndianMismatch`]: enum.Error.html#variant.EndianMismatch
+    /// [`new_mmap`]: struct.Reloadable.html#method.new_mmap
+    pub fn snapshot_to_path<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        let units = self.state.to_units();
+        let len = self.state_size.div_ceil(8).min(units.len());
+        let layout = self.sym.as_ref().and_then(AppSym::state_layout);
+        snapshot::write(std::io::BufWriter::new(file), self.state_size, layout, &units[..len])
+    }
+
+    /// Write the bytes of the state to the file at `path` as a hexdump, to
+    /// read or compare with standard tools.
+    ///
+    /// Unlike [`snapshot_to_path`][], this is only for looking at: it can't
+    /// be loaded back. The layout is that of `hexdump -C`, with the offset,
+    /// 16 bytes in hex and then the same bytes as ASCII on each line, and it
+    /// covers exactly [`state_size`][] bytes. So when two runs end up with a
+    /// different [`state_checksum`][], a `diff` of their dumps shows which
+    /// bytes differ.
+    ///
+    /// ```rust
+    /// # #[macro_use] extern crate live_reload;
+    /// # use live_reload::ShouldQuit;
+    /// # pub struct Host;
+    /// # #[repr(C)] pub struct State { name: [u8; 8], score: u64 }
+    /// # fn init(_: &mut Host, state: &mut State) { state.name = *b"player 1"; }
+    /// # fn nothing(_: &mut Host, _: &mut State) {}
+    /// # fn update(_: &mut Host, _: &mut State) -> ShouldQuit { ShouldQuit::No }
+    /// # live_reload! {
+    /// #     host: Host;
+    /// #     state: State;
+    /// #     init: init;
+    /// #     reload: nothing;
+    /// #     update: update;
+    /// #     unload: nothing;
+    /// #     deinit: nothing;
+    /// # }
+    /// # fn main() {
+    /// # if cfg!(miri) { return; } // Miri can't create files.
+    /// use live_reload::Reloadable;
+    ///
+    /// let app = Reloadable::new_in_process(RELOAD_API, Host).unwrap();
+    /// let path = std::env::temp_dir().join(format!("live-reload-dump-{}.txt", std::process::id()));
+    /// app.dump_state_hex(&path).unwrap();
+    ///
+    /// let dump = std::fs::read_to_string(&path).unwrap();
+    /// assert_eq!(
+    ///     dump,
+    ///     "00000000  70 6c 61 79 65 72 20 31  00 00 00 00 00 00 00 00  |player 1........|\n\
+    ///      00000010\n",
+    /// );
+    /// # std::fs::remove_file(&path).unwrap();
+    /// # }
+    /// ```
+    ///
+    /// [`snapshot_to_path`]: struct.Reloadable.html#method.snapshot_to_path
+    /// [`state_size`]: struct.Reloadable.html#method.state_size
+    /// [`state_checksum`]: struct.Reloadable.html#method.state_checksum
+    pub fn dump_state_hex<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        let file = std::fs::File::create(path)?;
+        snapshot::write_hex(std::io::BufWriter::new(file), self.state_bytes())
+    }
+
+    /// Load a copy of the state from a file written by [`snapshot_to_path`][].
+    ///
+    /// This returns an [`Error::Io`][] if the file can't be read or isn't a
+    /// snapshot, [`Error::EndianMismatch`][] if it was taken on a machine
+    /// with a different byte order, [`Error::SnapshotMismatch`][] if the
+    /// snapshot's `State` isn't the same size as the loaded library's, and
+    /// [`Error::StateLayoutMismatch`][] if it has a different layout, when
+    /// both report one. The state is left untouched in each case.
+    ///
+    /// [`snapshot_to_path`]: struct.Reloadable.html#method.snapshot_to_path
+    /// [`Error::Io`]: enum.Error.html#variant.Io
+    /// [`Error::EndianMismatch`]: enum.Error.html#variant.EndianMismatch
+    /// [`Error::SnapshotMismatch`]: enum.Error.html#variant.SnapshotMismatch
+    /// [`Error::StateLayoutMismatch`]: enum.Error.html#variant.StateLayoutMismatch
+    pub fn restore_from_path<P: AsRef<Path>>(&mut self, path: P) -> Result<(), Error> {
+        let file = std::fs::File::open(path)?;
+        let snapshot = snapshot::read(std::io::BufReader::new(file))?;
+        self.check_layout(snapshot.layout)?;
+        self.load_units(snapshot.size, &snapshot.units)
+    }
+
+    /// Start keeping a history of up to `capacity` states for undo and redo.
+    ///
+    /// Once enabled, the state is recorded after every [`update`][], and
+    /// whenever you call [`push_history`][]. The oldest states are forgotten
+    /// once there are more than `capacity` of them, so that memory use stays
+    /// bounded at `capacity` copies of the state. Enabling history again
+    /// discards whatever was recorded so far.
+    ///
+    /// [`update`]: struct.Reloadable.html#method.update
+    /// [`push_history`]: struct.Reloadable.html#method.push_history
+    pub fn enable_history(&mut self, capacity: usize) {
+        self.history = Some(History::new(capacity));
+    }
+
+    /// Stop keeping a history, and free whatever was recorded.
+    pub fn disable_history(&mut self) {
+        self.history = None;
+    }
+
+    /// Record the current state in the history.
+    ///
+    /// Anything that was undone can no longer be redone afterwards. This does
+    /// nothing unless history is enabled with [`enable_history`][].
+    ///
+    /// [`enable_history`]: struct.Reloadable.html#method.enable_history
+    pub fn push_history(&mut self) {
+        if self.history.is_some() {
+            let state = self.save_state();
+            if let Some(ref mut history) = self.history {
+                history.push(state);
+            }
+        }
+    }
+
+    /// Go back to the previously recorded state.
+    ///
+    /// Returns `false` if there is nothing to undo. This panics in the same
+    /// cases as [`load_state`](struct.Reloadable.html#method.load_state).
+    pub fn undo(&mut self) -> bool {
+        let (size, layout) = (self.state_size, self.sym.as_ref().and_then(AppSym::state_layout));
+        let state = &mut self.state;
+        match self.history.as_mut().and_then(History::undo) {
+            Some(save) => {
+                load_fitting(state, size, layout, save);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Go forward to a state that was undone.
+    ///
+    /// Returns `false` if there is nothing to redo. This panics in the same
+    /// cases as [`load_state`](struct.Reloadable.html#method.load_state).
+    pub fn redo(&mut self) -> bool {
+        let (size, layout) = (self.state_size, self.sym.as_ref().and_then(AppSym::state_layout));
+        let state = &mut self.state;
+        match self.history.as_mut().and_then(History::redo) {
+            Some(save) => {
+                load_fitting(state, size, layout, save);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+// The symbol points into the loaded library, whose code and `ReloadApi` stay
+// put until the `Reloadable` unloads it, so it can move to another thread
+// along with the `Host`.
+unsafe impl<Host: Send> Send for Reloadable<Host> {}
+
+impl Reloadable<()> {
+    /// Read what a library exports, without keeping it loaded.
+    ///
+    /// This opens the library at `path`, which is resolved like in
+    /// [`new`](#method.new), reads its ABI version, `State` size, build id,
+    /// capabilities and layout hash, and closes it again. For a library built
+    /// with [`live_reload_multi!`][], each of its modules is listed in
+    /// [`LibraryInfo::modules`][] instead, and the `State` size and
+    /// capabilities are `None`. None of the library's lifecycle functions are
+    /// called, but opening it does run any static initializers it has.
+    ///
+    /// A library built with a different ABI version can still be inspected,
+    /// to find out which version that is, but then the ABI version is all
+    /// that's read. No other checks are made, so this is also a way to see
+    /// why a library fails to load.
+    ///
+    /// This doesn't need to know the library's `Host`, since it never calls
+    /// anything that takes one. To check a library against a `Host` in a
+    /// build script, see [`build::verify_library`][].
+    ///
+    /// ```rust,no_run
+    /// # extern crate live_reload;
+    /// # fn main() {
+    /// use live_reload::Reloadable;
+    /// use live_reload::internals::ABI_VERSION;
+    ///
+    /// let info = Reloadable::inspect("target/debug/libplugin.so").unwrap();
+    /// assert_eq!(info.abi_version(), Some(ABI_VERSION));
+    /// for module in info.modules() {
+    ///     println!("{}: {} bytes of state", module.name(), module.state_size());
+    /// }
+    /// # }
+    /// ```
+    ///
+    /// Returns an error if the library can't be opened, or exports neither a
+    /// `RELOAD_API` nor a `RELOAD_API_TABLE`, or if either has a missing
+    /// lifecycle function.
+    ///
+    /// [`live_reload_multi!`]: macro.live_reload_multi.html
+    /// [`LibraryInfo::modules`]: struct.LibraryInfo.html#method.modules
+    /// [`build::verify_library`]: build/fn.verify_library.html
+    pub fn inspect<P: AsRef<Path>>(path: P) -> Result<LibraryInfo, Error> {
+        let config = Config::<()>::default();
+        let path = config.resolve(path.as_ref())?;
+        let mut info = LibraryInfo::new(&path);
+        info.metadata = std::fs::metadata(&path).ok();
+        let library = open_flags::open(&path, config.open_flags)?;
+        let api = unsafe { library.get::<*const internals::ReloadApi<()>>(b"RELOAD_API") };
+        let api = match api {
+            Ok(api) => Some(*api),
+            Err(err) => match unsafe {
+                library.get::<*const internals::ReloadApiTable<()>>(b"RELOAD_API_TABLE")
+            } {
+                Ok(_) => None,
+                Err(_) => return Err(err.into()),
+            },
+        };
+        match api {
+            Some(api) => {
+                // As when loading, check the version before anything else.
+                let found = unsafe { (*api).abi_version };
+                info.abi_version = Some(found);
+                if found != internals::ABI_VERSION {
+                    return Ok(info);
+                }
+                unsafe { internals::ReloadApi::validate(api)? };
+                let api = unsafe { &*api };
+                info.state_size = Some((api.size)());
+                info.capabilities = Some(api.capabilities());
+            }
+            None => {
+                let table = unsafe {
+                    *library.get::<*const internals::ReloadApiTable<()>>(b"RELOAD_API_TABLE")?
+                };
+                let found = unsafe { (*table).abi_version };
+                info.abi_version = Some(found);
+                if found != internals::ABI_VERSION {
+                    return Ok(info);
+                }
+                for entry in unsafe { (*table).entries } {
+                    unsafe { internals::ReloadApi::validate(&entry.api)? };
+                    info.modules.push(ModuleInfo {
+                        name: entry.name.to_string(),
+                        state_size: (entry.api.size)(),
+                        capabilities: entry.api.capabilities(),
+                    });
+                }
+            }
+        }
+        info.build_id = unsafe { read_build_id(&library) }.map(|id| id.to_string());
+        info.state_layout = unsafe { read_state_layout(&library) };
+        info.profile = unsafe { read_profile(&library) };
+        info.host_hash = unsafe { read_host_hash(&library) };
+        Ok(info)
+    }
+
+    /// Remove the temporary copies of libraries left behind in
+    /// `std::env::temp_dir()` by host processes that have exited, returning
+    /// the paths removed.
+    ///
+    /// This is for copies made with [`Builder::copy_library`][] (the default
+    /// on Windows) by hosts that crashed, which would otherwise pile up over
+    /// time. Copies are named as described in [`TEMP_COPY_PREFIX`][], and
+    /// those of processes that are still running, including this one, are
+    /// kept. For copies put somewhere else with [`Builder::copy_to`][], use
+    /// [`cleanup_temp_copies_in`](#method.cleanup_temp_copies_in).
+    ///
+    /// [`Builder::copy_library`]: struct.Builder.html#method.copy_library
+    /// [`Builder::copy_to`]: struct.Builder.html#method.copy_to
+    /// [`TEMP_COPY_PREFIX`]: constant.TEMP_COPY_PREFIX.html
+    pub fn cleanup_temp_copies() -> Result<Vec<PathBuf>, Error> {
+        Self::cleanup_temp_copies_in(std::env::temp_dir())
+    }
+
+    /// Remove the temporary copies of libraries left behind in `dir` by host
+    /// processes that have exited, returning the paths removed.
+    ///
+    /// See [`cleanup_temp_copies`](#method.cleanup_temp_copies).
+    ///
+    /// ```rust
+    /// # if cfg!(miri) { return; } // Miri can't create files.
+    /// use std::fs;
+    /// use live_reload::{Reloadable, TEMP_COPY_PREFIX};
+    ///
+    /// let dir = std::env::temp_dir().join("live-reload-cleanup-doctest");
+    /// fs::create_dir_all(&dir).unwrap();
+    /// // No process can have this id, so the copy is stale.
+    /// let stale = dir.join(format!("{}4294967295-0-libgame.so", TEMP_COPY_PREFIX));
+    /// let ours = dir.join(format!("{}{}-0-libgame.so", TEMP_COPY_PREFIX, std::process::id()));
+    /// fs::write(&stale, b"").unwrap();
+    /// fs::write(&ours, b"").unwrap();
+    ///
+    /// assert_eq!(Reloadable::cleanup_temp_copies_in(&dir).unwrap(), [stale.clone()]);
+    /// assert!(!stale.exists() && ours.exists());
+    /// # fs::remove_dir_all(&dir).unwrap();
+    /// ```
+    pub fn cleanup_temp_copies_in<P: AsRef<Path>>(dir: P) -> Result<Vec<PathBuf>, Error> {
+        Ok(temp_copy::remove_stale(dir.as_ref())?)
+    }
+}
+
+impl<Host> Drop for Reloadable<Host> {
+    fn drop(&mut self) {
+        // There's nowhere to report a failed flush, but a panic in `deinit`
+        // shouldn't go unnoticed.
+        if let Err(err @ Error::LibraryPanicked { .. }) = self.finalize() {
+            if !std::thread::panicking() {
+                panic!("{}", err);
+            }
+        }
+    }
+}
+
+// Nothing public can hand a `State` that `init` never ran on to the library,
+// so the check has to be tripped from in here, with `initialized` cleared by
+// hand.
+#[cfg(all(test, debug_assertions, feature = "testing"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[should_panic(expected = "the library's `reload` was about to run on a `State` that `init` never ran on")]
+    fn rolling_back_onto_a_state_init_never_ran_on_stops_a_debug_build() {
+        let mut app = Reloadable::with_mock((), MockLibrary::new()).unwrap();
+        app.initialized = false;
+        let previous = app.sym.take().unwrap();
+        app.old_images.push_back(previous);
+        let units = app.state.to_units();
+        let _ = app.roll_back(AppSym::in_process(MockLibrary::api()), 0, &units, true);
     }
 }
@@ -0,0 +1,420 @@
+//! Load, reload, save and drop a real dynamic library.
+//!
+//! The examples in the docs run their libraries in-process, so this is where
+//! the `libloading` path gets covered. The fixture in `tests/fixtures` is
+//! built with cargo the first time a test needs it, once as is and once with
+//! its `variant-b` feature, which the tests reload into, and once more with
+//! its `migrations` feature.
+
+extern crate live_reload;
+
+// Only the library reads the `Host`'s fields.
+#[allow(dead_code)]
+#[path = "fixtures/src/host.rs"]
+mod host;
+
+use std::cell::RefCell;
+use std::env::consts::{DLL_PREFIX, DLL_SUFFIX};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::OnceLock;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use host::Host;
+use live_reload::{Builder, Error, HostCheckMode, MissedChanges, Reloadable};
+
+/// The builds of the fixture.
+struct Fixture {
+    a: PathBuf,
+    b: PathBuf,
+    migrations: PathBuf,
+}
+
+/// Build both variants of the fixture, the first time it's asked for.
+fn fixture() -> &'static Fixture {
+    static FIXTURE: OnceLock<Fixture> = OnceLock::new();
+    FIXTURE.get_or_init(|| {
+        // A target directory of its own, since the one running the tests is
+        // locked.
+        let target = Path::new(env!("CARGO_TARGET_TMPDIR")).join("fixture");
+        Fixture {
+            a: build(&target, &[], "a"),
+            b: build(&target, &["--features", "variant-b"], "b"),
+            migrations: build(&target, &["--features", "migrations"], "migrations"),
+        }
+    })
+}
+
+/// Build the fixture with `args`, and keep a copy of the library named after
+/// `variant`, so the next build doesn't overwrite it.
+fn build(target: &Path, args: &[&str], variant: &str) -> PathBuf {
+    let status = Command::new(env!("CARGO"))
+        .arg("build")
+        .arg("--quiet")
+        .arg("--manifest-path")
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/Cargo.toml"))
+        .arg("--target-dir")
+        .arg(target)
+        .args(args)
+        .status()
+        .expect("cargo should run");
+    assert!(status.success(), "the fixture should build");
+    let built = target.join("debug").join(format!("{}fixture{}", DLL_PREFIX, DLL_SUFFIX));
+    let copy = target.join(format!("{}fixture-{}{}", DLL_PREFIX, variant, DLL_SUFFIX));
+    fs::copy(&built, &copy).expect("the fixture should be copied");
+    copy
+}
+
+/// A directory for `test` to load the fixture from, holding a fresh copy of
+/// the first variant.
+fn library_for(test: &str) -> PathBuf {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR")).join(test);
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let library = dir.join(format!("{}fixture{}", DLL_PREFIX, DLL_SUFFIX));
+    fs::copy(&fixture().a, &library).unwrap();
+    library
+}
+
+/// Replace the library at `library` with the one at `with`, the way a linker
+/// does, so the file that's loaded is never written to.
+fn replace(library: &Path, with: &Path) {
+    let staging = library.with_extension("staging");
+    fs::copy(with, &staging).unwrap();
+    fs::rename(&staging, library).unwrap();
+}
+
+thread_local! {
+    // Each test runs on a thread of its own, and the library only calls the
+    // `Host` from the thread that's driving it.
+    static LOG: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn log(message: &str) {
+    LOG.with(|log| log.borrow_mut().push(message.to_owned()));
+}
+
+fn take_log() -> Vec<String> {
+    LOG.with(|log| log.borrow_mut().drain(..).collect())
+}
+
+/// Load the fixture at `library`. Each version is copied before it's loaded,
+/// so that the loader can't hand back the previous one from the same path.
+fn load(library: &Path) -> Reloadable<Host> {
+    Builder::new(library)
+        .copy_library(true)
+        .build(Host { log })
+        .expect("the fixture should load")
+}
+
+fn counter(app: &Reloadable<Host>) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&app.state_bytes()[..8]);
+    u64::from_ne_bytes(bytes)
+}
+
+#[test]
+fn new_loads_and_updates() {
+    let library = library_for("new_loads_and_updates");
+    let mut app = load(&library);
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(counter(&app), 3);
+    assert_eq!(take_log(), ["init"]);
+    assert_eq!(app.library_info().unwrap().state_size(), Some(8));
+}
+
+#[test]
+fn reload_now_swaps_in_the_new_build() {
+    let library = library_for("reload_now_swaps_in_the_new_build");
+    let mut app = load(&library);
+    app.update();
+    app.update();
+
+    replace(&library, &fixture().b);
+    app.reload_now().expect("the new build should load");
+    app.update();
+    assert_eq!(counter(&app), 12);
+    assert_eq!(take_log(), ["init", "unload", "reload"]);
+    assert_eq!(app.reload_count(), 1);
+}
+
+#[test]
+fn save_and_load_state() {
+    let library = library_for("save_and_load_state");
+    let mut app = load(&library);
+    for _ in 0..5 {
+        app.update();
+    }
+    let save = app.save_state();
+    app.update();
+    app.update();
+    assert_eq!(counter(&app), 7);
+
+    app.load_state(&save);
+    assert_eq!(counter(&app), 5);
+    app.update();
+    assert_eq!(counter(&app), 6);
+}
+
+#[test]
+fn new_with_state_picks_up_a_save() {
+    let library = library_for("new_with_state_picks_up_a_save");
+    let mut app = load(&library);
+    for _ in 0..3 {
+        app.update();
+    }
+    let save = app.save_state();
+    drop(app);
+    take_log();
+
+    let mut app = Reloadable::new_with_state(&library, Host { log }, &save).unwrap();
+    app.update();
+    assert_eq!(counter(&app), 4);
+    assert_eq!(take_log(), ["reload"]);
+}
+
+#[test]
+fn drop_calls_deinit() {
+    let library = library_for("drop_calls_deinit");
+    let app = load(&library);
+    drop(app);
+    assert_eq!(take_log(), ["init", "deinit"]);
+}
+
+#[test]
+fn a_save_from_another_layout_is_refused() {
+    let mut a = load(&library_for("a_save_from_another_layout_is_refused_a"));
+    let library = library_for("a_save_from_another_layout_is_refused_b");
+    replace(&library, &fixture().b);
+    let mut b = load(&library);
+    a.update();
+
+    let layouts = |result| {
+        matches!(result, Err(Error::StateLayoutMismatch { expected: 0xb, found: 0xa }))
+    };
+    let save = a.save_state();
+    assert!(save.serialized().is_some());
+    assert!(layouts(b.try_load_state(&save)));
+    assert!(layouts(b.load_state_checked(&save)));
+    assert!(layouts(Reloadable::new_with_state(&library, Host { log }, &save).map(drop)));
+    assert!(layouts(a.clone_state_into(&mut b)));
+    let snapshot = library.with_extension("snapshot");
+    a.snapshot_to_path(&snapshot).unwrap();
+    assert!(layouts(b.restore_from_path(&snapshot)));
+    assert_eq!(counter(&b), 0);
+}
+
+/// A `Host` with a different fingerprint from the fixture's, but which still
+/// starts with one, so a library that loads anyway can use it.
+#[repr(C)]
+struct WiderHost {
+    host: Host,
+    extra: u64,
+}
+
+/// A copy of the fixture for `test` that doesn't export a `Host`
+/// fingerprint, like one built before they existed.
+fn library_without_host_hash(test: &str) -> PathBuf {
+    let library = library_for(test);
+    let mut bytes = fs::read(&library).unwrap();
+    let name = b"RELOAD_HOST_HASH";
+    let mut renamed = 0;
+    for i in 0..bytes.len() - name.len() {
+        if &bytes[i..i + name.len()] == name {
+            bytes[i + name.len() - 1] = b'X';
+            renamed += 1;
+        }
+    }
+    assert!(renamed > 0, "the fixture should export a fingerprint");
+    fs::write(&library, bytes).unwrap();
+    library
+}
+
+/// A builder for the fixture at `library`, checking its `Host` with `mode`.
+fn checking_host<H>(library: &Path, mode: HostCheckMode) -> Builder<H> {
+    Builder::new(library).copy_library(true).host_check(mode)
+}
+
+fn wider_host() -> WiderHost {
+    WiderHost { host: Host { log }, extra: 0 }
+}
+
+#[test]
+fn strict_host_check_refuses_a_missing_or_different_fingerprint() {
+    let library = library_for("strict_host_check_same");
+    assert!(checking_host(&library, HostCheckMode::Strict).build(Host { log }).is_ok());
+    let library = library_for("strict_host_check_different");
+    let loaded = checking_host(&library, HostCheckMode::Strict).build(wider_host());
+    assert!(matches!(loaded, Err(Error::MismatchedHost)));
+    let library = library_without_host_hash("strict_host_check_missing");
+    let loaded = checking_host(&library, HostCheckMode::Strict).build(Host { log });
+    assert!(matches!(loaded, Err(Error::MismatchedHost)));
+}
+
+#[test]
+fn lenient_host_check_only_refuses_a_different_fingerprint() {
+    let library = library_for("lenient_host_check_different");
+    let loaded = checking_host(&library, HostCheckMode::Lenient).build(wider_host());
+    assert!(matches!(loaded, Err(Error::MismatchedHost)));
+    let library = library_without_host_hash("lenient_host_check_missing");
+    assert!(checking_host(&library, HostCheckMode::Lenient).build(Host { log }).is_ok());
+
+    // Lenient is the default.
+    let library = library_for("lenient_host_check_default");
+    let loaded = Builder::new(&library).copy_library(true).build(wider_host());
+    assert!(matches!(loaded, Err(Error::MismatchedHost)));
+}
+
+#[test]
+fn host_check_off_loads_any_fingerprint() {
+    let library = library_for("host_check_off_different");
+    let loaded = checking_host(&library, HostCheckMode::Off).build(wider_host());
+    assert_eq!(loaded.expect("the fingerprint shouldn't be checked").host().extra, 0);
+    let library = library_without_host_hash("host_check_off_missing");
+    assert!(checking_host(&library, HostCheckMode::Off).build(Host { log }).is_ok());
+}
+
+#[test]
+fn a_library_created_while_still_growing_loads_once_complete() {
+    let dir = Path::new(env!("CARGO_TARGET_TMPDIR"))
+        .join(format!("a_library_created_while_still_growing-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let library = dir.join(format!("{}fixture{}", DLL_PREFIX, DLL_SUFFIX));
+    let mut app = Builder::new(&library)
+        .copy_library(true)
+        .debounce(Duration::from_millis(10))
+        .reload_retries(50, Duration::from_millis(100))
+        .build(Host { log })
+        .expect("a missing library should be waited for");
+    assert!(!app.is_loaded());
+
+    // Create the library and fill it in slowly, the way a linker writing to
+    // a new file does, so the watcher reports it being created while it's
+    // still cut short.
+    let bytes = fs::read(&fixture().a).unwrap();
+    let mut file = fs::File::create(&library).unwrap();
+    let writer = thread::spawn(move || {
+        use std::io::Write;
+        for chunk in bytes.chunks(bytes.len() / 8 + 1) {
+            file.write_all(chunk).unwrap();
+            file.flush().unwrap();
+            thread::sleep(Duration::from_millis(50));
+        }
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(20);
+    while !app.is_loaded() && Instant::now() < deadline {
+        app.reload().expect("a growing library should be waited for, not loaded");
+        thread::sleep(Duration::from_millis(10));
+    }
+    writer.join().unwrap();
+    assert!(app.is_loaded());
+    app.update();
+    assert_eq!(counter(&app), 1);
+    assert_eq!(take_log(), ["init"]);
+    drop(app);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+/// The `u64` at `offset` in `app`'s `State`.
+fn field(app: &Reloadable<Host>, offset: usize) -> u64 {
+    let mut bytes = [0; 8];
+    bytes.copy_from_slice(&app.state_bytes()[offset..offset + 8]);
+    u64::from_ne_bytes(bytes)
+}
+
+#[test]
+fn a_reload_migrates_across_several_versions() {
+    let library = library_for("a_reload_migrates_across_several_versions");
+    let mut app = load(&library);
+    for _ in 0..3 {
+        app.update();
+    }
+    assert_eq!(app.state_version(), 0);
+
+    replace(&library, &fixture().migrations);
+    app.reload_now().expect("the migrations should run");
+    assert_eq!(app.state_version(), 2);
+    assert_eq!((field(&app, 0), field(&app, 8)), (300, 3));
+    app.update();
+    assert_eq!(field(&app, 8), 4);
+    assert_eq!(take_log(), ["init", "unload", "reload"]);
+}
+
+#[test]
+fn a_failed_migration_leaves_the_state_as_it_was() {
+    let library = library_for("a_failed_migration_leaves_the_state_as_it_was");
+    let mut app = load(&library);
+    for _ in 0..13 {
+        app.update();
+    }
+
+    replace(&library, &fixture().migrations);
+    match app.reload_now() {
+        Err(Error::LibraryPanicked { ref message, .. }) => assert!(message.contains("unlucky")),
+        other => panic!("the second migration should have panicked, got {:?}", other.err()),
+    }
+    // The first step ran, but was undone along with the second.
+    assert_eq!(app.state_version(), 0);
+    assert_eq!(app.state_bytes(), &13u64.to_ne_bytes());
+
+    replace(&library, &fixture().a);
+    app.reload_now().expect("the old version should take the state back");
+    app.update();
+    assert_eq!(counter(&app), 14);
+}
+
+/// A copy of the first variant next to `library`, for it to load as a
+/// companion.
+fn companion_for(library: &Path) -> PathBuf {
+    let companion = library.with_file_name(format!("{}companion{}", DLL_PREFIX, DLL_SUFFIX));
+    fs::copy(&fixture().a, &companion).unwrap();
+    companion
+}
+
+#[test]
+fn a_change_to_a_companion_reloads_the_group() {
+    let library = library_for("a_change_to_a_companion_reloads_the_group");
+    let companion = companion_for(&library);
+    let mut app = Builder::new(&library)
+        .companion_library(&companion)
+        .copy_library(true)
+        .require_mtime_change(true)
+        .build(Host { log })
+        .expect("the group should load");
+    app.update();
+
+    // Only the companion changes, while nothing's watching.
+    app.pause_watching().unwrap();
+    replace(&companion, &fixture().b);
+    app.resume_watching(MissedChanges::Reload).unwrap();
+    app.reload().expect("the group should reload");
+    assert_eq!(app.reload_count(), 1);
+    app.update();
+    assert_eq!(counter(&app), 2);
+    assert_eq!(take_log(), ["init", "unload", "reload"]);
+}
+
+#[test]
+fn fixing_a_companion_lifts_the_quarantine() {
+    let library = library_for("fixing_a_companion_lifts_the_quarantine");
+    let companion = companion_for(&library);
+    let mut app = Builder::new(&library)
+        .companion_library(&companion)
+        .copy_library(true)
+        .quarantine_bad_libraries(true)
+        .build(Host { log })
+        .expect("the group should load");
+
+    fs::write(&companion, b"not a library").unwrap();
+    assert!(app.reload_now().is_err());
+    assert!(matches!(app.reload_now(), Err(Error::QuarantinedLibrary { .. })));
+    replace(&companion, &fixture().a);
+    app.reload_now().expect("the fixed group should load");
+    assert_eq!(take_log(), ["init", "unload", "reload"]);
+}
@@ -0,0 +1,6 @@
+/// The `Host` shared by the fixture and the tests that load it.
+pub struct Host {
+    /// Note which lifecycle function ran. The message is only borrowed for
+    /// the call, since it lives in the library.
+    pub log: fn(&str),
+}
@@ -0,0 +1,125 @@
+//! A tiny reloadable library for the integration tests to load for real.
+//!
+//! It's built twice, with and without the `variant-b` feature, so that the
+//! tests have a second version to reload into. The two only differ in how
+//! far `update` moves the counter, and in the layout hash they report, so
+//! that the tests can check that a save from one is refused by the other.
+//! Both also serialize the counter, so that the tests can check that a
+//! refused save isn't deserialized either.
+//! With the `migrations` feature, it's built a third time, two versions of
+//! the `State` on, for the tests to migrate to.
+
+#[macro_use]
+extern crate live_reload;
+
+mod host;
+
+#[cfg(not(feature = "migrations"))]
+use std::io::{self, Read, Write};
+
+use host::Host;
+use live_reload::ShouldQuit;
+
+#[cfg(not(feature = "migrations"))]
+live_reload! {
+    host: Host;
+    state: State;
+    init: init;
+    reload: reload;
+    update: update;
+    unload: unload;
+    deinit: deinit;
+    serialize: serialize;
+    deserialize: deserialize;
+    state_layout: LAYOUT;
+}
+
+#[cfg(feature = "migrations")]
+live_reload! {
+    host: Host;
+    state: State;
+    init: init;
+    reload: reload;
+    update: update;
+    unload: unload;
+    deinit: deinit;
+    migrations: [move_counter, add_total];
+    state_layout: LAYOUT;
+}
+
+#[cfg(not(feature = "migrations"))]
+#[repr(C)]
+pub struct State {
+    counter: u64,
+}
+
+#[cfg(feature = "migrations")]
+#[repr(C)]
+pub struct State {
+    total: u64,
+    counter: u64,
+}
+
+#[cfg(not(feature = "variant-b"))]
+const STEP: u64 = 1;
+#[cfg(feature = "variant-b")]
+const STEP: u64 = 10;
+
+#[cfg(not(any(feature = "variant-b", feature = "migrations")))]
+const LAYOUT: u64 = 0xa;
+#[cfg(feature = "variant-b")]
+const LAYOUT: u64 = 0xb;
+#[cfg(feature = "migrations")]
+const LAYOUT: u64 = 0xc;
+
+/// Version 0 to 1: the counter moved back, to make room for the total.
+#[cfg(feature = "migrations")]
+fn move_counter(_: &mut Host, state: &mut State, old: &[u8]) {
+    let mut counter = [0; 8];
+    counter.copy_from_slice(&old[..8]);
+    state.counter = u64::from_ne_bytes(counter);
+    state.total = 0;
+}
+
+/// Version 1 to 2: keep a total, which can't be worked out for a counter
+/// of 13.
+#[cfg(feature = "migrations")]
+fn add_total(_: &mut Host, state: &mut State, _: &[u8]) {
+    assert_ne!(state.counter, 13, "unlucky");
+    state.total = state.counter * 100;
+}
+
+fn init(host: &mut Host, state: &mut State) {
+    state.counter = 0;
+    (host.log)("init");
+}
+
+fn reload(host: &mut Host, _: &mut State) {
+    (host.log)("reload");
+}
+
+fn update(_: &mut Host, state: &mut State) -> ShouldQuit {
+    state.counter += STEP;
+    ShouldQuit::No
+}
+
+fn unload(host: &mut Host, _: &mut State) {
+    (host.log)("unload");
+}
+
+fn deinit(host: &mut Host, _: &mut State) {
+    (host.log)("deinit");
+}
+
+#[cfg(not(feature = "migrations"))]
+fn serialize(state: &State, out: &mut dyn Write) -> io::Result<()> {
+    out.write_all(&state.counter.to_le_bytes())
+}
+
+#[cfg(not(feature = "migrations"))]
+fn deserialize(state: &mut State, input: &mut dyn Read) -> io::Result<()> {
+    let mut counter = [0; 8];
+    input.read_exact(&mut counter)?;
+    state.counter = u64::from_le_bytes(counter);
+    Ok(())
+}
@@ -0,0 +1,213 @@
+//! Drive the state buffer and the lifecycle without a dynamic library, so
+//! that Miri can check the raw pointer handling underneath.
+//!
+//! Miri can't load a library, so everything here runs in-process, or against
+//! a mock, and stays off the filesystem:
+//!
+//! ```sh
+//! cargo +nightly miri test --test miri
+//! ```
+
+#[macro_use]
+extern crate live_reload;
+
+use std::sync::{Arc, Mutex};
+
+use live_reload::{MockLibrary, MultiReloadable, Reloadable, ShouldQuit};
+
+pub struct Host {
+    /// Shared with the test, so it can still be read once `deinit` has run.
+    log: Arc<Mutex<Vec<String>>>,
+    /// Where `update` last found the `State`.
+    state_at: usize,
+}
+
+impl Host {
+    fn new() -> Self {
+        Host { log: Arc::default(), state_at: 0 }
+    }
+
+    fn log(&self, entry: String) {
+        self.log.lock().unwrap().push(entry);
+    }
+}
+
+/// A `State` that's awkward to place: its `u64` isn't aligned to eight
+/// bytes, and it doesn't fill a whole number of the buffer's `u64`s.
+#[repr(C, packed(4))]
+pub struct Aligned {
+    narrow: u8,
+    wide: u64,
+}
+
+fn aligned_init(host: &mut Host, state: &mut Aligned) {
+    state.wide = u64::MAX;
+    state.narrow = 1;
+    host.log("init".into());
+}
+
+fn aligned_reload(host: &mut Host, state: &mut Aligned) {
+    host.log(format!("reload {}", state.narrow));
+}
+
+fn aligned_update(host: &mut Host, state: &mut Aligned) -> ShouldQuit {
+    host.state_at = state as *mut Aligned as usize;
+    state.wide = state.wide.wrapping_add(1);
+    state.narrow += 1;
+    ShouldQuit::No
+}
+
+fn aligned_unload(host: &mut Host, state: &mut Aligned) {
+    host.log(format!("unload {}", state.narrow));
+}
+
+fn aligned_deinit(host: &mut Host, state: &mut Aligned) {
+    host.log(format!("deinit {}", state.narrow));
+}
+
+live_reload! {
+    module: aligned;
+    host: Host;
+    state: Aligned;
+    init: aligned_init;
+    reload: aligned_reload;
+    update: aligned_update;
+    unload: aligned_unload;
+    deinit: aligned_deinit;
+}
+
+#[test]
+fn the_lifecycle_runs_in_order() {
+    let mut app = Reloadable::new_in_process(aligned::RELOAD_API, Host::new()).unwrap();
+    assert_eq!(app.update(), ShouldQuit::No);
+    app.reload_now().unwrap();
+    assert_eq!(app.update(), ShouldQuit::No);
+    let log = app.host().log.clone();
+    drop(app);
+    assert_eq!(*log.lock().unwrap(), ["init", "unload 2", "reload 2", "deinit 3"]);
+}
+
+#[test]
+fn the_state_is_aligned_for_its_type() {
+    let mut app = Reloadable::new_in_process(aligned::RELOAD_API, Host::new()).unwrap();
+    app.update();
+    assert_eq!(app.host().state_at % std::mem::align_of::<Aligned>(), 0);
+    assert_eq!(app.state_bytes().len(), std::mem::size_of::<Aligned>());
+}
+
+#[test]
+fn a_save_round_trips_through_the_buffer() {
+    let mut app = Reloadable::new_in_process(aligned::RELOAD_API, Host::new()).unwrap();
+    app.update();
+    let saved = app.save_state();
+    app.update();
+    app.update();
+    app.load_state(&saved);
+    assert_eq!(app.state_bytes(), saved.bytes());
+
+    // The loaded bytes are still a `State` the library can use in place.
+    app.update();
+    assert_eq!(app.host().state_at % std::mem::align_of::<Aligned>(), 0);
+}
+
+#[test]
+fn state_moves_between_buffers() {
+    let mut from = Reloadable::new_in_process(aligned::RELOAD_API, Host::new()).unwrap();
+    let mut to = Reloadable::new_in_process(aligned::RELOAD_API, Host::new()).unwrap();
+    from.update();
+    from.update();
+    from.clone_state_into(&mut to).unwrap();
+    assert_eq!(to.state_bytes(), from.state_bytes());
+    from.update();
+    from.transfer_state_to(&mut to).unwrap();
+    assert_eq!(to.state_bytes(), from.state_bytes());
+}
+
+live_reload_multi! {
+    host: Host;
+    aligned {
+        state: Aligned;
+        init: aligned_init;
+        reload: aligned_reload;
+        update: aligned_update;
+        unload: aligned_unload;
+        deinit: aligned_deinit;
+    }
+}
+
+#[test]
+fn a_module_state_is_aligned_for_its_type() {
+    let mut app = MultiReloadable::new_in_process(&RELOAD_API_TABLE, Host::new()).unwrap();
+    app.update();
+    app.reload_now().unwrap();
+    app.update();
+    assert_eq!(app.host().state_at % std::mem::align_of::<Aligned>(), 0);
+    let log = app.host().log.clone();
+    drop(app);
+    assert_eq!(*log.lock().unwrap(), ["init", "unload 2", "reload 2", "deinit 3"]);
+}
+
+/// A `State` holding a pointer into the heap, which has to survive being
+/// kept in the buffer and handed back.
+#[repr(C)]
+pub struct Boxed {
+    frames: Option<Box<u64>>,
+}
+
+fn boxed_init(_: &mut Host, state: &mut Boxed) {
+    state.frames = Some(Box::new(0));
+}
+
+fn boxed_update(_: &mut Host, state: &mut Boxed) -> ShouldQuit {
+    let frames = state.frames.as_mut().unwrap();
+    **frames += 1;
+    if **frames == 3 { ShouldQuit::Yes } else { ShouldQuit::No }
+}
+
+fn boxed_deinit(_: &mut Host, state: &mut Boxed) {
+    state.frames.take();
+}
+
+fn nothing(_: &mut Host, _: &mut Boxed) {}
+
+live_reload! {
+    module: boxed;
+    host: Host;
+    state: Boxed;
+    init: boxed_init;
+    reload: nothing;
+    update: boxed_update;
+    unload: nothing;
+    deinit: boxed_deinit;
+}
+
+#[test]
+fn a_heap_pointer_in_the_state_survives_reloads() {
+    let mut app = Reloadable::new_in_process(boxed::RELOAD_API, Host::new()).unwrap();
+    assert_eq!(app.update(), ShouldQuit::No);
+    app.reload_now().unwrap();
+    assert_eq!(app.update(), ShouldQuit::No);
+    app.reload_now().unwrap();
+    assert_eq!(app.update(), ShouldQuit::Yes);
+    // Dropping runs `deinit`, which frees the box; Miri reports a leak if it
+    // doesn't.
+}
+
+#[test]
+fn a_mock_reaches_its_closures_through_the_state_pointer() {
+    let mock = MockLibrary::new()
+        .on_init(|host: &mut Host| host.log("init".into()))
+        .on_update(|host: &mut Host| {
+            host.log("update".into());
+            ShouldQuit::No
+        })
+        .on_deinit(|host: &mut Host| host.log("deinit".into()));
+    let mut app = Reloadable::with_mock(Host::new(), mock).unwrap();
+    app.update();
+    app.reload_now().unwrap();
+    app.update();
+    assert!(app.state_bytes().is_empty());
+    let log = app.host().log.clone();
+    drop(app);
+    assert_eq!(*log.lock().unwrap(), ["init", "update", "update", "deinit"]);
+}